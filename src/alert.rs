@@ -0,0 +1,169 @@
+use crate::json::Json;
+use crate::GPUInfo;
+use log::{info, warn};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Output, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+/// How long a webhook gets to accept the alert before it's given up on.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A plain `http://host[:port]/path` URL to POST alerts to. HTTPS would need a TLS
+/// stack, so a local relay such as a reverse proxy has to forward to those.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Webhook {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl FromStr for Webhook {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| String::from("only http:// URLs are supported"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("invalid port `{}`", port))?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(String::from("the URL has no host"));
+        }
+        Ok(Webhook {
+            host: String::from(host),
+            port,
+            path: String::from(path),
+        })
+    }
+}
+
+impl fmt::Display for Webhook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+/// What to do when a GPU reaches `--temp-crit`, for unattended machines that nobody is
+/// watching. The caller fires them once per crossing, not on every tick above it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CritHooks {
+    /// Run through the shell, with the GPU in the `NVIDIA_GPU_INDEX`, `NVIDIA_GPU_NAME`
+    /// and `NVIDIA_GPU_TEMP` environment variables. It gets no input, and what it prints
+    /// goes to the log rather than over the TUI.
+    pub command: Option<String>,
+    /// Sent the GPU as a JSON object.
+    pub webhook: Option<Webhook>,
+}
+
+impl CritHooks {
+    /// Fires every hook for `gpu_info` from background threads, so a slow command or an
+    /// unreachable webhook never stalls the TUI. Failures are only logged.
+    pub fn fire(&self, gpu_info: &GPUInfo, threshold: u32) {
+        let temperature = gpu_info.temperature.latest();
+        if let Some(command) = &self.command {
+            let mut command = shell(command);
+            command
+                .env("NVIDIA_GPU_INDEX", gpu_info.id())
+                .env("NVIDIA_GPU_NAME", &gpu_info.device_name)
+                .env("NVIDIA_GPU_TEMP", temperature.to_string())
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            let label = gpu_info.label();
+            thread::spawn(move || match command.output() {
+                Ok(output) => log_output(&label, &output),
+                Err(error) => warn!("{} --on-crit command failed to start: {}", label, error),
+            });
+        }
+        if let Some(webhook) = &self.webhook {
+            let body = Json::object()
+                .field("gpu", gpu_info.id())
+                .field("name", gpu_info.device_name.as_str())
+                .field("temperature", temperature)
+                .field("threshold", threshold)
+                .to_string();
+            let webhook = webhook.clone();
+            let label = gpu_info.label();
+            thread::spawn(move || match post(&webhook, &body) {
+                Ok(status) => info!("{} webhook answered {}", label, status),
+                Err(error) => warn!("{} webhook {} failed: {}", label, webhook, error),
+            });
+        }
+    }
+}
+
+/// Logs how an `--on-crit` command exited, with every line it printed.
+fn log_output(label: &str, output: &Output) {
+    if output.status.success() {
+        info!("{} --on-crit command ran", label);
+    } else {
+        warn!("{} --on-crit command failed: {}", label, output.status);
+    }
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        info!("{} --on-crit: {}", label, line);
+    }
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        warn!("{} --on-crit: {}", label, line);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+/// Connects to `webhook`, trying every address its host resolves to in turn, each for
+/// at most [`WEBHOOK_TIMEOUT`].
+fn connect(webhook: &Webhook) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in (webhook.host.as_str(), webhook.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, WEBHOOK_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the host has no addresses")))
+}
+
+/// POSTs `body` as JSON to `webhook` and returns the status line of the response.
+fn post(webhook: &Webhook, body: &str) -> io::Result<String> {
+    let mut stream = connect(webhook)?;
+    stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+    stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        webhook.path,
+        webhook.host,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(String::from(response.lines().next().unwrap_or_default()))
+}
@@ -0,0 +1,132 @@
+use crate::metric::Metric;
+use crate::theme::Theme;
+use crate::{ChartOptions, GPUInfo};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::Line,
+    widgets::{block::Title, Block, Gauge, Widget},
+};
+
+/// The rows of the bars view of one GPU: a bar per metric inside the borders.
+pub const BARS_HEIGHT: u16 = 7;
+
+/// The width of the metric names left of the bars.
+const NAME_WIDTH: u16 = 6;
+
+/// Renders one GPU as a stack of labeled horizontal bars, like the bar view of `nvtop`:
+/// the temperature up to `gradient_threshold`, the clock of the chart domain against its
+/// maximum, the utilization, the memory in use and the power against the enforced limit.
+/// It reads at a glance in a pane far too small for the charts.
+pub fn render_gpu_bars(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    gradient_threshold: u32,
+    alert_style: Option<Style>,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let memory = gpu_info
+        .memory_title()
+        .map(|memory| format!(", {}", memory))
+        .unwrap_or_default();
+    let name_width = usize::from(area.width.saturating_sub(2))
+        .saturating_sub(gpu_info.label().len() + 1 + memory.chars().count());
+    let title = format!(
+        "{} {}{}",
+        gpu_info.label(),
+        gpu_info.title_name(name_width),
+        memory
+    );
+    let block = Block::bordered()
+        .border_style(alert_style.unwrap_or(Style::new().fg(theme.border)))
+        .title(Title::from(title).alignment(ratatui::layout::Alignment::Center));
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let unit = gpu_info.temperature_unit;
+    let temperature = gpu_info.temperature.latest();
+    let clock = gpu_info.clock(options.domain).latest();
+    let max_clock = gpu_info
+        .max_clock(options.domain)
+        .unwrap_or_default()
+        .max(clock);
+    let utilization = gpu_info.gpu_utilization.latest();
+    let memory_used = gpu_info.memory_used.latest();
+    let power = gpu_info.power_usage.latest();
+    let heat = f64::from(temperature) / f64::from(gradient_threshold.max(1));
+    let bars = [
+        (
+            "Temp",
+            Metric::Temperature,
+            heat,
+            unit.format(temperature),
+            theme.temp_gradient_color(heat),
+        ),
+        (
+            "Clock",
+            Metric::Clock,
+            ratio(u64::from(clock), u64::from(max_clock)),
+            options.units.clock(f64::from(clock)),
+            theme.clock_color,
+        ),
+        (
+            "Util",
+            Metric::Utilization,
+            ratio(u64::from(utilization), 100),
+            format!("{}%", utilization),
+            theme.sm_utilization_color,
+        ),
+        (
+            "Mem",
+            Metric::Memory,
+            ratio(memory_used, gpu_info.memory_total),
+            format!(
+                "{} / {}",
+                options.units.memory_number(memory_used),
+                options.units.memory(gpu_info.memory_total)
+            ),
+            theme.memory_color,
+        ),
+        (
+            "Power",
+            Metric::Power,
+            ratio(u64::from(power), u64::from(gpu_info.power_limit)),
+            format!(
+                "{} / {}",
+                gpu_info.precision.watts_number(f64::from(power)),
+                gpu_info.precision.watts(f64::from(gpu_info.power_limit))
+            ),
+            theme.power_color,
+        ),
+    ];
+
+    let rows = Layout::vertical([Constraint::Length(1); 5]).split(inner);
+    for (&row, (name, metric, ratio, value, color)) in rows.iter().zip(bars) {
+        let [name_area, bar_area] =
+            Layout::horizontal([Constraint::Length(NAME_WIDTH), Constraint::Fill(1)]).areas(row);
+        Line::from(name)
+            .style(Style::new().fg(theme.text))
+            .render(name_area, buf);
+        let (ratio, label, color) = if gpu_info.available(metric) {
+            (ratio.clamp(0.0, 1.0), value, color)
+        } else {
+            (0.0, String::from("N/A"), theme.unavailable)
+        };
+        Gauge::default()
+            .ratio(ratio)
+            .label(label)
+            .gauge_style(Style::new().fg(color).bg(theme.background))
+            .render(bar_area, buf);
+    }
+}
+
+/// `value` as a share of `total`, zero without a total to compare against.
+fn ratio(value: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        total => value as f64 / total as f64,
+    }
+}
@@ -0,0 +1,156 @@
+use crate::metric::{self, Metric};
+use crate::source::{GpuSource, MediaEngine};
+use crate::status::DriverVersions;
+use nvml_wrapper::error::NvmlError;
+use nvml_wrapper_sys::bindings::NVML_NVLINK_MAX_LINKS;
+use std::fmt::Write;
+
+/// How a metric query of `--check` went on one GPU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Support {
+    Supported,
+    /// The GPU or the driver can't report the metric at all.
+    Unsupported,
+    /// The query needs more privileges, usually root.
+    Denied,
+    /// The query failed otherwise, with the error.
+    Failed(String),
+}
+
+impl Support {
+    fn new(result: &Result<(), NvmlError>) -> Self {
+        match result {
+            Ok(()) => Support::Supported,
+            Err(NvmlError::NoPermission) => Support::Denied,
+            Err(error) if metric::is_unsupported(error) => Support::Unsupported,
+            Err(error) => Support::Failed(error.to_string()),
+        }
+    }
+
+    /// The cell of the report table.
+    fn cell(&self) -> &'static str {
+        match self {
+            Support::Supported => "yes",
+            Support::Unsupported => "no",
+            Support::Denied => "denied",
+            Support::Failed(_) => "error",
+        }
+    }
+}
+
+/// The outcome of `--check` on one GPU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuCheck {
+    pub name: String,
+    /// How the query of every metric went, in the order of [`Metric::ALL`].
+    pub metrics: Vec<(Metric, Support)>,
+}
+
+/// Queries every metric the monitor reads on every GPU of `source` once, by hardware
+/// index.
+pub fn check_gpus(source: &dyn GpuSource) -> Result<Vec<GpuCheck>, NvmlError> {
+    let mut gpus = Vec::new();
+    for index in 0..source.device_count()? {
+        let gpu_device = match source.device(index) {
+            Ok(gpu_device) => gpu_device,
+            Err(error) => {
+                let failed = Support::new(&Err(error));
+                gpus.push(GpuCheck {
+                    name: format!("GPU {}", index),
+                    metrics: Metric::ALL.map(|metric| (metric, failed.clone())).to_vec(),
+                });
+                continue;
+            }
+        };
+        let name = gpu_device
+            .name()
+            .unwrap_or_else(|_| format!("GPU {}", index));
+        let mut results: Vec<(Metric, Result<(), NvmlError>)> =
+            metric::probe_queries(gpu_device).into();
+        // Supported as soon as one link or engine answers
+        let nvlink = (0..NVML_NVLINK_MAX_LINKS)
+            .map(|link| source.nvlink_counters(index, link).map(drop))
+            .reduce(|first, result| first.or(result));
+        results.push((
+            Metric::NvLink,
+            nvlink.unwrap_or(Err(NvmlError::NotSupported)),
+        ));
+        let engines = MediaEngine::ALL
+            .map(|engine| source.engine_utilization(index, engine).map(drop))
+            .into_iter()
+            .reduce(|first, result| first.or(result));
+        results.push((
+            Metric::Engines,
+            engines.unwrap_or(Err(NvmlError::NotSupported)),
+        ));
+        let metrics = Metric::ALL
+            .into_iter()
+            .filter_map(|metric| {
+                let (_, result) = results.iter().find(|(queried, _)| *queried == metric)?;
+                Some((metric, Support::new(result)))
+            })
+            .collect();
+        gpus.push(GpuCheck { name, metrics });
+    }
+    Ok(gpus)
+}
+
+/// The report of `--check`: the driver versions, the GPUs, a table of which metrics each
+/// GPU supports, and the queries that were denied or failed, for bug reports.
+pub fn check_report(source: &dyn GpuSource) -> Result<String, NvmlError> {
+    let gpus = check_gpus(source)?;
+    let mut report = String::new();
+    let _ = writeln!(report, "{}", DriverVersions::read(source));
+    for (index, gpu) in gpus.iter().enumerate() {
+        let _ = writeln!(report, "GPU {}: {}", index, gpu.name);
+    }
+    if gpus.is_empty() {
+        let _ = writeln!(report, "No GPUs found");
+        return Ok(report);
+    }
+
+    let name_width = Metric::ALL
+        .iter()
+        .map(|metric| metric.to_string().len())
+        .max()
+        .unwrap_or_default();
+    let mut header = format!("{:<width$}", "Metric", width = name_width);
+    for index in 0..gpus.len() {
+        let _ = write!(header, "  {:<6}", format!("GPU {}", index));
+    }
+    let _ = writeln!(report, "\n{}", header.trim_end());
+    for (row, metric) in Metric::ALL.iter().enumerate() {
+        let mut line = format!("{:<width$}", metric.to_string(), width = name_width);
+        for gpu in &gpus {
+            let cell = gpu
+                .metrics
+                .get(row)
+                .map_or("", |(_, support)| support.cell());
+            let _ = write!(line, "  {:<6}", cell);
+        }
+        let _ = writeln!(report, "{}", line.trim_end());
+    }
+
+    let mut notes = Vec::new();
+    for (index, gpu) in gpus.iter().enumerate() {
+        for (metric, support) in &gpu.metrics {
+            match support {
+                Support::Denied => notes.push(format!(
+                    "GPU {}: {} needs more privileges (run as root or add to video group)",
+                    index, metric
+                )),
+                Support::Failed(error) => {
+                    notes.push(format!("GPU {}: {} failed: {}", index, metric, error))
+                }
+                Support::Supported | Support::Unsupported => {}
+            }
+        }
+    }
+    if !notes.is_empty() {
+        let _ = writeln!(report);
+        for note in notes {
+            let _ = writeln!(report, "{}", note);
+        }
+    }
+    Ok(report)
+}
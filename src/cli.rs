@@ -0,0 +1,563 @@
+use crate::alert::CritHooks;
+use crate::metric::{Metric, Precision};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The delay between two metric samples when `--interval` isn't given.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The charts whose y-axis can be fixed, by the name of their `--<name>-range` flag and
+/// `<name>_range` config key.
+pub const AXIS_RANGE_METRICS: [(&str, Metric); 3] = [
+    ("temp", Metric::Temperature),
+    ("power", Metric::Power),
+    ("clock", Metric::Clock),
+];
+
+/// Fixed y-axis bounds for a chart, written `min:max` in the chart's unit: °C, W or MHz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl FromStr for AxisRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (min, max) = value
+            .split_once(':')
+            .ok_or_else(|| String::from("expected `min:max`"))?;
+        let bound = |bound: &str| {
+            bound
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|bound| bound.is_finite())
+                .ok_or_else(|| format!("invalid bound `{}`", bound))
+        };
+        let range = AxisRange {
+            min: bound(min)?,
+            max: bound(max)?,
+        };
+        if range.min >= range.max {
+            return Err(String::from("the minimum must be below the maximum"));
+        }
+        Ok(range)
+    }
+}
+
+impl fmt::Display for AxisRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.min, self.max)
+    }
+}
+
+/// The grid of `--layout`, written `<columns>x<rows>`, that the overview arranges the
+/// GPUs in, row by row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridLayout {
+    pub columns: u16,
+    pub rows: u16,
+}
+
+impl GridLayout {
+    /// The columns and rows `count` GPUs take up. Fewer GPUs than cells share the space
+    /// of the cells left empty, and more GPUs add columns, so the panels keep the height
+    /// of their row instead of being left out.
+    pub fn fit(self, count: usize) -> (usize, usize) {
+        let rows = usize::from(self.rows);
+        if count > usize::from(self.columns) * rows {
+            return (count.div_ceil(rows), rows);
+        }
+        let columns = usize::from(self.columns).min(count).max(1);
+        (columns, count.div_ceil(columns).max(1))
+    }
+}
+
+impl FromStr for GridLayout {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (columns, rows) = value
+            .split_once('x')
+            .ok_or_else(|| String::from("expected `<columns>x<rows>`"))?;
+        let count = |count: &str| {
+            count
+                .trim()
+                .parse::<u16>()
+                .ok()
+                .filter(|&count| count > 0)
+                .ok_or_else(|| format!("invalid count `{}`", count))
+        };
+        Ok(GridLayout {
+            columns: count(columns)?,
+            rows: count(rows)?,
+        })
+    }
+}
+
+/// What `--help` prints.
+pub const HELP: &str = "\
+Monitor NVIDIA GPUs in the terminal.
+
+Usage: nvidia [COMMAND] [OPTIONS]
+
+Commands:
+  tui     Chart the GPUs in the terminal (default)
+  dump    Print one reading of every GPU as JSON and exit, like --dump-json
+  check   Print which metrics every GPU supports and exit, like --check
+  daemon  Export the metrics without a terminal until interrupted, like --daemon
+
+Monitoring:
+  --interval <MS>           Milliseconds between two readings [default: 1000]
+  --gpus <LIST>             Only monitor these GPU indices, such as 0,2
+  --rescan <TICKS>          Look for attached or detached GPUs every TICKS readings
+  --high-res                Average utilization and power over the driver's samples
+  --xid                     Alert on the critical XID errors the driver reports
+  --demo                    Monitor synthetic GPUs instead of querying NVML
+  --replay <PATH>           Play back a recording of --record instead of querying NVML
+  --replay-speed <FACTOR>   Play the recording back this many times faster
+
+Display:
+  --layout <COLSxROWS>      Arrange the GPUs in a grid, such as 2x2
+  --per-page <N>            Show N GPUs at a time, flipping pages with PgUp/PgDn
+  --compact                 Show one table row per GPU instead of the charts
+  --bars                    Show gauge bars per GPU instead of the charts
+  --label <TEXT>            Show TEXT next to the hostname in the banner
+  --no-color                Leave every color to the terminal's defaults
+  --accent <#RRGGBB>        Draw the borders and the clock line in this color
+  --fahrenheit              Show temperatures in °F
+  --timing                  Show how long each reading took in the status bar
+  --temp-range <MIN:MAX>    Fix the y-axis of the temperature chart, in °C
+  --power-range <MIN:MAX>   Fix the y-axis of the power chart, in W
+  --clock-range <MIN:MAX>   Fix the y-axis of the clock chart, in MHz
+
+Alerts:
+  --temp-warn <C>           Highlight a GPU once it reaches C °C
+  --temp-crit <C>           Flash a GPU once it reaches C °C
+  --temp-rate-warn <C>      Highlight a GPU heating by C °C per minute or more
+  --anomaly-sigma <SIGMA>   Mark samples SIGMA standard deviations from the mean
+  --on-crit <COMMAND>       Run COMMAND as a GPU reaches --temp-crit
+  --on-crit-webhook <URL>   Post to URL as a GPU reaches --temp-crit
+  --bell                    Ring the terminal bell at --temp-crit or the power limit
+
+Exports:
+  --log-csv <PATH>          Append every reading to a CSV file
+  --influx-file <PATH>      Append every reading in InfluxDB line protocol
+  --rotate-mb <MB>          Rotate the CSV and InfluxDB files past MB megabytes
+  --record <PATH>           Record every reading for --replay
+  --prometheus <PORT>       Serve Prometheus metrics over HTTP on PORT
+  --socket <PATH>           Serve JSON readings on a Unix socket at PATH
+  --summary <PATH>          Write each GPU's session peaks to PATH on exit
+  --raw                     Leave the exported readings unrounded
+
+One-shot:
+  --dump-json               Print one reading of every GPU as JSON and exit
+  --once                    Print one reading of every GPU as a table and exit
+  --xml                     Print one reading in the XML of nvidia-smi and exit
+  --check                   Print which metrics every GPU supports and exit
+  --daemon                  Export the metrics without a terminal until interrupted
+  -V, --version             Print the versions of this program and the driver
+  -h, --help                Print this help
+";
+
+/// Command line options accepted by the program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub interval: Duration,
+    /// Appends every tick's metrics to this CSV file.
+    pub log_csv: Option<PathBuf>,
+    /// Appends every tick's metrics to this file in InfluxDB line protocol.
+    pub influx_file: Option<PathBuf>,
+    /// Rotates the CSV and InfluxDB files once they grow past this many bytes, keeping
+    /// one previous file, or `None` to let them grow without bound.
+    pub rotate_size: Option<u64>,
+    /// Records every tick of every GPU to this file, for `--replay`.
+    pub record: Option<PathBuf>,
+    /// Monitors the GPUs recorded in this file with `--record` instead of querying NVML.
+    pub replay: Option<PathBuf>,
+    /// How many times faster than it was recorded `--replay` plays the recording back.
+    pub replay_speed: f64,
+    /// Serves Prometheus metrics over HTTP on this port.
+    pub prometheus: Option<u16>,
+    /// Serves the latest JSON snapshot to every client of a Unix socket at this path.
+    pub socket: Option<PathBuf>,
+    /// Writes the highest temperature, clock and power and the lowest clock of every GPU
+    /// over the session to this JSON file on exit.
+    pub summary: Option<PathBuf>,
+    /// Hardware indices of the GPUs to monitor, or `None` for all of them.
+    pub gpus: Option<Vec<u32>>,
+    /// Enumerates the GPUs again every this many ticks, to pick up GPUs attached or
+    /// detached at runtime, or `None` to keep the GPUs found at startup.
+    pub rescan: Option<u32>,
+    /// Highlights a GPU in yellow once its temperature reaches this many °C.
+    pub temp_warn: Option<u32>,
+    /// Highlights a GPU in flashing red once its temperature reaches this many °C.
+    pub temp_crit: Option<u32>,
+    /// Highlights a GPU in yellow while its temperature rises by this many °C per minute
+    /// or more, before it reaches `temp_warn`.
+    pub temp_rate_warn: Option<f64>,
+    /// Marks the chart samples this many standard deviations or more from the mean of
+    /// their history.
+    pub anomaly_sigma: Option<f64>,
+    /// Run or notified by `--on-crit` and `--on-crit-webhook` as a GPU reaches
+    /// `temp_crit`.
+    pub crit_hooks: CritHooks,
+    /// Fixed y-axis bounds of the charts in [`AXIS_RANGE_METRICS`]. Charts without one
+    /// scale to their data.
+    pub axis_ranges: BTreeMap<Metric, AxisRange>,
+    /// Shown next to the hostname in the banner, to tell several monitors apart.
+    pub label: Option<String>,
+    /// Arranges the GPUs in a grid instead of stacking them vertically.
+    pub layout: Option<GridLayout>,
+    /// Shows at most this many GPUs at a time, on pages flipped with PageUp and
+    /// PageDown, or `None` to show every GPU.
+    pub per_page: Option<usize>,
+    /// How power and efficiency are rounded, from the config file, and whether the
+    /// exports are left unrounded with `--raw`.
+    pub precision: Precision,
+    /// Shows one table row per GPU instead of the charts.
+    pub compact: bool,
+    /// Shows a stack of gauge bars per GPU instead of the charts.
+    pub bars: bool,
+    /// Leaves every color to the terminal's defaults, for dumb terminals and serial
+    /// consoles.
+    pub no_color: bool,
+    /// The `#RRGGBB` color of the borders and the clock line, over the theme's.
+    pub accent: Option<String>,
+    /// Shows temperatures in °F instead of °C. Thresholds, ranges and exports stay in °C.
+    pub fahrenheit: bool,
+    /// Averages utilization and power over the samples the driver takes between ticks,
+    /// instead of reading them once a tick.
+    pub high_res: bool,
+    /// Shows how long collecting each tick's readings took in the status bar.
+    pub timing: bool,
+    /// Waits for the critical XID errors the driver reports and shows them as alerts.
+    pub xid: bool,
+    /// Rings the terminal bell as a GPU reaches the critical temperature or its power
+    /// limit.
+    pub bell: bool,
+    /// Prints one snapshot of every GPU as JSON and exits instead of starting the TUI.
+    pub dump_json: bool,
+    /// Prints which metrics every GPU supports and exits instead of starting the TUI.
+    pub check: bool,
+    /// Prints one snapshot of every GPU as a text table and exits instead of starting
+    /// the TUI.
+    pub once: bool,
+    /// Prints one snapshot of every GPU in the XML format of `nvidia-smi -q -x` and exits
+    /// instead of starting the TUI.
+    pub xml: bool,
+    /// Monitors synthetic GPUs with oscillating readings instead of querying NVML, for
+    /// developing and screenshotting the TUI on machines without NVIDIA hardware.
+    pub demo: bool,
+    /// Exports the metrics without a TUI until interrupted.
+    pub daemon: bool,
+    /// Prints a line of metrics per GPU on every tick instead of starting the TUI, as
+    /// when stdout isn't a terminal. Not a flag of its own.
+    pub stream: bool,
+    /// Prints the version of this program and of the driver stack and exits.
+    pub version: bool,
+    /// Prints [`HELP`] and exits.
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_INTERVAL,
+            log_csv: None,
+            influx_file: None,
+            rotate_size: None,
+            record: None,
+            summary: None,
+            replay: None,
+            replay_speed: 1.0,
+            prometheus: None,
+            socket: None,
+            gpus: None,
+            rescan: None,
+            temp_warn: None,
+            temp_crit: None,
+            temp_rate_warn: None,
+            anomaly_sigma: None,
+            crit_hooks: CritHooks::default(),
+            axis_ranges: BTreeMap::new(),
+            label: None,
+            layout: None,
+            per_page: None,
+            precision: Precision::default(),
+            compact: false,
+            bars: false,
+            no_color: false,
+            accent: None,
+            fahrenheit: false,
+            high_res: false,
+            timing: false,
+            xid: false,
+            bell: false,
+            dump_json: false,
+            check: false,
+            once: false,
+            xml: false,
+            demo: false,
+            daemon: false,
+            stream: false,
+            version: false,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    /// Parses options from the program arguments over the built-in defaults.
+    #[cfg(test)]
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Options::parse_over(Options::default(), args)
+    }
+
+    /// Parses options from the program arguments, excluding the program name itself.
+    /// Options that aren't given keep their value in `defaults`, e.g. the ones read from
+    /// the config file.
+    pub fn parse_over<I>(defaults: Options, args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = defaults;
+        let mut args = args.into_iter().peekable();
+
+        // A command only comes first, and stands for the flag of the same mode
+        if let Some(command) = args.next_if(|arg| !arg.starts_with('-')) {
+            match command.as_str() {
+                "tui" => {}
+                "dump" => options.dump_json = true,
+                "check" => options.check = true,
+                "daemon" => options.daemon = true,
+                _ => return Err(format!("Unknown command: {} (see --help)", command)),
+            }
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--interval" => {
+                    let millis: u64 = parse_value(&arg, args.next())?;
+                    if millis == 0 {
+                        return Err(String::from("--interval must be greater than zero"));
+                    }
+                    options.interval = Duration::from_millis(millis);
+                }
+                "--log-csv" => {
+                    options.log_csv = Some(parse_value(&arg, args.next())?);
+                }
+                "--influx-file" => {
+                    options.influx_file = Some(parse_value(&arg, args.next())?);
+                }
+                "--rotate-mb" => {
+                    let megabytes: u64 = parse_value(&arg, args.next())?;
+                    if megabytes == 0 {
+                        return Err(String::from("--rotate-mb must be greater than zero"));
+                    }
+                    let bytes = megabytes
+                        .checked_mul(1_000_000)
+                        .ok_or_else(|| String::from("--rotate-mb is too large"))?;
+                    options.rotate_size = Some(bytes);
+                }
+                "--summary" => {
+                    options.summary = Some(parse_value(&arg, args.next())?);
+                }
+                "--record" => {
+                    options.record = Some(parse_value(&arg, args.next())?);
+                }
+                "--replay" => {
+                    options.replay = Some(parse_value(&arg, args.next())?);
+                }
+                "--replay-speed" => {
+                    let speed: f64 = parse_value(&arg, args.next())?;
+                    if !(speed > 0.0 && speed.is_finite()) {
+                        return Err(String::from("--replay-speed must be greater than zero"));
+                    }
+                    options.replay_speed = speed;
+                }
+                "--prometheus" => {
+                    options.prometheus = Some(parse_value(&arg, args.next())?);
+                }
+                "--socket" => {
+                    if cfg!(not(unix)) {
+                        return Err(String::from("--socket is only supported on Unix"));
+                    }
+                    options.socket = Some(parse_value(&arg, args.next())?);
+                }
+                "--gpus" => {
+                    options.gpus = Some(parse_gpus(args.next())?);
+                }
+                "--rescan" => {
+                    let ticks: u32 = parse_value(&arg, args.next())?;
+                    if ticks == 0 {
+                        return Err(String::from("--rescan must be greater than zero"));
+                    }
+                    options.rescan = Some(ticks);
+                }
+                "--temp-warn" => {
+                    options.temp_warn = Some(parse_value(&arg, args.next())?);
+                }
+                "--temp-crit" => {
+                    options.temp_crit = Some(parse_value(&arg, args.next())?);
+                }
+                "--temp-rate-warn" => {
+                    let rate: f64 = parse_value(&arg, args.next())?;
+                    if !(rate > 0.0 && rate.is_finite()) {
+                        return Err(String::from("--temp-rate-warn must be greater than zero"));
+                    }
+                    options.temp_rate_warn = Some(rate);
+                }
+                "--anomaly-sigma" => {
+                    let sigma: f64 = parse_value(&arg, args.next())?;
+                    if !(sigma > 0.0 && sigma.is_finite()) {
+                        return Err(String::from("--anomaly-sigma must be greater than zero"));
+                    }
+                    options.anomaly_sigma = Some(sigma);
+                }
+                "--on-crit" => {
+                    options.crit_hooks.command = Some(parse_value(&arg, args.next())?);
+                }
+                "--on-crit-webhook" => {
+                    let url = args
+                        .next()
+                        .ok_or_else(|| format!("{} requires a value", arg))?;
+                    let webhook = url
+                        .parse()
+                        .map_err(|error| format!("Invalid value for {}: {}", arg, error))?;
+                    options.crit_hooks.webhook = Some(webhook);
+                }
+                flag if flag.starts_with("--") && flag.ends_with("-range") => {
+                    let name = &flag[2..flag.len() - "-range".len()];
+                    let (_, metric) = AXIS_RANGE_METRICS
+                        .into_iter()
+                        .find(|&(candidate, _)| candidate == name)
+                        .ok_or_else(|| format!("Unknown argument: {} (see --help)", arg))?;
+                    let range = args
+                        .next()
+                        .ok_or_else(|| format!("{} requires a value", flag))?;
+                    let range = range
+                        .parse()
+                        .map_err(|error| format!("Invalid value for {}: {}", flag, error))?;
+                    options.axis_ranges.insert(metric, range);
+                }
+                "--label" => {
+                    options.label = Some(parse_value(&arg, args.next())?);
+                }
+                "--layout" => {
+                    let layout = args
+                        .next()
+                        .ok_or_else(|| format!("{} requires a value", arg))?;
+                    let layout = layout
+                        .parse()
+                        .map_err(|error| format!("Invalid value for {}: {}", arg, error))?;
+                    options.layout = Some(layout);
+                }
+                "--per-page" => {
+                    let count: usize = parse_value(&arg, args.next())?;
+                    if count == 0 {
+                        return Err(String::from("--per-page must be greater than zero"));
+                    }
+                    options.per_page = Some(count);
+                }
+                "--raw" => options.precision.raw = true,
+                "--compact" => options.compact = true,
+                "--bars" => options.bars = true,
+                "--no-color" => options.no_color = true,
+                "--accent" => {
+                    options.accent = Some(parse_value(&arg, args.next())?);
+                }
+                "--fahrenheit" => options.fahrenheit = true,
+                "--high-res" => options.high_res = true,
+                "--timing" => options.timing = true,
+                "--xid" => options.xid = true,
+                "--bell" => options.bell = true,
+                "--dump-json" => options.dump_json = true,
+                "--check" => options.check = true,
+                "--once" => options.once = true,
+                "--demo" => options.demo = true,
+                "--daemon" => options.daemon = true,
+                "--xml" => options.xml = true,
+                "--version" | "-V" => options.version = true,
+                "--help" | "-h" => options.help = true,
+                _ => return Err(format!("Unknown argument: {} (see --help)", arg)),
+            }
+        }
+        // Whatever else is given, `--help` only prints the help
+        if options.help {
+            return Ok(options);
+        }
+
+        if options.daemon && !options.exports() {
+            return Err(String::from(
+                "--daemon requires --log-csv, --influx-file, --record, --prometheus, --socket or --summary",
+            ));
+        }
+        if options.replay_speed != 1.0 && options.replay.is_none() {
+            return Err(String::from("--replay-speed requires --replay"));
+        }
+        if options.rotate_size.is_some()
+            && options.log_csv.is_none()
+            && options.influx_file.is_none()
+        {
+            return Err(String::from(
+                "--rotate-mb requires --log-csv or --influx-file",
+            ));
+        }
+        if options.temp_crit.is_none() && options.crit_hooks != CritHooks::default() {
+            return Err(String::from(
+                "--on-crit and --on-crit-webhook require --temp-crit",
+            ));
+        }
+        if let (Some(temp_warn), Some(temp_crit)) = (options.temp_warn, options.temp_crit) {
+            if temp_warn > temp_crit {
+                return Err(String::from("--temp-warn must not be above --temp-crit"));
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Whether any export sink is given.
+    pub fn exports(&self) -> bool {
+        self.log_csv.is_some()
+            || self.influx_file.is_some()
+            || self.record.is_some()
+            || self.prometheus.is_some()
+            || self.socket.is_some()
+            || self.summary.is_some()
+    }
+}
+
+/// Parses a comma-separated list of GPU indices such as `0,3,5`, sorted and without
+/// duplicates.
+fn parse_gpus(value: Option<String>) -> Result<Vec<u32>, String> {
+    let value = value.ok_or_else(|| String::from("--gpus requires a value"))?;
+    let mut gpus = value
+        .split(',')
+        .map(|index| {
+            index
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid GPU index for --gpus: {}", index))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+    gpus.sort_unstable();
+    gpus.dedup();
+    Ok(gpus)
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
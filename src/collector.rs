@@ -0,0 +1,300 @@
+use crate::demo::DemoSource;
+use crate::source::{GpuSource, NvmlSource};
+use crate::status::DriverVersions;
+use crate::sysfs::SysfsSource;
+use crate::xid::{self, XidEvent};
+use crate::{nvml_error, GPUInfo, NvidiaApp};
+use log::{debug, warn};
+use nvml_wrapper::Nvml;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The GPUs found by the collection thread, before their first sample.
+pub struct Probe {
+    /// Every monitored GPU with its supported metrics, with one-sample histories.
+    pub gpus: Vec<GPUInfo>,
+    pub versions: DriverVersions,
+}
+
+/// What the collection thread sends the TUI.
+pub enum Update {
+    /// The latest readings of every GPU, as `GPUInfo`s whose histories hold just that
+    /// one sample, in the order of [`Probe::gpus`] until the GPUs are rescanned, and how
+    /// long collecting them took.
+    Readings(Vec<GPUInfo>, Duration),
+    /// NVML kept failing and is being restarted. Readings resume once it's back.
+    Reconnecting,
+    /// A GPU reported a critical XID error, with `--xid`.
+    Xid(XidEvent),
+}
+
+/// The number of consecutive ticks where no GPU could be read before NVML is restarted.
+pub const RECONNECT_AFTER: u32 = 5;
+
+/// Counts consecutive ticks where every query of every GPU failed, as happens when the
+/// driver was reset or reloaded under a running NVML.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    failed_ticks: u32,
+}
+
+impl Watchdog {
+    /// Records the outcome of a tick, returning whether NVML should be restarted.
+    pub fn check(&mut self, gpus: &[GPUInfo]) -> bool {
+        if !gpus.is_empty() && gpus.iter().all(GPUInfo::tick_failed) {
+            self.failed_ticks += 1;
+        } else {
+            self.failed_ticks = 0;
+        }
+        if self.failed_ticks < RECONNECT_AFTER {
+            return false;
+        }
+        self.failed_ticks = 0;
+        true
+    }
+}
+
+/// Polls NVML on a background thread, so that slow queries never hold up drawing or
+/// input handling.
+///
+/// Every `interval` the thread sends the latest readings of every GPU. The histories and
+/// everything else the user can change stay with the TUI, which pushes each reading into
+/// its own `GPUInfo`s. When the [`Watchdog`] fires, NVML and every device handle are torn
+/// down and acquired again. With `rescan`, the GPUs are enumerated again every that many
+/// ticks, and the readings then list whichever GPUs are present. With `high_res`,
+/// utilization and power are averaged over the driver's samples since the tick before.
+/// With `xid`, the XID errors the driver reports are sent as they happen, from a thread
+/// of their own. The thread stops once the receiver is dropped.
+pub fn spawn(
+    interval: Duration,
+    filter: Option<Vec<u32>>,
+    rescan: Option<u32>,
+    high_res: bool,
+    xid: bool,
+    show_processes: Arc<AtomicBool>,
+) -> io::Result<(Probe, Receiver<Update>)> {
+    let (probe_tx, probe_rx) = mpsc::channel();
+    let (updates_tx, updates_rx) = mpsc::channel();
+    if xid {
+        xid::spawn(updates_tx.clone());
+    }
+    thread::spawn(move || {
+        // NVML stays on this thread, since every device handle borrows it
+        let mut nvml = match Nvml::init() {
+            Ok(nvml) => nvml,
+            Err(error) => {
+                let _ = probe_tx.send(Err(nvml_error("Failed to initialize NVML", error)));
+                return;
+            }
+        };
+        let source = match NvmlSource::new(&nvml) {
+            Ok(source) => source,
+            Err(error) => {
+                let _ = probe_tx.send(Err(nvml_error("Failed to get device count", error)));
+                return;
+            }
+        };
+        let mut collector = NvidiaApp {
+            interval,
+            history_len: 1,
+            high_res,
+            ..NvidiaApp::default()
+        };
+        if let Err(error) = collector.add_gpus(&source, filter.as_deref()) {
+            let _ = probe_tx.send(Err(error));
+            return;
+        }
+        let probe = Probe {
+            gpus: collector.gpus.clone(),
+            versions: DriverVersions::read(&source),
+        };
+        if probe_tx.send(Ok(probe)).is_err() {
+            return;
+        }
+
+        let mut keep_sampling = sample(
+            &mut collector,
+            &nvml,
+            source,
+            rescan,
+            filter.as_deref(),
+            &show_processes,
+            &updates_tx,
+        );
+        while keep_sampling {
+            if updates_tx.send(Update::Reconnecting).is_err() {
+                return;
+            }
+            // The GPUs keep their indices and supported metrics, so only the handles change
+            drop(nvml);
+            nvml = reconnect(interval);
+            keep_sampling = match NvmlSource::new(&nvml) {
+                Ok(source) => sample(
+                    &mut collector,
+                    &nvml,
+                    source,
+                    rescan,
+                    filter.as_deref(),
+                    &show_processes,
+                    &updates_tx,
+                ),
+                Err(error) => {
+                    debug!(
+                        "Failed to get device count after restarting NVML: {}",
+                        error
+                    );
+                    true
+                }
+            };
+        }
+    });
+
+    let probe = probe_rx
+        .recv()
+        .map_err(|_| io::Error::other("The collection thread stopped before finding any GPUs"))??;
+    Ok((probe, updates_rx))
+}
+
+/// Samples every GPU each `collector.interval`, rescanning them every `rescan` ticks.
+/// Returns `true` when the watchdog asks for NVML to be restarted, and `false` once
+/// sampling should stop for good.
+fn sample<'nvml>(
+    collector: &mut NvidiaApp,
+    nvml: &'nvml Nvml,
+    mut source: NvmlSource<'nvml>,
+    rescan: Option<u32>,
+    filter: Option<&[u32]>,
+    show_processes: &AtomicBool,
+    updates_tx: &Sender<Update>,
+) -> bool {
+    let interval = collector.interval;
+    let mut watchdog = Watchdog::default();
+    let mut ticks_since_rescan = 0;
+    loop {
+        let sampling_started = Instant::now();
+        if let Some(rescan) = rescan {
+            ticks_since_rescan += 1;
+            if ticks_since_rescan >= rescan {
+                ticks_since_rescan = 0;
+                // The device count is only read anew by a new source, which acquires
+                // every device handle again on first use
+                match NvmlSource::new(nvml) {
+                    Ok(rescanned) => {
+                        source = rescanned;
+                        if let Err(error) = collector.rescan_gpus(&source, filter) {
+                            debug!("Failed to rescan the GPUs: {}", error);
+                        }
+                    }
+                    Err(error) => debug!("Failed to get device count for a rescan: {}", error),
+                }
+            }
+        }
+        if !tick(collector, &source, show_processes, updates_tx) {
+            return false;
+        }
+        if watchdog.check(&collector.gpus) {
+            warn!(
+                "No GPU could be read for {} ticks, restarting NVML",
+                RECONNECT_AFTER
+            );
+            return true;
+        }
+        thread::sleep(interval.saturating_sub(sampling_started.elapsed()));
+    }
+}
+
+/// Like [`spawn`], but samples the synthetic GPUs of a [`DemoSource`] for `--demo`.
+/// Those never fail and never change, so there's nothing to restart or rescan.
+pub fn spawn_demo(
+    interval: Duration,
+    filter: Option<Vec<u32>>,
+    show_processes: Arc<AtomicBool>,
+) -> io::Result<(Probe, Receiver<Update>)> {
+    spawn_source(DemoSource::new(), interval, filter, show_processes)
+}
+
+/// The GPUs listed in sysfs, if NVML can't be initialized but the kernel knows of some,
+/// so that their temperature and clocks can still be monitored.
+pub fn sysfs_fallback() -> Option<SysfsSource> {
+    let error = Nvml::init().err()?;
+    let source = SysfsSource::detect(Path::new("/sys"));
+    if source.device_count().unwrap_or_default() == 0 {
+        return None;
+    }
+    warn!(
+        "Failed to initialize NVML ({}), reading only temperatures and clocks from sysfs",
+        error
+    );
+    Some(source)
+}
+
+/// Like [`spawn`], but samples a source without NVML, such as a [`SysfsSource`]. There's
+/// nothing to restart, and the GPUs are only listed at startup.
+pub fn spawn_source<S: GpuSource + Send + 'static>(
+    source: S,
+    interval: Duration,
+    filter: Option<Vec<u32>>,
+    show_processes: Arc<AtomicBool>,
+) -> io::Result<(Probe, Receiver<Update>)> {
+    let mut collector = NvidiaApp {
+        interval,
+        history_len: 1,
+        ..NvidiaApp::default()
+    };
+    collector.add_gpus(&source, filter.as_deref())?;
+    let probe = Probe {
+        gpus: collector.gpus.clone(),
+        versions: DriverVersions::read(&source),
+    };
+    let (updates_tx, updates_rx) = mpsc::channel();
+    thread::spawn(move || loop {
+        let sampling_started = Instant::now();
+        if !tick(&mut collector, &source, &show_processes, &updates_tx) {
+            return;
+        }
+        thread::sleep(interval.saturating_sub(sampling_started.elapsed()));
+    });
+    Ok((probe, updates_rx))
+}
+
+/// Samples every GPU of `source` once and sends the readings to the TUI. Returns
+/// `false` once sampling should stop for good.
+fn tick(
+    collector: &mut NvidiaApp,
+    source: &dyn GpuSource,
+    show_processes: &AtomicBool,
+    updates_tx: &Sender<Update>,
+) -> bool {
+    let sampling_started = Instant::now();
+    collector.show_processes = show_processes.load(Ordering::Relaxed);
+    if let Err(error) = collector.update_state(source) {
+        debug!("Sampling failed, stopping the collection thread: {}", error);
+        return false;
+    }
+    let collection_time = sampling_started.elapsed();
+    debug!("Sampled all GPUs in {:?}", collection_time);
+    if updates_tx
+        .send(Update::Readings(collector.gpus.clone(), collection_time))
+        .is_err()
+    {
+        debug!("The TUI is gone, stopping the collection thread");
+        return false;
+    }
+    true
+}
+
+/// Initializes NVML again, retrying every `interval` until the driver is back.
+fn reconnect(interval: Duration) -> Nvml {
+    loop {
+        thread::sleep(interval);
+        match Nvml::init() {
+            Ok(nvml) => return nvml,
+            Err(error) => debug!("Failed to restart NVML: {}", error),
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use crate::cli::{AxisRange, Options, AXIS_RANGE_METRICS};
+use crate::metric::{
+    ChartMarker, ClockDomain, Metric, MetricFlags, Precision, Units, MAX_DECIMALS,
+};
+use crate::theme::THEMES;
+use crate::NvidiaApp;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The charts that `charts` and `gpu<index>_charts` can list, by name.
+pub const CHART_NAMES: [(&str, Metric); 11] = [
+    ("clock", Metric::Clock),
+    ("temperature", Metric::Temperature),
+    ("memory", Metric::Memory),
+    ("utilization", Metric::Utilization),
+    ("power", Metric::Power),
+    ("pcie", Metric::Pcie),
+    ("nvlink", Metric::NvLink),
+    ("codec", Metric::Codec),
+    ("engines", Metric::Engines),
+    ("fan", Metric::Fan),
+    ("ecc", Metric::Ecc),
+];
+
+/// The charts that can be drawn on a log scale and listed in `log_charts`.
+pub const LOG_SCALE_CHARTS: MetricFlags = MetricFlags::POWER.union(MetricFlags::PCIE);
+
+/// Settings remembered between runs, stored as a small TOML file.
+///
+/// Every field is optional: missing keys fall back to the built-in defaults, and command
+/// line flags override whatever the file says. Only the flat `key = value` subset of
+/// TOML is understood, which is all this file ever needs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub interval: Option<Duration>,
+    pub history_len: Option<usize>,
+    /// The name of one of the [`THEMES`].
+    pub theme: Option<String>,
+    pub clock_domain: Option<ClockDomain>,
+    pub show_processes: Option<bool>,
+    /// `mega` for MHz and MiB, `giga` for GHz and GiB.
+    pub units: Option<Units>,
+    /// The number of samples averaged by the moving average toggled with `s`.
+    pub smoothing_window: Option<usize>,
+    /// The most frames the TUI draws per second.
+    pub max_fps: Option<u32>,
+    /// `dot`, `braille`, `block` or `bar`.
+    pub marker: Option<ChartMarker>,
+    /// Whether the clock chart draws gridlines.
+    pub gridlines: Option<bool>,
+    /// Whether the charts draw the newest sample on the left.
+    pub newest_left: Option<bool>,
+    pub temp_warn: Option<u32>,
+    pub temp_crit: Option<u32>,
+    /// How many °C below a threshold a GPU has to cool before its alert clears, such as
+    /// `temp_hysteresis = 5` to warn at 80 °C and clear at 75 °C.
+    pub temp_hysteresis: Option<u32>,
+    /// Fixed y-axis bounds, such as `temp_range = "0:100"`.
+    pub axis_ranges: BTreeMap<Metric, AxisRange>,
+    /// The charts laid out for every GPU, such as `charts = "temperature,power"`, or
+    /// `None` for all of them.
+    pub charts: Option<MetricFlags>,
+    /// The charts laid out for single GPUs by hardware index, such as
+    /// `gpu1_charts = "temperature"`, instead of `charts`.
+    pub gpu_charts: BTreeMap<u32, MetricFlags>,
+    /// The charts `l` switches to a log scale, such as `log_charts = "pcie,power"`.
+    pub log_charts: Option<MetricFlags>,
+    /// The charts laid out first for every GPU, in order, such as
+    /// `default_charts = "utilization,memory"`, so they fill the first page of `Tab`.
+    pub default_charts: Option<Vec<Metric>>,
+    /// The metrics that must be readable on every GPU, such as
+    /// `required_metrics = "temperature"`, or the program exits.
+    pub required_metrics: Option<MetricFlags>,
+    /// The decimal places of power readings in W.
+    pub power_decimals: Option<usize>,
+    /// The decimal places of the clock per watt of the efficiency chart.
+    pub efficiency_decimals: Option<usize>,
+    /// The decimal places of the share of memory in use.
+    pub memory_percent_decimals: Option<usize>,
+}
+
+/// Where the config file lives: `$XDG_CONFIG_HOME/nvidia-rs/config.toml`, falling back to
+/// `~/.config` on Unix and `%APPDATA%` on Windows. `None` if none of these are set.
+pub fn default_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_dir.join("nvidia-rs").join("config.toml"))
+}
+
+impl Config {
+    /// Reads the config file at `path`. A missing file is an empty config, since nothing
+    /// has been saved yet on the first run.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                Config::parse(&contents).map_err(|error| format!("{}: {}", path.display(), error))
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(error) => Err(format!("Failed to read {}: {}", path.display(), error)),
+        }
+    }
+
+    /// Writes the config to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_toml())
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut hysteresis_line = 0;
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: &str| format!("line {}: {}", number + 1, message);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected `key = value`"))?;
+            let value = value.trim();
+            let number_value = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| invalid(&format!("{} must be a whole number", key.trim())))
+            };
+            let u32_value = || {
+                u32::try_from(number_value()?)
+                    .map_err(|_| invalid(&format!("{} is too large", key.trim())))
+            };
+            let decimals_value = || match number_value()? as usize {
+                decimals if decimals > MAX_DECIMALS => Err(invalid(&format!(
+                    "at most {} decimal places are supported",
+                    MAX_DECIMALS
+                ))),
+                decimals => Ok(decimals),
+            };
+
+            match key.trim() {
+                "interval_ms" => match number_value()? {
+                    0 => return Err(invalid("interval_ms must be greater than zero")),
+                    millis => config.interval = Some(Duration::from_millis(millis)),
+                },
+                "history_len" => match number_value()? {
+                    0 => return Err(invalid("history_len must be greater than zero")),
+                    len => config.history_len = Some(len as usize),
+                },
+                "theme" => {
+                    let name = parse_string(value).ok_or_else(|| invalid("expected a string"))?;
+                    if !THEMES.iter().any(|theme| theme.name == name) {
+                        return Err(invalid(&format!("unknown theme \"{}\"", name)));
+                    }
+                    config.theme = Some(name);
+                }
+                "clock_domain" => {
+                    let name = parse_string(value).ok_or_else(|| invalid("expected a string"))?;
+                    let domain = ClockDomain::ALL
+                        .into_iter()
+                        .find(|domain| domain.to_string().eq_ignore_ascii_case(&name))
+                        .ok_or_else(|| invalid(&format!("unknown clock domain \"{}\"", name)))?;
+                    config.clock_domain = Some(domain);
+                }
+                "show_processes" => {
+                    config.show_processes = Some(match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => return Err(invalid("expected true or false")),
+                    });
+                }
+                "units" => {
+                    let name = parse_string(value).ok_or_else(|| invalid("expected a string"))?;
+                    let units = Units::ALL
+                        .into_iter()
+                        .find(|units| units.to_string() == name)
+                        .ok_or_else(|| invalid(&format!("unknown units \"{}\"", name)))?;
+                    config.units = Some(units);
+                }
+                "marker" => {
+                    let name = parse_string(value).ok_or_else(|| invalid("expected a string"))?;
+                    let marker = ChartMarker::ALL
+                        .into_iter()
+                        .find(|marker| marker.to_string() == name)
+                        .ok_or_else(|| invalid(&format!("unknown marker \"{}\"", name)))?;
+                    config.marker = Some(marker);
+                }
+                "gridlines" => {
+                    config.gridlines = Some(match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => return Err(invalid("expected true or false")),
+                    });
+                }
+                "newest_left" => {
+                    config.newest_left = Some(match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => return Err(invalid("expected true or false")),
+                    });
+                }
+                "smoothing_window" => match number_value()? {
+                    0 => return Err(invalid("smoothing_window must be greater than zero")),
+                    window => config.smoothing_window = Some(window as usize),
+                },
+                "max_fps" => match u32_value()? {
+                    0 => return Err(invalid("max_fps must be greater than zero")),
+                    max_fps => config.max_fps = Some(max_fps),
+                },
+                "power_decimals" => config.power_decimals = Some(decimals_value()?),
+                "efficiency_decimals" => config.efficiency_decimals = Some(decimals_value()?),
+                "memory_percent_decimals" => {
+                    config.memory_percent_decimals = Some(decimals_value()?)
+                }
+                "temp_warn" => config.temp_warn = Some(u32_value()?),
+                "temp_crit" => config.temp_crit = Some(u32_value()?),
+                "temp_hysteresis" => {
+                    config.temp_hysteresis = Some(u32_value()?);
+                    hysteresis_line = number + 1;
+                }
+                "charts" => {
+                    config.charts = Some(parse_charts(value).map_err(|error| invalid(&error))?)
+                }
+                "log_charts" => {
+                    let charts = parse_charts(value).map_err(|error| invalid(&error))?;
+                    if !LOG_SCALE_CHARTS.contains(charts) {
+                        return Err(invalid("only the power and pcie charts have a log scale"));
+                    }
+                    config.log_charts = Some(charts);
+                }
+                "default_charts" => {
+                    let charts = parse_chart_order(value).map_err(|error| invalid(&error))?;
+                    config.default_charts = Some(charts);
+                }
+                "required_metrics" => {
+                    let metrics = parse_charts(value).map_err(|error| invalid(&error))?;
+                    config.required_metrics = Some(metrics);
+                }
+                key if key.ends_with("_charts") => {
+                    let index = gpu_charts_index(key)
+                        .ok_or_else(|| invalid(&format!("unknown setting `{}`", key)))?;
+                    let charts = parse_charts(value).map_err(|error| invalid(&error))?;
+                    config.gpu_charts.insert(index, charts);
+                }
+                key => {
+                    let (_, metric) = AXIS_RANGE_METRICS
+                        .into_iter()
+                        .find(|(name, _)| key.strip_suffix("_range") == Some(name))
+                        .ok_or_else(|| invalid(&format!("unknown setting `{}`", key)))?;
+                    let range = parse_string(value)
+                        .ok_or_else(|| invalid("expected a string"))?
+                        .parse()
+                        .map_err(|error: String| invalid(&error))?;
+                    config.axis_ranges.insert(metric, range);
+                }
+            }
+        }
+        // A clear point at 0 °C would hold the alert forever
+        let lowest_threshold = config.temp_warn.into_iter().chain(config.temp_crit).min();
+        if let (Some(hysteresis), Some(threshold)) = (config.temp_hysteresis, lowest_threshold) {
+            if hysteresis >= threshold {
+                return Err(format!(
+                    "line {}: temp_hysteresis must be below temp_warn and temp_crit",
+                    hysteresis_line
+                ));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut toml = String::from("# Settings for the nvidia monitor, saved on exit.\n");
+        // Writing to a String can't fail
+        if let Some(interval) = self.interval {
+            let _ = writeln!(toml, "interval_ms = {}", interval.as_millis());
+        }
+        if let Some(history_len) = self.history_len {
+            let _ = writeln!(toml, "history_len = {}", history_len);
+        }
+        if let Some(theme) = &self.theme {
+            let _ = writeln!(toml, "theme = \"{}\"", theme);
+        }
+        if let Some(clock_domain) = self.clock_domain {
+            let _ = writeln!(
+                toml,
+                "clock_domain = \"{}\"",
+                clock_domain.to_string().to_lowercase()
+            );
+        }
+        if let Some(show_processes) = self.show_processes {
+            let _ = writeln!(toml, "show_processes = {}", show_processes);
+        }
+        if let Some(units) = self.units {
+            let _ = writeln!(toml, "units = \"{}\"", units);
+        }
+        if let Some(smoothing_window) = self.smoothing_window {
+            let _ = writeln!(toml, "smoothing_window = {}", smoothing_window);
+        }
+        if let Some(max_fps) = self.max_fps {
+            let _ = writeln!(toml, "max_fps = {}", max_fps);
+        }
+        if let Some(marker) = self.marker {
+            let _ = writeln!(toml, "marker = \"{}\"", marker);
+        }
+        if let Some(gridlines) = self.gridlines {
+            let _ = writeln!(toml, "gridlines = {}", gridlines);
+        }
+        if let Some(newest_left) = self.newest_left {
+            let _ = writeln!(toml, "newest_left = {}", newest_left);
+        }
+        if let Some(power_decimals) = self.power_decimals {
+            let _ = writeln!(toml, "power_decimals = {}", power_decimals);
+        }
+        if let Some(efficiency_decimals) = self.efficiency_decimals {
+            let _ = writeln!(toml, "efficiency_decimals = {}", efficiency_decimals);
+        }
+        if let Some(memory_percent_decimals) = self.memory_percent_decimals {
+            let _ = writeln!(
+                toml,
+                "memory_percent_decimals = {}",
+                memory_percent_decimals
+            );
+        }
+        if let Some(temp_warn) = self.temp_warn {
+            let _ = writeln!(toml, "temp_warn = {}", temp_warn);
+        }
+        if let Some(temp_crit) = self.temp_crit {
+            let _ = writeln!(toml, "temp_crit = {}", temp_crit);
+        }
+        if let Some(temp_hysteresis) = self.temp_hysteresis {
+            let _ = writeln!(toml, "temp_hysteresis = {}", temp_hysteresis);
+        }
+        for (name, metric) in AXIS_RANGE_METRICS {
+            if let Some(range) = self.axis_ranges.get(&metric) {
+                let _ = writeln!(toml, "{}_range = \"{}\"", name, range);
+            }
+        }
+        if let Some(charts) = self.charts {
+            let _ = writeln!(toml, "charts = \"{}\"", chart_list(charts));
+        }
+        for (index, &charts) in &self.gpu_charts {
+            let _ = writeln!(toml, "gpu{}_charts = \"{}\"", index, chart_list(charts));
+        }
+        if let Some(log_charts) = self.log_charts {
+            let _ = writeln!(toml, "log_charts = \"{}\"", chart_list(log_charts));
+        }
+        if let Some(default_charts) = &self.default_charts {
+            let names: Vec<&str> = default_charts
+                .iter()
+                .filter_map(|&metric| {
+                    let (name, _) = CHART_NAMES
+                        .into_iter()
+                        .find(|&(_, chart)| chart == metric)?;
+                    Some(name)
+                })
+                .collect();
+            let _ = writeln!(toml, "default_charts = \"{}\"", names.join(","));
+        }
+        if let Some(required_metrics) = self.required_metrics {
+            let _ = writeln!(
+                toml,
+                "required_metrics = \"{}\"",
+                chart_list(required_metrics)
+            );
+        }
+        toml
+    }
+
+    /// The options to use when no flag says otherwise.
+    pub fn options(&self) -> Options {
+        let defaults = Options::default();
+        Options {
+            interval: self.interval.unwrap_or(defaults.interval),
+            temp_warn: self.temp_warn,
+            temp_crit: self.temp_crit,
+            axis_ranges: self.axis_ranges.clone(),
+            precision: Precision {
+                power: self.power_decimals.unwrap_or(defaults.precision.power),
+                efficiency: self
+                    .efficiency_decimals
+                    .unwrap_or(defaults.precision.efficiency),
+                memory_percent: self
+                    .memory_percent_decimals
+                    .unwrap_or(defaults.precision.memory_percent),
+                raw: false,
+            },
+            ..defaults
+        }
+    }
+
+    /// Applies the settings that only the TUI uses, rather than the command line.
+    pub fn apply(&self, app: &mut NvidiaApp) {
+        if let Some(history_len) = self.history_len {
+            app.history_len = history_len;
+        }
+        if let Some(theme) = &self.theme {
+            app.theme = THEMES
+                .iter()
+                .position(|candidate| candidate.name == *theme)
+                .unwrap_or_default();
+        }
+        if let Some(clock_domain) = self.clock_domain {
+            app.clock_domain = clock_domain;
+        }
+        if let Some(show_processes) = self.show_processes {
+            app.show_processes = show_processes;
+        }
+        if let Some(units) = self.units {
+            app.units = units;
+        }
+        if let Some(smoothing_window) = self.smoothing_window {
+            app.smoothing_window = smoothing_window;
+        }
+        if let Some(max_fps) = self.max_fps {
+            app.max_fps = max_fps;
+        }
+        if let Some(marker) = self.marker {
+            app.marker = marker;
+        }
+        if let Some(gridlines) = self.gridlines {
+            app.gridlines = gridlines;
+        }
+        if let Some(newest_left) = self.newest_left {
+            app.newest_left = newest_left;
+        }
+        if let Some(charts) = self.charts {
+            app.charts = charts;
+        }
+        app.gpu_charts = self.gpu_charts.clone();
+        if let Some(log_charts) = self.log_charts {
+            app.log_charts = log_charts;
+        }
+        if let Some(default_charts) = &self.default_charts {
+            app.default_charts = default_charts.clone();
+        }
+        if let Some(required_metrics) = self.required_metrics {
+            app.required_metrics = required_metrics;
+        }
+        if let Some(temp_hysteresis) = self.temp_hysteresis {
+            app.temp_hysteresis = temp_hysteresis;
+        }
+    }
+
+    /// Remembers the settings the user changed from inside the TUI. Settings given as
+    /// flags are left as they were in the file, so a one-off `--interval` doesn't stick.
+    pub fn remember(&mut self, app: &NvidiaApp) {
+        self.history_len = Some(app.history_len);
+        // Not `app.theme()`, which is the plain theme under `--no-color`
+        self.theme = Some(String::from(THEMES[app.theme].name));
+        self.clock_domain = Some(app.clock_domain);
+        self.show_processes = Some(app.show_processes);
+        self.units = Some(app.units);
+    }
+}
+
+/// The hardware index of a `gpu<index>_charts` key.
+fn gpu_charts_index(key: &str) -> Option<u32> {
+    key.strip_prefix("gpu")?
+        .strip_suffix("_charts")?
+        .parse()
+        .ok()
+}
+
+/// Parses a quoted, comma-separated list of [`CHART_NAMES`].
+fn parse_charts(value: &str) -> Result<MetricFlags, String> {
+    Ok(parse_chart_order(value)?
+        .into_iter()
+        .fold(MetricFlags::empty(), |charts, metric| {
+            charts | MetricFlags::from(metric)
+        }))
+}
+
+/// Parses a comma-separated list of [`CHART_NAMES`] in the order given, leaving out
+/// repeats.
+fn parse_chart_order(value: &str) -> Result<Vec<Metric>, String> {
+    let list = parse_string(value).ok_or_else(|| String::from("expected a string"))?;
+    let mut charts = Vec::new();
+    for name in list
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let (_, metric) = CHART_NAMES
+            .into_iter()
+            .find(|&(candidate, _)| candidate == name)
+            .ok_or_else(|| format!("unknown chart \"{}\"", name))?;
+        if !charts.contains(&metric) {
+            charts.push(metric);
+        }
+    }
+    if charts.is_empty() {
+        return Err(String::from("expected at least one chart"));
+    }
+    Ok(charts)
+}
+
+/// Writes `charts` the way [`parse_charts`] reads them.
+fn chart_list(charts: MetricFlags) -> String {
+    CHART_NAMES
+        .into_iter()
+        .filter(|&(_, metric)| charts.contains(MetricFlags::from(metric)))
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Unquotes a TOML basic string. Escape sequences aren't needed for any setting.
+fn parse_string(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    (!inner.contains('"')).then(|| String::from(inner))
+}
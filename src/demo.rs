@@ -0,0 +1,423 @@
+use crate::nvlink::NvLinkCounters;
+use crate::process::{GpuProcess, ProcessKind};
+use crate::source::{FanPolicy, GpuDevice, GpuSource, MediaEngine};
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{
+        Clock, ComputeMode, EccCounter, EncoderType, MemoryError, PcieUtilCounter,
+        PerformanceState, Sampling, TemperatureSensor, TemperatureThreshold,
+    },
+    error::NvmlError,
+    struct_wrappers::device::{MemoryInfo, Utilization},
+    structs::device::UtilizationInfo,
+};
+use std::f64::consts::TAU;
+use std::time::Instant;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// One card of `--demo`.
+struct DemoModel {
+    name: &'static str,
+    memory_gib: u64,
+    /// The power limit in W.
+    power_limit: u32,
+    /// The base and highest graphics clock in MHz.
+    base_clock: u32,
+    max_clock: u32,
+    /// The width of the memory bus in bits.
+    bus_width: u32,
+    /// Passively cooled datacenter cards have no fans, but ECC memory, a memory
+    /// temperature sensor and NVLink.
+    datacenter: bool,
+}
+
+/// A mix of desktop and datacenter cards, so that every optional panel shows up.
+const DEMO_MODELS: [DemoModel; 4] = [
+    DemoModel {
+        name: "NVIDIA GeForce RTX 4090",
+        memory_gib: 24,
+        power_limit: 450,
+        base_clock: 2235,
+        max_clock: 2520,
+        bus_width: 384,
+        datacenter: false,
+    },
+    DemoModel {
+        name: "NVIDIA GeForce RTX 3060",
+        memory_gib: 12,
+        power_limit: 170,
+        base_clock: 1320,
+        max_clock: 1777,
+        bus_width: 192,
+        datacenter: false,
+    },
+    DemoModel {
+        name: "NVIDIA A100-SXM4-80GB",
+        memory_gib: 80,
+        power_limit: 400,
+        base_clock: 1275,
+        max_clock: 1410,
+        bus_width: 5120,
+        datacenter: true,
+    },
+    DemoModel {
+        name: "NVIDIA H100 80GB HBM3",
+        memory_gib: 80,
+        power_limit: 700,
+        base_clock: 1590,
+        max_clock: 1980,
+        bus_width: 5120,
+        datacenter: true,
+    },
+];
+
+/// The number of NVLinks of each datacenter card, the last of which is down.
+const DEMO_NVLINKS: u32 = 4;
+
+/// Synthetic GPUs for the hidden `--demo` flag, so the TUI can be developed and
+/// screenshotted on machines without an NVIDIA GPU.
+///
+/// Every reading is a sine wave of the time since the source was created, with a
+/// different phase on every GPU. The load drives the utilization, clocks, power and
+/// memory, and the temperature and fans follow it with a lag.
+pub struct DemoSource {
+    devices: Vec<DemoDevice>,
+}
+
+impl DemoSource {
+    pub fn new() -> Self {
+        let started = Instant::now();
+        Self {
+            devices: (0..DEMO_MODELS.len() as u32)
+                .map(|index| DemoDevice { index, started })
+                .collect(),
+        }
+    }
+
+    fn demo_device(&self, index: u32) -> Result<&DemoDevice, NvmlError> {
+        self.devices
+            .get(index as usize)
+            .ok_or(NvmlError::InvalidArg)
+    }
+}
+
+impl GpuSource for DemoSource {
+    fn device_count(&self) -> Result<u32, NvmlError> {
+        Ok(self.devices.len() as u32)
+    }
+
+    fn driver_version(&self) -> Result<String, NvmlError> {
+        Ok(String::from("demo"))
+    }
+
+    fn nvml_version(&self) -> Result<String, NvmlError> {
+        Ok(String::from("demo"))
+    }
+
+    fn cuda_driver_version(&self) -> Result<i32, NvmlError> {
+        Ok(12060)
+    }
+
+    fn device(&self, index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        Ok(self.demo_device(index)?)
+    }
+
+    fn mig_device_count(&self, _index: u32) -> Result<u32, NvmlError> {
+        Ok(0)
+    }
+
+    fn mig_device(&self, _index: u32, _mig_index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        Err(NvmlError::NotFound)
+    }
+
+    fn clock_vf_offsets(&self, _index: u32) -> Result<(i32, i32), NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn clock_vf_offset_ranges(&self, _index: u32) -> Result<[(i32, i32); 2], NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn reserved_memory(&self, _index: u32) -> Result<u64, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn die_power(&self, index: u32) -> Result<u32, NvmlError> {
+        let device = self.demo_device(index)?;
+        if !device.model().datacenter {
+            return Err(NvmlError::NotSupported);
+        }
+        // The memory and the rest of the board take about a fifth
+        Ok(device.power_usage()? / 5 * 4)
+    }
+
+    fn nvlink_counters(&self, index: u32, link: u32) -> Result<NvLinkCounters, NvmlError> {
+        let device = self.demo_device(index)?;
+        if !device.model().datacenter || link >= DEMO_NVLINKS {
+            return Err(NvmlError::NotSupported);
+        }
+        if link == DEMO_NVLINKS - 1 {
+            return Ok(NvLinkCounters::default());
+        }
+        // The counters are cumulative, so a steady rate keeps them increasing
+        let seconds = device.started.elapsed().as_secs_f64();
+        let kib = (seconds * (10e9 + f64::from(link) * 5e9) / 1024.0) as u64;
+        Ok(NvLinkCounters {
+            active: true,
+            rx: kib,
+            tx: kib / 2,
+        })
+    }
+
+    fn engine_utilization(&self, _index: u32, _engine: MediaEngine) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn fan_policy(&self, index: u32, fan: u32) -> Result<FanPolicy, NvmlError> {
+        if fan >= self.demo_device(index)?.num_fans()? {
+            return Err(NvmlError::InvalidArg);
+        }
+        Ok(FanPolicy::Auto)
+    }
+}
+
+/// One synthetic GPU of a [`DemoSource`].
+struct DemoDevice {
+    index: u32,
+    started: Instant,
+}
+
+impl DemoDevice {
+    fn model(&self) -> &'static DemoModel {
+        &DEMO_MODELS[self.index as usize]
+    }
+
+    /// A wave between 0 and 1 that repeats every `period` seconds, `lag` seconds behind
+    /// the other waves of this GPU.
+    fn wave(&self, period: f64, lag: f64) -> f64 {
+        let seconds = self.started.elapsed().as_secs_f64() - lag;
+        let phase = f64::from(self.index) * 1.7;
+        0.5 + 0.5 * (seconds * TAU / period + phase).sin()
+    }
+
+    /// How busy the GPU is, from 0 to 1.
+    fn load(&self) -> f64 {
+        self.wave(40.0, 0.0)
+    }
+
+    fn temperature_celsius(&self) -> u32 {
+        35 + (self.wave(40.0, 6.0) * 50.0) as u32
+    }
+
+    fn memory_used(&self) -> u64 {
+        let total = self.model().memory_gib * GIB;
+        GIB + (self.wave(90.0, 0.0) * 0.8 * total as f64) as u64
+    }
+}
+
+impl GpuDevice for DemoDevice {
+    fn name(&self) -> Result<String, NvmlError> {
+        Ok(String::from(self.model().name))
+    }
+
+    fn uuid(&self) -> Result<String, NvmlError> {
+        Ok(format!("GPU-de300000-0000-4000-8000-{:012x}", self.index))
+    }
+
+    fn serial(&self) -> Result<String, NvmlError> {
+        if !self.model().datacenter {
+            return Err(NvmlError::NotSupported);
+        }
+        Ok(format!("DEMO{:09}", self.index))
+    }
+
+    fn clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        let graphics = 210 + (self.load() * f64::from(self.model().max_clock - 210)) as u32;
+        Ok(match clock {
+            Clock::Graphics | Clock::SM => graphics,
+            Clock::Memory => 405 + (self.load() * 10_000.0) as u32,
+            Clock::Video => graphics * 4 / 5,
+        })
+    }
+
+    fn max_clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        let max_clock = self.model().max_clock;
+        Ok(match clock {
+            Clock::Graphics | Clock::SM => max_clock,
+            Clock::Memory => 10_501,
+            Clock::Video => max_clock * 4 / 5,
+        })
+    }
+
+    fn memory_bus_width(&self) -> Result<u32, NvmlError> {
+        Ok(self.model().bus_width)
+    }
+
+    fn applications_clock(&self, _clock: Clock) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn default_applications_clock(&self, clock: Clock) -> Result<u32, NvmlError> {
+        let base_clock = self.model().base_clock;
+        Ok(match clock {
+            Clock::Graphics | Clock::SM => base_clock,
+            Clock::Memory => 10_501,
+            Clock::Video => base_clock * 4 / 5,
+        })
+    }
+
+    fn temperature(&self, _sensor: TemperatureSensor) -> Result<u32, NvmlError> {
+        Ok(self.temperature_celsius())
+    }
+
+    fn memory_temperature(&self) -> Result<u32, NvmlError> {
+        if !self.model().datacenter {
+            return Err(NvmlError::NotSupported);
+        }
+        Ok(self.temperature_celsius() + 8)
+    }
+
+    fn temperature_threshold(&self, threshold: TemperatureThreshold) -> Result<u32, NvmlError> {
+        match threshold {
+            TemperatureThreshold::Slowdown => Ok(90),
+            TemperatureThreshold::Shutdown => Ok(98),
+            _ => Err(NvmlError::NotSupported),
+        }
+    }
+
+    fn memory_info(&self) -> Result<MemoryInfo, NvmlError> {
+        let total = self.model().memory_gib * GIB;
+        let used = self.memory_used();
+        Ok(MemoryInfo {
+            free: total.saturating_sub(used),
+            total,
+            used,
+        })
+    }
+
+    fn utilization_rates(&self) -> Result<Utilization, NvmlError> {
+        Ok(Utilization {
+            gpu: (self.load() * 100.0) as u32,
+            memory: (self.load() * 70.0) as u32,
+        })
+    }
+
+    fn power_usage(&self) -> Result<u32, NvmlError> {
+        let limit = f64::from(self.model().power_limit) * 1000.0;
+        Ok((limit * (0.1 + 0.9 * self.load())) as u32)
+    }
+
+    fn enforced_power_limit(&self) -> Result<u32, NvmlError> {
+        Ok(self.model().power_limit * 1000)
+    }
+
+    fn power_limit_constraints(&self) -> Result<(u32, u32), NvmlError> {
+        let limit = self.model().power_limit * 1000;
+        Ok((limit / 3, limit * 4 / 3))
+    }
+
+    fn performance_state(&self) -> Result<PerformanceState, NvmlError> {
+        Ok(if self.load() < 0.1 {
+            PerformanceState::Eight
+        } else {
+            PerformanceState::Zero
+        })
+    }
+
+    fn auto_boost_enabled(&self) -> Result<bool, NvmlError> {
+        if !self.model().datacenter {
+            return Err(NvmlError::NotSupported);
+        }
+        Ok(true)
+    }
+
+    fn persistence_mode_enabled(&self) -> Result<bool, NvmlError> {
+        // Datacenter cards are usually run with it, desktop ones rarely
+        Ok(self.model().datacenter)
+    }
+
+    fn compute_mode(&self) -> Result<ComputeMode, NvmlError> {
+        // As cluster schedulers commonly set them up, one job per GPU
+        Ok(if self.model().datacenter {
+            ComputeMode::ExclusiveProcess
+        } else {
+            ComputeMode::Default
+        })
+    }
+
+    fn current_throttle_reasons(&self) -> Result<ThrottleReasons, NvmlError> {
+        let mut reasons = ThrottleReasons::empty();
+        if self.load() > 0.95 {
+            reasons |= ThrottleReasons::SW_POWER_CAP;
+        }
+        if self.temperature_celsius() >= 83 {
+            reasons |= ThrottleReasons::SW_THERMAL_SLOWDOWN;
+        }
+        if self.load() < 0.1 {
+            reasons |= ThrottleReasons::GPU_IDLE;
+        }
+        Ok(reasons)
+    }
+
+    fn pcie_throughput(&self, counter: PcieUtilCounter) -> Result<u32, NvmlError> {
+        let kib_per_second = self.wave(15.0, 0.0) * 8_000_000.0;
+        Ok(match counter {
+            PcieUtilCounter::Receive => kib_per_second as u32,
+            PcieUtilCounter::Send => (kib_per_second / 4.0) as u32,
+        })
+    }
+
+    fn encoder_capacity(&self, _for_type: EncoderType) -> Result<u32, NvmlError> {
+        Ok(100)
+    }
+
+    fn encoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Ok(UtilizationInfo {
+            utilization: (self.wave(25.0, 0.0) * 40.0) as u32,
+            sampling_period: 100_000,
+        })
+    }
+
+    fn decoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Ok(UtilizationInfo {
+            utilization: (self.wave(25.0, 12.5) * 30.0) as u32,
+            sampling_period: 100_000,
+        })
+    }
+
+    fn num_fans(&self) -> Result<u32, NvmlError> {
+        Ok(if self.model().datacenter { 0 } else { 2 })
+    }
+
+    fn fan_speed(&self, fan: u32) -> Result<u32, NvmlError> {
+        if fan >= self.num_fans()? {
+            return Err(NvmlError::InvalidArg);
+        }
+        Ok(30 + (self.wave(40.0, 8.0) * 60.0) as u32 + fan * 3)
+    }
+
+    fn total_ecc_errors(
+        &self,
+        _error_type: MemoryError,
+        _counter_type: EccCounter,
+    ) -> Result<u64, NvmlError> {
+        if !self.model().datacenter {
+            return Err(NvmlError::NotSupported);
+        }
+        Ok(0)
+    }
+
+    fn running_processes(&self) -> Result<Vec<GpuProcess>, NvmlError> {
+        Ok(vec![GpuProcess {
+            pid: 4200 + self.index,
+            name: String::from("demo-workload"),
+            kind: ProcessKind::Compute,
+            used_memory: Some(self.memory_used().saturating_sub(GIB / 2)),
+        }])
+    }
+
+    fn samples(&self, _sampling: Sampling, _since: u64) -> Result<Vec<(u64, u32)>, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::metric::Metric;
+use crate::source::GpuDevice;
+use crate::theme::Theme;
+use crate::{metric_style, metric_title, GPUInfo};
+use nvml_wrapper::{
+    enum_wrappers::device::{EccCounter, MemoryError},
+    error::NvmlError,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Cell, Row, Table, Widget},
+};
+
+/// The ECC error counts of one GPU's memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EccErrors {
+    /// Counted since the driver was last loaded.
+    pub volatile_corrected: u64,
+    pub volatile_uncorrected: u64,
+    /// Counted over the lifetime of the GPU.
+    pub aggregate_corrected: u64,
+    pub aggregate_uncorrected: u64,
+}
+
+impl EccErrors {
+    pub fn read(gpu_device: &dyn GpuDevice) -> Result<Self, NvmlError> {
+        Ok(Self {
+            volatile_corrected: gpu_device
+                .total_ecc_errors(MemoryError::Corrected, EccCounter::Volatile)?,
+            volatile_uncorrected: gpu_device
+                .total_ecc_errors(MemoryError::Uncorrected, EccCounter::Volatile)?,
+            aggregate_corrected: gpu_device
+                .total_ecc_errors(MemoryError::Corrected, EccCounter::Aggregate)?,
+            aggregate_uncorrected: gpu_device
+                .total_ecc_errors(MemoryError::Uncorrected, EccCounter::Aggregate)?,
+        })
+    }
+}
+
+/// Renders the corrected and uncorrected ECC error counts of a single GPU as a table.
+///
+/// Uncorrected errors are an early sign of failing memory, so any nonzero count is
+/// highlighted in the critical color.
+pub fn render_ecc_panel(gpu_info: &GPUInfo, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let ecc = &gpu_info.ecc_errors;
+    let title = metric_title(
+        "ECC Errors",
+        gpu_info,
+        Metric::Ecc,
+        format!("{} uncorrected", ecc.aggregate_uncorrected),
+        area.width,
+    );
+    let block = Block::bordered()
+        .border_style(metric_style(theme, gpu_info, Metric::Ecc, theme.border))
+        .title(title.alignment(ratatui::layout::Alignment::Center));
+
+    let uncorrected_cell = |count: u64| {
+        let cell = Cell::from(count.to_string());
+        if count > 0 {
+            cell.style(
+                Style::new()
+                    .fg(theme.temp_crit_color)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            cell
+        }
+    };
+    let rows = vec![
+        Row::new(vec![
+            Cell::from("Volatile"),
+            Cell::from(ecc.volatile_corrected.to_string()),
+            uncorrected_cell(ecc.volatile_uncorrected),
+        ]),
+        Row::new(vec![
+            Cell::from("Aggregate"),
+            Cell::from(ecc.aggregate_corrected.to_string()),
+            uncorrected_cell(ecc.aggregate_uncorrected),
+        ]),
+    ];
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(vec!["", "Corrected", "Uncorrected"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .block(block)
+    .style(metric_style(theme, gpu_info, Metric::Ecc, theme.text));
+    table.render(area, buf);
+}
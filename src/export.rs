@@ -0,0 +1,487 @@
+use crate::cli::Options;
+use crate::metric::{ClockDomain, Metric};
+use crate::prometheus::{self, SharedGpus};
+use crate::snapshot::GpuSnapshot;
+#[cfg(unix)]
+use crate::socket::{self, SocketFile};
+use crate::GPUInfo;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CSV_HEADER: &str = "timestamp,gpu,name,clock_mhz,temperature_c,memory_used_bytes,memory_total_bytes,sm_utilization_pct,memory_utilization_pct,power_mw,fan_speeds_pct,uuid,serial";
+
+/// The columns of a `--record` file, which [`crate::replay::ReplaySource`] reads back.
+pub const RECORD_HEADER: &str = "timestamp,gpu,name,uuid,clock_mhz,memory_clock_mhz,max_clock_mhz,temperature_c,memory_used_bytes,memory_total_bytes,sm_utilization_pct,memory_utilization_pct,power_mw,power_limit_mw,fan_speeds_pct";
+
+/// Every export sink given on the command line, fed the GPUs after every reading by
+/// both the TUI and `--daemon`.
+pub struct Exporters {
+    csv_logger: Option<CsvLogger>,
+    influx_logger: Option<InfluxLogger>,
+    recorder: Option<Recorder>,
+    text_logger: Option<TextLogger>,
+    /// The GPUs the Prometheus server and the socket read, if either is running.
+    shared_gpus: Option<SharedGpus>,
+    /// Removes the socket file once dropped.
+    #[cfg(unix)]
+    _socket_file: Option<SocketFile>,
+}
+
+impl Exporters {
+    /// Creates the files and starts the servers of `options`.
+    pub fn start(options: &Options) -> io::Result<Self> {
+        let context = |error: io::Error, target: String| {
+            io::Error::new(error.kind(), format!("{}: {}", target, error))
+        };
+        let csv_logger =
+            match &options.log_csv {
+                Some(path) => Some(CsvLogger::create(path, options.rotate_size).map_err(
+                    |error| context(error, format!("Failed to create {}", path.display())),
+                )?),
+                None => None,
+            };
+        let influx_logger =
+            match &options.influx_file {
+                Some(path) => Some(InfluxLogger::create(path, options.rotate_size).map_err(
+                    |error| context(error, format!("Failed to create {}", path.display())),
+                )?),
+                None => None,
+            };
+
+        let recorder =
+            match &options.record {
+                Some(path) => Some(Recorder::create(path).map_err(|error| {
+                    context(error, format!("Failed to create {}", path.display()))
+                })?),
+                None => None,
+            };
+        let text_logger = options.stream.then(|| TextLogger::new(io::stdout()));
+
+        // The servers all read the same copy of the GPUs
+        let shared_gpus: Option<SharedGpus> = (options.prometheus.is_some()
+            || options.socket.is_some())
+        .then(|| Arc::new(Mutex::new(Vec::new())));
+        if let (Some(port), Some(shared_gpus)) = (options.prometheus, &shared_gpus) {
+            prometheus::spawn(port, Arc::clone(shared_gpus))
+                .map_err(|error| context(error, format!("Failed to listen on port {}", port)))?;
+        }
+        #[cfg(unix)]
+        let _socket_file = match (&options.socket, &shared_gpus) {
+            (Some(path), Some(shared_gpus)) => Some(
+                socket::spawn(path, Arc::clone(shared_gpus)).map_err(|error| {
+                    context(error, format!("Failed to listen on {}", path.display()))
+                })?,
+            ),
+            _ => None,
+        };
+        Ok(Self {
+            csv_logger,
+            influx_logger,
+            recorder,
+            text_logger,
+            shared_gpus,
+            #[cfg(unix)]
+            _socket_file,
+        })
+    }
+
+    /// Writes the latest reading of `gpus` to every sink.
+    pub fn export(&mut self, gpus: &[GPUInfo]) -> io::Result<()> {
+        if let Some(csv_logger) = self.csv_logger.as_mut() {
+            csv_logger.log(gpus)?;
+        }
+        if let Some(influx_logger) = self.influx_logger.as_mut() {
+            influx_logger.log(gpus)?;
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(gpus)?;
+        }
+        if let Some(text_logger) = self.text_logger.as_mut() {
+            text_logger.log(gpus)?;
+        }
+        if let Some(shared_gpus) = &self.shared_gpus {
+            *shared_gpus.lock().unwrap() = gpus.to_vec();
+        }
+        Ok(())
+    }
+}
+
+/// Appends one CSV row per GPU on every tick, for plotting the telemetry offline.
+///
+/// Unsupported metrics and metrics whose query failed on a tick are written as empty
+/// fields, like a UUID or serial number the GPU doesn't report. Multiple fans are joined
+/// with `;` in their column. The UUID and serial number come last, so the columns
+/// before them stay where they were in older files.
+pub struct CsvLogger<W: Write = RotatingFile> {
+    writer: W,
+}
+
+impl CsvLogger {
+    /// Creates (or truncates) the file at `path` and writes the header row, which every
+    /// file rotated into past `rotate_size` bytes starts with too.
+    pub fn create(path: &Path, rotate_size: Option<u64>) -> io::Result<Self> {
+        let mut file = RotatingFile::create(path, rotate_size)?;
+        file.header = Some(format!("{}\n", CSV_HEADER));
+        CsvLogger::new(file)
+    }
+}
+
+impl<W: Write> CsvLogger<W> {
+    /// Writes the header row to `writer`.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+        writer.flush()?;
+        Ok(Self { writer })
+    }
+
+    /// Writes the latest sample of every GPU and flushes, so a crash loses at most the
+    /// current tick.
+    pub fn log(&mut self, gpus: &[GPUInfo]) -> io::Result<()> {
+        let timestamp = unix_timestamp();
+        for gpu_info in gpus {
+            let fan_speeds: Vec<String> = gpu_info
+                .fan_speeds
+                .iter()
+                .map(|fan_speed| fan_speed.latest().to_string())
+                .collect();
+            writeln!(
+                self.writer,
+                "{:.3},{},\"{}\",{},{},{},{},{},{},{},{},{},{}",
+                timestamp,
+                gpu_info.id(),
+                gpu_info.device_name.replace('"', "\"\""),
+                field(gpu_info, Metric::Clock, gpu_info.core_clock.latest()),
+                field(gpu_info, Metric::Temperature, gpu_info.temperature.latest()),
+                field(gpu_info, Metric::Memory, gpu_info.memory_used.latest()),
+                gpu_info.memory_total,
+                field(
+                    gpu_info,
+                    Metric::Utilization,
+                    gpu_info.gpu_utilization.latest()
+                ),
+                field(
+                    gpu_info,
+                    Metric::Utilization,
+                    gpu_info.memory_utilization.latest()
+                ),
+                field(gpu_info, Metric::Power, gpu_info.power_usage.latest()),
+                field(gpu_info, Metric::Fan, fan_speeds.join(";")),
+                gpu_info.uuid.as_deref().unwrap_or_default(),
+                gpu_info.serial.as_deref().unwrap_or_default(),
+            )?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Records every tick of every GPU for `--replay`, with `--record`: rows like those of
+/// [`CsvLogger`], with the memory clock, the maximum clock and the power limit the
+/// charts are scaled against instead of the serial number. The file isn't rotated, since
+/// a replay needs all of it.
+pub struct Recorder<W: Write = BufWriter<File>> {
+    writer: W,
+}
+
+impl Recorder {
+    /// Creates (or truncates) the file at `path` and writes the header row.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Recorder::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    /// Writes the header row to `writer`.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writeln!(writer, "{}", RECORD_HEADER)?;
+        writer.flush()?;
+        Ok(Self { writer })
+    }
+
+    /// Writes the latest sample of every GPU and flushes, like [`CsvLogger::log`].
+    pub fn record(&mut self, gpus: &[GPUInfo]) -> io::Result<()> {
+        let timestamp = unix_timestamp();
+        for gpu_info in gpus {
+            let fan_speeds: Vec<String> = gpu_info
+                .fan_speeds
+                .iter()
+                .map(|fan_speed| fan_speed.latest().to_string())
+                .collect();
+            writeln!(
+                self.writer,
+                "{:.3},{},\"{}\",{},{},{},{},{},{},{},{},{},{},{},{}",
+                timestamp,
+                gpu_info.id(),
+                gpu_info.device_name.replace('"', "\"\""),
+                gpu_info.uuid.as_deref().unwrap_or_default(),
+                field(gpu_info, Metric::Clock, gpu_info.core_clock.latest()),
+                field(gpu_info, Metric::Clock, gpu_info.memory_clock.latest()),
+                gpu_info
+                    .max_clock(ClockDomain::Graphics)
+                    .map(|max_clock| max_clock.to_string())
+                    .unwrap_or_default(),
+                field(gpu_info, Metric::Temperature, gpu_info.temperature.latest()),
+                field(gpu_info, Metric::Memory, gpu_info.memory_used.latest()),
+                gpu_info.memory_total,
+                field(
+                    gpu_info,
+                    Metric::Utilization,
+                    gpu_info.gpu_utilization.latest()
+                ),
+                field(
+                    gpu_info,
+                    Metric::Utilization,
+                    gpu_info.memory_utilization.latest()
+                ),
+                field(gpu_info, Metric::Power, gpu_info.power_usage.latest()),
+                field(gpu_info, Metric::Power, gpu_info.power_limit),
+                field(gpu_info, Metric::Fan, fan_speeds.join(";")),
+            )?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Appends one InfluxDB line protocol point per GPU on every tick, for feeding existing
+/// InfluxDB or Telegraf pipelines, e.g. `gpu,index=0,name=RTX\ 4090,uuid=GPU-… temp=65 <ns>`.
+///
+/// Unsupported metrics and metrics whose query failed on a tick are left out of the
+/// point, and a GPU without any readable metric is skipped for that tick.
+pub struct InfluxLogger<W: Write = RotatingFile> {
+    writer: W,
+}
+
+impl InfluxLogger {
+    /// Creates (or truncates) the file at `path`, rotated past `rotate_size` bytes.
+    pub fn create(path: &Path, rotate_size: Option<u64>) -> io::Result<Self> {
+        Ok(InfluxLogger::new(RotatingFile::create(path, rotate_size)?))
+    }
+}
+
+impl<W: Write> InfluxLogger<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes the latest sample of every GPU and flushes, like [`CsvLogger::log`].
+    pub fn log(&mut self, gpus: &[GPUInfo]) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        for gpu_info in gpus {
+            let snapshot = GpuSnapshot::new(gpu_info);
+            let mut fields = Vec::new();
+            let mut field = |name: &str, value: Option<f64>| {
+                if let Some(value) = value {
+                    fields.push(format!("{}={}", name, value));
+                }
+            };
+            field("temp", snapshot.temperature_c.map(f64::from));
+            field("clock", snapshot.clock_mhz.map(f64::from));
+            field(
+                "memory_used",
+                snapshot.memory_used_bytes.map(|used| used as f64),
+            );
+            field(
+                "memory_total",
+                snapshot
+                    .memory_used_bytes
+                    .map(|_| snapshot.memory_total_bytes as f64),
+            );
+            field("sm_utilization", snapshot.sm_utilization_pct.map(f64::from));
+            field(
+                "memory_utilization",
+                snapshot.memory_utilization_pct.map(f64::from),
+            );
+            field(
+                "power",
+                snapshot
+                    .power_mw
+                    .map(|power| gpu_info.precision.export_watts(power)),
+            );
+            for (fan, speed) in snapshot.fan_speeds_pct.iter().flatten().enumerate() {
+                field(&format!("fan{}", fan), Some(f64::from(*speed)));
+            }
+            if fields.is_empty() {
+                continue;
+            }
+            // Tags can't be empty, so an unknown UUID is left out
+            let uuid = gpu_info
+                .uuid
+                .as_deref()
+                .map(|uuid| format!(",uuid={}", escape_tag(uuid)))
+                .unwrap_or_default();
+            writeln!(
+                self.writer,
+                "gpu,index={},name={}{} {} {}",
+                escape_tag(&gpu_info.id()),
+                escape_tag(&gpu_info.device_name),
+                uuid,
+                fields.join(","),
+                timestamp
+            )?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Writes one line of `key=value` pairs per GPU on every tick, for reading the metrics
+/// through a pipe rather than a terminal, e.g.
+/// `1760000000.125 gpu=0 name="RTX 4090" temp_c=65 clock_mhz=2520 util_pct=98 …`.
+///
+/// Metrics that can't be read on a tick are left out of the line, so `grep`s and
+/// `awk`s on a field never see a placeholder for a number.
+pub struct TextLogger<W: Write = io::Stdout> {
+    writer: W,
+}
+
+impl<W: Write> TextLogger<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes the latest sample of every GPU and flushes, so each line reaches the
+    /// program on the other end of the pipe right away.
+    pub fn log(&mut self, gpus: &[GPUInfo]) -> io::Result<()> {
+        let timestamp = unix_timestamp();
+        for gpu_info in gpus {
+            let snapshot = GpuSnapshot::new(gpu_info);
+            let mut line = format!(
+                "{:.3} gpu={} name=\"{}\"",
+                timestamp,
+                gpu_info.id(),
+                gpu_info.device_name.replace('"', "'")
+            );
+            let mut field = |name: &str, value: Option<String>| {
+                if let Some(value) = value {
+                    line.push_str(&format!(" {}={}", name, value));
+                }
+            };
+            field(
+                "temp_c",
+                snapshot.temperature_c.map(|temp| temp.to_string()),
+            );
+            field(
+                "clock_mhz",
+                snapshot.clock_mhz.map(|clock| clock.to_string()),
+            );
+            field(
+                "util_pct",
+                snapshot.sm_utilization_pct.map(|util| util.to_string()),
+            );
+            field(
+                "mem_used_mib",
+                snapshot
+                    .memory_used_bytes
+                    .map(|used| (used / 1024 / 1024).to_string()),
+            );
+            field(
+                "mem_total_mib",
+                snapshot
+                    .memory_used_bytes
+                    .map(|_| (snapshot.memory_total_bytes / 1024 / 1024).to_string()),
+            );
+            field(
+                "power_w",
+                snapshot
+                    .power_mw
+                    .map(|power| gpu_info.precision.watts_number(f64::from(power))),
+            );
+            writeln!(self.writer, "{}", line)?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// A buffered export file that, once it has grown past its size limit, is renamed with
+/// a `.1` suffix, replacing the previous one, and started over. The loggers flush after
+/// every tick, so the files are only rotated between ticks and never split a row, and a
+/// run of weeks takes up at most twice the limit.
+pub struct RotatingFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    /// How many bytes the current file holds.
+    size: u64,
+    rotate_size: Option<u64>,
+    /// Written at the start of every file after the first, like the CSV header row.
+    header: Option<String>,
+}
+
+impl RotatingFile {
+    /// Creates (or truncates) the file at `path`.
+    pub fn create(path: &Path, rotate_size: Option<u64>) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_owned(),
+            writer: BufWriter::new(File::create(path)?),
+            size: 0,
+            rotate_size,
+            header: None,
+        })
+    }
+
+    /// Where the file is moved to when it's rotated.
+    fn rotated_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".1");
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        fs::rename(&self.path, self.rotated_path())?;
+        self.writer = BufWriter::new(File::create(&self.path)?);
+        self.size = 0;
+        if let Some(header) = self.header.clone() {
+            self.write_all(header.as_bytes())?;
+        }
+        self.writer.flush()
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        match self.rotate_size {
+            Some(rotate_size) if self.size >= rotate_size => self.rotate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Escapes the characters that delimit tags in line protocol.
+fn escape_tag(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | '=' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Formats `value`, or an empty field if `metric` isn't supported or failed to be read
+/// on this tick.
+fn field<T: ToString>(gpu_info: &GPUInfo, metric: Metric, value: T) -> String {
+    if gpu_info.available(metric) {
+        value.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Seconds since the Unix epoch, with millisecond precision.
+fn unix_timestamp() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs_f64())
+        .unwrap_or_default()
+}
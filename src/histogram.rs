@@ -0,0 +1,110 @@
+use crate::history::History;
+use crate::metric::{ClockDomain, Metric, Units};
+use crate::theme::Theme;
+use crate::{metric_style, metric_title, GPUInfo};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Widget},
+};
+
+/// The number of bars of the clock histogram, unless the chart is too narrow for them.
+const CLOCK_BINS: usize = 10;
+
+/// How many samples of `clock` fall into each of `bins` equal ranges from 0 MHz up to
+/// `max_clock`, with anything above it counted in the last one. Unfilled slots of the
+/// history are left out, since they were never sampled.
+pub fn clock_histogram(clock: &History<u32>, max_clock: u32, bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins.max(1)];
+    let width = max_clock.div_ceil(counts.len() as u32).max(1);
+    for sample in clock.recent(clock.len()) {
+        if sample == 0 {
+            continue;
+        }
+        let bin = ((sample / width) as usize).min(counts.len() - 1);
+        counts[bin] += 1;
+    }
+    counts
+}
+
+/// Renders how the samples of one clock domain of a single GPU spread over its clock
+/// range, as the share of the history window spent in each bin, to tell whether the
+/// card mostly sits at boost, base or idle clocks.
+pub fn render_clock_histogram(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    domain: ClockDomain,
+    units: Units,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let clock = gpu_info.clock(domain);
+    let max_clock = gpu_info
+        .max_clock(domain)
+        .unwrap_or_default()
+        .max(clock.max())
+        .max(1);
+    // Room for a four-digit label and a gap per bar, inside the borders
+    let bins = CLOCK_BINS.min(usize::from(area.width.saturating_sub(2) / 5).max(1));
+    let counts = clock_histogram(clock, max_clock, bins);
+    let total: usize = counts.iter().sum();
+    let width = max_clock.div_ceil(bins as u32).max(1);
+
+    let busiest = counts
+        .iter()
+        .enumerate()
+        .max_by_key(|&(bin, &count)| (count, bin))
+        .filter(|&(_, &count)| count > 0)
+        .map(|(bin, _)| bin as u32);
+    let value = match busiest {
+        Some(bin) => format!(
+            "mostly {}–{}",
+            units.clock_number((bin * width) as f64),
+            units.clock(((bin + 1) * width) as f64)
+        ),
+        None => String::from("no samples yet"),
+    };
+    let title = metric_title(
+        &format!("{} Clock Distribution", domain),
+        gpu_info,
+        Metric::Clock,
+        value,
+        area.width,
+    );
+    let block = Block::bordered()
+        .border_style(metric_style(theme, gpu_info, Metric::Clock, theme.border))
+        .title(title.alignment(ratatui::layout::Alignment::Center));
+
+    let bars: Vec<Bar> = counts
+        .iter()
+        .enumerate()
+        .map(|(bin, &count)| {
+            let percent = (count * 100).checked_div(total).unwrap_or_default();
+            Bar::default()
+                .value(percent as u64)
+                .text_value(format!("{}%", percent))
+                .label(Line::from(units.clock_number((bin as u32 * width) as f64)))
+        })
+        .collect();
+    let bar_width = (area.width.saturating_sub(2) / bins as u16)
+        .saturating_sub(1)
+        .max(4);
+    BarChart::default()
+        .block(block)
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .max(100)
+        .bar_style(Style::new().fg(theme.clock_color))
+        .value_style(Style::new().fg(theme.background).bg(theme.clock_color))
+        .label_style(Style::new().fg(theme.text))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Clock,
+            theme.clock_color,
+        ))
+        .render(area, buf);
+}
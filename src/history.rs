@@ -0,0 +1,303 @@
+use std::collections::VecDeque;
+
+/// Rolling buffer lengths that can be cycled through at runtime, in samples.
+pub const HISTORY_LENGTHS: [usize; 5] = [30, 60, 120, 300, 600];
+
+/// The number of samples kept per metric when nothing else is configured.
+pub const DEFAULT_HISTORY_LEN: usize = HISTORY_LENGTHS[0];
+
+/// The number of samples averaged by the moving average of smoothed charts, unless
+/// configured otherwise.
+pub const DEFAULT_SMOOTHING_WINDOW: usize = 5;
+
+/// The spread of the samples in a [`History`] window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+/// A fixed-length rolling buffer of metric samples, ordered oldest to newest.
+///
+/// Pushing a new sample evicts the oldest one, so the buffer always holds exactly
+/// `len()` samples. Unfilled slots are zero, matching the behavior of the original
+/// fixed-size arrays, but are left out of the chart points and stats. So are the gaps
+/// pushed for failed readings, which also read as zero, unlike a reading of zero.
+#[derive(Debug, Clone, Default)]
+pub struct History<T> {
+    samples: VecDeque<T>,
+    /// Whether each slot is a gap rather than a sample.
+    gaps: VecDeque<bool>,
+    /// How many of the newest slots hold pushed samples rather than padding.
+    filled: usize,
+    /// The largest sample ever pushed, kept after it scrolls out of the window.
+    peak: T,
+    /// The smallest sample ever pushed, or `None` before the first one.
+    trough: Option<T>,
+    /// How many samples of the history this is a [`History::window`] of are newer than
+    /// its newest one.
+    offset: usize,
+    /// Whether the x offsets of the points are positive, for charts that draw the newest
+    /// sample on the left.
+    mirrored: bool,
+}
+
+impl<T: Copy + Default + PartialOrd> History<T> {
+    pub fn new(len: usize) -> Self {
+        Self {
+            samples: std::iter::repeat_n(T::default(), len).collect(),
+            gaps: std::iter::repeat_n(false, len).collect(),
+            filled: 0,
+            peak: T::default(),
+            trough: None,
+            offset: 0,
+            mirrored: false,
+        }
+    }
+
+    /// Appends `value` as the newest sample and drops the oldest one.
+    pub fn push(&mut self, value: T) {
+        if value > self.peak {
+            self.peak = value;
+        }
+        if self.trough.is_none_or(|trough| value < trough) {
+            self.trough = Some(value);
+        }
+        self.push_slot(value, false);
+    }
+
+    /// Appends a gap for a reading that failed, which reads as zero but isn't charted.
+    pub fn push_gap(&mut self) {
+        self.push_slot(T::default(), true);
+    }
+
+    /// Appends `value`, or a gap for a reading that failed.
+    pub fn push_or_gap(&mut self, value: Option<T>) {
+        match value {
+            Some(value) => self.push(value),
+            None => self.push_gap(),
+        }
+    }
+
+    /// Appends the newest sample of `other`, or a gap if that's what it has.
+    pub fn push_latest(&mut self, other: &History<T>) {
+        match other.gaps.back() {
+            Some(true) => self.push_gap(),
+            _ => self.push(other.latest()),
+        }
+    }
+
+    fn push_slot(&mut self, value: T, gap: bool) {
+        if self.samples.is_empty() {
+            return;
+        }
+        self.samples.pop_front();
+        self.samples.push_back(value);
+        self.gaps.pop_front();
+        self.gaps.push_back(gap);
+        self.filled = (self.filled + 1).min(self.samples.len());
+    }
+
+    /// The largest sample recorded this session, including ones no longer in the buffer.
+    pub fn peak(&self) -> T {
+        self.peak
+    }
+
+    /// The smallest sample recorded this session, or `None` before the first one.
+    pub fn trough(&self) -> Option<T> {
+        self.trough
+    }
+
+    /// The most recent sample, or zero if the buffer has no slots.
+    pub fn latest(&self) -> T {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    /// The largest sample in the buffer, or zero if it has no slots.
+    pub fn max(&self) -> T
+    where
+        T: Ord,
+    {
+        self.samples.iter().max().copied().unwrap_or_default()
+    }
+
+    /// The sample taken `back` samples before the newest one, or `None` for a gap and
+    /// before the first sample.
+    pub fn ago(&self, back: usize) -> Option<T> {
+        if back >= self.filled {
+            return None;
+        }
+        let index = self.samples.len() - 1 - back;
+        (!self.gaps[index]).then(|| self.samples[index])
+    }
+
+    /// The newest `n` samples, oldest first, or every sample if there are fewer.
+    pub fn recent(&self, n: usize) -> Vec<T> {
+        let skip = self.samples.len().saturating_sub(n);
+        self.samples.iter().skip(skip).copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Zeroes every sample and forgets the session peak and trough, keeping the buffer
+    /// length.
+    pub fn clear(&mut self) {
+        self.samples
+            .iter_mut()
+            .for_each(|sample| *sample = T::default());
+        self.gaps.iter_mut().for_each(|gap| *gap = false);
+        self.filled = 0;
+        self.peak = T::default();
+        self.trough = None;
+    }
+
+    /// Changes the number of samples kept. Growing pads the oldest end with zeros,
+    /// shrinking discards the oldest samples, so the newest data is always retained.
+    pub fn resize(&mut self, len: usize) {
+        while self.samples.len() > len {
+            self.samples.pop_front();
+            self.gaps.pop_front();
+        }
+        while self.samples.len() < len {
+            self.samples.push_front(T::default());
+            self.gaps.push_front(false);
+        }
+        self.filled = self.filled.min(len);
+    }
+
+    /// The `len` samples that were the newest `offset` samples ago, as a history of their
+    /// own that keeps them at their place on the time axis, for panning back through a
+    /// paused history. `offset` is capped so that the window stays within the pushed
+    /// samples.
+    pub fn window(&self, len: usize, offset: usize) -> Self {
+        let offset = offset.min(self.filled.saturating_sub(len));
+        let end = self.samples.len() - offset;
+        let samples: VecDeque<T> = self
+            .samples
+            .range(end.saturating_sub(len)..end)
+            .copied()
+            .collect();
+        let gaps = self
+            .gaps
+            .range(end.saturating_sub(len)..end)
+            .copied()
+            .collect();
+        Self {
+            filled: (self.filled - offset).min(samples.len()),
+            samples,
+            gaps,
+            peak: self.peak,
+            trough: self.trough,
+            offset: self.offset + offset,
+            mirrored: self.mirrored,
+        }
+    }
+
+    /// This history with its points mirrored to positive x offsets if `mirrored`, so that
+    /// the newest sample is at the left edge of a chart and older ones further right.
+    pub fn mirror(mut self, mirrored: bool) -> Self {
+        self.mirrored = mirrored;
+        self
+    }
+
+    /// Maps each pushed sample to chart coordinates, with the newest sample at x = 0 and
+    /// older samples at negative x offsets, or further left in a window. Until the
+    /// buffer fills up after startup or a clear, the line is as short as the samples so
+    /// far, instead of trailing off into a flat run of zeros. Gaps are left out, so the
+    /// x offsets skip them. A mirrored history has the same points at positive x offsets
+    /// instead.
+    pub fn points(&self, value: impl Fn(T) -> f64) -> Vec<(f64, f64)> {
+        let oldest = -(self.filled as f64 - 1.0) - self.offset as f64;
+        let skip = self.samples.len() - self.filled;
+        let sign = if self.mirrored { -1.0 } else { 1.0 };
+        self.samples
+            .iter()
+            .zip(&self.gaps)
+            .skip(skip)
+            .enumerate()
+            .filter(|(_, (_, &gap))| !gap)
+            .map(|(i, (sample, _))| (sign * (oldest + i as f64), value(*sample)))
+            .collect()
+    }
+
+    /// Like [`History::points`], but every sample is replaced by the mean of itself and
+    /// up to `window - 1` samples before it. A `window` of 1 returns the raw samples.
+    pub fn smoothed_points(&self, window: usize, value: impl Fn(T) -> f64) -> Vec<(f64, f64)> {
+        let raw = self.points(value);
+        let window = window.max(1);
+        let mut sum = 0.0;
+        raw.iter()
+            .enumerate()
+            .map(|(i, &(x, y))| {
+                sum += y;
+                if i >= window {
+                    sum -= raw[i - window].1;
+                }
+                (x, sum / (i + 1).min(window) as f64)
+            })
+            .collect()
+    }
+
+    /// The minimum, mean and maximum of the pushed samples in the buffer, mapped through
+    /// `value` like [`History::points`].
+    pub fn stats(&self, value: impl Fn(T) -> f64) -> Stats {
+        let values: Vec<f64> = self
+            .points(value)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        if values.is_empty() {
+            return Stats {
+                min: 0.0,
+                mean: 0.0,
+                max: 0.0,
+            };
+        }
+        Stats {
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+
+    /// The x-axis bounds that fit every sample returned by [`History::points`], which
+    /// widen with the samples pushed until they span the whole buffer.
+    pub fn x_bounds(&self) -> [f64; 2] {
+        let newest = -(self.offset as f64);
+        let bounds = [newest - self.filled.max(1) as f64, newest];
+        match self.mirrored {
+            true => [-bounds[1], -bounds[0]],
+            false => bounds,
+        }
+    }
+}
+
+impl History<u32> {
+    /// The sample by sample mean of `histories`, as long as the longest of them. Every
+    /// slot is averaged over the histories with a sample pushed into it, so one that was
+    /// added recently doesn't drag the older samples down with its padding, and a slot
+    /// where every history has a gap is a gap.
+    pub fn mean(histories: &[&History<u32>]) -> Self {
+        let len = histories.iter().map(|history| history.len()).max();
+        let mut mean = History::new(len.unwrap_or_default());
+        let filled = histories.iter().map(|history| history.filled).max();
+        for age in (0..filled.unwrap_or_default()).rev() {
+            let samples: Vec<u64> = histories
+                .iter()
+                .filter(|history| age < history.filled)
+                .filter_map(|history| {
+                    let i = history.samples.len() - 1 - age;
+                    (!history.gaps[i]).then(|| u64::from(history.samples[i]))
+                })
+                .collect();
+            match samples.len() {
+                0 => mean.push_gap(),
+                count => mean.push((samples.iter().sum::<u64>() / count as u64) as u32),
+            }
+        }
+        mean
+    }
+}
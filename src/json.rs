@@ -0,0 +1,170 @@
+use std::fmt::{self, Write};
+
+/// A JSON value, built by the export features and printed with `Display` (compact) or
+/// `pretty` (indented).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keys keep their insertion order.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Starts an empty object, filled in with `field`.
+    pub fn object() -> Self {
+        Json::Object(Vec::new())
+    }
+
+    /// Appends `key` to an object. Has no effect on other values.
+    pub fn field(mut self, key: &str, value: impl Into<Json>) -> Self {
+        if let Json::Object(fields) = &mut self {
+            fields.push((String::from(key), value.into()));
+        }
+        self
+    }
+
+    /// Formats the value with two-space indentation and one field or element per line.
+    pub fn pretty(&self) -> String {
+        let mut output = String::new();
+        // Writing into a String can't fail
+        let _ = self.write(&mut output, Some(0));
+        output
+    }
+
+    fn write(&self, out: &mut impl Write, indent: Option<usize>) -> fmt::Result {
+        match self {
+            Json::Null => out.write_str("null"),
+            Json::Bool(value) => write!(out, "{}", value),
+            Json::Number(value) if !value.is_finite() => out.write_str("null"),
+            Json::Number(value) => write!(out, "{}", value),
+            Json::String(value) => write_string(out, value),
+            Json::Array(elements) => {
+                write_container(out, indent, '[', ']', elements, |out, element, indent| {
+                    element.write(out, indent)
+                })
+            }
+            Json::Object(fields) => write_container(
+                out,
+                indent,
+                '{',
+                '}',
+                fields,
+                |out, (key, value), indent| {
+                    write_string(out, key)?;
+                    out.write_str(if indent.is_some() { ": " } else { ":" })?;
+                    value.write(out, indent)
+                },
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, None)
+    }
+}
+
+/// Writes `items` between `open` and `close`. With an indent, every item goes on its
+/// own line one level deeper than the brackets.
+fn write_container<W: Write, T>(
+    out: &mut W,
+    indent: Option<usize>,
+    open: char,
+    close: char,
+    items: &[T],
+    write_item: impl Fn(&mut W, &T, Option<usize>) -> fmt::Result,
+) -> fmt::Result {
+    out.write_char(open)?;
+    if items.is_empty() {
+        return out.write_char(close);
+    }
+    let inner = indent.map(|indent| indent + 1);
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        if let Some(inner) = inner {
+            write!(out, "\n{:width$}", "", width = inner * 2)?;
+        }
+        write_item(out, item, inner)?;
+    }
+    if let Some(indent) = indent {
+        write!(out, "\n{:width$}", "", width = indent * 2)?;
+    }
+    out.write_char(close)
+}
+
+fn write_string(out: &mut impl Write, value: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Self {
+        Json::Number(value.into())
+    }
+}
+
+impl From<u64> for Json {
+    fn from(value: u64) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(value: f64) -> Self {
+        Json::Number(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(String::from(value))
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Self {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
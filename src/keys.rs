@@ -0,0 +1,401 @@
+use crate::theme::Theme;
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{block::Title, Block, Clear, Paragraph, Widget},
+};
+
+/// Everything a key press can do in the TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    LongerHistory,
+    ShorterHistory,
+    NextGpu,
+    PreviousGpu,
+    Overview,
+    Pause,
+    ScrollBack,
+    ScrollForward,
+    ToggleCursor,
+    PauseGpu,
+    ToggleProcesses,
+    CycleTheme,
+    CycleClockDomain,
+    ToggleOverlay,
+    ToggleCoolingOverlay,
+    ToggleEfficiency,
+    ToggleBandwidth,
+    ToggleUtilizationScatter,
+    ToggleClockHistogram,
+    ToggleInterpolation,
+    ToggleAnomalies,
+    CycleUnits,
+    ToggleSmoothing,
+    ToggleStats,
+    ToggleDeviation,
+    ClearHistory,
+    ToggleBaseline,
+    ToggleUuid,
+    SaveScreen,
+    SaveHistory,
+    ToggleLogScale,
+    CycleGpuOrder,
+    ToggleHelp,
+    ToggleTotals,
+    ToggleAverage,
+    ToggleDashboard,
+    ToggleBars,
+    ToggleDetailed,
+    FocusChart,
+    ToggleMessages,
+    ScrollMessagesUp,
+    ScrollMessagesDown,
+    NextChartPage,
+    PreviousChartPage,
+}
+
+/// Keys that trigger an action while all of `modifiers` are held.
+pub struct KeyBinding {
+    pub keys: &'static [KeyCode],
+    pub modifiers: KeyModifiers,
+    pub action: Action,
+    pub description: &'static str,
+}
+
+const fn bind(keys: &'static [KeyCode], action: Action, description: &'static str) -> KeyBinding {
+    KeyBinding {
+        keys,
+        modifiers: KeyModifiers::NONE,
+        action,
+        description,
+    }
+}
+
+/// Every key the TUI reacts to, in the order the help popup lists them. The first
+/// binding that matches a key press wins, so Ctrl-C is listed before `c`.
+pub const KEY_BINDINGS: [KeyBinding; 46] = [
+    bind(
+        &[KeyCode::Char('q'), KeyCode::Char('Q')],
+        Action::Quit,
+        "Quit",
+    ),
+    KeyBinding {
+        keys: &[KeyCode::Char('c')],
+        modifiers: KeyModifiers::CONTROL,
+        action: Action::Quit,
+        description: "Quit",
+    },
+    bind(
+        &[KeyCode::Char('+'), KeyCode::Char('=')],
+        Action::LongerHistory,
+        "Keep a longer history",
+    ),
+    bind(
+        &[KeyCode::Char('-')],
+        Action::ShorterHistory,
+        "Keep a shorter history",
+    ),
+    bind(
+        &[KeyCode::Down, KeyCode::Char('j')],
+        Action::NextGpu,
+        "Show the next GPU full-screen",
+    ),
+    bind(
+        &[KeyCode::Up, KeyCode::Char('k')],
+        Action::PreviousGpu,
+        "Show the previous GPU full-screen",
+    ),
+    bind(&[KeyCode::Esc], Action::Overview, "Back to all GPUs"),
+    bind(
+        &[KeyCode::Char('p')],
+        Action::Pause,
+        "Pause or resume sampling",
+    ),
+    bind(
+        &[KeyCode::Left],
+        Action::ScrollBack,
+        "Pan back through the history while paused",
+    ),
+    bind(
+        &[KeyCode::Right],
+        Action::ScrollForward,
+        "Pan forward through the history while paused",
+    ),
+    bind(
+        &[KeyCode::Char('C')],
+        Action::ToggleCursor,
+        "Pause and read the values under a cursor moved with Left/Right",
+    ),
+    bind(
+        &[KeyCode::Char('P')],
+        Action::PauseGpu,
+        "Pause or resume the selected GPU",
+    ),
+    bind(
+        &[KeyCode::Char('t')],
+        Action::ToggleProcesses,
+        "Show or hide the process list",
+    ),
+    bind(
+        &[KeyCode::Char('c')],
+        Action::CycleTheme,
+        "Cycle the color theme",
+    ),
+    bind(
+        &[KeyCode::Char('d')],
+        Action::CycleClockDomain,
+        "Cycle the charted clock domain",
+    ),
+    bind(
+        &[KeyCode::Char('o')],
+        Action::ToggleOverlay,
+        "Overlay the clock and temperature",
+    ),
+    bind(
+        &[KeyCode::Char('f')],
+        Action::ToggleCoolingOverlay,
+        "Overlay the fans and temperature",
+    ),
+    bind(
+        &[KeyCode::Char('e')],
+        Action::ToggleEfficiency,
+        "Chart the clock per watt",
+    ),
+    bind(
+        &[KeyCode::Char('z')],
+        Action::ToggleBandwidth,
+        "Chart the memory bandwidth",
+    ),
+    bind(
+        &[KeyCode::Char('U')],
+        Action::ToggleUtilizationScatter,
+        "Plot SM against memory utilization",
+    ),
+    bind(
+        &[KeyCode::Char('v')],
+        Action::ToggleClockHistogram,
+        "Chart the clock distribution",
+    ),
+    bind(
+        &[KeyCode::Char('x')],
+        Action::ToggleInterpolation,
+        "Interpolate the clock line",
+    ),
+    bind(
+        &[KeyCode::Char('A')],
+        Action::ToggleAnomalies,
+        "Mark samples far from the mean",
+    ),
+    bind(
+        &[KeyCode::Char('u')],
+        Action::CycleUnits,
+        "Show clocks/memory in GHz/GiB",
+    ),
+    bind(
+        &[KeyCode::Char('s')],
+        Action::ToggleSmoothing,
+        "Smooth the temperature and power",
+    ),
+    bind(
+        &[KeyCode::Char('m')],
+        Action::ToggleStats,
+        "Show or hide min/avg/max lines",
+    ),
+    bind(
+        &[KeyCode::Char('O')],
+        Action::ToggleDeviation,
+        "Compare each GPU with the mean",
+    ),
+    bind(
+        &[KeyCode::Char('r')],
+        Action::ClearHistory,
+        "Clear every history and peak",
+    ),
+    bind(
+        &[KeyCode::Char('b')],
+        Action::ToggleBaseline,
+        "Compare against a baseline, or stop",
+    ),
+    bind(
+        &[KeyCode::Char('i')],
+        Action::ToggleUuid,
+        "Name the GPUs by UUID or by model",
+    ),
+    bind(
+        &[KeyCode::Char('w')],
+        Action::SaveScreen,
+        "Save the screen to a text file",
+    ),
+    bind(
+        &[KeyCode::Char('E')],
+        Action::SaveHistory,
+        "Save the history of every GPU to a JSON file",
+    ),
+    bind(
+        &[KeyCode::Char('l')],
+        Action::ToggleLogScale,
+        "Switch PCIe/power to a log scale",
+    ),
+    bind(
+        &[KeyCode::Char('g')],
+        Action::CycleGpuOrder,
+        "Sort the GPUs by temp/util/memory",
+    ),
+    bind(
+        &[KeyCode::Char('a')],
+        Action::ToggleTotals,
+        "Show or hide the power/memory totals",
+    ),
+    bind(
+        &[KeyCode::Char('G')],
+        Action::ToggleAverage,
+        "Chart the average of all GPUs",
+    ),
+    bind(
+        &[KeyCode::Char('y')],
+        Action::ToggleDashboard,
+        "Show every GPU and process at once",
+    ),
+    bind(
+        &[KeyCode::Char('B')],
+        Action::ToggleBars,
+        "Show bars instead of charts",
+    ),
+    bind(
+        &[KeyCode::Char('D')],
+        Action::ToggleDetailed,
+        "Stack the charts of one GPU",
+    ),
+    bind(
+        &[KeyCode::Char('F')],
+        Action::FocusChart,
+        "Show one chart full-screen",
+    ),
+    bind(
+        &[KeyCode::Char('n')],
+        Action::ToggleMessages,
+        "Show or hide the log messages",
+    ),
+    bind(
+        &[KeyCode::PageUp],
+        Action::ScrollMessagesUp,
+        "Scroll messages or GPUs up",
+    ),
+    bind(
+        &[KeyCode::PageDown],
+        Action::ScrollMessagesDown,
+        "Scroll messages or GPUs down",
+    ),
+    bind(
+        &[KeyCode::Tab],
+        Action::NextChartPage,
+        "Show the charts two at a time",
+    ),
+    bind(
+        &[KeyCode::BackTab],
+        Action::PreviousChartPage,
+        "Show the previous two charts",
+    ),
+    bind(
+        &[KeyCode::Char('?'), KeyCode::Char('h')],
+        Action::ToggleHelp,
+        "Show or hide this help",
+    ),
+];
+
+/// The action bound to `key_event`, if any.
+pub fn action(key_event: KeyEvent) -> Option<Action> {
+    KEY_BINDINGS
+        .iter()
+        .find(|binding| {
+            binding.keys.contains(&key_event.code)
+                && key_event.modifiers.contains(binding.modifiers)
+        })
+        .map(|binding| binding.action)
+}
+
+/// How a binding's keys are written in the help popup, e.g. `↓/j` or `Ctrl-c`.
+fn key_label(binding: &KeyBinding) -> String {
+    let prefix = if binding.modifiers.contains(KeyModifiers::CONTROL) {
+        "Ctrl-"
+    } else {
+        ""
+    };
+    binding
+        .keys
+        .iter()
+        .map(|key| match key {
+            KeyCode::Char(c) => format!("{}{}", prefix, c),
+            KeyCode::Up => String::from("↑"),
+            KeyCode::Down => String::from("↓"),
+            KeyCode::BackTab => String::from("Shift-Tab"),
+            KeyCode::PageUp => String::from("PgUp"),
+            KeyCode::PageDown => String::from("PgDn"),
+            key => format!("{}{}", prefix, key),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Renders a popup listing every key binding, centered over `area`. The bindings wrap
+/// into more columns when `area` is too short to list them in one.
+pub fn render_help_popup(theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let labels: Vec<String> = KEY_BINDINGS.iter().map(key_label).collect();
+    let label_width = labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let description_width = KEY_BINDINGS
+        .iter()
+        .map(|binding| binding.description.chars().count())
+        .max()
+        .unwrap_or(0);
+    let rows = usize::from(area.height.saturating_sub(2)).clamp(1, KEY_BINDINGS.len());
+    let lines: Vec<Line> = (0..rows)
+        .map(|row| {
+            let spans = KEY_BINDINGS
+                .iter()
+                .zip(&labels)
+                .skip(row)
+                .step_by(rows)
+                .flat_map(|(binding, label)| {
+                    [
+                        Span::styled(
+                            format!(" {:<width$}  ", label, width = label_width),
+                            Style::new().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(format!(
+                            "{:<width$}",
+                            binding.description,
+                            width = description_width
+                        )),
+                    ]
+                });
+            Line::from(spans.collect::<Vec<_>>())
+        })
+        .collect();
+
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 1;
+    let width = (content_width + 2).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme.border))
+        .title(Title::from(" Keys ").alignment(Alignment::Center));
+    Clear.render(popup_area, buf);
+    Paragraph::new(lines)
+        .block(block)
+        .style(Style::new().fg(theme.text))
+        .render(popup_area, buf);
+}
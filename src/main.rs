@@ -1,338 +1,5371 @@
-use crossterm::event::Event;
-use env_logger;
+use alert::CritHooks;
+use cli::{AxisRange, GridLayout, Options, DEFAULT_INTERVAL};
+use collector::Update;
+use config::Config;
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, MouseButton, MouseEvent, MouseEventKind,
+};
+use demo::DemoSource;
+use ecc::EccErrors;
+use export::Exporters;
+use history::{History, DEFAULT_HISTORY_LEN, DEFAULT_SMOOTHING_WINDOW, HISTORY_LENGTHS};
+use keys::Action;
 use log::{debug, info, warn};
-use nvml_wrapper::{enum_wrappers::device::TemperatureSensor, Nvml};
+use messages::{Message, MAX_MESSAGES};
+use metric::{
+    ChartMarker, ClockDomain, GpuOrder, Metric, MetricFlags, Precision, TemperatureUnit, Units,
+};
+use nvlink::NvLink;
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{
+        ComputeMode, PcieUtilCounter, PerformanceState, Sampling, TemperatureSensor,
+        TemperatureThreshold,
+    },
+    error::NvmlError,
+    Nvml,
+};
+use nvml_wrapper_sys::bindings::NVML_NVLINK_MAX_LINKS;
+use process::{GpuProcess, ProcessMemory};
 use ratatui::{
-    crossterm::event::{self, KeyCode, KeyEventKind},
+    buffer::Buffer,
+    crossterm::event::{self, KeyEventKind},
     layout::{Constraint, Layout, Rect},
-    style::Style,
+    style::{Color, Modifier, Style},
     symbols::Marker,
-    widgets::{block::Title, Axis, Block, Chart, Dataset, Widget},
+    text::Line,
+    widgets::{
+        block::Title, Axis, Block, Chart, Dataset, Gauge, LegendPosition, Paragraph, Widget, Wrap,
+    },
     DefaultTerminal, Frame,
 };
-use std::thread;
-use std::time::Duration;
+use replay::ReplaySource;
+use samples::SampleBuffer;
+use snapshot::GpuSnapshot;
+use source::{FanPolicy, GpuDevice, GpuSource, MediaEngine, NvmlSource};
+use status::{DriverVersions, StatusLine};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{IsTerminal, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use theme::{Theme, PLAIN, THEMES};
+use throttle::ThrottleEvents;
+use xid::{XidEvent, MAX_XID_ALERTS};
 
+mod alert;
+mod bars;
+mod check;
+mod cli;
+mod collector;
+mod config;
+mod demo;
+mod ecc;
+mod export;
+mod histogram;
+mod history;
+mod json;
+mod keys;
+mod messages;
+mod metric;
+mod nvlink;
+mod process;
+mod prometheus;
+mod replay;
+mod samples;
+mod screenshot;
+mod snapshot;
+#[cfg(unix)]
+mod socket;
+mod source;
+mod stats;
+mod status;
+mod summary;
+mod sysfs;
+#[cfg(test)]
 mod tests;
+mod theme;
+mod throttle;
+mod xid;
+
+/// The longest time `handle_events` waits for input before returning to the loop.
+const EVENT_POLL_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// How long the TUI waits for input before checking for new readings from the collection
+/// thread, independent of the sampling interval.
+const UI_TICK: Duration = Duration::from_millis(50);
+
+/// The most frames the TUI draws per second, unless `max_fps` in the config file says
+/// otherwise.
+const DEFAULT_MAX_FPS: u32 = 20;
+
+/// How many samples the left and right keys pan the charts of a paused history by.
+const SCROLL_STEP: usize = 10;
+
+/// The least time between two rings of the `--bell`, however many GPUs cross a threshold.
+const BELL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How many colors the temperature line steps through on its way to the threshold.
+const GRADIENT_STEPS: usize = 8;
+
+/// How far below the threshold, in °C, the temperature line starts to warm up from the
+/// safe color.
+const GRADIENT_SPAN: f64 = 30.0;
+
+/// The threshold the temperature line is colored against without `--temp-crit` or
+/// `--temp-warn`, in °C, around where most cards start to throttle.
+const DEFAULT_GRADIENT_THRESHOLD: u32 = 85;
+
+/// How often `--daemon` wakes up between readings to check for SIGINT and SIGTERM.
+const SIGNAL_CHECK: Duration = Duration::from_millis(250);
 
 fn main() {
-    env_logger::init();
+    messages::init();
+    install_panic_hook();
+    let config_path = config::default_path();
+    let parsed = config_path
+        .as_deref()
+        .map_or_else(|| Ok(Config::default()), Config::load)
+        .and_then(|config| {
+            let mut options = Options::parse_over(config.options(), std::env::args().skip(1))?;
+            // See https://no-color.org
+            if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                options.no_color = true;
+            }
+            Ok((config, options))
+        });
+    let (config, options) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            std::process::exit(2);
+        }
+    };
     info!("Starting application");
-    run_tui();
+    let result = if options.help {
+        print!("{}", cli::HELP);
+        Ok(())
+    } else if options.version {
+        print_version(&options)
+    } else if options.check {
+        print_check(&options)
+    } else if options.dump_json || options.once || options.xml {
+        print_snapshot(&options)
+    } else if options.daemon {
+        run_daemon(options, config)
+    } else if !std::io::stdout().is_terminal() {
+        run_stream(options, config)
+    } else {
+        // The terminal is already restored by the time an error is reported here
+        run_tui(options, config, config_path.as_deref())
+    };
+    if let Err(error) = result {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
 }
 
-fn run_tui() {
-    let mut terminal = ratatui::init();
-    terminal.clear().expect("Failed to clear terminal");
+/// A flag raised by SIGINT or SIGTERM, for exiting cleanly.
+fn register_signals() -> std::io::Result<Arc<AtomicBool>> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&interrupted))?;
+    }
+    Ok(interrupted)
+}
+
+/// Collects metrics into the export sinks of `options` until interrupted, without a
+/// terminal, for logging on headless servers.
+fn run_daemon(options: Options, config: Config) -> std::io::Result<()> {
+    let mut app = daemon_app(&options, register_signals()?);
+    config.apply(&mut app);
+    let result = app.run_export_loop(&options);
+    // The reader of a stream going away ends it as cleanly as an interrupt
+    let broken_pipe = matches!(&result, Err(error) if options.stream
+        && error.kind() == std::io::ErrorKind::BrokenPipe);
+    if result.is_ok() || broken_pipe {
+        write_summary(&app, &options)?;
+    }
+    result
+}
+
+/// The monitor `--daemon` runs until `interrupted`, with the temperature alerts of
+/// `options`, which are all an unattended machine has to tell anyone it's overheating.
+fn daemon_app(options: &Options, interrupted: Arc<AtomicBool>) -> NvidiaApp {
+    NvidiaApp {
+        interval: options.interval,
+        temp_warn: options.temp_warn,
+        temp_crit: options.temp_crit,
+        temp_rate_warn: options.temp_rate_warn,
+        crit_hooks: options.crit_hooks.clone(),
+        precision: options.precision,
+        interrupted,
+        ..NvidiaApp::default()
+    }
+}
+
+/// Prints a line of metrics per GPU on every tick, like `--daemon` with the exports of
+/// `options` too, for when stdout is piped or redirected and a TUI would only come out
+/// as escape codes. The program reading the pipe going away, as `head` does once it has
+/// its lines, ends the stream like an interrupt rather than as an error.
+fn run_stream(mut options: Options, config: Config) -> std::io::Result<()> {
+    options.stream = true;
+    match run_daemon(options, config) {
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
 
-    let mut app = NvidiaApp::default();
-    let _app_result = app.run_app(&mut terminal);
+/// Restores the terminal before the default panic handler prints the message, so a panic
+/// anywhere, even before or after the TUI runs, never leaves the shell in raw mode.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+}
 
+/// Hands mouse events back to the terminal, so text can be selected again, and leaves
+/// raw mode and the alternate screen.
+fn restore_terminal() {
+    messages::capture(false);
+    let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
     ratatui::restore();
 }
 
-#[derive(Debug, Default)]
+/// Runs the TUI, then saves the settings changed in it to the config file at
+/// `config_path`.
+fn run_tui(
+    options: Options,
+    mut config: Config,
+    config_path: Option<&Path>,
+) -> std::io::Result<()> {
+    // Raw mode turns Ctrl-C into a key event, but SIGINT and SIGTERM can still be sent
+    // with kill, e.g. when an SSH session is torn down
+    let interrupted = register_signals()?;
+
+    let mut app = NvidiaApp {
+        interval: options.interval,
+        temp_warn: options.temp_warn,
+        temp_crit: options.temp_crit,
+        temp_rate_warn: options.temp_rate_warn,
+        show_anomalies: options.anomaly_sigma.is_some(),
+        anomaly_sigma: options
+            .anomaly_sigma
+            .unwrap_or(stats::DEFAULT_ANOMALY_SIGMA),
+        crit_hooks: options.crit_hooks.clone(),
+        bell: options.bell,
+        show_timing: options.timing,
+        axis_ranges: options.axis_ranges.clone(),
+        compact: options.compact,
+        bars: options.bars,
+        layout: options.layout,
+        per_page: options.per_page,
+        // The hostname can't change while running, so it's only looked up once
+        banner: status::banner(options.label.as_deref(), status::hostname().as_deref()),
+        use_color: !options.no_color,
+        temperature_unit: if options.fahrenheit {
+            TemperatureUnit::Fahrenheit
+        } else {
+            TemperatureUnit::Celsius
+        },
+        precision: options.precision,
+        interrupted,
+        ..NvidiaApp::default()
+    };
+    config.apply(&mut app);
+    let accent = options.accent.as_deref().map(theme::parse_hex_color);
+    if let Some(Ok(accent)) = accent {
+        app.set_accent(accent);
+    }
+
+    let mut terminal = ratatui::init();
+    messages::capture(true);
+    // Only now does the warning reach the messages pane instead of the hidden screen
+    if let Some(Err(error)) = accent {
+        warn!("Ignoring --accent: {}", error);
+    }
+    let app_result = crossterm::execute!(std::io::stdout(), EnableMouseCapture)
+        .and_then(|()| terminal.clear())
+        .and_then(|()| app.run_app(&mut terminal, &options));
+    restore_terminal();
+    app_result?;
+    write_summary(&app, &options)?;
+
+    if let Some(config_path) = config_path {
+        config.remember(&app);
+        config.save(config_path)?;
+    }
+    Ok(())
+}
+
+/// Writes the session peaks of every GPU to the `--summary` file, if one is given. Only
+/// a clean exit gets here, by quitting or by an interrupt that ended the loop.
+fn write_summary(app: &NvidiaApp, options: &Options) -> std::io::Result<()> {
+    let Some(path) = &options.summary else {
+        return Ok(());
+    };
+    snapshot::save_summary(&app.gpus, path).map_err(|error| {
+        std::io::Error::new(
+            error.kind(),
+            format!("Failed to write {}: {}", path.display(), error),
+        )
+    })
+}
+
+/// Prints the versions of this program, the driver, NVML and CUDA on one line. NVML is
+/// only initialized to read them, and failing to is reported without hiding the version
+/// of this program, which is still worth including in a bug report.
+fn print_version(options: &Options) -> std::io::Result<()> {
+    let versions = if options.demo {
+        DriverVersions::read(&DemoSource::new())
+    } else {
+        match Nvml::init() {
+            Ok(nvml) => DriverVersions::read(
+                &NvmlSource::new(&nvml)
+                    .map_err(|error| nvml_error("Failed to get device count", error))?,
+            ),
+            Err(error) => {
+                eprintln!("Failed to initialize NVML: {}", error);
+                DriverVersions::default()
+            }
+        }
+    };
+    println!("{}", status::version_line(&versions));
+    Ok(())
+}
+
+/// Prints which metrics every GPU supports for `--check`, to see what can be monitored on
+/// this hardware before starting the TUI.
+fn print_check(options: &Options) -> std::io::Result<()> {
+    let report = if options.demo {
+        check::check_report(&DemoSource::new())
+    } else {
+        let nvml = Nvml::init().map_err(|error| nvml_error("Failed to initialize NVML", error))?;
+        let source = NvmlSource::new(&nvml)
+            .map_err(|error| nvml_error("Failed to get device count", error))?;
+        check::check_report(&source)
+    };
+    print!(
+        "{}",
+        report.map_err(|error| nvml_error("Failed to get device count", error))?
+    );
+    Ok(())
+}
+
+/// Samples every GPU once and prints the snapshot to stdout, as pretty JSON for
+/// `--dump-json`, as `nvidia-smi` XML for `--xml` and as a text table for `--once`.
+fn print_snapshot(options: &Options) -> std::io::Result<()> {
+    if options.demo {
+        return print_source_snapshot(&DemoSource::new(), options);
+    }
+    let nvml = Nvml::init().map_err(|error| nvml_error("Failed to initialize NVML", error))?;
+    let source =
+        NvmlSource::new(&nvml).map_err(|error| nvml_error("Failed to get device count", error))?;
+    print_source_snapshot(&source, options)
+}
+
+fn print_source_snapshot(source: &dyn GpuSource, options: &Options) -> std::io::Result<()> {
+    let mut app = NvidiaApp {
+        history_len: 1,
+        show_processes: true,
+        ..NvidiaApp::default()
+    };
+    app.add_gpus(source, options.gpus.as_deref())?;
+    for gpu_info in &mut app.gpus {
+        gpu_info.precision = options.precision;
+    }
+    app.update_state(source)?;
+    if options.dump_json {
+        println!("{}", snapshot::snapshot_json(&app.gpus).pretty());
+    } else if options.xml {
+        let versions = DriverVersions::read(source);
+        print!("{}", snapshot::snapshot_xml(&app.gpus, &versions));
+    } else {
+        print!("{}", snapshot::snapshot_table(&app.gpus));
+    }
+    Ok(())
+}
+
+/// The settings every chart of a GPU is drawn with, gathered once per frame.
+#[derive(Debug, Clone, Copy)]
+struct ChartOptions<'a> {
+    interval: Duration,
+    marker: Marker,
+    units: Units,
+    /// The clock domain the clock charts show.
+    domain: ClockDomain,
+    gridlines: bool,
+    interpolate: bool,
+    /// How many standard deviations the charts flag anomalies at, or `None` unless they
+    /// are shown.
+    anomaly_sigma: Option<f64>,
+    /// The number of samples the smoothed charts average, or 1 while smoothing is off.
+    smoothing_window: usize,
+    /// The charts drawn on a log scale, none while it's toggled off.
+    log_charts: MetricFlags,
+    axis_ranges: &'a BTreeMap<Metric, AxisRange>,
+    /// The GPUs whose mean the stats footers compare their GPU with, or `None` unless
+    /// they're compared.
+    compared_gpus: Option<&'a [GPUInfo]>,
+}
+
+/// The fixed y-axis bounds of [`ChartOptions::default`]: none.
+static NO_AXIS_RANGES: BTreeMap<Metric, AxisRange> = BTreeMap::new();
+
+impl Default for ChartOptions<'_> {
+    fn default() -> Self {
+        ChartOptions {
+            interval: DEFAULT_INTERVAL,
+            marker: ChartMarker::default().marker(),
+            units: Units::default(),
+            domain: ClockDomain::Graphics,
+            gridlines: false,
+            interpolate: false,
+            anomaly_sigma: None,
+            smoothing_window: 1,
+            log_charts: MetricFlags::empty(),
+            axis_ranges: &NO_AXIS_RANGES,
+            compared_gpus: None,
+        }
+    }
+}
+
+impl ChartOptions<'_> {
+    /// Whether the chart of `metric` is drawn on a log scale.
+    fn log_scaled(&self, metric: Metric) -> bool {
+        self.log_charts.contains(MetricFlags::from(metric))
+    }
+
+    /// The fixed y-axis bounds of the chart of `metric`, if any.
+    fn range(&self, metric: Metric) -> Option<AxisRange> {
+        self.axis_ranges.get(&metric).copied()
+    }
+}
+
+/// The temperature thresholds of `--temp-warn` and `--temp-crit` of a GPU, or its own
+/// slowdown and shutdown temperatures where they aren't given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TemperatureThresholds {
+    warn: Option<u32>,
+    crit: Option<u32>,
+}
+
+impl TemperatureThresholds {
+    /// The temperature in °C the temperature line turns hot at: the critical threshold,
+    /// else the warning one.
+    fn gradient(&self) -> u32 {
+        self.crit
+            .or(self.warn)
+            .unwrap_or(DEFAULT_GRADIENT_THRESHOLD)
+    }
+}
+
+/// How the GPUs are drawn in the area they're given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Charts,
+    /// A stack of gauge bars per GPU, with `--bars`.
+    Bars,
+    /// One table row per GPU, with `--compact` or when the charts wouldn't fit.
+    Compact,
+    /// Not even the table fits, so only a message asking for a bigger terminal is shown.
+    TooSmall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TemperatureLevel {
+    Warning,
+    Critical,
+}
+
+impl std::fmt::Display for TemperatureLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TemperatureLevel::Warning => "the warning temperature",
+            TemperatureLevel::Critical => "the critical temperature",
+        })
+    }
+}
+
+/// Logs the GPU labeled `label` starting or stopping to throttle, ignoring the idle
+/// reason, which comes and goes with every burst of work.
+fn log_throttle_change(label: &str, old: ThrottleReasons, new: ThrottleReasons) {
+    let active = |reasons: ThrottleReasons| -> Vec<&str> {
+        throttle::labels(reasons - ThrottleReasons::GPU_IDLE)
+    };
+    let (old, new) = (active(old), active(new));
+    if old == new {
+        return;
+    }
+    if new.is_empty() {
+        info!("{} stopped throttling", label);
+    } else {
+        info!("{} throttling: {}", label, new.join(", "));
+    }
+}
+
+/// Wraps a fatal NVML failure into an I/O error that `main` reports after the terminal
+/// has been restored.
+fn nvml_error(context: &str, error: NvmlError) -> std::io::Error {
+    std::io::Error::other(format!("{}: {}", context, error))
+}
+
+#[derive(Debug)]
 struct NvidiaApp {
     gpus: Vec<GPUInfo>,
+    history_len: usize,
+    interval: Duration,
+    /// The GPU shown full-screen, or `None` for the all-GPU overview.
+    selected: Option<usize>,
+    /// While set, no new samples are collected so the charts can be inspected.
+    paused: bool,
+    /// How many samples back the charts are panned while paused, each showing as many
+    /// samples as it has columns.
+    scroll: usize,
+    /// How many samples back from the newest the crosshair of `C` reads the charts, while
+    /// it's shown. Left and Right move it instead of panning.
+    cursor: Option<usize>,
+    /// Set while the collection thread restarts NVML, until readings arrive again.
+    reconnecting: bool,
+    /// When the charts last took in readings, or `None` before the first ones.
+    last_update: Option<Instant>,
+    /// Whether the status bar shows how long the last readings took to collect, with
+    /// `--timing`.
+    show_timing: bool,
+    /// How long the collection thread took to read every GPU on the last tick.
+    collection_time: Option<Duration>,
+    /// Whether a collection close to the interval was already warned about, so the
+    /// messages pane isn't flooded every tick.
+    slow_collection_warned: bool,
+    /// Whether the process panel is shown below the charts.
+    show_processes: bool,
+    /// Whether utilization and power are averaged over the driver's own samples since
+    /// the last tick, with `--high-res`, instead of read once a tick.
+    high_res: bool,
+    /// Whether clock and temperature share one normalized chart instead of two charts.
+    overlay: bool,
+    /// Whether the fan chart also draws the temperature, to check how the fan curve
+    /// follows it.
+    cooling_overlay: bool,
+    /// Whether the power chart is replaced by the clock per watt drawn.
+    show_efficiency: bool,
+    /// Whether the utilization chart is swapped for the estimated memory bandwidth.
+    show_bandwidth: bool,
+    /// Whether the utilization chart is swapped for a scatter plot of the SM against
+    /// the memory controller utilization.
+    show_utilization_scatter: bool,
+    /// Whether the clock chart is replaced by a histogram of the clocks in the window.
+    show_clock_histogram: bool,
+    /// Whether the clock line is drawn through points interpolated between the samples.
+    interpolate: bool,
+    /// Whether the clock, temperature and power charts mark the samples `anomaly_sigma`
+    /// standard deviations or more from the mean of their history, toggled with `A`.
+    show_anomalies: bool,
+    anomaly_sigma: f64,
+    /// Whether clocks and memory are shown in MHz and MiB or in GHz and GiB.
+    units: Units,
+    /// Whether the GPUs are named by their UUID rather than their model.
+    show_uuid: bool,
+    temperature_unit: TemperatureUnit,
+    precision: Precision,
+    /// How the samples of the line charts are drawn.
+    marker: ChartMarker,
+    /// Whether the clock chart draws a gridline at each of its y-axis labels.
+    gridlines: bool,
+    /// Whether the charts draw the newest sample at the left edge rather than the right.
+    newest_left: bool,
+    /// Whether the charts in `log_charts` have a logarithmic y-axis, toggled with `l`.
+    log_scale: bool,
+    /// The charts `l` switches to a log scale, from `log_charts` in the config file.
+    log_charts: MetricFlags,
+    /// The metrics that end the program once they can't be read on a GPU, from
+    /// `required_metrics` in the config file. The others degrade to "N/A".
+    required_metrics: MetricFlags,
+    /// Shown in the status bar, which is hidden until they have been read.
+    versions: Option<DriverVersions>,
+    /// The `--label` and hostname shown in the top row, if either is known.
+    banner: Option<String>,
+    /// The latest XID errors of `--xid`, oldest first, shown in an alert row.
+    xid_events: Vec<XidEvent>,
+    /// The messages logged while the TUI runs, oldest first, up to [`MAX_MESSAGES`].
+    messages: VecDeque<Message>,
+    /// Whether the messages pane is shown, toggled with `n`.
+    show_messages: bool,
+    /// How many lines the messages pane is scrolled up from the newest message.
+    messages_scroll: usize,
+    /// Whether the temperature and power charts show a moving average instead of the raw
+    /// samples, which are kept either way.
+    smoothing: bool,
+    /// The number of samples averaged while smoothing.
+    smoothing_window: usize,
+    /// The most frames drawn per second, however fast input and readings come in.
+    max_fps: u32,
+    /// Whether a min/avg/max line of the displayed history is shown under each chart.
+    show_stats: bool,
+    /// Whether the footers of the key charts show how far each GPU is from the mean of
+    /// every GPU, to spot the one card that runs hotter or slower than its peers.
+    show_deviation: bool,
+    /// The page of charts shown for every GPU, cycled with Tab, or `None` to show them all.
+    chart_page: Option<usize>,
+    /// Fixed y-axis bounds from `--temp-range` and friends, by metric.
+    axis_ranges: BTreeMap<Metric, AxisRange>,
+    /// The charts laid out for every GPU, from `charts` in the config file.
+    charts: MetricFlags,
+    /// The charts laid out for single GPUs by hardware index, instead of `charts`.
+    gpu_charts: BTreeMap<u32, MetricFlags>,
+    /// The charts laid out first, from `default_charts` in the config file.
+    default_charts: Vec<Metric>,
+    /// Whether the panel summing the power and memory of every GPU is shown. It always
+    /// is in the compact view.
+    show_totals: bool,
+    /// Whether the overview of several GPUs charts their average instead, as toggled
+    /// with `G`.
+    show_average: bool,
+    /// Whether the popup listing every key binding is shown.
+    show_help: bool,
+    /// Whether the next frame drawn is to be saved to a file, as asked for with `w`.
+    save_screen: bool,
+    gpu_order: GpuOrder,
+    /// Whether the charts are colored by the theme, rather than left to the terminal's
+    /// default colors by `--no-color`.
+    use_color: bool,
+    /// Temperatures in °C at which a GPU's charts are highlighted.
+    temp_warn: Option<u32>,
+    temp_crit: Option<u32>,
+    /// How many °C below a threshold a GPU's temperature has to fall before the alert
+    /// clears, so a GPU hovering at the threshold doesn't flap in and out of it.
+    temp_hysteresis: u32,
+    /// The rise in °C per minute at which a GPU's charts are highlighted early.
+    temp_rate_warn: Option<f64>,
+    /// Fired once every time a GPU reaches `temp_crit`.
+    crit_hooks: CritHooks,
+    /// Whether `--bell` rings the terminal bell as a GPU reaches the critical
+    /// temperature or its power limit.
+    bell: bool,
+    /// When the bell last rang, so it rings at most once every `BELL_INTERVAL`.
+    bell_rung_at: Option<Instant>,
+    /// Whether the bell is due, rung by the main loop rather than while taking readings.
+    ring_bell: bool,
+    /// Whether each GPU is summarized in one table row instead of charts.
+    compact: bool,
+    /// Whether the compact table is shown above the processes of every GPU at once,
+    /// like `nvtop`, instead of the charts.
+    dashboard: bool,
+    /// Whether each GPU is drawn as a stack of gauge bars instead of charts.
+    bars: bool,
+    /// Whether only the focused GPU is drawn, its main charts stacked in one column.
+    detailed: bool,
+    /// How many charts the column of the detailed view is scrolled down by.
+    detail_scroll: usize,
+    /// The GPU, by index into `gpus`, and the chart of it that fills the whole screen,
+    /// stepped through with F.
+    chart_focus: Option<(usize, Metric)>,
+    /// The grid of `--layout` the overview arranges the GPUs in, or `None` to stack
+    /// them vertically.
+    layout: Option<GridLayout>,
+    /// How many GPUs a page of the overview shows with `--per-page`, or `None` for all.
+    per_page: Option<usize>,
+    /// The page of `per_page` GPUs the overview shows, wrapping around.
+    gpu_page: usize,
+    /// The clock domain shown in the clock chart.
+    clock_domain: ClockDomain,
+    /// Index into `themes` of the colors in use.
+    theme: usize,
+    /// The themes cycled through with `c`: `THEMES`, with the color of `--accent`.
+    themes: [Theme; THEMES.len()],
+    /// Set from a signal handler to leave the loop through the normal exit path.
+    interrupted: Arc<AtomicBool>,
     exit: bool,
 }
 
-#[derive(Debug, Default)]
+impl Default for NvidiaApp {
+    fn default() -> Self {
+        Self {
+            gpus: Vec::new(),
+            history_len: DEFAULT_HISTORY_LEN,
+            interval: DEFAULT_INTERVAL,
+            selected: None,
+            paused: false,
+            scroll: 0,
+            cursor: None,
+            reconnecting: false,
+            last_update: None,
+            show_timing: false,
+            collection_time: None,
+            slow_collection_warned: false,
+            show_processes: false,
+            high_res: false,
+            overlay: false,
+            cooling_overlay: false,
+            show_efficiency: false,
+            show_bandwidth: false,
+            show_utilization_scatter: false,
+            interpolate: false,
+            show_anomalies: false,
+            anomaly_sigma: stats::DEFAULT_ANOMALY_SIGMA,
+            show_clock_histogram: false,
+            units: Units::default(),
+            show_uuid: false,
+            temperature_unit: TemperatureUnit::default(),
+            precision: Precision::default(),
+            marker: ChartMarker::default(),
+            gridlines: false,
+            newest_left: false,
+            log_scale: false,
+            log_charts: MetricFlags::PCIE,
+            required_metrics: MetricFlags::empty(),
+            versions: None,
+            banner: None,
+            xid_events: Vec::new(),
+            messages: VecDeque::new(),
+            show_messages: false,
+            messages_scroll: 0,
+            smoothing: false,
+            smoothing_window: DEFAULT_SMOOTHING_WINDOW,
+            max_fps: DEFAULT_MAX_FPS,
+            show_stats: false,
+            show_deviation: false,
+            chart_page: None,
+            axis_ranges: BTreeMap::new(),
+            charts: MetricFlags::all(),
+            gpu_charts: BTreeMap::new(),
+            default_charts: Vec::new(),
+            show_totals: false,
+            show_average: false,
+            show_help: false,
+            save_screen: false,
+            gpu_order: GpuOrder::Index,
+            use_color: true,
+            temp_warn: None,
+            temp_hysteresis: 0,
+            temp_crit: None,
+            temp_rate_warn: None,
+            crit_hooks: CritHooks::default(),
+            bell: false,
+            bell_rung_at: None,
+            ring_bell: false,
+            compact: false,
+            dashboard: false,
+            bars: false,
+            detailed: false,
+            detail_scroll: 0,
+            chart_focus: None,
+            layout: None,
+            per_page: None,
+            gpu_page: 0,
+            clock_domain: ClockDomain::Graphics,
+            theme: 0,
+            themes: THEMES,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            exit: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 struct GPUInfo {
-    core_clock: [u32; 30],
-    temperature: [u32; 30],
+    /// Graphics clock in MHz, the domain reported by the exports.
+    core_clock: History<u32>,
+    sm_clock: History<u32>,
+    memory_clock: History<u32>,
+    video_clock: History<u32>,
+    /// The highest clock of each domain in MHz, in `ClockDomain::ALL` order, or zero if
+    /// the GPU doesn't report it.
+    max_clocks: [u32; 4],
+    /// The base clock of each domain in MHz, its default application clock, in
+    /// `ClockDomain::ALL` order, or zero if the GPU doesn't report it.
+    base_clocks: [u32; 4],
+    /// The application clock of each domain in MHz, as set with `nvidia-smi -ac`, in
+    /// `ClockDomain::ALL` order, or zero if the GPU doesn't report one.
+    applications_clocks: [u32; 4],
+    /// The overclock offsets in MHz applied to the graphics and memory clocks, or `None`
+    /// on GPUs that don't report them, which includes every MIG instance.
+    clock_vf_offsets: Option<(i32, i32)>,
+    /// The lowest and highest offsets in MHz the graphics and memory clocks accept, or
+    /// `None` on GPUs that don't report them.
+    clock_vf_offset_ranges: Option<[(i32, i32); 2]>,
+    /// The current performance state, from 0 (maximum performance) to 15, or `None` if
+    /// the GPU doesn't report it.
+    performance_state: Option<u32>,
+    /// Whether the clocks may boost above the base clock, or `None` if the GPU doesn't
+    /// say, as on GeForce cards.
+    auto_boost: Option<bool>,
+    /// Whether persistence mode keeps the driver loaded, or `None` if the GPU doesn't
+    /// say, as on Windows.
+    persistence_mode: Option<bool>,
+    /// Whether CUDA contexts may be created, or `None` if the GPU doesn't say.
+    compute_mode: Option<ComputeMode>,
+    /// Manual if any fan is held at a speed set by the user, or `None` if no fan says.
+    fan_policy: Option<FanPolicy>,
+    temperature: History<u32>,
+    /// The memory junction temperature, drawn next to the core temperature, or `None`
+    /// if the GPU has no such sensor.
+    memory_temperature: Option<History<u32>>,
+    /// The temperatures in °C at which the card throttles and shuts down, which stand
+    /// in for a missing `--temp-warn` and `--temp-crit`. `None` if it doesn't say.
+    slowdown_temperature: Option<u32>,
+    shutdown_temperature: Option<u32>,
+    /// The temperature threshold reached as of the latest pushed reading, held until
+    /// the GPU cools `temp_hysteresis` below it.
+    temperature_alert: Option<TemperatureLevel>,
+    memory_used: History<u64>,
+    memory_total: u64,
+    /// The part of the used memory the driver reserves for itself, or `None` on older
+    /// drivers and MIG instances.
+    memory_reserved: Option<u64>,
+    gpu_utilization: History<u32>,
+    memory_utilization: History<u32>,
+    /// Power draw in milliwatts.
+    power_usage: History<u32>,
+    /// The power the GPU die alone draws, drawn under the board power, or `None` if the
+    /// GPU only reports the one figure.
+    die_power: Option<History<u32>>,
+    /// The graphics clock in MHz per watt drawn, zero on ticks where either couldn't be
+    /// read.
+    efficiency: History<f64>,
+    /// The enforced power limit in milliwatts, used as the power chart's upper bound.
+    power_limit: u32,
+    /// The lowest and highest power limit in milliwatts that can be set, drawn on the
+    /// power chart. `None` on cards without power management.
+    power_limit_constraints: Option<(u32, u32)>,
+    /// The width of the memory bus in bits, for the estimated memory bandwidth.
+    memory_bus_width: Option<u32>,
+    /// PCIe receive and transmit throughput in KB/s, as seen from the GPU.
+    pcie_rx: History<u32>,
+    pcie_tx: History<u32>,
+    /// NVENC and NVDEC utilization in percent.
+    encoder_utilization: History<u32>,
+    decoder_utilization: History<u32>,
+    /// Fan speed in percent, one history per fan. Empty for passively cooled cards.
+    fan_speeds: Vec<History<u32>>,
+    /// Utilization in percent of the media engines beyond NVENC and NVDEC, one history
+    /// per engine the GPU reports. Empty on older cards and drivers.
+    engine_utilization: Vec<(MediaEngine, History<u32>)>,
+    /// The NVLinks of the GPU, empty on cards without NVLink and on MIG instances.
+    nvlinks: Vec<NvLink>,
+    /// Why the clocks are currently held back, shown as badges under the clock chart.
+    throttle_reasons: ThrottleReasons,
+    throttle_events: ThrottleEvents,
+    ecc_errors: EccErrors,
+    /// Processes running on the GPU, only collected while the process panel is shown.
+    processes: Vec<GpuProcess>,
+    /// The memory of those processes over the history window.
+    process_memory: ProcessMemory,
+    /// The driver's SM utilization and power samples, read with `--high-res`.
+    utilization_samples: SampleBuffer,
+    power_samples: SampleBuffer,
     device_name: String,
+    /// Identifies the physical card, or MIG instance, unlike the index, which can change
+    /// between boots. `None` if the driver didn't report it.
+    uuid: Option<String>,
+    /// The board serial number, only reported by some datacenter cards.
+    serial: Option<String>,
+    /// Whether the chart titles name the GPU by its UUID, toggled for every GPU with `i`.
+    show_uuid: bool,
+    /// The unit temperatures are shown in, the same for every GPU.
+    temperature_unit: TemperatureUnit,
+    /// How power and efficiency are rounded, the same for every GPU.
+    precision: Precision,
+    /// The charts the config file lets this GPU lay out, of the ones it supports.
+    enabled_charts: MetricFlags,
+    /// The charts laid out before the others, in order.
+    leading_charts: Vec<Metric>,
+    /// The NVML hardware index, which differs from the position in `NvidiaApp::gpus`
+    /// when only some GPUs are monitored. MIG instances share the index of their GPU.
+    index: u32,
+    /// Which MIG instance of the GPU at `index` this is, or `None` for a whole GPU.
+    mig_index: Option<u32>,
+    /// How many GPUs this pseudo-GPU of [`GPUInfo::average`] averages, or `None` for a
+    /// real device.
+    average_of: Option<usize>,
+    /// Metrics this GPU can report. Unsupported ones are neither queried nor charted.
+    supported: MetricFlags,
+    /// The last query error for every metric that is currently failing.
+    errors: BTreeMap<Metric, String>,
+    /// The failing metrics the user isn't allowed to read, whose panels say so.
+    denied: MetricFlags,
+    /// The readings captured with `b`, which the chart titles show the change since.
+    baseline: Option<GpuSnapshot>,
+    /// Whether the charts of this GPU are frozen with `P` while the others keep updating.
+    paused: bool,
+    /// Whether the GPU was lost on the latest tick, as after a driver reset or with an
+    /// eGPU unplugged. Its panel is kept until a rescan no longer finds it.
+    disconnected: bool,
 }
 
-impl NvidiaApp {
-    pub fn run_app(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
-        let nvml = Nvml::init().expect("Failed to initialize NVML");
-        let device_count = nvml.device_count().expect("Failed to get device count");
-        debug!("Found {} devices", device_count);
+impl GPUInfo {
+    fn new(device_name: String, memory_total: u64, num_fans: u32, history_len: usize) -> Self {
+        Self {
+            core_clock: History::new(history_len),
+            sm_clock: History::new(history_len),
+            memory_clock: History::new(history_len),
+            video_clock: History::new(history_len),
+            max_clocks: [0; 4],
+            base_clocks: [0; 4],
+            applications_clocks: [0; 4],
+            temperature: History::new(history_len),
+            memory_temperature: None,
+            slowdown_temperature: None,
+            shutdown_temperature: None,
+            memory_used: History::new(history_len),
+            memory_total,
+            memory_reserved: None,
+            gpu_utilization: History::new(history_len),
+            memory_utilization: History::new(history_len),
+            power_usage: History::new(history_len),
+            die_power: None,
+            efficiency: History::new(history_len),
+            power_limit: 0,
+            power_limit_constraints: None,
+            memory_bus_width: None,
+            pcie_rx: History::new(history_len),
+            pcie_tx: History::new(history_len),
+            encoder_utilization: History::new(history_len),
+            decoder_utilization: History::new(history_len),
+            fan_speeds: (0..num_fans).map(|_| History::new(history_len)).collect(),
+            engine_utilization: Vec::new(),
+            nvlinks: Vec::new(),
+            throttle_reasons: ThrottleReasons::empty(),
+            throttle_events: ThrottleEvents::default(),
+            ecc_errors: EccErrors::default(),
+            clock_vf_offsets: None,
+            clock_vf_offset_ranges: None,
+            performance_state: None,
+            auto_boost: None,
+            persistence_mode: None,
+            compute_mode: None,
+            fan_policy: None,
+            temperature_alert: None,
+            processes: Vec::new(),
+            process_memory: ProcessMemory::new(history_len),
+            utilization_samples: SampleBuffer::default(),
+            power_samples: SampleBuffer::default(),
+            device_name,
+            uuid: None,
+            serial: None,
+            show_uuid: false,
+            temperature_unit: TemperatureUnit::default(),
+            precision: Precision::default(),
+            enabled_charts: MetricFlags::all(),
+            leading_charts: Vec::new(),
+            index: 0,
+            mig_index: None,
+            average_of: None,
+            supported: MetricFlags::all(),
+            errors: BTreeMap::new(),
+            denied: MetricFlags::empty(),
+            baseline: None,
+            paused: false,
+            disconnected: false,
+        }
+    }
 
-        if device_count == 0 {
-            eprintln!("Error: No GPUs found. Please ensure that your system has NVIDIA GPUs installed and try again.");
-            std::process::exit(1);
-        }
-
-        for i in 0..device_count {
-            let gpu_device = nvml
-                .device_by_index(i)
-                .expect("Failed to get device by index");
-            let device_name = gpu_device.name().expect("Failed to get GPU name");
-            debug!("Found device: {}", device_name);
-            self.gpus.push(GPUInfo {
-                core_clock: [0; 30],
-                temperature: [0; 30],
-                device_name,
-            });
+    /// Records the outcome of querying `metric`. A failure is kept until the metric is
+    /// successfully read again on a later tick.
+    fn record<T>(&mut self, metric: Metric, result: Result<T, NvmlError>) -> Option<T> {
+        self.denied.set(
+            MetricFlags::from(metric),
+            matches!(result, Err(NvmlError::NoPermission)),
+        );
+        match result {
+            Ok(value) => {
+                if self.errors.remove(&metric).is_some() {
+                    info!("{} {} query recovered", self.label(), metric);
+                }
+                Some(value)
+            }
+            Err(error) => {
+                self.disconnected |= matches!(error, NvmlError::GpuLost);
+                let error = match error {
+                    NvmlError::NoPermission => metric::permission_message(metric),
+                    error => error.to_string(),
+                };
+                // Only the first failure is worth an entry in an info-level log
+                match self.errors.insert(metric, error.clone()) {
+                    None => info!(
+                        "{} {} query started failing: {}",
+                        self.label(),
+                        metric,
+                        error
+                    ),
+                    Some(_) => debug!("{} {} query failed: {}", self.label(), metric, error),
+                }
+                None
+            }
+        }
+    }
+
+    /// Flags every supported metric as failed and advances all histories with a gap, for
+    /// ticks where the device itself couldn't be queried. Unsupported metrics are never
+    /// queried again, so flagging them would keep them failed after the device recovers.
+    fn skip_tick(&mut self, error: &NvmlError) {
+        for metric in Metric::ALL {
+            if self.supports(metric) {
+                self.errors.insert(metric, error.to_string());
+            }
+        }
+        self.core_clock.push_gap();
+        self.sm_clock.push_gap();
+        self.memory_clock.push_gap();
+        self.video_clock.push_gap();
+        self.temperature.push_gap();
+        if let Some(memory_temperature) = self.memory_temperature.as_mut() {
+            memory_temperature.push_gap();
+        }
+        self.memory_used.push_gap();
+        self.gpu_utilization.push_gap();
+        self.memory_utilization.push_gap();
+        self.power_usage.push_gap();
+        if let Some(die_power) = self.die_power.as_mut() {
+            die_power.push_gap();
+        }
+        self.efficiency.push_gap();
+        self.pcie_rx.push_gap();
+        self.pcie_tx.push_gap();
+        self.encoder_utilization.push_gap();
+        self.decoder_utilization.push_gap();
+        for fan_speed in self.fan_speeds.iter_mut() {
+            fan_speed.push_gap();
+        }
+        for (_, utilization) in self.engine_utilization.iter_mut() {
+            utilization.push_gap();
+        }
+        for link in self.nvlinks.iter_mut() {
+            link.update(None, Duration::ZERO);
         }
+    }
 
-        while !self.exit {
-            self.update_state()?;
-            let _ = terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
-            thread::sleep(Duration::from_secs(1));
+    /// Appends the latest samples of `reading`, the same GPU as sampled by the collection
+    /// thread, and takes over everything that isn't kept as a history.
+    fn push_reading(&mut self, reading: &GPUInfo) {
+        self.core_clock.push_latest(&reading.core_clock);
+        self.sm_clock.push_latest(&reading.sm_clock);
+        self.memory_clock.push_latest(&reading.memory_clock);
+        self.video_clock.push_latest(&reading.video_clock);
+        self.temperature.push_latest(&reading.temperature);
+        if let (Some(memory_temperature), Some(reading)) = (
+            self.memory_temperature.as_mut(),
+            &reading.memory_temperature,
+        ) {
+            memory_temperature.push_latest(reading);
         }
-        Ok(())
+        self.memory_used.push_latest(&reading.memory_used);
+        self.gpu_utilization.push_latest(&reading.gpu_utilization);
+        self.memory_utilization
+            .push_latest(&reading.memory_utilization);
+        self.power_usage.push_latest(&reading.power_usage);
+        if let (Some(die_power), Some(reading)) = (self.die_power.as_mut(), &reading.die_power) {
+            die_power.push_latest(reading);
+        }
+        self.efficiency.push_latest(&reading.efficiency);
+        self.pcie_rx.push_latest(&reading.pcie_rx);
+        self.pcie_tx.push_latest(&reading.pcie_tx);
+        self.encoder_utilization
+            .push_latest(&reading.encoder_utilization);
+        self.decoder_utilization
+            .push_latest(&reading.decoder_utilization);
+        for (fan_speed, reading) in self.fan_speeds.iter_mut().zip(&reading.fan_speeds) {
+            fan_speed.push_latest(reading);
+        }
+        let engine_readings = reading.engine_utilization.iter();
+        for ((_, utilization), (_, reading)) in
+            self.engine_utilization.iter_mut().zip(engine_readings)
+        {
+            utilization.push_latest(reading);
+        }
+        self.memory_total = reading.memory_total;
+        self.memory_reserved = reading.memory_reserved;
+        self.power_limit = reading.power_limit;
+        self.throttle_reasons = reading.throttle_reasons;
+        self.throttle_events = reading.throttle_events;
+        self.ecc_errors = reading.ecc_errors;
+        self.nvlinks.clone_from(&reading.nvlinks);
+        self.clock_vf_offsets = reading.clock_vf_offsets;
+        self.applications_clocks = reading.applications_clocks;
+        self.performance_state = reading.performance_state;
+        self.auto_boost = reading.auto_boost;
+        self.persistence_mode = reading.persistence_mode;
+        self.compute_mode.clone_from(&reading.compute_mode);
+        self.fan_policy = reading.fan_policy;
+        self.disconnected = reading.disconnected;
+        self.processes.clone_from(&reading.processes);
+        self.process_memory.record(&reading.processes);
+        self.utilization_samples = reading.utilization_samples;
+        self.power_samples = reading.power_samples;
+        self.errors.clone_from(&reading.errors);
+        self.denied = reading.denied;
+    }
+
+    /// Looks for the NVLinks `source` reports for this GPU. Only whole GPUs have any.
+    fn probe_nvlinks(&mut self, source: &dyn GpuSource) {
+        if self.mig_index.is_none() {
+            self.nvlinks = (0..NVML_NVLINK_MAX_LINKS)
+                .filter(|&link| source.nvlink_counters(self.index, link).is_ok())
+                .map(NvLink::new)
+                .collect();
+        }
+        self.supported
+            .set(MetricFlags::NVLINK, !self.nvlinks.is_empty());
+    }
+
+    /// Looks for the media engines whose utilization `source` reports for this GPU, which
+    /// only whole GPUs on recent drivers do. Engines that can't be queried are left out.
+    fn probe_engines(&mut self, source: &dyn GpuSource, history_len: usize) {
+        if self.mig_index.is_none() {
+            self.engine_utilization = MediaEngine::ALL
+                .into_iter()
+                .filter(|&engine| {
+                    !matches!(source.engine_utilization(self.index, engine), Err(error) if metric::is_unsupported(&error))
+                })
+                .map(|engine| (engine, History::new(history_len)))
+                .collect();
+        }
+        self.supported
+            .set(MetricFlags::ENGINES, !self.engine_utilization.is_empty());
+    }
+
+    /// Whether the latest power draw reached the enforced power limit.
+    fn at_power_limit(&self) -> bool {
+        self.power_limit > 0 && self.power_usage.latest() >= self.power_limit
     }
 
-    fn update_state(&mut self) -> std::io::Result<()> {
-        let nvml = Nvml::init().expect("Failed to initialize NVML");
+    /// The latest graphics clock in MHz per watt drawn, if both were read this tick.
+    fn clock_per_watt(&self) -> Option<f64> {
+        let watts = self.power_usage.latest() as f64 / 1000.0;
+        (self.available(Metric::Clock) && self.available(Metric::Power) && watts > 0.0)
+            .then(|| self.core_clock.latest() as f64 / watts)
+    }
 
-        for (i, gpu_info) in self.gpus.iter_mut().enumerate() {
-            let gpu_device = nvml
-                .device_by_index(i.try_into().unwrap())
-                .expect("Failed to get device by index");
+    /// The base clock of `domain`, if the GPU reports one.
+    fn base_clock(&self, domain: ClockDomain) -> Option<u32> {
+        Some(self.base_clocks[domain as usize]).filter(|&clock| clock > 0)
+    }
 
-            let current_clock = gpu_device
-                .clock_info(nvml_wrapper::enum_wrappers::device::Clock::Graphics)
-                .expect("Failed to retrieve GPU clock speed");
-            debug!("GPU {} clock: {}", i, current_clock);
-            gpu_info.core_clock.rotate_left(1);
-            gpu_info.core_clock[29] = current_clock;
+    /// The clock `domain` was asked to run at, if application clocks are set.
+    fn applications_clock(&self, domain: ClockDomain) -> Option<u32> {
+        Some(self.applications_clocks[domain as usize]).filter(|&clock| clock > 0)
+    }
 
-            let gpu_current_temperature = gpu_device
-                .temperature(TemperatureSensor::Gpu)
-                .expect("Failed to retrieve GPU temperature");
-            debug!("GPU {} temperature: {}", i, gpu_current_temperature);
-            gpu_info.temperature.rotate_left(1);
-            gpu_info.temperature[29] = gpu_current_temperature;
+    /// The overclock offset in MHz applied to `domain`, if the GPU reports one. The SM
+    /// clock follows the graphics clock offset, and the video clock has no offset.
+    fn clock_vf_offset(&self, domain: ClockDomain) -> Option<i32> {
+        let (gpc_offset, memory_offset) = self.clock_vf_offsets?;
+        match domain {
+            ClockDomain::Graphics | ClockDomain::Sm => Some(gpc_offset),
+            ClockDomain::Memory => Some(memory_offset),
+            ClockDomain::Video => None,
         }
+    }
 
-        Ok(())
+    /// The lowest and highest overclock offsets in MHz `domain` accepts, if the GPU
+    /// reports them.
+    fn clock_vf_offset_range(&self, domain: ClockDomain) -> Option<(i32, i32)> {
+        let [gpc_range, memory_range] = self.clock_vf_offset_ranges?;
+        match domain {
+            ClockDomain::Graphics | ClockDomain::Sm => Some(gpc_range),
+            ClockDomain::Memory => Some(memory_range),
+            ClockDomain::Video => None,
+        }
     }
 
-    fn draw(&self, frame: &mut Frame) {
-        // if number of gpus is less than one then error and exit
-        let num_gpus = self.gpus.len();
-        if num_gpus == 0 {
-            eprintln!("Error: No GPUs found. Please ensure that your system has NVIDIA GPUs installed and try again.");
-            std::process::exit(1);
+    fn clock(&self, domain: ClockDomain) -> &History<u32> {
+        match domain {
+            ClockDomain::Graphics => &self.core_clock,
+            ClockDomain::Sm => &self.sm_clock,
+            ClockDomain::Memory => &self.memory_clock,
+            ClockDomain::Video => &self.video_clock,
         }
+    }
+
+    /// How the GPU is named in titles: "GPU 0", "GPU 0 MIG 1" for a MIG instance, or
+    /// "Average" for the average of every GPU.
+    fn label(&self) -> String {
+        match (self.average_of, self.mig_index) {
+            (Some(_), _) => String::from("Average"),
+            (None, Some(mig_index)) => format!("GPU {} MIG {}", self.index, mig_index),
+            (None, None) => format!("GPU {}", self.index),
+        }
+    }
+
+    /// A pseudo-GPU named "All 4 GPUs", whose temperature, clocks and utilization
+    /// are the means of those of `gpus`, which must not be empty, for the pulse of a box
+    /// of identical cards. Only those charts are shown, and the clock axis goes up to
+    /// the highest maximum clock among them.
+    fn average(gpus: &[GPUInfo]) -> GPUInfo {
+        let mean = |history: fn(&GPUInfo) -> &History<u32>| {
+            History::mean(&gpus.iter().map(history).collect::<Vec<_>>())
+        };
+        let mut average = GPUInfo::new(
+            format!("All {} GPUs", gpus.len()),
+            0,
+            0,
+            gpus[0].temperature.len(),
+        );
+        average.average_of = Some(gpus.len());
+        average.temperature = mean(|gpu_info| &gpu_info.temperature);
+        average.core_clock = mean(|gpu_info| &gpu_info.core_clock);
+        average.sm_clock = mean(|gpu_info| &gpu_info.sm_clock);
+        average.memory_clock = mean(|gpu_info| &gpu_info.memory_clock);
+        average.video_clock = mean(|gpu_info| &gpu_info.video_clock);
+        average.gpu_utilization = mean(|gpu_info| &gpu_info.gpu_utilization);
+        average.memory_utilization = mean(|gpu_info| &gpu_info.memory_utilization);
+        for domain in ClockDomain::ALL {
+            average.max_clocks[domain as usize] = gpus
+                .iter()
+                .map(|gpu_info| gpu_info.max_clocks[domain as usize])
+                .max()
+                .unwrap_or_default();
+        }
+        average.supported =
+            MetricFlags::TEMPERATURE | MetricFlags::CLOCK | MetricFlags::UTILIZATION;
+        average.enabled_charts = average.supported;
+        average.leading_charts = gpus[0].leading_charts.clone();
+        average.temperature_unit = gpus[0].temperature_unit;
+        average.precision = gpus[0].precision;
+        average
+    }
+
+    /// Whether `other` is the same GPU or MIG instance as this one, such as a later
+    /// reading of it. Without UUIDs to compare, a different model at the same index is
+    /// taken for a GPU that was swapped.
+    fn same_gpu(&self, other: &GPUInfo) -> bool {
+        match (&self.uuid, &other.uuid) {
+            (Some(uuid), Some(other_uuid)) => uuid == other_uuid,
+            _ => {
+                self.index == other.index
+                    && self.mig_index == other.mig_index
+                    && self.device_name == other.device_name
+            }
+        }
+    }
+
+    /// How the GPU is named in the chart titles: by its UUID while `show_uuid` is set
+    /// and the UUID is known, by its model otherwise, and marked while disconnected. The
+    /// name is shortened by [`abbreviate_name`] to at most `width` characters, keeping
+    /// the mark.
+    fn title_name(&self, width: usize) -> String {
+        let name = match &self.uuid {
+            Some(uuid) if self.show_uuid => uuid,
+            _ => &self.device_name,
+        };
+        let mark = if self.disconnected {
+            " (disconnected)"
+        } else {
+            ""
+        };
+        let name = abbreviate_name(name, width.saturating_sub(mark.len()));
+        format!("{}{}", name, mark)
+    }
+
+    /// The share of memory in use for the GPU titles, such as `84% VRAM`, unless the
+    /// memory can't be read.
+    fn memory_title(&self) -> Option<String> {
+        if !self.available(Metric::Memory) || self.memory_total == 0 {
+            return None;
+        }
+        let percent = self
+            .precision
+            .memory_percent(self.memory_used.latest(), self.memory_total);
+        Some(format!("{} VRAM", percent))
+    }
+
+    /// How the GPU is identified in the exports: "0", or "0:1" for a MIG instance.
+    fn id(&self) -> String {
+        match self.mig_index {
+            Some(mig_index) => format!("{}:{}", self.index, mig_index),
+            None => self.index.to_string(),
+        }
+    }
+
+    /// Looks up this GPU, or this MIG instance, in `source`. Once disconnected, another
+    /// GPU may have taken its index, so it's only found again under its own UUID, or its
+    /// model without one.
+    fn device<'a>(&self, source: &'a dyn GpuSource) -> Result<&'a dyn GpuDevice, NvmlError> {
+        let gpu_device = match self.mig_index {
+            Some(mig_index) => source.mig_device(self.index, mig_index)?,
+            None => source.device(self.index)?,
+        };
+        let same_gpu = match &self.uuid {
+            Some(uuid) => gpu_device.uuid().ok().as_ref() == Some(uuid),
+            None => gpu_device.name().ok().as_ref() == Some(&self.device_name),
+        };
+        match self.disconnected && !same_gpu {
+            true => Err(NvmlError::NotFound),
+            false => Ok(gpu_device),
+        }
+    }
+
+    /// The highest clock `domain` can reach, if the GPU reports it.
+    fn max_clock(&self, domain: ClockDomain) -> Option<u32> {
+        Some(self.max_clocks[domain as usize]).filter(|&max_clock| max_clock > 0)
+    }
+
+    /// The theoretical memory bandwidth in GB/s, from the bus width and the highest
+    /// memory clock, at two transfers per clock. `None` unless the GPU reports both.
+    fn peak_memory_bandwidth(&self) -> Option<f64> {
+        let bus_width = self.memory_bus_width.filter(|&bus_width| bus_width > 0)?;
+        let memory_clock = self.max_clock(ClockDomain::Memory)?;
+        Some(f64::from(bus_width) / 8.0 * f64::from(memory_clock) * 2.0 / 1000.0)
+    }
+
+    /// Whether every supported metric failed on the latest tick, as when the device
+    /// itself couldn't be looked up.
+    fn tick_failed(&self) -> bool {
+        !self.errors.is_empty()
+            && Metric::ALL
+                .into_iter()
+                .filter(|&metric| self.supports(metric))
+                .all(|metric| self.errors.contains_key(&metric))
+    }
+
+    fn error(&self, metric: Metric) -> Option<&str> {
+        self.errors.get(&metric).map(String::as_str)
+    }
+
+    /// What a panel shows instead of the value of a failing `metric`: "N/A", or why the
+    /// user isn't allowed to read it.
+    fn unavailable(&self, metric: Metric) -> Option<&str> {
+        let error = self.error(metric)?;
+        Some(if self.denied.contains(MetricFlags::from(metric)) {
+            error
+        } else {
+            "N/A"
+        })
+    }
+
+    fn resize_history(&mut self, history_len: usize) {
+        self.core_clock.resize(history_len);
+        self.sm_clock.resize(history_len);
+        self.memory_clock.resize(history_len);
+        self.video_clock.resize(history_len);
+        self.temperature.resize(history_len);
+        if let Some(memory_temperature) = self.memory_temperature.as_mut() {
+            memory_temperature.resize(history_len);
+        }
+        self.memory_used.resize(history_len);
+        self.gpu_utilization.resize(history_len);
+        self.memory_utilization.resize(history_len);
+        self.power_usage.resize(history_len);
+        if let Some(die_power) = self.die_power.as_mut() {
+            die_power.resize(history_len);
+        }
+        self.efficiency.resize(history_len);
+        self.pcie_rx.resize(history_len);
+        self.pcie_tx.resize(history_len);
+        self.encoder_utilization.resize(history_len);
+        self.decoder_utilization.resize(history_len);
+        for fan_speed in self.fan_speeds.iter_mut() {
+            fan_speed.resize(history_len);
+        }
+        for (_, utilization) in self.engine_utilization.iter_mut() {
+            utilization.resize(history_len);
+        }
+        self.process_memory.resize(history_len);
+    }
+
+    /// A copy of this GPU with only the `len` samples of every history that were the
+    /// newest `offset` samples ago, to draw as they were then, with the newest sample on
+    /// the left if `mirrored`.
+    fn window(&self, len: usize, offset: usize, mirrored: bool) -> GPUInfo {
+        let mut window = self.clone();
+        let histories = [
+            &mut window.core_clock,
+            &mut window.sm_clock,
+            &mut window.memory_clock,
+            &mut window.video_clock,
+            &mut window.temperature,
+            &mut window.gpu_utilization,
+            &mut window.memory_utilization,
+            &mut window.power_usage,
+            &mut window.pcie_rx,
+            &mut window.pcie_tx,
+            &mut window.encoder_utilization,
+            &mut window.decoder_utilization,
+        ];
+        let optional = [
+            window.memory_temperature.as_mut(),
+            window.die_power.as_mut(),
+        ];
+        let fans = window.fan_speeds.iter_mut();
+        let engines = window
+            .engine_utilization
+            .iter_mut()
+            .map(|(_, history)| history);
+        for history in histories
+            .into_iter()
+            .chain(optional.into_iter().flatten())
+            .chain(fans)
+            .chain(engines)
+        {
+            *history = history.window(len, offset).mirror(mirrored);
+        }
+        window.memory_used = window.memory_used.window(len, offset).mirror(mirrored);
+        window.efficiency = window.efficiency.window(len, offset).mirror(mirrored);
+        window
+    }
+
+    /// Drops every sample and session peak, as if monitoring had just started.
+    fn clear_history(&mut self) {
+        self.core_clock.clear();
+        self.sm_clock.clear();
+        self.memory_clock.clear();
+        self.video_clock.clear();
+        self.temperature.clear();
+        if let Some(memory_temperature) = self.memory_temperature.as_mut() {
+            memory_temperature.clear();
+        }
+        self.memory_used.clear();
+        self.gpu_utilization.clear();
+        self.memory_utilization.clear();
+        self.power_usage.clear();
+        if let Some(die_power) = self.die_power.as_mut() {
+            die_power.clear();
+        }
+        self.efficiency.clear();
+        self.pcie_rx.clear();
+        self.pcie_tx.clear();
+        self.encoder_utilization.clear();
+        self.decoder_utilization.clear();
+        for fan_speed in self.fan_speeds.iter_mut() {
+            fan_speed.clear();
+        }
+        for (_, utilization) in self.engine_utilization.iter_mut() {
+            utilization.clear();
+        }
+        self.process_memory.clear();
+    }
 
-        let percentage = 100 / num_gpus as u16;
+    /// Runs `query` for a supported `metric` and records its outcome. Unsupported metrics
+    /// are never queried and yield `None` without flagging an error.
+    fn query<T>(
+        &mut self,
+        metric: Metric,
+        query: impl FnOnce() -> Result<T, NvmlError>,
+    ) -> Option<T> {
+        if !self.supports(metric) {
+            return None;
+        }
+        self.record(metric, query())
+    }
 
-        let constraints = vec![Constraint::Percentage(percentage); num_gpus];
+    fn supports(&self, metric: Metric) -> bool {
+        self.supported.contains(MetricFlags::from(metric))
+    }
 
-        let chunks: Vec<Rect> = Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .constraints(constraints)
-            .split(frame.area())
-            .to_vec();
+    /// Whether the latest sample of `metric` holds real data: the metric is supported
+    /// and its last query succeeded.
+    fn available(&self, metric: Metric) -> bool {
+        self.supports(metric) && self.error(metric).is_none()
+    }
 
-        for (i, gpu_info) in self.gpus.iter().enumerate() {
-            debug!("Drawing GPU {}: {}", i, gpu_info.device_name);
-            let gpu_chunks: Vec<Rect> = Layout::default()
-                .direction(ratatui::layout::Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                .split(chunks[i])
+    /// Lays out one chart area per supported metric within `area`, so panels for
+    /// metrics the GPU can't report are left out instead of showing empty charts.
+    ///
+    /// With `overlay`, temperature is drawn in the clock chart and gets no area of its own.
+    ///
+    /// With a `page`, only that page of [`CHARTS_PER_PAGE`] charts is laid out, wrapping
+    /// around on GPUs with fewer pages.
+    fn chart_areas(
+        &self,
+        area: Rect,
+        overlay: bool,
+        page: Option<usize>,
+    ) -> BTreeMap<Metric, Rect> {
+        let (_, area) = self.split_gauge(area);
+        let mut charts = self.charts(overlay);
+        if let Some(page) = page {
+            let page_count = self.chart_page_count(overlay).max(1);
+            charts = charts
+                .chunks(CHARTS_PER_PAGE)
+                .nth(page % page_count)
+                .unwrap_or_default()
                 .to_vec();
+        }
+        let areas = split_charts(area, charts.len());
+        charts.into_iter().zip(areas).collect()
+    }
+
+    /// Whether `metric` is supported and its chart is enabled in the config file.
+    fn shows(&self, metric: Metric) -> bool {
+        self.supports(metric) && self.enabled_charts.contains(MetricFlags::from(metric))
+    }
 
-            let clock_chunk = gpu_chunks[0];
-            let temp_chunk = gpu_chunks[1];
+    /// The charts and panels this GPU gets, in layout order: the leading charts, then
+    /// the others. Leaving some out makes the others larger.
+    fn charts(&self, overlay: bool) -> Vec<Metric> {
+        let overlaid = overlay && self.overlays_temperature();
+        let others = CHART_METRICS
+            .into_iter()
+            .filter(|metric| !self.leading_charts.contains(metric));
+        self.leading_charts
+            .iter()
+            .copied()
+            .chain(others)
+            .filter(|&metric| self.shows(metric))
+            .filter(|&metric| metric != Metric::Fan || !self.fan_speeds.is_empty())
+            .filter(|&metric| metric != Metric::Engines || !self.engine_utilization.is_empty())
+            .filter(|&metric| metric != Metric::NvLink || !self.nvlinks.is_empty())
+            .filter(|&metric| metric != Metric::Temperature || !overlaid)
+            .collect()
+    }
 
-            let clock_title = Title::from(format!("NVIDIA GPU Clock - {}", gpu_info.device_name));
-            let clock_block = Block::bordered()
-                .border_style(Style::new().fg(ratatui::style::Color::Rgb(117, 255, 0)))
-                .title(clock_title.alignment(ratatui::layout::Alignment::Center));
+    /// The charts the detailed view stacks, in order.
+    fn detail_charts(&self) -> Vec<Metric> {
+        DETAIL_METRICS
+            .into_iter()
+            .filter(|&metric| self.shows(metric))
+            .collect()
+    }
 
-            let temp_title =
-                Title::from(format!("NVIDIA GPU Temperature - {}", gpu_info.device_name));
-            let temp_block = Block::bordered()
-                .border_style(Style::new().fg(ratatui::style::Color::Rgb(255, 0, 255)))
-                .title(temp_title.alignment(ratatui::layout::Alignment::Center));
+    /// Stacks the charts of the detailed view in `area`, each the full width so their
+    /// time axes line up. As many as fit at [`DETAIL_CHART_HEIGHT`] are shown, starting
+    /// `scroll` charts down the column.
+    fn detail_areas(&self, area: Rect, scroll: usize) -> BTreeMap<Metric, Rect> {
+        let (_, area) = self.split_gauge(area);
+        let charts = self.detail_charts();
+        let fit = usize::from(area.height / DETAIL_CHART_HEIGHT).clamp(1, charts.len().max(1));
+        let start = scroll.min(charts.len().saturating_sub(fit));
+        let charts = &charts[start..(start + fit).min(charts.len())];
+        let areas = Layout::vertical(vec![
+            Constraint::Ratio(1, charts.len().max(1) as u32);
+            charts.len()
+        ])
+        .split(area);
+        charts.iter().copied().zip(areas.iter().copied()).collect()
+    }
 
-            let gpu_clock_data: Vec<(f64, f64)> = gpu_info
-                .core_clock
-                .iter()
-                .zip(-29..=0)
-                .map(|(clock, time)| (time as f64, *clock as f64))
-                .collect();
-            let gpu_temperature_data: Vec<(f64, f64)> = gpu_info
-                .temperature
-                .iter()
-                .zip(-29..=0)
-                .map(|(temp, time)| (time as f64, *temp as f64))
-                .collect();
+    /// The number of pages `chart_areas` splits this GPU's charts into.
+    fn chart_page_count(&self, overlay: bool) -> usize {
+        self.charts(overlay).len().div_ceil(CHARTS_PER_PAGE)
+    }
 
-            let current_clock = gpu_info.core_clock[29].max(1); // Ensure the value is at least 1
-            let current_temp = gpu_info.temperature[29].max(1); // Ensure the value is at least 1
+    /// Whether the overlay layout can draw this GPU's temperature in its clock chart.
+    fn overlays_temperature(&self) -> bool {
+        self.shows(Metric::Clock) && self.shows(Metric::Temperature)
+    }
 
-            debug!("Current clock: {}", current_clock);
-            debug!("Current temperature: {}", current_temp);
+    /// Splits the memory gauge row off the top of `area`, if the GPU reports its memory.
+    /// Returns the gauge area and the area left for the charts.
+    fn split_gauge(&self, area: Rect) -> (Option<Rect>, Rect) {
+        if !self.shows(Metric::Memory) || area.height < 2 {
+            return (None, area);
+        }
+        let [gauge_area, charts_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        (Some(gauge_area), charts_area)
+    }
+}
 
-            let current_clock_str = current_clock.to_string();
-            let current_temp_str = current_temp.to_string();
+impl NvidiaApp {
+    pub fn run_app(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        options: &Options,
+    ) -> std::io::Result<()> {
+        let show_processes = Arc::new(AtomicBool::new(self.show_processes));
+        let updates = self.start_collector(options, &show_processes)?;
+        let mut exporters = Exporters::start(options)?;
 
-            let chart_gpu_clock_data = Dataset::default()
-                .name("GPU Clock")
-                .marker(Marker::Dot)
-                .graph_type(ratatui::widgets::GraphType::Line)
-                .data(&gpu_clock_data);
-            let chart_gpu_temperature_data = Dataset::default()
-                .name("GPU Temperature")
-                .marker(Marker::Dot)
-                .graph_type(ratatui::widgets::GraphType::Line)
-                .data(&gpu_temperature_data);
-
-            let chart_gpu_clock_x_axis = Axis::default()
-                .title("Time")
-                .bounds([-30.0, 0.0])
-                .labels(vec!["Time"]);
-            let chart_gpu_clock_y_axis = Axis::default()
-                .title("GPU Clock Speed")
-                .bounds([0.0, current_clock as f64])
-                .labels(vec!["0", current_clock_str.as_str()]);
-
-            let chart_gpu_temperature_x_axis = Axis::default()
-                .title("Time")
-                .bounds([-30.0, 0.0])
-                .labels(vec!["Time"]);
-            // For the temperature chart
-            let chart_gpu_temperature_y_axis = Axis::default()
-                .title("GPU Temperature")
-                .bounds([0.0, current_temp as f64])
-                .labels(vec!["0", current_temp_str.as_str()]);
-
-            let chart_gpu_clock = Chart::new(vec![chart_gpu_clock_data])
-                .block(clock_block.clone())
-                .x_axis(chart_gpu_clock_x_axis)
-                .y_axis(chart_gpu_clock_y_axis)
-                .style(Style::new().fg(ratatui::style::Color::Rgb(48, 226, 173)));
-            chart_gpu_clock.render(clock_chunk, frame.buffer_mut());
-
-            let chart_gpu_temperature = Chart::new(vec![chart_gpu_temperature_data])
-                .block(temp_block.clone())
-                .x_axis(chart_gpu_temperature_x_axis)
-                .y_axis(chart_gpu_temperature_y_axis)
-                .style(Style::new().fg(ratatui::style::Color::Rgb(255, 0, 255)));
-            chart_gpu_temperature.render(temp_chunk, frame.buffer_mut());
-        }
-    }
-
-    fn handle_events(&mut self) -> std::io::Result<()> {
-        if !event::poll(std::time::Duration::from_millis(150)).unwrap() {
-            return Ok(()); // Don't try to read any events if there aren't any available
-        }
-        match event::read()? {
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event);
+        let _ = terminal.draw(|frame| self.draw(frame))?;
+        let mut drawn_at = Instant::now();
+        // The age of the readings in the status bar keeps counting without new ones
+        let mut drawn_age = self.update_age().map(|age| age.as_secs());
+        let mut redraw = false;
+        while !self.exit {
+            show_processes.store(self.lists_processes(), Ordering::Relaxed);
+            // Input is handled right away, but a pending redraw only waits for the next
+            // frame, so a burst of key presses or readings can't spin the CPU
+            let timeout = if redraw {
+                self.frame_time()
+                    .saturating_sub(drawn_at.elapsed())
+                    .min(UI_TICK)
+            } else {
+                UI_TICK
+            };
+            // Redraw on input instead of leaving a resized or changed screen stale until
+            // the next reading arrives
+            redraw |= self.handle_events(timeout)?;
+            redraw |= self.take_messages();
+            loop {
+                match updates.try_recv() {
+                    Ok(Update::Reconnecting) => {
+                        self.reconnecting = true;
+                        redraw = true;
+                    }
+                    // Recorded even while paused, since a GPU fault is never stale
+                    Ok(Update::Xid(event)) => {
+                        self.record_xid(event);
+                        redraw = true;
+                    }
+                    Ok(Update::Readings(readings, _)) if self.paused => {
+                        redraw |= mem::take(&mut self.reconnecting);
+                        debug!("Paused, dropping {} readings", readings.len())
+                    }
+                    Ok(Update::Readings(readings, collection_time)) => {
+                        self.reconnecting = false;
+                        self.record_collection_time(collection_time);
+                        self.push_readings(&readings);
+                        self.check_required_metrics()?;
+                        exporters.export(&self.gpus)?;
+                        redraw = true;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        return Err(std::io::Error::other("The collection thread stopped"));
+                    }
+                }
+            }
+            if mem::take(&mut self.ring_bell) {
+                let backend = terminal.backend_mut();
+                backend.write_all(b"\x07")?;
+                backend.flush()?;
+            }
+            let age = self.update_age().map(|age| age.as_secs());
+            redraw |= age != drawn_age;
+            if redraw && drawn_at.elapsed() >= self.frame_time() {
+                let frame = terminal.draw(|frame| self.draw(frame))?;
+                drawn_at = Instant::now();
+                redraw = false;
+                if mem::take(&mut self.save_screen) {
+                    match screenshot::save(frame.buffer, Path::new(".")) {
+                        Ok(path) => info!("Saved the screen to {}", path.display()),
+                        Err(error) => warn!("Failed to save the screen: {}", error),
+                    }
+                }
+                drawn_age = age;
             }
-            _ => {}
         }
         Ok(())
     }
 
-    fn handle_key_event(&mut self, key_event: event::KeyEvent) {
-        match key_event.code {
-            KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.exit();
+    /// Collects and exports metrics without a TUI until SIGINT or SIGTERM, with
+    /// `--daemon`. The thread sleeps until the next reading arrives, so it costs little
+    /// more than the collection itself.
+    fn run_export_loop(&mut self, options: &Options) -> std::io::Result<()> {
+        let show_processes = Arc::new(AtomicBool::new(self.show_processes));
+        let updates = self.start_collector(options, &show_processes)?;
+        let mut exporters = Exporters::start(options)?;
+        info!(
+            "Exporting {} GPUs every {:?}",
+            self.gpus.len(),
+            self.interval
+        );
+        while !self.interrupted.load(Ordering::Relaxed) {
+            // Woken up now and then to notice a signal between two readings
+            match updates.recv_timeout(SIGNAL_CHECK) {
+                Ok(Update::Readings(readings, collection_time)) => {
+                    self.record_collection_time(collection_time);
+                    self.push_readings(&readings);
+                    self.check_required_metrics()?;
+                    exporters.export(&self.gpus)?;
+                }
+                Ok(Update::Reconnecting) => warn!("Reconnecting to NVML"),
+                Ok(Update::Xid(event)) => self.record_xid(event),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(std::io::Error::other("The collection thread stopped"));
+                }
             }
-            _ => {}
         }
+        info!("Stopping the daemon");
+        Ok(())
     }
 
-    fn exit(&mut self) {
-        self.exit = true;
+    /// Starts the collection thread of `options` and monitors the GPUs it probed.
+    fn start_collector(
+        &mut self,
+        options: &Options,
+        show_processes: &Arc<AtomicBool>,
+    ) -> std::io::Result<mpsc::Receiver<Update>> {
+        let (probe, updates) = if let Some(path) = &options.replay {
+            let source = ReplaySource::load(path, options.replay_speed).map_err(|error| {
+                std::io::Error::new(
+                    error.kind(),
+                    format!("Failed to replay {}: {}", path.display(), error),
+                )
+            })?;
+            // Sample the replay as often as the recording was sampled
+            if let Some(interval) = source.interval() {
+                self.interval = interval;
+            }
+            collector::spawn_source(
+                source,
+                self.interval,
+                options.gpus.clone(),
+                Arc::clone(show_processes),
+            )?
+        } else if options.demo {
+            collector::spawn_demo(
+                self.interval,
+                options.gpus.clone(),
+                Arc::clone(show_processes),
+            )?
+        } else if let Some(source) = collector::sysfs_fallback() {
+            collector::spawn_source(
+                source,
+                self.interval,
+                options.gpus.clone(),
+                Arc::clone(show_processes),
+            )?
+        } else {
+            collector::spawn(
+                self.interval,
+                options.gpus.clone(),
+                options.rescan,
+                options.high_res,
+                options.xid,
+                Arc::clone(show_processes),
+            )?
+        };
+        let mut gpus = probe.gpus;
+        for gpu_info in gpus.iter_mut() {
+            gpu_info.resize_history(self.history_len);
+            self.apply_display_settings(gpu_info);
+        }
+        self.gpus = gpus;
+        self.versions = Some(probe.versions);
+        Ok(updates)
     }
-}
 
-impl Widget for &NvidiaApp {
-    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
-        let chunks = Layout::default()
-            .direction(ratatui::layout::Direction::Vertical)
-            .constraints(
-                self.gpus
-                    .iter()
-                    .map(|_| Constraint::Percentage(100 / self.gpus.len() as u16))
-                    .collect::<Vec<Constraint>>(),
-            )
-            .split(area)
-            .to_vec();
-
-        for (i, gpu_info) in self.gpus.iter().enumerate() {
-            let gpu_chunks: Vec<Rect> = Layout::default()
-                .direction(ratatui::layout::Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                .split(chunks[i])
-                .to_vec();
+    /// Keeps the messages logged since the last call for the messages pane, returning
+    /// whether there were any.
+    fn take_messages(&mut self) -> bool {
+        let messages = messages::take();
+        if messages.is_empty() {
+            return false;
+        }
+        self.messages.extend(messages);
+        let excess = self.messages.len().saturating_sub(MAX_MESSAGES);
+        self.messages.drain(..excess);
+        self.messages_scroll = self.messages_scroll.min(self.messages.len());
+        true
+    }
 
-            let clock_chunk = gpu_chunks[0];
-            let temp_chunk = gpu_chunks[1];
+    /// Alerts of an XID error, keeping only the [`MAX_XID_ALERTS`] most recent.
+    fn record_xid(&mut self, event: XidEvent) {
+        warn!("GPU {} reported XID error {:?}", event.index, event.xid);
+        self.xid_events.push(event);
+        let excess = self.xid_events.len().saturating_sub(MAX_XID_ALERTS);
+        self.xid_events.drain(..excess);
+    }
 
-            let clock_title = Title::from(format!("NVIDIA GPU Clock - {}", gpu_info.device_name));
-            let clock_block = Block::bordered()
-                .border_style(Style::new().fg(ratatui::style::Color::Rgb(117, 255, 0)))
-                .title(clock_title.alignment(ratatui::layout::Alignment::Center));
+    /// Copies the display settings that the chart renderers read from every GPU.
+    fn apply_display_settings(&self, gpu_info: &mut GPUInfo) {
+        gpu_info.show_uuid = self.show_uuid;
+        gpu_info.temperature_unit = self.temperature_unit;
+        gpu_info.precision = self.precision;
+        gpu_info.enabled_charts = self
+            .gpu_charts
+            .get(&gpu_info.index)
+            .copied()
+            .unwrap_or(self.charts);
+        gpu_info.leading_charts = self.default_charts.clone();
+    }
 
-            let temp_title =
-                Title::from(format!("NVIDIA GPU Temperature - {}", gpu_info.device_name));
-            let temp_block = Block::bordered()
-                .border_style(Style::new().fg(ratatui::style::Color::Rgb(255, 0, 255)))
-                .title(temp_title.alignment(ratatui::layout::Alignment::Center));
+    /// Enumerates the GPUs visible to `source`, or only the hardware indices in `filter`,
+    /// and probes the metrics each one supports.
+    fn add_gpus(&mut self, source: &dyn GpuSource, filter: Option<&[u32]>) -> std::io::Result<()> {
+        let device_count = source
+            .device_count()
+            .map_err(|error| nvml_error("Failed to get device count", error))?;
+        debug!("Found {} devices", device_count);
 
-            let gpu_clock_data: Vec<(f64, f64)> = gpu_info
-                .core_clock
-                .iter()
-                .zip(-29..=0)
-                .map(|(clock, time)| (time as f64, *clock as f64))
-                .collect();
-            let gpu_temperature_data: Vec<(f64, f64)> = gpu_info
-                .temperature
-                .iter()
-                .zip(-29..=0)
-                .map(|(temp, time)| (time as f64, *temp as f64))
-                .collect();
+        if device_count == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No GPUs found. Please ensure that your system has NVIDIA GPUs installed and try again.",
+            ));
+        }
 
-            let current_clock = gpu_info.core_clock[29].max(1); // Ensure the value is at least 1
-            let current_temp = gpu_info.temperature[29].max(1); // Ensure the value is at least 1
+        let indices: Vec<u32> = match filter {
+            Some(filter) => {
+                if let Some(&missing) = filter.iter().find(|&&i| i >= device_count) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!(
+                            "GPU {} not found. Only {} GPUs are installed, numbered from 0.",
+                            missing, device_count
+                        ),
+                    ));
+                }
+                filter.to_vec()
+            }
+            None => (0..device_count).collect(),
+        };
 
-            let current_clock_str = current_clock.to_string();
-            let current_temp_str = current_temp.to_string();
+        for i in indices {
+            let gpus = self
+                .probe_device(source, i)
+                .map_err(|error| nvml_error("Failed to get device by index", error))?;
+            self.gpus.extend(gpus);
+        }
+        Ok(())
+    }
 
-            let chart_gpu_clock_data = Dataset::default()
-                .name("GPU Clock")
-                .marker(Marker::Dot)
-                .graph_type(ratatui::widgets::GraphType::Line)
-                .data(&gpu_clock_data);
-            let chart_gpu_temperature_data = Dataset::default()
-                .name("GPU Temperature")
-                .marker(Marker::Dot)
-                .graph_type(ratatui::widgets::GraphType::Line)
-                .data(&gpu_temperature_data);
-
-            let chart_gpu_clock_x_axis = Axis::default()
-                .title("Time")
-                .bounds([-30.0, 0.0])
-                .labels(vec!["Time"]);
-            let chart_gpu_clock_y_axis = Axis::default()
-                .title("GPU Clock Speed")
-                .bounds([0.0, current_clock as f64])
-                .labels(vec![current_clock_str.as_str()]);
-
-            let chart_gpu_temperature_x_axis = Axis::default()
-                .title("Time")
-                .bounds([-30.0, 0.0])
-                .labels(vec!["Time"]);
-            let chart_gpu_temperature_y_axis = Axis::default()
-                .title("GPU Temperature")
-                .bounds([0.0, current_temp as f64])
-                .labels(vec![current_temp_str.as_str()]);
-
-            let chart_gpu_clock = Chart::new(vec![chart_gpu_clock_data])
-                .block(clock_block.clone())
-                .x_axis(chart_gpu_clock_x_axis)
-                .y_axis(chart_gpu_clock_y_axis)
-                .style(Style::new().fg(ratatui::style::Color::Rgb(48, 226, 173)));
-            chart_gpu_clock.render(clock_chunk, buf);
-
-            let chart_gpu_temperature = Chart::new(vec![chart_gpu_temperature_data])
-                .block(temp_block.clone())
-                .x_axis(chart_gpu_temperature_x_axis)
-                .y_axis(chart_gpu_temperature_y_axis)
-                .style(Style::new().fg(ratatui::style::Color::Rgb(255, 0, 255)));
-            chart_gpu_temperature.render(temp_chunk, buf);
+    /// Enumerates the GPUs again, for containers and vGPU setups where they come and go
+    /// at runtime. New GPUs are probed, GPUs that are gone are dropped and the others
+    /// keep their history. Unlike at startup, finding no GPUs is not an error.
+    fn rescan_gpus(
+        &mut self,
+        source: &dyn GpuSource,
+        filter: Option<&[u32]>,
+    ) -> Result<(), NvmlError> {
+        let device_count = source.device_count()?;
+        let mut gpus = Vec::new();
+        for i in (0..device_count).filter(|i| filter.is_none_or(|filter| filter.contains(i))) {
+            match self.probe_device(source, i) {
+                Ok(probed) => gpus.extend(probed),
+                Err(error) => debug!("GPU {} lookup failed during a rescan: {}", i, error),
+            }
+        }
+        // A GPU that was fine until now keeps its slot until the next rescan, in case it
+        // only blipped, while one that was already disconnected is dropped
+        for (slot, gpu_info) in self.gpus.iter().enumerate() {
+            if !gpu_info.disconnected && !gpus.iter().any(|probed| probed.same_gpu(gpu_info)) {
+                info!(
+                    "{} ({}) disconnected",
+                    gpu_info.label(),
+                    gpu_info.device_name
+                );
+                let mut kept = gpu_info.clone();
+                kept.disconnected = true;
+                gpus.insert(slot.min(gpus.len()), kept);
+            }
+        }
+        self.replace_gpus(gpus);
+        Ok(())
+    }
+
+    /// Probes the GPU at hardware index `index`, followed by its MIG instances.
+    fn probe_device(&self, source: &dyn GpuSource, index: u32) -> Result<Vec<GPUInfo>, NvmlError> {
+        let gpu_device = source.device(index)?;
+        let mut gpu_info = self.probe_gpu(gpu_device, index, None);
+        gpu_info.probe_engines(source, self.history_len);
+        gpu_info.clock_vf_offset_ranges = source.clock_vf_offset_ranges(index).ok();
+        if source.die_power(index).is_ok_and(|power| power > 0) {
+            gpu_info.die_power = Some(History::new(self.history_len));
+        }
+        gpu_info.probe_nvlinks(source);
+        let mut gpus = vec![gpu_info];
+
+        // A GPU split into MIG instances is monitored as a whole and per instance
+        let mig_count = source.mig_device_count(index).unwrap_or_else(|error| {
+            debug!("GPU {} MIG lookup failed: {}", index, error);
+            0
+        });
+        for mig_index in 0..mig_count {
+            match source.mig_device(index, mig_index) {
+                Ok(mig_device) => gpus.push(self.probe_gpu(mig_device, index, Some(mig_index))),
+                Err(error) => debug!("GPU {} MIG {} lookup failed: {}", index, mig_index, error),
+            }
+        }
+        Ok(gpus)
+    }
+
+    /// Monitors `gpus` from now on. Every GPU that was already monitored keeps its
+    /// `GPUInfo`, and with it its history; new ones start with an empty history.
+    fn replace_gpus(&mut self, gpus: Vec<GPUInfo>) {
+        let selected = self.selected.and_then(|i| self.gpus.get(i)).cloned();
+        let mut previous = mem::take(&mut self.gpus);
+        for gpu_info in gpus {
+            match previous.iter().position(|old| old.same_gpu(&gpu_info)) {
+                Some(i) => {
+                    // A GPU recognized by its UUID may have been enumerated elsewhere
+                    let mut kept = previous.remove(i);
+                    kept.index = gpu_info.index;
+                    kept.mig_index = gpu_info.mig_index;
+                    kept.disconnected = gpu_info.disconnected;
+                    self.gpus.push(kept);
+                }
+                None => {
+                    info!("{} ({}) appeared", gpu_info.label(), gpu_info.device_name);
+                    let mut gpu_info = gpu_info;
+                    self.apply_display_settings(&mut gpu_info);
+                    gpu_info.resize_history(self.history_len);
+                    gpu_info.clear_history();
+                    self.gpus.push(gpu_info);
+                }
+            }
+        }
+        for gpu_info in previous {
+            info!("{} ({}) is gone", gpu_info.label(), gpu_info.device_name);
+        }
+        self.selected = selected.and_then(|selected| {
+            self.gpus
+                .iter()
+                .position(|gpu_info| gpu_info.same_gpu(&selected))
+        });
+    }
+
+    /// Reads what never changes about `gpu_device` and probes the metrics it supports.
+    fn probe_gpu(&self, gpu_device: &dyn GpuDevice, index: u32, mig_index: Option<u32>) -> GPUInfo {
+        let device_name = gpu_device
+            .name()
+            .unwrap_or_else(|_| format!("GPU {}", index));
+        debug!("Found device: {}", device_name);
+        // Memory info is refreshed every tick, so a failure here isn't fatal
+        let memory_total = gpu_device
+            .memory_info()
+            .map(|memory_info| memory_info.total)
+            .unwrap_or_default();
+        // Passively cooled and datacenter cards report no fans at all
+        let num_fans = gpu_device.num_fans().unwrap_or_default();
+        debug!("Device {} has {} fans", device_name, num_fans);
+        let mut gpu_info = GPUInfo::new(device_name, memory_total, num_fans, self.history_len);
+        gpu_info.index = index;
+        gpu_info.mig_index = mig_index;
+        gpu_info.uuid = gpu_device.uuid().ok();
+        gpu_info.serial = gpu_device.serial().ok();
+        debug!(
+            "Device {} has UUID {:?} and serial {:?}",
+            gpu_info.device_name, gpu_info.uuid, gpu_info.serial
+        );
+        // The maximum and base clocks never change, so they're only read once
+        for domain in ClockDomain::ALL {
+            gpu_info.max_clocks[domain as usize] = gpu_device
+                .max_clock_info(domain.clock())
+                .unwrap_or_default();
+            gpu_info.base_clocks[domain as usize] = gpu_device
+                .default_applications_clock(domain.clock())
+                .unwrap_or_default();
+        }
+        gpu_info.power_limit_constraints = gpu_device.power_limit_constraints().ok();
+        gpu_info.memory_bus_width = gpu_device.memory_bus_width().ok();
+        gpu_info.slowdown_temperature = gpu_device
+            .temperature_threshold(TemperatureThreshold::Slowdown)
+            .ok();
+        gpu_info.shutdown_temperature = gpu_device
+            .temperature_threshold(TemperatureThreshold::Shutdown)
+            .ok();
+        gpu_info.supported = metric::probe_metrics(gpu_device);
+        // Most cards have no memory junction sensor, and MIG instances share their GPU's
+        if mig_index.is_none() && gpu_device.memory_temperature().is_ok_and(|temp| temp > 0) {
+            gpu_info.memory_temperature = Some(History::new(self.history_len));
         }
+        debug!(
+            "Device {} supports {:?}",
+            gpu_info.device_name, gpu_info.supported
+        );
+        gpu_info
+    }
+
+    /// Samples every tracked metric once from `source`, reusing the device handles of
+    /// the GPUs, as the collection thread does on every tick.
+    ///
+    /// A failed query never aborts the loop: the metric is flagged on its `GPUInfo` and a
+    /// gap keeps its history aligned with the other metrics until it recovers.
+    fn update_state(&mut self, source: &dyn GpuSource) -> std::io::Result<()> {
+        if self.paused {
+            return Ok(());
+        }
+
+        let show_processes = self.lists_processes();
+        let high_res = self.high_res;
+        let interval = self.interval;
+        for gpu_info in self.gpus.iter_mut().filter(|gpu_info| !gpu_info.paused) {
+            let i = gpu_info.label();
+            let gpu_device = match gpu_info.device(source) {
+                Ok(gpu_device) => {
+                    // Until a query finds it lost again
+                    gpu_info.disconnected = false;
+                    gpu_device
+                }
+                Err(error) => {
+                    gpu_info.disconnected = true;
+                    if gpu_info.tick_failed() {
+                        debug!("{} device lookup failed: {}", i, error);
+                    } else {
+                        info!("{} device lookup started failing: {}", i, error);
+                    }
+                    gpu_info.skip_tick(&error);
+                    continue;
+                }
+            };
+
+            // An idle GPU reads 0 MHz, which is charted, while a failed read is a gap
+            let current_clock = gpu_info.query(Metric::Clock, || {
+                gpu_device.clock_info(ClockDomain::Graphics.clock())
+            });
+            debug!("{} clock: {:?}", i, current_clock);
+            gpu_info.core_clock.push_or_gap(current_clock);
+            // Errors are tracked for the graphics clock only; the other domains are read
+            // on a best-effort basis since not every card reports all of them
+            let supports_clock = gpu_info.supports(Metric::Clock);
+            let other_clock = |domain: ClockDomain| {
+                gpu_device
+                    .clock_info(domain.clock())
+                    .ok()
+                    .filter(|_| supports_clock)
+            };
+            gpu_info.sm_clock.push_or_gap(other_clock(ClockDomain::Sm));
+            gpu_info
+                .memory_clock
+                .push_or_gap(other_clock(ClockDomain::Memory));
+            gpu_info
+                .video_clock
+                .push_or_gap(other_clock(ClockDomain::Video));
+            // Like the other domains, the offsets are best effort: only some consumer cards
+            // report them, and MIG instances share the offsets of their GPU
+            if supports_clock && gpu_info.mig_index.is_none() {
+                gpu_info.clock_vf_offsets = source.clock_vf_offsets(gpu_info.index).ok();
+                debug!("{} clock offsets: {:?}", i, gpu_info.clock_vf_offsets);
+                // Read every tick, since `nvidia-smi -ac` can change them at any time
+                for domain in ClockDomain::ALL {
+                    gpu_info.applications_clocks[domain as usize] = gpu_device
+                        .applications_clock(domain.clock())
+                        .unwrap_or_default();
+                }
+                debug!(
+                    "{} application clocks: {:?}",
+                    i, gpu_info.applications_clocks
+                );
+            }
+            gpu_info.performance_state = match gpu_device.performance_state() {
+                Ok(PerformanceState::Unknown) | Err(_) => None,
+                Ok(state) => Some(state.as_c()),
+            };
+            debug!("{} performance state: {:?}", i, gpu_info.performance_state);
+            gpu_info.auto_boost = gpu_device.auto_boost_enabled().ok();
+            debug!("{} auto boost: {:?}", i, gpu_info.auto_boost);
+            gpu_info.persistence_mode = gpu_device.persistence_mode_enabled().ok();
+            debug!("{} persistence mode: {:?}", i, gpu_info.persistence_mode);
+            gpu_info.compute_mode = gpu_device.compute_mode().ok();
+            debug!("{} compute mode: {:?}", i, gpu_info.compute_mode);
+
+            let gpu_current_temperature = gpu_info.query(Metric::Temperature, || {
+                gpu_device.temperature(TemperatureSensor::Gpu)
+            });
+            debug!("{} temperature: {:?}", i, gpu_current_temperature);
+            gpu_info.temperature.push_or_gap(gpu_current_temperature);
+            if let Some(memory_temperature) = gpu_info.memory_temperature.as_mut() {
+                // Not a metric of its own, so a failure only leaves a gap in its line
+                let temperature = gpu_device.memory_temperature().map_err(|error| {
+                    debug!("{} memory temperature query failed: {}", i, error);
+                });
+                debug!("{} memory temperature: {:?}", i, temperature);
+                memory_temperature.push_or_gap(temperature.ok());
+            }
+
+            match gpu_info.query(Metric::Memory, || gpu_device.memory_info()) {
+                Some(memory_info) => {
+                    debug!("{} memory used: {}", i, memory_info.used);
+                    gpu_info.memory_used.push(memory_info.used);
+                    gpu_info.memory_total = memory_info.total;
+                }
+                None => gpu_info.memory_used.push_gap(),
+            }
+            if gpu_info.mig_index.is_none() {
+                gpu_info.memory_reserved = source.reserved_memory(gpu_info.index).ok();
+                debug!("{} memory reserved: {:?}", i, gpu_info.memory_reserved);
+            }
+
+            let utilization =
+                gpu_info.query(Metric::Utilization, || gpu_device.utilization_rates());
+            let (mut sm_utilization, memory_utilization) = utilization
+                .as_ref()
+                .map(|utilization| (utilization.gpu, utilization.memory))
+                .unzip();
+            if high_res && utilization.is_some() {
+                let samples = &mut gpu_info.utilization_samples;
+                if let Some(mean) = samples.read(gpu_device, Sampling::GpuUtilization) {
+                    sm_utilization = Some(mean);
+                }
+            }
+            debug!(
+                "{} utilization: sm {:?}%, memory {:?}%",
+                i, sm_utilization, memory_utilization
+            );
+            gpu_info.gpu_utilization.push_or_gap(sm_utilization);
+            gpu_info.memory_utilization.push_or_gap(memory_utilization);
+
+            let power = gpu_info.query(Metric::Power, || gpu_device.power_usage());
+            let power_usage = match power {
+                Some(_) if high_res => gpu_info
+                    .power_samples
+                    .read(gpu_device, Sampling::Power)
+                    .or(power),
+                _ => power,
+            };
+            debug!("{} power usage: {:?} mW", i, power_usage);
+            gpu_info.power_usage.push_or_gap(power_usage);
+            if let Some(die_power) = gpu_info.die_power.as_mut() {
+                // Not a metric of its own, so a failure only leaves a gap in its line
+                let power = source.die_power(gpu_info.index).map_err(|error| {
+                    debug!("{} die power query failed: {}", i, error);
+                });
+                debug!("{} die power: {:?} mW", i, power);
+                die_power.push_or_gap(power.ok());
+            }
+            // The limit only bounds the chart, so keep the last known one if it can't be read
+            if let Ok(power_limit) = gpu_device.enforced_power_limit() {
+                gpu_info.power_limit = power_limit;
+            }
+            let efficiency = gpu_info.clock_per_watt();
+            debug!("{} efficiency: {:.2?} MHz/W", i, efficiency);
+            gpu_info.efficiency.push_or_gap(efficiency);
+
+            let throttle_reasons =
+                gpu_info.query(Metric::Throttle, || gpu_device.current_throttle_reasons());
+            // A failed query isn't the GPU throttling differently
+            if let Some(throttle_reasons) = throttle_reasons {
+                let old = gpu_info.throttle_reasons;
+                gpu_info.throttle_events.record(old, throttle_reasons);
+            }
+            let throttle_reasons = throttle_reasons.unwrap_or_else(ThrottleReasons::empty);
+            debug!("{} throttle reasons: {:?}", i, throttle_reasons);
+            log_throttle_change(&i, gpu_info.throttle_reasons, throttle_reasons);
+            gpu_info.throttle_reasons = throttle_reasons;
+
+            gpu_info.ecc_errors = gpu_info
+                .query(Metric::Ecc, || EccErrors::read(gpu_device))
+                .unwrap_or_default();
+            debug!("{} ECC errors: {:?}", i, gpu_info.ecc_errors);
+
+            let pcie_throughput = gpu_info.query(Metric::Pcie, || {
+                Ok((
+                    gpu_device.pcie_throughput(PcieUtilCounter::Receive)?,
+                    gpu_device.pcie_throughput(PcieUtilCounter::Send)?,
+                ))
+            });
+            let (pcie_rx, pcie_tx) = pcie_throughput.unzip();
+            debug!(
+                "{} PCIe throughput: rx {:?} KB/s, tx {:?} KB/s",
+                i, pcie_rx, pcie_tx
+            );
+            gpu_info.pcie_rx.push_or_gap(pcie_rx);
+            gpu_info.pcie_tx.push_or_gap(pcie_tx);
+
+            let codec_utilization = gpu_info.query(Metric::Codec, || {
+                Ok((
+                    gpu_device.encoder_utilization()?,
+                    gpu_device.decoder_utilization()?,
+                ))
+            });
+            let (encoder_utilization, decoder_utilization) = codec_utilization
+                .map(|(encoder, decoder)| {
+                    debug!(
+                        "{} codec sampling periods: encoder {} us, decoder {} us",
+                        i, encoder.sampling_period, decoder.sampling_period
+                    );
+                    (encoder.utilization, decoder.utilization)
+                })
+                .unzip();
+            debug!(
+                "{} codec utilization: encoder {:?}%, decoder {:?}%",
+                i, encoder_utilization, decoder_utilization
+            );
+            gpu_info
+                .encoder_utilization
+                .push_or_gap(encoder_utilization);
+            gpu_info
+                .decoder_utilization
+                .push_or_gap(decoder_utilization);
+
+            for fan in 0..gpu_info.fan_speeds.len() {
+                let fan_speed = gpu_info.query(Metric::Fan, || gpu_device.fan_speed(fan as u32));
+                debug!("{} fan {} speed: {:?}%", i, fan, fan_speed);
+                gpu_info.fan_speeds[fan].push_or_gap(fan_speed);
+            }
+            // Best effort like the offsets, since `nvidia-settings` can take over a fan at
+            // any time
+            if gpu_info.mig_index.is_none() {
+                gpu_info.fan_policy = (0..gpu_info.fan_speeds.len() as u32)
+                    .filter_map(|fan| source.fan_policy(gpu_info.index, fan).ok())
+                    .max_by_key(|&policy| policy == FanPolicy::Manual);
+                debug!("{} fan policy: {:?}", i, gpu_info.fan_policy);
+            }
+
+            let index = gpu_info.index;
+            for engine in 0..gpu_info.engine_utilization.len() {
+                let engine_kind = gpu_info.engine_utilization[engine].0;
+                let utilization = gpu_info.query(Metric::Engines, || {
+                    source.engine_utilization(index, engine_kind)
+                });
+                debug!("{} {} utilization: {:?}%", i, engine_kind, utilization);
+                gpu_info.engine_utilization[engine]
+                    .1
+                    .push_or_gap(utilization);
+            }
+
+            for link in 0..gpu_info.nvlinks.len() {
+                let link_index = gpu_info.nvlinks[link].link;
+                let counters =
+                    gpu_info.query(Metric::NvLink, || source.nvlink_counters(index, link_index));
+                debug!("{} NVLink {}: {:?}", i, link_index, counters);
+                gpu_info.nvlinks[link].update(counters, interval);
+            }
+
+            if show_processes {
+                gpu_info.processes = gpu_info
+                    .query(Metric::Processes, || gpu_device.running_processes())
+                    .unwrap_or_default();
+                gpu_info.process_memory.record(&gpu_info.processes);
+                debug!("{} processes: {}", i, gpu_info.processes.len());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pushes one tick of readings from the collection thread into the history of every
+    /// GPU, unless paused. `readings` is in the order the GPUs were probed in, and lists
+    /// other GPUs than before once the collection thread rescanned them.
+    fn push_readings(&mut self, readings: &[GPUInfo]) {
+        if self.paused {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+        if readings.len() != self.gpus.len()
+            || readings.iter().zip(&self.gpus).any(|(reading, gpu_info)| {
+                !reading.same_gpu(gpu_info) || reading.index != gpu_info.index
+            })
+        {
+            self.replace_gpus(readings.to_vec());
+        }
+        let mut crossed = false;
+        for (i, reading) in readings.iter().enumerate().take(self.gpus.len()) {
+            if self.gpus[i].paused {
+                continue;
+            }
+            let level = self.gpus[i].temperature_alert;
+            let at_power_limit = self.gpus[i].at_power_limit();
+            self.gpus[i].push_reading(reading);
+            let new_level = self.temperature_level(&self.gpus[i]);
+            self.gpus[i].temperature_alert = new_level;
+            let gpu_info = &self.gpus[i];
+            crossed |= !at_power_limit && gpu_info.at_power_limit();
+            if new_level != level {
+                let temperature = gpu_info.temperature.latest();
+                match new_level {
+                    Some(new_level) => {
+                        info!(
+                            "{} reached {} °C, above {}",
+                            gpu_info.label(),
+                            temperature,
+                            new_level
+                        );
+                    }
+                    None => info!("{} cooled down to {} °C", gpu_info.label(), temperature),
+                }
+                // Only on the tick it's reached, so a GPU that stays hot fires once
+                if let (Some(TemperatureLevel::Critical), Some(temp_crit)) =
+                    (new_level, self.temperature_thresholds(gpu_info).crit)
+                {
+                    self.crit_hooks.fire(gpu_info, temp_crit);
+                }
+                crossed |= new_level == Some(TemperatureLevel::Critical);
+            }
+        }
+        if crossed {
+            self.request_bell();
+        }
+    }
+
+    /// How many standard deviations the charts flag anomalies at, or `None` unless they
+    /// are shown.
+    fn anomaly_sigma(&self) -> Option<f64> {
+        self.show_anomalies.then_some(self.anomaly_sigma)
+    }
+
+    /// Fails once a metric in `required_metrics` is unsupported on a GPU or couldn't be
+    /// read on the latest tick, for setups where a missing reading means something is
+    /// seriously wrong.
+    fn check_required_metrics(&self) -> std::io::Result<()> {
+        for gpu_info in &self.gpus {
+            let required = Metric::ALL
+                .into_iter()
+                .filter(|&metric| self.required_metrics.contains(MetricFlags::from(metric)));
+            for metric in required {
+                let reason = match gpu_info.error(metric) {
+                    _ if !gpu_info.supports(metric) => "it isn't supported",
+                    Some(error) => error,
+                    None => continue,
+                };
+                return Err(std::io::Error::other(format!(
+                    "{} {} can't be read: {}, and it's listed in required_metrics",
+                    gpu_info.label(),
+                    metric,
+                    reason
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Has the main loop ring the bell with `--bell`, unless it rang in the last
+    /// `BELL_INTERVAL`, so a GPU hovering around a threshold doesn't beep every tick.
+    fn request_bell(&mut self) {
+        if self.bell
+            && self
+                .bell_rung_at
+                .is_none_or(|rung_at| rung_at.elapsed() >= BELL_INTERVAL)
+        {
+            self.ring_bell = true;
+            self.bell_rung_at = Some(Instant::now());
+        }
+    }
+
+    /// Keeps how long the last readings took to collect for the status bar, and warns
+    /// once when it comes close to the interval, which is then too short for this system.
+    fn record_collection_time(&mut self, collection_time: Duration) {
+        self.collection_time = Some(collection_time);
+        if status::collection_slow(collection_time, self.interval)
+            && !mem::replace(&mut self.slow_collection_warned, true)
+        {
+            warn!(
+                "Collecting the readings took {} ms of the {} ms interval, consider a longer --interval",
+                collection_time.as_millis(),
+                self.interval.as_millis()
+            );
+        }
+    }
+
+    /// How long ago the charts last took in readings.
+    fn update_age(&self) -> Option<Duration> {
+        self.last_update.map(|last_update| last_update.elapsed())
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        frame.render_widget(self, frame.area());
+    }
+
+    /// Marks the panel `area` of `gpu_info` while that GPU alone is paused.
+    fn render_paused_badge(&self, gpu_info: &GPUInfo, area: Rect, buf: &mut Buffer) {
+        if gpu_info.paused {
+            let theme = self.theme();
+            render_corner_badge(" PAUSED ", theme.temp_warn_color, theme, area, buf);
+        }
+    }
+
+    /// Draws the badges and the help popup over whatever else is on the screen.
+    fn render_overlays(&self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme();
+        if self.reconnecting {
+            render_corner_badge(" RECONNECTING... ", theme.temp_crit_color, theme, area, buf);
+        } else if self.paused && self.scroll > 0 {
+            let ago = format_time_ago(self.interval * self.scroll as u32);
+            let badge = format!(" PAUSED, {} ", ago);
+            render_corner_badge(&badge, theme.temp_warn_color, theme, area, buf);
+        } else if self.paused {
+            render_corner_badge(" PAUSED ", theme.temp_warn_color, theme, area, buf);
+        }
+
+        if self.show_help {
+            keys::render_help_popup(self.theme(), area, buf);
+        }
+    }
+
+    /// The copy of `gpu_info` its charts in `area` draw, unless they draw it as it is: the
+    /// window of its paused history that was scrolled back to, as wide as the charts,
+    /// and mirrored with `newest_left`.
+    fn charted(&self, gpu_info: &GPUInfo, area: Rect) -> Option<GPUInfo> {
+        let scrolled = self.paused && self.scroll > 0;
+        match (scrolled, self.newest_left) {
+            (true, _) => {
+                Some(gpu_info.window(usize::from(area.width), self.scroll, self.newest_left))
+            }
+            (false, true) => Some(gpu_info.window(self.history_len, 0, true)),
+            (false, false) => None,
+        }
+    }
+
+    /// Renders every supported chart of one GPU within `area`.
+    fn render_gpu(&self, gpu_info: &GPUInfo, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme();
+        let options = &self.chart_options();
+        if let (Some(gauge_area), None) = (gpu_info.split_gauge(area).0, self.focused_chart()) {
+            render_memory_gauge(gpu_info, theme, self.units, gauge_area, buf);
+        }
+        let chart_areas = self.gpu_chart_areas(gpu_info, area);
+        for (&metric, &area) in chart_areas.iter() {
+            let (chart_area, footer_area) = self.split_stats_footer(metric, area);
+            if let (Some(footer_area), Some(cursor)) = (footer_area, self.cursor) {
+                stats::render_cursor_footer(
+                    gpu_info,
+                    theme,
+                    options,
+                    metric,
+                    cursor,
+                    footer_area,
+                    buf,
+                );
+            } else if let Some(footer_area) = footer_area {
+                stats::render_stats_footer(gpu_info, theme, options, metric, footer_area, buf);
+            }
+            match metric {
+                Metric::Clock if self.show_clock_histogram => {
+                    histogram::render_clock_histogram(
+                        gpu_info,
+                        theme,
+                        self.clock_domain,
+                        self.units,
+                        chart_area,
+                        buf,
+                    );
+                }
+                Metric::Clock
+                    if self.overlay && !self.detailed && gpu_info.overlays_temperature() =>
+                {
+                    render_overlay_chart(gpu_info, theme, options, chart_area, buf);
+                    throttle::render_throttle_badges(gpu_info, theme, chart_area, buf);
+                }
+                Metric::Clock => {
+                    render_clock_chart(gpu_info, theme, options, chart_area, buf);
+                    throttle::render_throttle_badges(gpu_info, theme, chart_area, buf);
+                }
+                Metric::Temperature => render_temperature_chart(
+                    gpu_info,
+                    theme,
+                    options,
+                    self.temperature_thresholds(gpu_info),
+                    chart_area,
+                    buf,
+                ),
+                Metric::Memory => render_memory_chart(gpu_info, theme, options, chart_area, buf),
+                Metric::Utilization if self.show_utilization_scatter => {
+                    render_utilization_scatter(gpu_info, theme, options, chart_area, buf)
+                }
+                Metric::Utilization
+                    if self.show_bandwidth && gpu_info.peak_memory_bandwidth().is_some() =>
+                {
+                    render_bandwidth_chart(gpu_info, theme, options, chart_area, buf)
+                }
+                Metric::Utilization => {
+                    render_utilization_chart(gpu_info, theme, options, chart_area, buf)
+                }
+                Metric::Power if self.show_efficiency && gpu_info.supports(Metric::Clock) => {
+                    render_efficiency_chart(gpu_info, theme, options, chart_area, buf)
+                }
+                Metric::Power => render_power_chart(gpu_info, theme, options, chart_area, buf),
+                Metric::Pcie => render_pcie_chart(gpu_info, theme, options, chart_area, buf),
+                Metric::Codec => render_codec_chart(gpu_info, theme, options, chart_area, buf),
+                Metric::Engines => render_engine_chart(gpu_info, theme, options, chart_area, buf),
+                Metric::Fan if self.cooling_overlay && gpu_info.supports(Metric::Temperature) => {
+                    render_cooling_chart(gpu_info, theme, options, chart_area, buf);
+                    throttle::render_fan_policy_badge(gpu_info, theme, chart_area, buf);
+                }
+                Metric::Fan => {
+                    render_fan_chart(gpu_info, theme, options, chart_area, buf);
+                    throttle::render_fan_policy_badge(gpu_info, theme, chart_area, buf);
+                }
+                Metric::Ecc => ecc::render_ecc_panel(gpu_info, theme, chart_area, buf),
+                Metric::NvLink => nvlink::render_nvlink_panel(gpu_info, theme, chart_area, buf),
+                // Listed in the process panel and on the clock chart rather than charted
+                Metric::Processes | Metric::Throttle => {}
+            }
+            if let Some(cursor) = self.cursor.filter(|_| self.charts_over_time(metric)) {
+                let x = if self.newest_left {
+                    cursor as f64
+                } else {
+                    -(cursor as f64)
+                };
+                render_crosshair(
+                    x,
+                    gpu_info.temperature.x_bounds(),
+                    Style::new().fg(theme.text),
+                    chart_area,
+                    buf,
+                );
+            }
+        }
+
+        if let Some(alert_style) = self.temperature_alert(gpu_info) {
+            for (&metric, &area) in chart_areas.iter() {
+                let (chart_area, _) = self.split_stats_footer(metric, area);
+                highlight_border(chart_area, alert_style, buf);
+            }
+        }
+    }
+
+    /// Whether the chart of `metric` is drawn over time, rather than as a panel, the
+    /// clock histogram or the utilization scatter plot.
+    fn charts_over_time(&self, metric: Metric) -> bool {
+        match metric {
+            Metric::Ecc | Metric::NvLink | Metric::Processes | Metric::Throttle => false,
+            Metric::Clock => !self.show_clock_histogram,
+            Metric::Utilization => !self.show_utilization_scatter,
+            _ => true,
+        }
+    }
+
+    /// Splits the statistics footer off a chart area while the footers or the cursor are
+    /// shown. The ECC and NVLink panels have no history to summarize.
+    fn split_stats_footer(&self, metric: Metric, area: Rect) -> (Rect, Option<Rect>) {
+        let deviation = self.compares_gpus()
+            && stats::DEVIATION_METRICS
+                .iter()
+                .any(|&(compared, _)| compared == metric);
+        if (self.show_stats || self.cursor.is_some() || deviation)
+            && !matches!(metric, Metric::Ecc | Metric::NvLink)
+        {
+            stats::split_footer(area)
+        } else {
+            (area, None)
+        }
+    }
+
+    /// Whether the footers compare each GPU with the mean, which the chart of the average
+    /// of every GPU has nothing to compare against.
+    fn compares_gpus(&self) -> bool {
+        self.show_deviation && !self.shows_average()
+    }
+
+    /// The settings the charts of every GPU are drawn with.
+    fn chart_options(&self) -> ChartOptions<'_> {
+        ChartOptions {
+            interval: self.interval,
+            marker: self.marker.marker(),
+            units: self.units,
+            domain: self.clock_domain,
+            gridlines: self.gridlines,
+            interpolate: self.interpolate,
+            anomaly_sigma: self.anomaly_sigma(),
+            smoothing_window: self.active_smoothing_window(),
+            log_charts: if self.log_scale {
+                self.log_charts
+            } else {
+                MetricFlags::empty()
+            },
+            axis_ranges: &self.axis_ranges,
+            compared_gpus: self.compares_gpus().then_some(self.gpus.as_slice()),
+        }
+    }
+
+    /// The shortest time between two frames, from `max_fps`.
+    fn frame_time(&self) -> Duration {
+        Duration::from_secs(1) / self.max_fps.max(1)
+    }
+
+    /// The number of samples the smoothed charts average, or 1 while smoothing is off.
+    fn active_smoothing_window(&self) -> usize {
+        if self.smoothing {
+            self.smoothing_window
+        } else {
+            1
+        }
+    }
+
+    /// The border style for a GPU whose temperature reached a threshold or rises faster
+    /// than `--temp-rate-warn`, or `None` while it stays below them.
+    fn temperature_alert(&self, gpu_info: &GPUInfo) -> Option<Style> {
+        let theme = self.theme();
+        match self.temperature_level(gpu_info) {
+            Some(TemperatureLevel::Critical) => Some(
+                Style::new()
+                    .fg(theme.temp_crit_color)
+                    .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+            ),
+            Some(TemperatureLevel::Warning) => Some(
+                Style::new()
+                    .fg(theme.temp_warn_color)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            // An early warning of a runaway temperature, before any threshold is reached
+            None if self.heating_fast(gpu_info) => Some(Style::new().fg(theme.temp_warn_color)),
+            None => None,
+        }
+    }
+
+    /// Whether the temperature of `gpu_info` rises by `--temp-rate-warn` or faster.
+    fn heating_fast(&self, gpu_info: &GPUInfo) -> bool {
+        self.temp_rate_warn.is_some_and(|limit| {
+            stats::temperature_rate(gpu_info, self.interval).is_some_and(|rate| rate >= limit)
+        })
+    }
+
+    /// The highest temperature threshold `gpu_info` reached, if any. A threshold is
+    /// reached at or above it, and one latched by an earlier reading stays reached until
+    /// the temperature falls `temp_hysteresis` below it.
+    fn temperature_level(&self, gpu_info: &GPUInfo) -> Option<TemperatureLevel> {
+        if !gpu_info.available(Metric::Temperature) {
+            return None;
+        }
+        let temperature = gpu_info.temperature.latest();
+        let thresholds = self.temperature_thresholds(gpu_info);
+        let reached = |level: TemperatureLevel, threshold: Option<u32>| {
+            threshold.is_some_and(|limit| {
+                let latched = gpu_info.temperature_alert >= Some(level);
+                temperature >= limit
+                    || latched && temperature >= limit.saturating_sub(self.temp_hysteresis)
+            })
+        };
+        if reached(TemperatureLevel::Critical, thresholds.crit) {
+            Some(TemperatureLevel::Critical)
+        } else if reached(TemperatureLevel::Warning, thresholds.warn) {
+            Some(TemperatureLevel::Warning)
+        } else {
+            None
+        }
+    }
+
+    /// Waits up to `timeout` for one terminal event and handles it. Returns whether the
+    /// screen needs to be redrawn.
+    fn handle_events(&mut self, timeout: Duration) -> std::io::Result<bool> {
+        if self.interrupted.load(Ordering::Relaxed) {
+            self.exit();
+            return Ok(false);
+        }
+        // Wake up regularly to notice signals even when the poll interval is long
+        if !event::poll(EVENT_POLL_TIMEOUT.min(timeout))? {
+            return Ok(false); // Don't try to read any events if there aren't any available
+        }
+        Ok(self.handle_event(event::read()?))
+    }
+
+    /// Handles one terminal event. Returns whether the screen needs to be redrawn.
+    fn handle_event(&mut self, event: Event) -> bool {
+        match event {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                self.handle_key_event(key_event);
+                true
+            }
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                ..
+            }) => match crossterm::terminal::size() {
+                Ok((width, height)) => self.click(Rect::new(0, 0, width, height), column, row),
+                Err(_) => false,
+            },
+            // The terminal is resized to the new size when it's next drawn
+            Event::Resize(_, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Shows the GPU whose panel or compact row is at `column`, `row` of a screen of size
+    /// `area` full-screen. Returns whether a GPU was clicked.
+    fn click(&mut self, area: Rect, column: u16, row: u16) -> bool {
+        let position = ratatui::layout::Position::new(column, row);
+        let clicked = self
+            .gpu_areas(area)
+            .into_iter()
+            .find(|(_, gpu_area)| gpu_area.contains(position));
+        match clicked {
+            Some((i, _)) => {
+                debug!("Clicked GPU {}", i);
+                self.selected = Some(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn handle_key_event(&mut self, key_event: event::KeyEvent) {
+        let Some(action) = keys::action(key_event) else {
+            return;
+        };
+        // Esc closes the help popup before it leaves the full-screen view
+        if self.show_help && action == Action::Overview {
+            self.show_help = false;
+            return;
+        }
+        // and leaves a full-screen chart before the GPU
+        if self.chart_focus.is_some() && action == Action::Overview {
+            self.chart_focus = None;
+            return;
+        }
+        match action {
+            Action::Quit => self.exit(),
+            Action::LongerHistory => self.cycle_history_len(true),
+            Action::ShorterHistory => self.cycle_history_len(false),
+            Action::NextGpu => self.select_next_gpu(true),
+            Action::PreviousGpu => self.select_next_gpu(false),
+            Action::Overview => self.selected = None,
+            Action::Pause => {
+                self.paused = !self.paused;
+                self.scroll = 0;
+                self.cursor = None;
+            }
+            Action::ToggleCursor => {
+                // The whole history is charted while unscrolled, so the cursor never
+                // leaves the charts
+                self.cursor = match self.cursor {
+                    Some(_) => None,
+                    None => Some(0),
+                };
+                self.paused = true;
+                self.scroll = 0;
+            }
+            Action::ScrollBack if self.cursor.is_some() => {
+                let oldest = self.history_len.saturating_sub(1);
+                self.cursor = self.cursor.map(|cursor| (cursor + 1).min(oldest));
+            }
+            Action::ScrollForward if self.cursor.is_some() => {
+                self.cursor = self.cursor.map(|cursor| cursor.saturating_sub(1));
+            }
+            Action::ScrollBack if self.paused => {
+                self.scroll = (self.scroll + SCROLL_STEP).min(self.history_len);
+            }
+            Action::ScrollBack => {}
+            Action::ScrollForward => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+            Action::PauseGpu => {
+                if let Some(gpu_info) = self.selected.and_then(|i| self.gpus.get_mut(i)) {
+                    gpu_info.paused = !gpu_info.paused;
+                }
+            }
+            Action::ToggleProcesses => self.show_processes = !self.show_processes,
+            Action::CycleTheme => self.cycle_theme(),
+            Action::CycleClockDomain => self.clock_domain = self.clock_domain.next(),
+            Action::ToggleOverlay => self.overlay = !self.overlay,
+            Action::ToggleCoolingOverlay => self.cooling_overlay = !self.cooling_overlay,
+            Action::ToggleEfficiency => self.show_efficiency = !self.show_efficiency,
+            Action::ToggleBandwidth => self.show_bandwidth = !self.show_bandwidth,
+            Action::ToggleUtilizationScatter => {
+                self.show_utilization_scatter = !self.show_utilization_scatter
+            }
+            Action::ToggleClockHistogram => self.show_clock_histogram = !self.show_clock_histogram,
+            Action::ToggleInterpolation => self.interpolate = !self.interpolate,
+            Action::ToggleAnomalies => self.show_anomalies = !self.show_anomalies,
+            Action::CycleUnits => self.units = self.units.next(),
+            Action::ToggleSmoothing => self.smoothing = !self.smoothing,
+            Action::ToggleStats => self.show_stats = !self.show_stats,
+            Action::ToggleDeviation => self.show_deviation = !self.show_deviation,
+            Action::ClearHistory => {
+                debug!("Clearing the history of every GPU");
+                for gpu_info in self.gpus.iter_mut() {
+                    gpu_info.clear_history();
+                }
+            }
+            Action::ToggleBaseline => self.toggle_baseline(),
+            Action::ToggleUuid => {
+                self.show_uuid = !self.show_uuid;
+                for gpu_info in self.gpus.iter_mut() {
+                    gpu_info.show_uuid = self.show_uuid;
+                }
+            }
+            Action::SaveScreen => self.save_screen = true,
+            Action::SaveHistory => {
+                match snapshot::save_history(&self.gpus, self.interval, Path::new(".")) {
+                    Ok(path) => info!("Saved the history to {}", path.display()),
+                    Err(error) => warn!("Failed to save the history: {}", error),
+                }
+            }
+            Action::ToggleLogScale => self.log_scale = !self.log_scale,
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::ToggleTotals => self.show_totals = !self.show_totals,
+            Action::ToggleAverage => self.show_average = !self.show_average,
+            Action::ToggleDashboard => self.dashboard = !self.dashboard,
+            Action::ToggleBars => self.bars = !self.bars,
+            Action::ToggleDetailed => {
+                self.detailed = !self.detailed;
+                self.detail_scroll = 0;
+            }
+            Action::ToggleMessages => self.show_messages = !self.show_messages,
+            Action::ScrollMessagesUp if self.flips_gpu_pages() => self.flip_gpu_page(false),
+            Action::ScrollMessagesDown if self.flips_gpu_pages() => self.flip_gpu_page(true),
+            Action::ScrollMessagesUp => {
+                self.messages_scroll =
+                    (self.messages_scroll + 1).min(self.messages.len().saturating_sub(1))
+            }
+            Action::ScrollMessagesDown => {
+                self.messages_scroll = self.messages_scroll.saturating_sub(1)
+            }
+            Action::FocusChart => self.focus_next_chart(),
+            Action::NextChartPage => self.cycle_chart_page(true),
+            Action::PreviousChartPage => self.cycle_chart_page(false),
+            Action::CycleGpuOrder => {
+                self.gpu_order = self.gpu_order.next();
+                debug!("Sorting the GPUs by {}", self.gpu_order);
+            }
+        }
+    }
+
+    /// Captures the latest readings of every GPU as the baseline to compare against, or
+    /// drops the baseline if there is one.
+    fn toggle_baseline(&mut self) {
+        let capture = self.gpus.iter().all(|gpu_info| gpu_info.baseline.is_none());
+        debug!(
+            "{} the baseline",
+            if capture { "Capturing" } else { "Dropping" }
+        );
+        for gpu_info in self.gpus.iter_mut() {
+            let baseline = capture.then(|| GpuSnapshot::new(gpu_info));
+            gpu_info.baseline = baseline;
+        }
+    }
+
+    /// Moves the full-screen selection to the next or previous GPU, wrapping around.
+    /// Starting from the overview selects the first or last GPU respectively.
+    fn select_next_gpu(&mut self, forward: bool) {
+        let order = self.display_order();
+        let num_gpus = order.len();
+        if num_gpus == 0 {
+            return;
+        }
+        let position = self
+            .selected
+            .and_then(|selected| order.iter().position(|&i| i == selected));
+        self.selected = Some(
+            order[match (position, forward) {
+                (None, true) => 0,
+                (None, false) => num_gpus - 1,
+                (Some(i), true) => (i + 1) % num_gpus,
+                (Some(i), false) => (i + num_gpus - 1) % num_gpus,
+            }],
+        );
+    }
+
+    /// Steps through the pages of charts of the visible GPUs, then back to showing every
+    /// chart. The GPU with the most charts decides how many pages there are.
+    fn cycle_chart_page(&mut self, forward: bool) {
+        if self.detailed {
+            self.scroll_detailed(forward);
+            return;
+        }
+        let page_count = self
+            .visible_gpus()
+            .iter()
+            .map(|(_, gpu_info)| gpu_info.chart_page_count(self.overlay))
+            .max()
+            .unwrap_or(0);
+        if page_count <= 1 {
+            self.chart_page = None;
+            return;
+        }
+        self.chart_page = match (self.chart_page, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(page_count - 1),
+            (Some(page), true) if page + 1 < page_count => Some(page + 1),
+            (Some(page), false) if page > 0 && page < page_count => Some(page - 1),
+            (Some(_), _) => None,
+        };
+        debug!("Showing chart page {:?} of {}", self.chart_page, page_count);
+    }
+
+    /// Shows the first chart of the focused GPU full-screen, or the chart after the one
+    /// shown, and after the last chart every chart again.
+    fn focus_next_chart(&mut self) {
+        let Some(i) = self.focused_gpu() else {
+            return;
+        };
+        let charts = self.gpus[i].charts(self.overlay);
+        let next = match self.chart_focus {
+            Some((focused, metric)) if focused == i => charts
+                .iter()
+                .position(|&chart| chart == metric)
+                .and_then(|position| charts.get(position + 1)),
+            _ => charts.first(),
+        };
+        self.chart_focus = next.map(|&metric| (i, metric));
+        debug!("Showing chart {:?} full-screen", self.chart_focus);
+    }
+
+    /// The chart that fills the screen, unless its GPU is gone or no longer has it, as
+    /// after toggling the overlay.
+    fn focused_chart(&self) -> Option<(usize, Metric)> {
+        self.chart_focus.filter(|&(i, metric)| {
+            self.gpus
+                .get(i)
+                .is_some_and(|gpu_info| gpu_info.charts(self.overlay).contains(&metric))
+        })
+    }
+
+    /// Scrolls the column of the detailed view by one chart, wrapping at either end.
+    fn scroll_detailed(&mut self, down: bool) {
+        let count = self
+            .process_gpu()
+            .map_or(0, |gpu_info| gpu_info.detail_charts().len());
+        self.detail_scroll = match (self.detail_scroll, down) {
+            (scroll, true) if scroll + 1 < count => scroll + 1,
+            (_, true) => 0,
+            (0, false) => count.saturating_sub(1),
+            (scroll, false) => scroll - 1,
+        };
+    }
+
+    /// The GPU whose processes are listed: the selected one, or the first GPU in the
+    /// overview.
+    fn process_gpu(&self) -> Option<&GPUInfo> {
+        self.gpus.get(self.focused_gpu()?)
+    }
+
+    /// The index into `gpus` of the selected GPU, or of the first GPU in the overview.
+    fn focused_gpu(&self) -> Option<usize> {
+        let first = || self.display_order().first().copied();
+        self.selected.or_else(first)
+    }
+
+    /// Where the charts of `gpu_info` go in its panel `area`: stacked in one column in
+    /// the detailed view, laid out in rows otherwise.
+    fn gpu_chart_areas(&self, gpu_info: &GPUInfo, area: Rect) -> BTreeMap<Metric, Rect> {
+        if let Some((_, metric)) = self.focused_chart() {
+            BTreeMap::from([(metric, area)])
+        } else if self.detailed {
+            gpu_info.detail_areas(area, self.detail_scroll)
+        } else {
+            gpu_info.chart_areas(area, self.overlay, self.chart_page)
+        }
+    }
+
+    /// Whether the processes of the GPUs are queried, for the process panel or the
+    /// dashboard.
+    fn lists_processes(&self) -> bool {
+        self.show_processes || self.dashboard
+    }
+
+    /// Splits `area` into the compact table of the visible GPUs and the process table of
+    /// the dashboard below it, giving the table no more than half the height.
+    fn split_dashboard(&self, area: Rect) -> (Rect, Rect) {
+        let table_height = (self.visible_gpus().len() as u16 + 3).min(area.height / 2);
+        let [table_area, process_area] =
+            Layout::vertical([Constraint::Length(table_height), Constraint::Fill(1)]).areas(area);
+        (table_area, process_area)
+    }
+
+    /// Splits the bottom third of `area` off for the process panel while it's shown.
+    fn split_process_panel(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if !self.show_processes {
+            return (area, None);
+        }
+        let [charts_area, process_area] =
+            Layout::vertical([Constraint::Ratio(2, 3), Constraint::Ratio(1, 3)]).areas(area);
+        (charts_area, Some(process_area))
+    }
+
+    /// Splits the totals panel off the bottom of `area` while it's shown, unless that
+    /// would leave no room for anything else.
+    fn split_totals_panel(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if !(self.compact || self.show_totals) || area.height < 8 {
+            return (area, None);
+        }
+        let [main_area, totals_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(area);
+        (main_area, Some(totals_area))
+    }
+
+    /// Splits the banner row off the top of `area`, if there's a banner to show.
+    fn split_banner(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if self.banner.is_none() || area.height < 2 {
+            return (area, None);
+        }
+        let [banner_area, main_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        (main_area, Some(banner_area))
+    }
+
+    /// Splits the alert row off the top of `area` once a GPU reported an XID error.
+    fn split_xid_alerts(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if self.xid_events.is_empty() || area.height < 2 {
+            return (area, None);
+        }
+        let [alerts_area, main_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        (main_area, Some(alerts_area))
+    }
+
+    /// Splits the messages pane off the bottom of `area` while it's shown, unless that
+    /// would leave no room for anything else.
+    fn split_messages_panel(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if !self.show_messages || area.height < 16 {
+            return (area, None);
+        }
+        let [main_area, messages_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(8)]).areas(area);
+        (main_area, Some(messages_area))
+    }
+
+    /// Splits the status bar row off the bottom of `area`, once there are versions to
+    /// show in it.
+    fn split_status_bar(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if self.versions.is_none() || area.height < 2 {
+            return (area, None);
+        }
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+        (main_area, Some(status_area))
+    }
+
+    /// Splits `charts_area` into one panel per visible GPU, stacked vertically or laid
+    /// out row by row in the grid of `--layout`.
+    fn gpu_chunks(&self, charts_area: Rect) -> Vec<(usize, Rect)> {
+        if self.shows_average() {
+            return Vec::new();
+        }
+        if self.detailed {
+            return self
+                .focused_gpu()
+                .map(|i| (i, charts_area))
+                .into_iter()
+                .collect();
+        }
+        let visible_gpus = self.visible_gpus();
+        let (columns, rows) = match self.layout {
+            Some(layout) => layout.fit(visible_gpus.len()),
+            None => (1, visible_gpus.len()),
+        };
+        let percentage = 100 / rows.max(1) as u16;
+        let chunks = Layout::vertical(vec![Constraint::Percentage(percentage); rows])
+            .split(charts_area)
+            .iter()
+            .flat_map(|&row| {
+                Layout::horizontal(vec![Constraint::Ratio(1, columns as u32); columns])
+                    .split(row)
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        visible_gpus.iter().map(|&(i, _)| i).zip(chunks).collect()
+    }
+
+    /// How the visible GPUs are drawn in `charts_area`. Too many GPUs or a tiny window
+    /// would squeeze the charts below [`MIN_CHART_HEIGHT`], so the compact table takes
+    /// over then, until the terminal is too small for it as well. The bars of `--bars`
+    /// need far less room, but fall back to the table the same way.
+    fn view(&self, charts_area: Rect) -> View {
+        let average = self.average_gpu();
+        let chart_fits = |gpu_info: &GPUInfo, chunk: Rect| {
+            self.gpu_chart_areas(gpu_info, chunk)
+                .values()
+                .all(|area| area.height >= MIN_CHART_HEIGHT)
+        };
+        let charts_fit = || match &average {
+            Some(average) => chart_fits(average, charts_area),
+            None => self
+                .gpu_chunks(charts_area)
+                .iter()
+                .all(|&(i, chunk)| chart_fits(&self.gpus[i], chunk)),
+        };
+        let bars_fit = || match &average {
+            Some(_) => charts_area.height >= bars::BARS_HEIGHT,
+            None => self
+                .gpu_chunks(charts_area)
+                .iter()
+                .all(|&(_, chunk)| chunk.height >= bars::BARS_HEIGHT),
+        };
+        let (min_width, min_height) = MIN_TABLE_SIZE;
+        if self.bars && !self.compact && bars_fit() {
+            View::Bars
+        } else if !self.bars && !self.compact && charts_fit() {
+            View::Charts
+        } else if charts_area.width >= min_width && charts_area.height >= min_height {
+            View::Compact
+        } else {
+            View::TooSmall
+        }
+    }
+
+    /// Where each visible GPU is drawn on a screen of size `area`: its panel of charts,
+    /// or its table row in the compact view. Mirrors the layout of `render`.
+    fn gpu_areas(&self, area: Rect) -> Vec<(usize, Rect)> {
+        if let Some((i, _)) = self.focused_chart() {
+            return vec![(i, area)];
+        }
+        let (area, _) = self.split_banner(area);
+        let (area, _) = self.split_xid_alerts(area);
+        let (area, _) = self.split_status_bar(area);
+        let (area, _) = self.split_messages_panel(area);
+        let table_area = if self.dashboard {
+            self.split_dashboard(area).0
+        } else {
+            let (charts_area, _) = self.split_process_panel(area);
+            let (charts_area, _) = self.split_totals_panel(charts_area);
+            match self.view(charts_area) {
+                View::Charts | View::Bars => return self.gpu_chunks(charts_area),
+                View::Compact => charts_area,
+                View::TooSmall => return Vec::new(),
+            }
+        };
+        // Below the table's top border and header row
+        let table_area = Block::bordered().inner(table_area);
+        self.visible_gpus()
+            .iter()
+            .zip(table_area.y + 1..table_area.bottom())
+            .map(|(&(i, _), y)| (i, Rect::new(table_area.x, y, table_area.width, 1)))
+            .collect()
+    }
+
+    /// Whether the overview charts the average of every GPU in place of the GPUs, which
+    /// takes several GPUs and neither a focused GPU nor the detailed view.
+    fn shows_average(&self) -> bool {
+        self.show_average && self.selected.is_none() && !self.detailed && self.gpus.len() > 1
+    }
+
+    /// The pseudo-GPU of [`GPUInfo::average`] while [`NvidiaApp::shows_average`].
+    fn average_gpu(&self) -> Option<GPUInfo> {
+        self.shows_average().then(|| GPUInfo::average(&self.gpus))
+    }
+
+    /// The GPUs to lay out, paired with their index in `gpus`: only the selected one
+    /// when a GPU is focused, every GPU in `gpu_order` otherwise.
+    fn visible_gpus(&self) -> Vec<(usize, &GPUInfo)> {
+        match self
+            .selected
+            .and_then(|i| self.gpus.get(i).map(|gpu| (i, gpu)))
+        {
+            Some(selected) => vec![selected],
+            None => {
+                let order = self.display_order();
+                let (skip, take) = match self.gpu_pages() {
+                    Some((page, _)) => (page * self.per_page.unwrap_or(0), self.per_page),
+                    None => (0, None),
+                };
+                order
+                    .into_iter()
+                    .skip(skip)
+                    .take(take.unwrap_or(usize::MAX))
+                    .map(|i| (i, &self.gpus[i]))
+                    .collect()
+            }
+        }
+    }
+
+    /// The page of the overview shown with `--per-page` and how many pages there are,
+    /// or `None` while every GPU fits on one page or a single GPU is selected.
+    fn gpu_pages(&self) -> Option<(usize, usize)> {
+        let per_page = self.per_page?;
+        let page_count = self.gpus.len().div_ceil(per_page);
+        if page_count <= 1 || self.selected.is_some() {
+            return None;
+        }
+        Some((self.gpu_page % page_count, page_count))
+    }
+
+    /// Whether PageUp and PageDown flip the pages of `--per-page` rather than scroll the
+    /// messages, which they still do while the messages are shown.
+    fn flips_gpu_pages(&self) -> bool {
+        !self.show_messages && self.gpu_pages().is_some()
+    }
+
+    /// Shows the next or the previous page of GPUs, wrapping around.
+    fn flip_gpu_page(&mut self, forward: bool) {
+        let Some((page, page_count)) = self.gpu_pages() else {
+            return;
+        };
+        self.gpu_page = if forward {
+            (page + 1) % page_count
+        } else {
+            (page + page_count - 1) % page_count
+        };
+    }
+
+    /// Indices into `gpus` in the order the overview lays them out. Sorting only changes
+    /// the layout, so every `GPUInfo` keeps querying its own hardware. GPUs with equal
+    /// readings stay in hardware order.
+    fn display_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.gpus.len()).collect();
+        let key = |i: &usize| {
+            let gpu_info = &self.gpus[*i];
+            match self.gpu_order {
+                GpuOrder::Index => 0,
+                GpuOrder::Temperature => u64::from(gpu_info.temperature.latest()),
+                GpuOrder::Utilization => u64::from(gpu_info.gpu_utilization.latest()),
+                GpuOrder::Memory => gpu_info.memory_used.latest(),
+            }
+        };
+        order.sort_by_key(|i| std::cmp::Reverse(key(i)));
+        order
+    }
+
+    /// Steps the rolling history window to the next longer or shorter preset length.
+    fn cycle_history_len(&mut self, longer: bool) {
+        let next = if longer {
+            HISTORY_LENGTHS.iter().find(|&&len| len > self.history_len)
+        } else {
+            HISTORY_LENGTHS
+                .iter()
+                .rev()
+                .find(|&&len| len < self.history_len)
+        };
+        if let Some(&history_len) = next {
+            self.set_history_len(history_len);
+        }
+    }
+
+    fn set_history_len(&mut self, history_len: usize) {
+        debug!("Setting history length to {} samples", history_len);
+        self.history_len = history_len;
+        for gpu_info in self.gpus.iter_mut() {
+            gpu_info.resize_history(history_len);
+        }
+    }
+
+    /// The temperature thresholds of `gpu_info`, where those of the command line or the
+    /// config file take precedence over the card's own.
+    fn temperature_thresholds(&self, gpu_info: &GPUInfo) -> TemperatureThresholds {
+        TemperatureThresholds {
+            warn: self.temp_warn.or(gpu_info.slowdown_temperature),
+            crit: self.temp_crit.or(gpu_info.shutdown_temperature),
+        }
+    }
+
+    fn theme(&self) -> &Theme {
+        if self.use_color {
+            &self.themes[self.theme]
+        } else {
+            &PLAIN
+        }
+    }
+
+    /// Draws the borders and the clock line of every theme in `accent`.
+    fn set_accent(&mut self, accent: Color) {
+        self.themes = THEMES.map(|theme| theme.with_accent(accent));
+    }
+
+    fn cycle_theme(&mut self) {
+        self.theme = (self.theme + 1) % self.themes.len();
+        debug!("Theme: {}", self.theme().name);
+    }
+
+    fn exit(&mut self) {
+        self.exit = true;
+    }
+}
+
+/// How many charts each page shows once Tab pages through them.
+const CHARTS_PER_PAGE: usize = 2;
+
+/// The metrics that get a chart or panel in each GPU's panel, in display order.
+const CHART_METRICS: [Metric; 11] = [
+    Metric::Clock,
+    Metric::Temperature,
+    Metric::Memory,
+    Metric::Utilization,
+    Metric::Power,
+    Metric::Pcie,
+    Metric::NvLink,
+    Metric::Codec,
+    Metric::Engines,
+    Metric::Fan,
+    Metric::Ecc,
+];
+
+/// The charts of the detailed view of one GPU, stacked top to bottom.
+const DETAIL_METRICS: [Metric; 4] = [
+    Metric::Clock,
+    Metric::Temperature,
+    Metric::Power,
+    Metric::Utilization,
+];
+
+/// The least height of a chart in the detailed view before the column has to scroll.
+const DETAIL_CHART_HEIGHT: u16 = 8;
+
+/// The most charts placed side by side before a GPU's panel wraps onto another row.
+const CHARTS_PER_ROW: usize = 3;
+
+/// The fewest rows a chart is drawn in: its two borders and one row of data. Below
+/// that, the GPUs are listed in the compact table instead.
+const MIN_CHART_HEIGHT: u16 = 3;
+
+/// The smallest area the compact table fits in, with its borders, header and a GPU row.
+const MIN_TABLE_SIZE: (u16, u16) = (30, 4);
+
+/// Splits a GPU's panel into `count` equally sized chart areas, laid out in rows of at
+/// most [`CHARTS_PER_ROW`] charts.
+fn split_charts(area: Rect, count: usize) -> Vec<Rect> {
+    let rows = count.div_ceil(CHARTS_PER_ROW).max(1);
+    let row_chunks = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, rows as u32); rows])
+        .split(area);
+
+    let mut remaining = count;
+    let mut areas = Vec::with_capacity(count);
+    for row in row_chunks.iter() {
+        let columns = remaining.min(CHARTS_PER_ROW);
+        remaining -= columns;
+        let column_chunks = Layout::default()
+            .direction(ratatui::layout::Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+            .split(*row);
+        areas.extend(column_chunks.iter().copied());
+    }
+    areas
+}
+
+/// Headroom added above the largest sample so the line isn't pinned to the top of a chart.
+const Y_AXIS_HEADROOM: f64 = 1.1;
+
+/// The upper y-axis bound for a chart whose largest sample in the window is `max`.
+fn y_axis_bound(max: f64) -> f64 {
+    (max * Y_AXIS_HEADROOM).max(1.0) // Ensure the bound is at least 1
+}
+
+/// The y-axis bounds of a chart: the fixed `range` if one was configured, and otherwise
+/// zero to `auto_max`, the bound the chart computed from its data.
+fn y_bounds(range: Option<AxisRange>, auto_max: f64) -> [f64; 2] {
+    match range {
+        Some(range) => [range.min, range.max],
+        None => [0.0, auto_max],
+    }
+}
+
+/// The smallest value drawn on a log scale, which zero and below are clamped to. Idle
+/// PCIe links and sleeping cards read 0, which has no logarithm.
+const LOG_EPSILON: f64 = 1.0;
+
+/// The points of a chart with their values replaced by their base-10 logarithm.
+fn log_points(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    points
+        .iter()
+        .map(|&(x, y)| (x, y.max(LOG_EPSILON).log10()))
+        .collect()
+}
+
+/// The bounds and labels of a logarithmic y-axis reaching at least `max`, with a label
+/// at every power of ten, as in `1`, `10`, ..., `100k KB/s`.
+fn log_axis(max: f64, unit: &str) -> ([f64; 2], Vec<String>) {
+    let decades = max.max(LOG_EPSILON).log10().ceil().max(1.0) as u32;
+    let labels = (0..=decades)
+        .map(|decade| {
+            let prefix = ["", "k", "M", "G", "T"][(decade / 3).min(4) as usize];
+            let label = format!("{}{}", 10u64.pow(decade % 3), prefix);
+            if decade == decades {
+                format!("{} {}", label, unit)
+            } else {
+                label
+            }
+        })
+        .collect();
+    ([0.0, f64::from(decades)], labels)
+}
+
+/// The number of gaps between the y-axis labels of the clock chart, which puts labels at
+/// 0, 25, 50, 75 and 100% of the axis.
+const Y_LABEL_GAPS: u32 = 4;
+
+/// The values of evenly spaced y-axis labels from the lower to the upper bound.
+fn y_ticks(bounds: [f64; 2]) -> Vec<f64> {
+    (0..=Y_LABEL_GAPS)
+        .map(|gap| bounds[0] + (bounds[1] - bounds[0]) * f64::from(gap) / f64::from(Y_LABEL_GAPS))
+        .collect()
+}
+
+/// The words dropped from the front of a GPU name, in this order, until it fits.
+const NAME_PREFIXES: [&str; 4] = ["NVIDIA ", "GeForce ", "Quadro ", "Tesla "];
+
+/// `name` shortened to at most `width` characters: the vendor and brand words such as
+/// "NVIDIA GeForce" go first, then a trailing " GPU", and whatever still doesn't fit
+/// is cut off with an ellipsis. "NVIDIA GeForce RTX 4090 Laptop GPU" becomes "RTX 4090
+/// Laptop" rather than losing the model number off the end of the title.
+fn abbreviate_name(name: &str, width: usize) -> String {
+    let fits = |name: &str| name.chars().count() <= width;
+    let mut short = name;
+    for prefix in NAME_PREFIXES {
+        if fits(short) {
+            return short.to_owned();
+        }
+        short = short.strip_prefix(prefix).unwrap_or(short);
+    }
+    let short = short.strip_suffix(" GPU").unwrap_or(short);
+    if fits(short) {
+        return short.to_owned();
+    }
+    let mut truncated: String = short.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// The fewest characters of the GPU name a chart title keeps, however long the rest of
+/// the title is; past that, the end of the title is cut off instead.
+const MIN_NAME_WIDTH: usize = 12;
+
+/// Builds a chart title for `metric` showing its live `value`, or "N/A" while its query
+/// keeps failing (or why it isn't allowed). GPUs are numbered by hardware index, so
+/// identical cards can be told apart even when only some of them are monitored. The GPU
+/// name is abbreviated to fit the title on a chart `width` columns wide, borders
+/// included.
+fn metric_title(
+    kind: &str,
+    gpu_info: &GPUInfo,
+    metric: Metric,
+    value: String,
+    width: u16,
+) -> Title<'static> {
+    let delta = gpu_info.baseline.as_ref().and_then(|baseline| {
+        baseline.delta(
+            &GpuSnapshot::new(gpu_info),
+            metric,
+            gpu_info.temperature_unit,
+            gpu_info.precision,
+        )
+    });
+    let value = match (gpu_info.unavailable(metric), delta) {
+        (Some(unavailable), _) => String::from(unavailable),
+        (None, Some(delta)) => format!("{}, {} since baseline", value, delta),
+        (None, None) => value,
+    };
+    // The memory gauge above the charts shows it already
+    let memory = gpu_info
+        .memory_title()
+        .filter(|_| !gpu_info.shows(Metric::Memory))
+        .map(|memory| format!(", {}", memory))
+        .unwrap_or_default();
+    let prefix = format!("{} {} - ", gpu_info.label(), kind);
+    let suffix = format!("{} ({})", memory, value);
+    named_title(&prefix, gpu_info, &suffix, width)
+}
+
+/// Builds a chart title of the GPU name between `prefix` and `suffix`, with the name
+/// abbreviated to fit the title on a chart `width` columns wide, borders included.
+fn named_title(prefix: &str, gpu_info: &GPUInfo, suffix: &str, width: u16) -> Title<'static> {
+    let name_width = usize::from(width.saturating_sub(2))
+        .saturating_sub(prefix.chars().count() + suffix.chars().count())
+        .max(MIN_NAME_WIDTH);
+    Title::from(format!(
+        "{}{}{}",
+        prefix,
+        gpu_info.title_name(name_width),
+        suffix
+    ))
+}
+
+/// The style for anything drawn for `metric`, dimmed while its query keeps failing.
+fn metric_style(theme: &Theme, gpu_info: &GPUInfo, metric: Metric, color: Color) -> Style {
+    match gpu_info.error(metric) {
+        Some(_) => Style::new()
+            .fg(theme.unavailable)
+            .add_modifier(Modifier::DIM),
+        None => Style::new().fg(color),
+    }
+}
+
+/// The scatter plot of the samples flagged by [`stats::anomalies`], if there are any.
+fn anomaly_dataset<'a>(theme: &Theme, anomaly_data: &'a [(f64, f64)]) -> Option<Dataset<'a>> {
+    (!anomaly_data.is_empty()).then(|| {
+        Dataset::default()
+            .name("Anomaly")
+            .marker(Marker::Block)
+            .graph_type(ratatui::widgets::GraphType::Scatter)
+            .style(Style::new().fg(theme.temp_crit_color))
+            .data(anomaly_data)
+    })
+}
+
+/// Breaks a line off where `points`, as returned by [`History::points`], skip the gap of
+/// a failed reading. Ratatui neither draws a point outside the chart nor connects it
+/// with a line, so a point below every chart goes between the two sides of each gap.
+fn break_at_gaps(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut broken = Vec::with_capacity(points.len());
+    for (i, &point) in points.iter().enumerate() {
+        if let Some(&(x, _)) = i.checked_sub(1).and_then(|i| points.get(i)) {
+            if (point.0 - x).abs() > 1.0 {
+                broken.push(((x + point.0) / 2.0, f64::NEG_INFINITY));
+            }
+        }
+        broken.push(point);
+    }
+    broken
+}
+
+/// Where the legend of a chart goes, given the points of its `named` datasets: the
+/// corner the fewest of the points fall near within `x_bounds` and `y_bounds`, so it
+/// hides as little of the lines as it can. The legend of a single series only repeats
+/// the title, so it's left out.
+fn legend_position(
+    named: &[&[(f64, f64)]],
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+) -> Option<LegendPosition> {
+    let named: Vec<&[(f64, f64)]> = named
+        .iter()
+        .copied()
+        .filter(|points| !points.is_empty())
+        .collect();
+    if named.len() < 2 {
+        return None;
+    }
+    // Within the outer third of the bounds on the `high` or low side
+    let near = |value: f64, [min, max]: [f64; 2], high: bool| {
+        let share = (value - min) / (max - min);
+        if high {
+            share >= 2.0 / 3.0
+        } else {
+            share <= 1.0 / 3.0
+        }
+    };
+    // Ratatui's default corner first, since the first of equally crowded corners wins
+    [
+        (LegendPosition::TopRight, true, true),
+        (LegendPosition::TopLeft, false, true),
+        (LegendPosition::BottomRight, true, false),
+        (LegendPosition::BottomLeft, false, false),
+    ]
+    .into_iter()
+    .min_by_key(|&(_, right, top)| {
+        named
+            .iter()
+            .flat_map(|points| points.iter())
+            .filter(|&&(x, y)| y.is_finite() && near(x, x_bounds, right) && near(y, y_bounds, top))
+            .count()
+    })
+    .map(|(position, ..)| position)
+}
+
+/// The smallest change between two samples, in MHz, that the clock chart marks as a
+/// sudden boost or drop.
+const CLOCK_STEP_MHZ: u32 = 300;
+
+/// The points the interpolated clock line has per sample, including the sample itself.
+const INTERPOLATION_STEPS: usize = 4;
+
+/// The samples of `clock` that moved by at least [`CLOCK_STEP_MHZ`] from the one before,
+/// as chart points. A step needs a sample on either side, so a gap isn't one.
+fn clock_steps(clock: &History<u32>) -> Vec<(f64, f64)> {
+    clock
+        .points(|clock| clock as f64)
+        .windows(2)
+        .filter(|pair| {
+            (pair[1].0 - pair[0].0).abs() == 1.0
+                && (pair[1].1 - pair[0].1).abs() >= f64::from(CLOCK_STEP_MHZ)
+        })
+        .map(|pair| pair[1])
+        .collect()
+}
+
+/// Inserts `steps - 1` evenly spaced points on the straight line between every two
+/// neighbouring `points`, so a line of few samples draws as a smooth slope rather than
+/// in steps. The samples themselves are kept as they are, and gaps aren't bridged.
+fn interpolate_points(points: &[(f64, f64)], steps: usize) -> Vec<(f64, f64)> {
+    let steps = steps.max(1);
+    let mut interpolated = Vec::with_capacity(points.len() * steps);
+    for pair in points.windows(2) {
+        let [(x0, y0), (x1, y1)] = [pair[0], pair[1]];
+        if (x1 - x0).abs() > 1.0 {
+            interpolated.push((x0, y0));
+            continue;
+        }
+        interpolated.extend((0..steps).map(|step| {
+            let t = step as f64 / steps as f64;
+            (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t)
+        }));
+    }
+    interpolated.extend(points.last());
+    interpolated
+}
+
+/// Renders the history of one clock domain of a single GPU, in MHz, with sudden clock
+/// steps marked in the warning color.
+fn render_clock_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let clock = gpu_info.clock(options.domain);
+    let max_clock = gpu_info.max_clock(options.domain);
+    let latest = options.units.clock(clock.latest() as f64);
+    let peak = options.units.clock_number(clock.peak() as f64);
+    let clock_value = match max_clock {
+        Some(max_clock) => format!(
+            "{} ({}%), max {}",
+            latest,
+            clock.latest() * 100 / max_clock,
+            peak
+        ),
+        None => format!("{}, max {}", latest, peak),
+    };
+    // Only an applied overclock is worth the room in the title
+    let clock_value = match gpu_info.clock_vf_offset(options.domain) {
+        Some(offset) if offset != 0 => match gpu_info.clock_vf_offset_range(options.domain) {
+            Some((min_offset, max_offset)) => format!(
+                "{}, {:+} MHz offset ({:+} to {:+})",
+                clock_value, offset, min_offset, max_offset
+            ),
+            None => format!("{}, {:+} MHz offset", clock_value, offset),
+        },
+        _ => clock_value,
+    };
+    let clock_value = match gpu_info.performance_state {
+        Some(state) => format!("{}, P{}", clock_value, state),
+        None => clock_value,
+    };
+    let clock_value = match gpu_info.throttle_events.summary() {
+        Some(summary) => format!("{}, {}", clock_value, summary),
+        None => clock_value,
+    };
+    let clock_title = metric_title(
+        &format!("{} Clock", options.domain),
+        gpu_info,
+        Metric::Clock,
+        clock_value,
+        area.width,
+    );
+    let clock_block = Block::bordered()
+        .border_style(metric_style(theme, gpu_info, Metric::Clock, theme.border))
+        .title(clock_title.alignment(ratatui::layout::Alignment::Center));
+
+    let gpu_clock_data = clock.points(|clock| clock as f64);
+    let anomaly_data = options
+        .anomaly_sigma
+        .map(|sigma| stats::anomalies(&gpu_clock_data, sigma))
+        .unwrap_or_default();
+    let gpu_clock_data = if options.interpolate {
+        interpolate_points(&gpu_clock_data, INTERPOLATION_STEPS)
+    } else {
+        gpu_clock_data
+    };
+    let gpu_clock_data = break_at_gaps(&gpu_clock_data);
+    let clock_step_data = clock_steps(clock);
+    let [x_min, x_max] = clock.x_bounds();
+    // Every other sample, so the lines read as dashed next to the solid clock line. An
+    // application clock still at its default is the base clock, and isn't drawn twice
+    let base_clock = gpu_info.base_clock(options.domain);
+    let applications_clock = gpu_info
+        .applications_clock(options.domain)
+        .filter(|&setpoint| Some(setpoint) != base_clock);
+    let reference_clocks: Vec<(&str, u32)> = [
+        ("Application", applications_clock),
+        ("Base", base_clock),
+        ("Boost", max_clock),
+    ]
+    .into_iter()
+    .filter_map(|(label, clock)| Some((label, clock?)))
+    .collect();
+    let reference_data: Vec<Vec<(f64, f64)>> = reference_clocks
+        .iter()
+        .map(|&(_, clock)| {
+            (x_min as i64..=x_max as i64)
+                .step_by(2)
+                .map(|x| (x as f64, clock as f64))
+                .collect()
+        })
+        .collect();
+
+    // Scale to the GPU's maximum clock when known, so the axis doesn't jump around, and
+    // otherwise to the whole window rather than the latest sample so trends stay visible
+    let window_max = clock
+        .max()
+        .max(applications_clock.unwrap_or_default())
+        .max(base_clock.unwrap_or_default());
+    let clock_bounds = y_bounds(
+        options.range(Metric::Clock),
+        match max_clock {
+            Some(max_clock) => max_clock.max(window_max) as f64,
+            None => y_axis_bound(window_max as f64),
+        },
+    );
+    debug!("Clock axis bounds: {:?}", clock_bounds);
+    let clock_ticks = y_ticks(clock_bounds);
+    let clock_labels: Vec<String> = clock_ticks
+        .iter()
+        .map(|&tick| options.units.clock_number(tick))
+        .collect();
+    // Only between the bounds, since the axis and the border already mark those
+    let gridline_data: Vec<[(f64, f64); 2]> = if options.gridlines {
+        clock_ticks[1..clock_ticks.len() - 1]
+            .iter()
+            .map(|&tick| [(x_min, tick), (x_max, tick)])
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let chart_gpu_clock_data = Dataset::default()
+        .name("GPU Clock")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .data(&gpu_clock_data);
+
+    let chart_gpu_clock_x_axis = time_axis(clock.x_bounds(), options.interval);
+    let chart_gpu_clock_y_axis = Axis::default()
+        .title("GPU Clock Speed")
+        .bounds(clock_bounds)
+        .labels(clock_labels);
+
+    // Drawn first, so the clock line stays on top where they cross. The reference lines
+    // are labeled where they're drawn rather than in the legend, which short charts hide
+    let mut datasets: Vec<Dataset> = gridline_data
+        .iter()
+        .map(|gridline| {
+            Dataset::default()
+                .marker(Marker::Dot)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(Style::new().fg(theme.border).add_modifier(Modifier::DIM))
+                .data(gridline)
+        })
+        .collect();
+    datasets.push(chart_gpu_clock_data);
+    if !clock_step_data.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("Clock Step")
+                .marker(Marker::Block)
+                .graph_type(ratatui::widgets::GraphType::Scatter)
+                .style(Style::new().fg(theme.temp_warn_color))
+                .data(&clock_step_data),
+        );
+    }
+    datasets.extend(anomaly_dataset(theme, &anomaly_data));
+    for data in &reference_data {
+        datasets.push(
+            Dataset::default()
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Scatter)
+                .style(Style::new().fg(theme.text))
+                .data(data),
+        );
+    }
+    let graph_area = clock_block.inner(area);
+    let chart_gpu_clock = Chart::new(datasets)
+        .block(clock_block)
+        .x_axis(chart_gpu_clock_x_axis)
+        .y_axis(chart_gpu_clock_y_axis)
+        .legend_position(legend_position(
+            &[&gpu_clock_data, &clock_step_data, &anomaly_data],
+            clock.x_bounds(),
+            clock_bounds,
+        ))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Clock,
+            theme.clock_color,
+        ));
+    chart_gpu_clock.render(area, buf);
+    let labels: Vec<(f64, String)> = reference_clocks
+        .iter()
+        .map(|&(label, clock)| {
+            (
+                f64::from(clock),
+                format!("{} {}", label, options.units.clock(f64::from(clock))),
+            )
+        })
+        .collect();
+    render_line_labels(&labels, clock_bounds, theme, graph_area, buf);
+}
+
+/// Draws a vertical line at `x` over the chart drawn in `area`, with its x-axis spanning
+/// `bounds`, for the cursor. The graph is found right of the corner where the chart's
+/// axes meet, and the line only fills the blank cells so the points it crosses stay.
+fn render_crosshair(x: f64, bounds: [f64; 2], style: Style, area: Rect, buf: &mut Buffer) {
+    let inner = area.inner(ratatui::layout::Margin::new(1, 1));
+    let corner = (inner.top()..inner.bottom()).rev().find_map(|row| {
+        (inner.left()..inner.right())
+            .find(|&column| buf[(column, row)].symbol() == ratatui::symbols::line::BOTTOM_LEFT)
+            .map(|column| (column, row))
+    });
+    let Some((axis_column, axis_row)) = corner else {
+        return;
+    };
+    let graph_left = axis_column + 1;
+    let graph_width = inner.right().saturating_sub(graph_left);
+    if graph_width == 0 || !(bounds[0]..=bounds[1]).contains(&x) {
+        return;
+    }
+    let share = if bounds[1] > bounds[0] {
+        (x - bounds[0]) / (bounds[1] - bounds[0])
+    } else {
+        1.0
+    };
+    let column = graph_left + (share * f64::from(graph_width - 1)).round() as u16;
+    for row in inner.top()..axis_row {
+        let cell = &mut buf[(column, row)];
+        if cell.symbol() == " " {
+            cell.set_symbol(ratatui::symbols::line::VERTICAL)
+                .set_style(style);
+        }
+    }
+}
+
+/// Labels horizontal reference lines of a chart drawn in `area`, inside its borders,
+/// with its y-axis spanning `bounds`: each `(value, label)` is written centered on the
+/// row of the line at `value`, where neither the legend nor the axis titles are. The
+/// legend doesn't fit in a short chart, and these labels take no more rows than the
+/// lines themselves. Lines outside the bounds and labels that would land on the row of
+/// an earlier one are left out.
+fn render_line_labels(
+    labels: &[(f64, String)],
+    bounds: [f64; 2],
+    theme: &Theme,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    // The bottom two rows hold the x-axis and its labels
+    let graph_bottom = area.bottom().saturating_sub(3);
+    if graph_bottom <= area.top() || bounds[1] <= bounds[0] {
+        return;
+    }
+    let mut rows = Vec::new();
+    for (value, label) in labels {
+        if !(bounds[0]..=bounds[1]).contains(value) {
+            continue;
+        }
+        // Rounded down from the top, like the chart's own points
+        let share = (bounds[1] - value) / (bounds[1] - bounds[0]);
+        let row = area.top() + (share * f64::from(graph_bottom - area.top())) as u16;
+        if rows.contains(&row) {
+            continue;
+        }
+        rows.push(row);
+        let label = format!(" {} ", label);
+        let x = area.x + area.width.saturating_sub(label.chars().count() as u16) / 2;
+        buf.set_stringn(
+            x,
+            row,
+            &label,
+            usize::from(area.width),
+            Style::new().fg(theme.text),
+        );
+    }
+}
+
+/// Renders how full the memory of a single GPU is as a one-line gauge, colored by how
+/// close it is to running out.
+fn render_memory_gauge(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    units: Units,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let memory_used = gpu_info.memory_used.latest();
+    let ratio = match gpu_info.memory_total {
+        0 => 0.0,
+        total => (memory_used as f64 / total as f64).min(1.0),
+    };
+    let color = if !gpu_info.available(Metric::Memory) {
+        theme.unavailable
+    } else if ratio < 0.7 {
+        theme.gauge_color
+    } else if ratio < 0.9 {
+        theme.temp_warn_color
+    } else {
+        theme.temp_crit_color
+    };
+    let reserved = gpu_info
+        .memory_reserved
+        .filter(|_| gpu_info.available(Metric::Memory) && gpu_info.memory_total > 0);
+    let label = match gpu_info.error(Metric::Memory) {
+        Some(_) => format!("{} Memory N/A", gpu_info.label()),
+        None => format!(
+            "{} Memory {} / {} ({} VRAM){}",
+            gpu_info.label(),
+            units.memory_number(memory_used),
+            units.memory(gpu_info.memory_total),
+            gpu_info
+                .precision
+                .memory_percent(memory_used, gpu_info.memory_total),
+            reserved
+                .map(|reserved| format!(", {} reserved", units.memory(reserved)))
+                .unwrap_or_default()
+        ),
+    };
+    let Some(reserved) = reserved else {
+        Gauge::default()
+            .ratio(ratio)
+            .label(label)
+            .gauge_style(Style::new().fg(color).bg(theme.background))
+            .render(area, buf);
+        return;
+    };
+
+    // A Gauge only has one filled part, so the used / reserved / free split is stacked by
+    // hand: memory in use first, then the driver's reservation, which counts as used
+    let cells = |bytes: u64| {
+        (area.width as f64 * (bytes as f64 / gpu_info.memory_total as f64).min(1.0)).round() as u16
+    };
+    let used_end = area.x + cells(memory_used.saturating_sub(reserved));
+    let reserved_end = (area.x + cells(memory_used)).max(used_end);
+    // Like a Gauge, the label is centered and inverted where it crosses the filled part
+    let label_x = area.x + area.width.saturating_sub(label.chars().count() as u16) / 2;
+    let label_y = area.y + area.height / 2;
+    let mut label_chars = label.chars();
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            let cell = &mut buf[(x, y)];
+            if x < used_end {
+                cell.set_fg(theme.background).set_bg(color);
+            } else if x < reserved_end {
+                cell.set_fg(theme.background)
+                    .set_bg(theme.memory_reserved_color);
+            } else {
+                cell.set_fg(color).set_bg(theme.background);
+            }
+            let symbol = if y == label_y && x >= label_x {
+                label_chars.next().unwrap_or(' ')
+            } else {
+                ' '
+            };
+            cell.set_char(symbol);
+        }
+    }
+}
+
+/// Renders the clock of the chart domain and the temperature of a single GPU on shared
+/// axes, each scaled to its highest value in the window, so clock drops can be matched
+/// with the temperature that caused them.
+fn render_overlay_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let clock = gpu_info.clock(options.domain);
+    let temperature = &gpu_info.temperature;
+    let prefix = format!("{} {} Clock vs Temp - ", gpu_info.label(), options.domain);
+    let suffix = format!(
+        " ({}, {})",
+        match gpu_info.unavailable(Metric::Clock) {
+            Some(unavailable) => String::from(unavailable),
+            None => options.units.clock(clock.latest() as f64),
+        },
+        match gpu_info.unavailable(Metric::Temperature) {
+            Some(unavailable) => String::from(unavailable),
+            None => gpu_info.temperature_unit.format(temperature.latest()),
+        },
+    );
+    let overlay_title = named_title(&prefix, gpu_info, &suffix, area.width);
+    let overlay_block = Block::bordered()
+        .border_style(metric_style(theme, gpu_info, Metric::Clock, theme.border))
+        .title(overlay_title.alignment(ratatui::layout::Alignment::Center));
+
+    let clock_scale = clock.max().max(1) as f64;
+    let clock_data = break_at_gaps(&clock.points(|clock| clock as f64 / clock_scale));
+    let temperature_scale = temperature.max().max(1) as f64;
+    let temperature_data =
+        break_at_gaps(&temperature.points(|temp| temp as f64 / temperature_scale));
+
+    let datasets = vec![
+        Dataset::default()
+            .name("Clock")
+            .marker(options.marker)
+            .graph_type(ratatui::widgets::GraphType::Line)
+            .style(metric_style(
+                theme,
+                gpu_info,
+                Metric::Clock,
+                theme.clock_color,
+            ))
+            .data(&clock_data),
+        Dataset::default()
+            .name("Temperature")
+            .marker(options.marker)
+            .graph_type(ratatui::widgets::GraphType::Line)
+            .style(metric_style(
+                theme,
+                gpu_info,
+                Metric::Temperature,
+                theme.temp_color,
+            ))
+            .data(&temperature_data),
+    ];
+
+    let overlay_x_axis = time_axis(clock.x_bounds(), options.interval);
+    let overlay_y_axis = Axis::default()
+        .title("Share of max")
+        .bounds([0.0, Y_AXIS_HEADROOM])
+        .labels(vec!["0", "1"]);
+
+    // The legend is what tells the two lines apart, so keep it on narrower charts too
+    Chart::new(datasets)
+        .block(overlay_block)
+        .x_axis(overlay_x_axis)
+        .y_axis(overlay_y_axis)
+        .legend_position(legend_position(
+            &[&clock_data, &temperature_data],
+            clock.x_bounds(),
+            [0.0, Y_AXIS_HEADROOM],
+        ))
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
+        .render(area, buf);
+}
+
+/// Splits the temperature line `points` into runs of the same color of the gradient,
+/// from `cool` up to `threshold`, in [`GRADIENT_STEPS`] steps. Every run starts at the
+/// last point of the run before, so the line stays connected where the color changes.
+fn gradient_segments(
+    points: &[(f64, f64)],
+    cool: f64,
+    threshold: f64,
+) -> Vec<(f64, Vec<(f64, f64)>)> {
+    let step = |temperature: f64| {
+        let heat = (temperature - cool) / (threshold - cool);
+        (heat.clamp(0.0, 1.0) * GRADIENT_STEPS as f64).round() as usize
+    };
+    let mut segments: Vec<(usize, Vec<(f64, f64)>)> = Vec::new();
+    for (i, &point) in points.iter().enumerate() {
+        let level = step(point.1);
+        match segments.last_mut() {
+            Some((last, segment)) if *last == level => segment.push(point),
+            _ => {
+                let mut segment: Vec<(f64, f64)> = points[i.saturating_sub(1)..i].to_vec();
+                segment.push(point);
+                segments.push((level, segment));
+            }
+        }
+    }
+    segments
+        .into_iter()
+        .map(|(level, segment)| (level as f64 / GRADIENT_STEPS as f64, segment))
+        .collect()
+}
+
+/// Renders the core temperature history of a single GPU, in °C or °F, as a moving
+/// average over the smoothing window of `options`, with the memory junction temperature
+/// on GPUs that report it. The line is colored by how close it runs to the critical
+/// threshold, in °C, from the safe color of the theme's gradient [`GRADIENT_SPAN`] below
+/// it to the hot one at it, and both thresholds are drawn as dashed lines.
+fn render_temperature_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    thresholds: TemperatureThresholds,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let unit = gpu_info.temperature_unit;
+    let memory_temperature = gpu_info.memory_temperature.as_ref();
+    let memory_reading = memory_temperature
+        .map(|temperature| format!(", memory {}", unit.format(temperature.latest())))
+        .unwrap_or_default();
+    let temp_title = metric_title(
+        "Temperature",
+        gpu_info,
+        Metric::Temperature,
+        format!(
+            "{}{}, max {}",
+            unit.format(gpu_info.temperature.latest()),
+            memory_reading,
+            unit.number(gpu_info.temperature.peak())
+        ),
+        area.width,
+    );
+    let temp_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Temperature,
+            theme.temp_color,
+        ))
+        .title(temp_title.alignment(ratatui::layout::Alignment::Center));
+
+    let gpu_temperature_data = gpu_info
+        .temperature
+        .smoothed_points(options.smoothing_window, |temp| unit.convert(temp as f64));
+
+    let anomaly_data = options
+        .anomaly_sigma
+        .map(|sigma| stats::anomalies(&gpu_temperature_data, sigma))
+        .unwrap_or_default();
+
+    let memory_temperature_data = memory_temperature
+        .map(|temperature| {
+            break_at_gaps(
+                &temperature
+                    .smoothed_points(options.smoothing_window, |temp| unit.convert(temp as f64)),
+            )
+        })
+        .unwrap_or_default();
+
+    let hottest = gpu_info
+        .temperature
+        .max()
+        .max(memory_temperature.map_or(0, History::max))
+        .max(thresholds.warn.max(thresholds.crit).unwrap_or_default());
+    // Computed and configured in °C like everything else, and only then converted
+    let temp_bounds = y_bounds(
+        options.range(Metric::Temperature),
+        y_axis_bound(hottest as f64),
+    )
+    .map(|bound| unit.convert(bound));
+    debug!("Temperature axis bounds: {:?}", temp_bounds);
+    let temp_bound_strs = temp_bounds.map(|bound| format!("{:.0}", bound));
+
+    let threshold = thresholds.gradient();
+    let segments = gradient_segments(
+        &break_at_gaps(&gpu_temperature_data),
+        unit.convert(f64::from(threshold) - GRADIENT_SPAN),
+        unit.convert(f64::from(threshold)),
+    );
+    // Every other sample, so the lines read as dashed next to the solid temperature line
+    let [x_min, x_max] = gpu_info.temperature.x_bounds();
+    let threshold_data: [Vec<(f64, f64)>; 2] =
+        [thresholds.warn, thresholds.crit].map(|threshold| {
+            threshold
+                .map(|threshold| {
+                    (x_min as i64..=x_max as i64)
+                        .step_by(2)
+                        .map(|x| (x as f64, unit.convert(f64::from(threshold))))
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+
+    let chart_gpu_temperature_x_axis = time_axis(gpu_info.temperature.x_bounds(), options.interval);
+    let chart_gpu_temperature_y_axis = Axis::default()
+        .title(smoothed_axis_title(
+            &format!("GPU Temperature ({})", unit.symbol()),
+            options.smoothing_window,
+        ))
+        .bounds(temp_bounds)
+        .labels(temp_bound_strs.to_vec());
+
+    let mut datasets: Vec<Dataset> = segments
+        .iter()
+        .enumerate()
+        .map(|(i, (heat, segment))| {
+            let dataset = Dataset::default()
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(Style::new().fg(theme.temp_gradient_color(*heat)))
+                .data(segment);
+            // One legend entry for the whole line
+            if i == 0 {
+                dataset.name("GPU Temperature")
+            } else {
+                dataset
+            }
+        })
+        .collect();
+    if memory_temperature.is_some() {
+        datasets.push(
+            Dataset::default()
+                .name("Memory Temperature")
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(Style::new().fg(theme.memory_color))
+                .data(&memory_temperature_data),
+        );
+    }
+    for (data, (name, color)) in threshold_data.iter().zip([
+        ("Warning", theme.temp_warn_color),
+        ("Critical", theme.temp_crit_color),
+    ]) {
+        if !data.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .name(name)
+                    .marker(options.marker)
+                    .graph_type(ratatui::widgets::GraphType::Scatter)
+                    .style(Style::new().fg(color))
+                    .data(data),
+            );
+        }
+    }
+
+    datasets.extend(anomaly_dataset(theme, &anomaly_data));
+
+    let chart_gpu_temperature = Chart::new(datasets)
+        .block(temp_block)
+        .x_axis(chart_gpu_temperature_x_axis)
+        .y_axis(chart_gpu_temperature_y_axis)
+        .legend_position(legend_position(
+            &[
+                &gpu_temperature_data,
+                &memory_temperature_data,
+                &threshold_data[0],
+                &threshold_data[1],
+                &anomaly_data,
+            ],
+            gpu_info.temperature.x_bounds(),
+            temp_bounds,
+        ))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Temperature,
+            theme.temp_color,
+        ));
+    chart_gpu_temperature.render(area, buf);
+}
+
+/// Renders the VRAM usage history of a single GPU, in MiB.
+///
+/// The y-axis is bounded by the total memory of the device so the chart stays stable
+/// between samples.
+fn render_memory_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    const MIB: f64 = 1024.0 * 1024.0;
+
+    let memory_title = metric_title(
+        "Memory",
+        gpu_info,
+        Metric::Memory,
+        format!(
+            "{}, max {}",
+            options.units.memory(gpu_info.memory_used.latest()),
+            options.units.memory_number(gpu_info.memory_used.peak())
+        ),
+        area.width,
+    );
+    let memory_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Memory,
+            theme.memory_color,
+        ))
+        .title(memory_title.alignment(ratatui::layout::Alignment::Center));
+
+    let gpu_memory_data = break_at_gaps(&gpu_info.memory_used.points(|used| used as f64 / MIB));
+
+    let memory_total = (gpu_info.memory_total as f64 / MIB).max(1.0); // Ensure the bound is at least 1
+    let memory_total_str = options.units.memory(gpu_info.memory_total);
+
+    let chart_gpu_memory_data = Dataset::default()
+        .name("GPU Memory Used")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .data(&gpu_memory_data);
+
+    let chart_gpu_memory_x_axis = time_axis(gpu_info.memory_used.x_bounds(), options.interval);
+    let chart_gpu_memory_y_axis = Axis::default()
+        .title("GPU Memory Used")
+        .bounds([0.0, memory_total])
+        .labels(vec!["0", memory_total_str.as_str()]);
+
+    let chart_gpu_memory = Chart::new(vec![chart_gpu_memory_data])
+        .block(memory_block)
+        .x_axis(chart_gpu_memory_x_axis)
+        .y_axis(chart_gpu_memory_y_axis)
+        .legend_position(None)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Memory,
+            theme.memory_color,
+        ));
+    chart_gpu_memory.render(area, buf);
+}
+
+/// Renders SM and memory-controller utilization of a single GPU as two lines bounded
+/// 0–100%.
+fn render_utilization_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let utilization_title = metric_title(
+        "Utilization",
+        gpu_info,
+        Metric::Utilization,
+        match gpu_info
+            .utilization_samples
+            .spread(|utilization| utilization.to_string(), "%")
+        {
+            Some(spread) => format!(
+                "SM {}% / Mem {}%, SM {}",
+                gpu_info.gpu_utilization.latest(),
+                gpu_info.memory_utilization.latest(),
+                spread
+            ),
+            None => format!(
+                "SM {}% / Mem {}%",
+                gpu_info.gpu_utilization.latest(),
+                gpu_info.memory_utilization.latest()
+            ),
+        },
+        area.width,
+    );
+    let utilization_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Utilization,
+            theme.sm_utilization_color,
+        ))
+        .title(utilization_title.alignment(ratatui::layout::Alignment::Center));
+
+    let gpu_utilization_data = break_at_gaps(&gpu_info.gpu_utilization.points(|util| util as f64));
+    let memory_utilization_data =
+        break_at_gaps(&gpu_info.memory_utilization.points(|util| util as f64));
+
+    let chart_gpu_utilization_data = Dataset::default()
+        .name("SM")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Utilization,
+            theme.sm_utilization_color,
+        ))
+        .data(&gpu_utilization_data);
+    let chart_memory_utilization_data = Dataset::default()
+        .name("Memory")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Utilization,
+            theme.memory_utilization_color,
+        ))
+        .data(&memory_utilization_data);
+
+    let chart_utilization_x_axis = time_axis(gpu_info.gpu_utilization.x_bounds(), options.interval);
+    let chart_utilization_y_axis = Axis::default()
+        .title("Utilization %")
+        .bounds([0.0, 100.0])
+        .labels(vec!["0", "100"]);
+
+    let chart_utilization = Chart::new(vec![
+        chart_gpu_utilization_data,
+        chart_memory_utilization_data,
+    ])
+    .block(utilization_block)
+    .x_axis(chart_utilization_x_axis)
+    .y_axis(chart_utilization_y_axis)
+    .legend_position(legend_position(
+        &[&gpu_utilization_data, &memory_utilization_data],
+        gpu_info.gpu_utilization.x_bounds(),
+        [0.0, 100.0],
+    ))
+    .style(metric_style(
+        theme,
+        gpu_info,
+        Metric::Utilization,
+        theme.sm_utilization_color,
+    ));
+    chart_utilization.render(area, buf);
+}
+
+/// Renders the power draw history of a single GPU, in watts, as a moving average over
+/// the smoothing window of `options`. Where the GPU reports the power of its die apart
+/// from the whole board, that is drawn as a second line, with the gap between the two
+/// going to the memory and the rest of the board.
+///
+/// The y-axis is bounded by the enforced power limit so users can see how close the card
+/// runs to its cap. On cards with power management, the limit and the range it can be
+/// set within are drawn as dashed lines. On a log scale the axis starts at 1 W instead
+/// of any fixed range.
+fn render_power_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let precision = gpu_info.precision;
+    let power_value = match gpu_info
+        .power_samples
+        .spread(|power| precision.watts_number(f64::from(power)), " W")
+    {
+        Some(spread) => format!(
+            "{}, {}",
+            precision.watts(gpu_info.power_usage.latest() as f64),
+            spread
+        ),
+        None => precision.watts(gpu_info.power_usage.latest() as f64),
+    };
+    let die_power = gpu_info.die_power.as_ref();
+    let power_value = match die_power {
+        Some(die_power) => format!(
+            "{}, die {}",
+            power_value,
+            precision.watts(die_power.latest() as f64)
+        ),
+        None => power_value,
+    };
+    let power_value = match gpu_info.power_limit_constraints {
+        Some((min_limit, max_limit)) => format!(
+            "{}, limit {} ({}–{})",
+            power_value,
+            precision.watts(f64::from(gpu_info.power_limit)),
+            precision.watts_number(f64::from(min_limit)),
+            precision.watts(f64::from(max_limit))
+        ),
+        None => power_value,
+    };
+    let power_title = metric_title("Power", gpu_info, Metric::Power, power_value, area.width);
+    let power_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Power,
+            theme.power_color,
+        ))
+        .title(power_title.alignment(ratatui::layout::Alignment::Center));
+
+    let mut gpu_power_data = gpu_info
+        .power_usage
+        .smoothed_points(options.smoothing_window, |milliwatts| {
+            milliwatts as f64 / 1000.0
+        });
+    let mut die_power_data = die_power
+        .map(|die_power| {
+            die_power.smoothed_points(options.smoothing_window, |milliwatts| {
+                milliwatts as f64 / 1000.0
+            })
+        })
+        .unwrap_or_default();
+
+    // Fall back to the current draw on cards that don't report a limit, and make room
+    // for the highest limit that can be set
+    let power_limit = gpu_info
+        .power_limit
+        .max(gpu_info.power_usage.latest())
+        .max(gpu_info.power_limit_constraints.map_or(0, |(_, max)| max))
+        .max(1000) as f64
+        / 1000.0;
+    // Every other sample, so the lines read as dashed next to the solid power line
+    let [x_min, x_max] = gpu_info.power_usage.x_bounds();
+    let mut limit_data: Vec<Vec<(f64, f64)>> = match gpu_info.power_limit_constraints {
+        Some((min_limit, max_limit)) => [min_limit, gpu_info.power_limit, max_limit]
+            .into_iter()
+            .map(|limit| {
+                (x_min as i64..=x_max as i64)
+                    .step_by(2)
+                    .map(|x| (x as f64, f64::from(limit) / 1000.0))
+                    .collect()
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let mut anomaly_data = options
+        .anomaly_sigma
+        .map(|sigma| stats::anomalies(&gpu_power_data, sigma))
+        .unwrap_or_default();
+    let (power_bounds, power_bound_strs) = if options.log_scaled(Metric::Power) {
+        gpu_power_data = log_points(&gpu_power_data);
+        anomaly_data = log_points(&anomaly_data);
+        die_power_data = log_points(&die_power_data);
+        for data in &mut limit_data {
+            *data = log_points(data);
+        }
+        log_axis(power_limit, "W")
+    } else {
+        let power_bounds = y_bounds(options.range(Metric::Power), power_limit);
+        let power_bound_strs = vec![
+            format!("{:.0}", power_bounds[0]),
+            format!("{:.0} W", power_bounds[1]),
+        ];
+        (power_bounds, power_bound_strs)
+    };
+    let gpu_power_data = break_at_gaps(&gpu_power_data);
+    let die_power_data = break_at_gaps(&die_power_data);
+
+    let chart_gpu_power_data = Dataset::default()
+        .name("GPU Power")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .data(&gpu_power_data);
+
+    let chart_gpu_power_x_axis = time_axis(gpu_info.power_usage.x_bounds(), options.interval);
+    let chart_gpu_power_y_axis = Axis::default()
+        .title(log_axis_title(
+            smoothed_axis_title("GPU Power Draw", options.smoothing_window),
+            options.log_scaled(Metric::Power),
+        ))
+        .bounds(power_bounds)
+        .labels(power_bound_strs);
+
+    let mut datasets = vec![chart_gpu_power_data];
+    if die_power.is_some() {
+        datasets.push(
+            Dataset::default()
+                .name("Die Power")
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(Style::new().fg(theme.memory_color))
+                .data(&die_power_data),
+        );
+    }
+    for (data, name) in limit_data
+        .iter()
+        .zip(["Min Limit", "Power Limit", "Max Limit"])
+    {
+        datasets.push(
+            Dataset::default()
+                .name(name)
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Scatter)
+                .style(Style::new().fg(theme.text).add_modifier(Modifier::DIM))
+                .data(data),
+        );
+    }
+    datasets.extend(anomaly_dataset(theme, &anomaly_data));
+    let mut named: Vec<&[(f64, f64)]> = vec![&gpu_power_data, &die_power_data];
+    named.extend(limit_data.iter().map(Vec::as_slice));
+    named.push(&anomaly_data);
+
+    let chart_gpu_power = Chart::new(datasets)
+        .block(power_block)
+        .x_axis(chart_gpu_power_x_axis)
+        .y_axis(chart_gpu_power_y_axis)
+        .legend_position(legend_position(
+            &named,
+            gpu_info.power_usage.x_bounds(),
+            power_bounds,
+        ))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Power,
+            theme.power_color,
+        ));
+    chart_gpu_power.render(area, buf);
+}
+
+/// Renders the graphics clock per watt of a single GPU, in MHz/W, to find the power
+/// limit past which extra watts stop buying much clock.
+fn render_efficiency_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let efficiency_title = metric_title(
+        "Efficiency",
+        gpu_info,
+        Metric::Power,
+        format!(
+            "{} MHz/W, best {}",
+            gpu_info.precision.efficiency(gpu_info.efficiency.latest()),
+            gpu_info.precision.efficiency(gpu_info.efficiency.peak())
+        ),
+        area.width,
+    );
+    let efficiency_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Power,
+            theme.power_color,
+        ))
+        .title(efficiency_title.alignment(ratatui::layout::Alignment::Center));
+
+    let efficiency_data = break_at_gaps(&gpu_info.efficiency.points(|efficiency| efficiency));
+    let highest = gpu_info.efficiency.stats(|efficiency| efficiency).max;
+    let efficiency_bound = y_axis_bound(highest);
+
+    let chart_efficiency_data = Dataset::default()
+        .name("Clock per Watt")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .data(&efficiency_data);
+
+    let chart_efficiency_x_axis = time_axis(gpu_info.efficiency.x_bounds(), options.interval);
+    let chart_efficiency_y_axis = Axis::default()
+        .title("MHz / W")
+        .bounds([0.0, efficiency_bound])
+        .labels(vec![
+            String::from("0"),
+            gpu_info.precision.efficiency(efficiency_bound),
+        ]);
+
+    let chart_efficiency = Chart::new(vec![chart_efficiency_data])
+        .block(efficiency_block)
+        .x_axis(chart_efficiency_x_axis)
+        .y_axis(chart_efficiency_y_axis)
+        .legend_position(None)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Power,
+            theme.power_color,
+        ));
+    chart_efficiency.render(area, buf);
+}
+
+/// Renders the SM utilization of a single GPU against its memory controller
+/// utilization, one point per sample in the window. Points crowding the bottom right
+/// mean a compute-bound workload, the top left a memory-bound one.
+fn render_utilization_scatter(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let scatter_title = metric_title(
+        "SM vs Memory",
+        gpu_info,
+        Metric::Utilization,
+        format!(
+            "SM {}% / Mem {}%",
+            gpu_info.gpu_utilization.latest(),
+            gpu_info.memory_utilization.latest()
+        ),
+        area.width,
+    );
+    let scatter_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Utilization,
+            theme.sm_utilization_color,
+        ))
+        .title(scatter_title.alignment(ratatui::layout::Alignment::Center));
+
+    // Both come from one query, but pair them by sample so a gap in either drops it
+    let memory_utilization: BTreeMap<i64, f64> = gpu_info
+        .memory_utilization
+        .points(|util| util as f64)
+        .into_iter()
+        .map(|(x, util)| (x as i64, util))
+        .collect();
+    let scatter_data: Vec<(f64, f64)> = gpu_info
+        .gpu_utilization
+        .points(|util| util as f64)
+        .into_iter()
+        .filter_map(|(x, sm)| Some((sm, *memory_utilization.get(&(x as i64))?)))
+        .collect();
+
+    let chart_scatter_data = Dataset::default()
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Scatter)
+        .data(&scatter_data);
+
+    let percent_labels = || vec![String::from("0"), String::from("50"), String::from("100")];
+    let chart_scatter_x_axis = Axis::default()
+        .title("SM %")
+        .bounds([0.0, 100.0])
+        .labels(percent_labels());
+    let chart_scatter_y_axis = Axis::default()
+        .title("Memory %")
+        .bounds([0.0, 100.0])
+        .labels(percent_labels());
+
+    let chart_scatter = Chart::new(vec![chart_scatter_data])
+        .block(scatter_block)
+        .x_axis(chart_scatter_x_axis)
+        .y_axis(chart_scatter_y_axis)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Utilization,
+            theme.sm_utilization_color,
+        ));
+    chart_scatter.render(area, buf);
+}
+
+/// Renders an estimate of the memory bandwidth a single GPU uses, in GB/s, as the share
+/// of time the memory controller was busy times the theoretical bandwidth. A busy
+/// controller rarely moves data at the full rate, so this is an upper bound.
+fn render_bandwidth_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let peak = gpu_info.peak_memory_bandwidth().unwrap_or_default();
+    let bandwidth = |util: u32| f64::from(util) / 100.0 * peak;
+    let bandwidth_title = metric_title(
+        "Bandwidth (est.)",
+        gpu_info,
+        Metric::Utilization,
+        format!(
+            "~{:.0}/{:.0} GB/s",
+            bandwidth(gpu_info.memory_utilization.latest()),
+            peak
+        ),
+        area.width,
+    );
+    let bandwidth_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Utilization,
+            theme.memory_utilization_color,
+        ))
+        .title(bandwidth_title.alignment(ratatui::layout::Alignment::Center));
+
+    let bandwidth_data = break_at_gaps(&gpu_info.memory_utilization.points(bandwidth));
+
+    let chart_bandwidth_data = Dataset::default()
+        .name("Estimated Bandwidth")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .data(&bandwidth_data);
+
+    let chart_bandwidth_x_axis =
+        time_axis(gpu_info.memory_utilization.x_bounds(), options.interval);
+    let chart_bandwidth_y_axis = Axis::default()
+        .title("Memory GB/s (estimate)")
+        .bounds([0.0, peak])
+        .labels(vec![String::from("0"), format!("{:.0}", peak)]);
+
+    let chart_bandwidth = Chart::new(vec![chart_bandwidth_data])
+        .block(bandwidth_block)
+        .x_axis(chart_bandwidth_x_axis)
+        .y_axis(chart_bandwidth_y_axis)
+        .legend_position(None)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Utilization,
+            theme.memory_utilization_color,
+        ));
+    chart_bandwidth.render(area, buf);
+}
+
+/// Renders PCIe receive and transmit throughput of a single GPU as two lines, in KB/s.
+///
+/// The y-axis follows the busiest direction over the window, since the link's peak
+/// bandwidth isn't reported alongside the counters.
+fn render_pcie_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let pcie_title = metric_title(
+        "PCIe",
+        gpu_info,
+        Metric::Pcie,
+        format!(
+            "RX {} / TX {} KB/s",
+            gpu_info.pcie_rx.latest(),
+            gpu_info.pcie_tx.latest()
+        ),
+        area.width,
+    );
+    let pcie_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Pcie,
+            theme.pcie_rx_color,
+        ))
+        .title(pcie_title.alignment(ratatui::layout::Alignment::Center));
+
+    let mut pcie_rx_data = gpu_info.pcie_rx.points(|throughput| throughput as f64);
+    let mut pcie_tx_data = gpu_info.pcie_tx.points(|throughput| throughput as f64);
+
+    let pcie_max = gpu_info.pcie_rx.max().max(gpu_info.pcie_tx.max()) as f64;
+    let (pcie_bounds, pcie_bound_strs) = if options.log_scaled(Metric::Pcie) {
+        pcie_rx_data = log_points(&pcie_rx_data);
+        pcie_tx_data = log_points(&pcie_tx_data);
+        log_axis(pcie_max, "KB/s")
+    } else {
+        let pcie_bound = y_axis_bound(pcie_max);
+        (
+            [0.0, pcie_bound],
+            vec![String::from("0"), format!("{:.0}", pcie_bound)],
+        )
+    };
+    let pcie_rx_data = break_at_gaps(&pcie_rx_data);
+    let pcie_tx_data = break_at_gaps(&pcie_tx_data);
+
+    let chart_pcie_rx_data = Dataset::default()
+        .name("RX")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Pcie,
+            theme.pcie_rx_color,
+        ))
+        .data(&pcie_rx_data);
+    let chart_pcie_tx_data = Dataset::default()
+        .name("TX")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Pcie,
+            theme.pcie_tx_color,
+        ))
+        .data(&pcie_tx_data);
+
+    let chart_pcie_x_axis = time_axis(gpu_info.pcie_rx.x_bounds(), options.interval);
+    let chart_pcie_y_axis = Axis::default()
+        .title(log_axis_title(
+            String::from("PCIe KB/s"),
+            options.log_scaled(Metric::Pcie),
+        ))
+        .bounds(pcie_bounds)
+        .labels(pcie_bound_strs);
+
+    let chart_pcie = Chart::new(vec![chart_pcie_rx_data, chart_pcie_tx_data])
+        .block(pcie_block)
+        .x_axis(chart_pcie_x_axis)
+        .y_axis(chart_pcie_y_axis)
+        .legend_position(legend_position(
+            &[&pcie_rx_data, &pcie_tx_data],
+            gpu_info.pcie_rx.x_bounds(),
+            pcie_bounds,
+        ))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Pcie,
+            theme.pcie_rx_color,
+        ));
+    chart_pcie.render(area, buf);
+}
+
+/// Renders NVENC and NVDEC utilization of a single GPU as two lines bounded 0–100%.
+fn render_codec_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let codec_title = metric_title(
+        "Video",
+        gpu_info,
+        Metric::Codec,
+        format!(
+            "Enc {}% / Dec {}%",
+            gpu_info.encoder_utilization.latest(),
+            gpu_info.decoder_utilization.latest()
+        ),
+        area.width,
+    );
+    let codec_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Codec,
+            theme.encoder_color,
+        ))
+        .title(codec_title.alignment(ratatui::layout::Alignment::Center));
+
+    let encoder_data = break_at_gaps(&gpu_info.encoder_utilization.points(|util| util as f64));
+    let decoder_data = break_at_gaps(&gpu_info.decoder_utilization.points(|util| util as f64));
+
+    let chart_encoder_data = Dataset::default()
+        .name("Encoder")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Codec,
+            theme.encoder_color,
+        ))
+        .data(&encoder_data);
+    let chart_decoder_data = Dataset::default()
+        .name("Decoder")
+        .marker(options.marker)
+        .graph_type(ratatui::widgets::GraphType::Line)
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Codec,
+            theme.decoder_color,
+        ))
+        .data(&decoder_data);
+
+    let chart_codec_x_axis = time_axis(gpu_info.encoder_utilization.x_bounds(), options.interval);
+    let chart_codec_y_axis = Axis::default()
+        .title("Codec %")
+        .bounds([0.0, 100.0])
+        .labels(vec!["0", "100"]);
+
+    let chart_codec = Chart::new(vec![chart_encoder_data, chart_decoder_data])
+        .block(codec_block)
+        .x_axis(chart_codec_x_axis)
+        .y_axis(chart_codec_y_axis)
+        .legend_position(legend_position(
+            &[&encoder_data, &decoder_data],
+            gpu_info.encoder_utilization.x_bounds(),
+            [0.0, 100.0],
+        ))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Codec,
+            theme.encoder_color,
+        ));
+    chart_codec.render(area, buf);
+}
+
+/// Restyles the border of an already rendered chart, title included.
+fn highlight_border(area: Rect, style: Style, buf: &mut Buffer) {
+    let edges = [
+        Rect::new(area.x, area.y, area.width, 1),
+        Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1),
+        Rect::new(area.x, area.y, 1, area.height),
+        Rect::new(area.right().saturating_sub(1), area.y, 1, area.height),
+    ];
+    for edge in edges {
+        buf.set_style(edge.intersection(area), style);
+    }
+}
+
+/// The number of gaps between the time labels under every chart.
+const TIME_LABEL_GAPS: u32 = 3;
+
+/// The x-axis shared by every chart, labeled with how long ago the samples at the left
+/// edge, in between and at the right edge were taken, e.g. from "-30s" to "0s", or from
+/// "0s" to "-30s" for a mirrored history.
+fn time_axis(x_bounds: [f64; 2], interval: Duration) -> Axis<'static> {
+    let [left, right] = x_bounds;
+    let labels: Vec<String> = (0..=TIME_LABEL_GAPS)
+        .map(|gap| {
+            let x = left + (right - left) * f64::from(gap) / f64::from(TIME_LABEL_GAPS);
+            // Samples are at negative offsets, or positive ones when mirrored
+            format_time_ago(interval.mul_f64(x.abs()))
+        })
+        .collect();
+    Axis::default()
+        .title("Time")
+        .bounds(x_bounds)
+        .labels(labels)
+}
+
+/// Formats how long ago a sample was taken, as in "-45s", "-2m" or "-1m30s".
+fn format_time_ago(ago: Duration) -> String {
+    let seconds = ago.as_secs_f64().round() as u64;
+    match (seconds / 60, seconds % 60) {
+        (0, 0) => String::from("0s"),
+        (0, seconds) => format!("-{}s", seconds),
+        (minutes, 0) => format!("-{}m", minutes),
+        (minutes, seconds) => format!("-{}m{}s", minutes, seconds),
+    }
+}
+
+/// Marks the axis title of a chart drawn on a log scale.
+fn log_axis_title(title: String, log_scale: bool) -> String {
+    if log_scale {
+        format!("{} (log)", title)
+    } else {
+        title
+    }
+}
+
+/// Labels the y-axis of a chart, noting when its line is a moving average.
+fn smoothed_axis_title(title: &str, smoothing_window: usize) -> String {
+    match smoothing_window {
+        0 | 1 => String::from(title),
+        window => format!("{} (avg of {})", title, window),
+    }
+}
+
+/// Draws a badge such as " PAUSED " over the top-right corner of `area`, on the top
+/// border of the charts, so it's obvious the data is frozen.
+fn render_corner_badge(label: &str, color: Color, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let width = (label.len() as u16).min(area.width);
+    let badge_area = Rect::new(area.right().saturating_sub(width + 1), area.y, width, 1);
+    Line::from(label)
+        .style(
+            Style::new()
+                .fg(theme.background)
+                .bg(color)
+                .add_modifier(Modifier::BOLD),
+        )
+        .render(badge_area, buf);
+}
+
+/// Renders the utilization of the JPEG and optical flow engines the GPU reports, 0–100%.
+fn render_engine_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let engine_color = |engine: MediaEngine| match engine {
+        MediaEngine::Jpeg => theme.decoder_color,
+        MediaEngine::Ofa => theme.encoder_color,
+    };
+    let utilizations: Vec<String> = gpu_info
+        .engine_utilization
+        .iter()
+        .map(|(engine, utilization)| format!("{} {}%", engine, utilization.latest()))
+        .collect();
+    let engine_title = metric_title(
+        "Media Engines",
+        gpu_info,
+        Metric::Engines,
+        utilizations.join(" / "),
+        area.width,
+    );
+    let engine_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Engines,
+            theme.decoder_color,
+        ))
+        .title(engine_title.alignment(ratatui::layout::Alignment::Center));
+
+    let engine_names: Vec<String> = gpu_info
+        .engine_utilization
+        .iter()
+        .map(|(engine, _)| engine.to_string())
+        .collect();
+    let engine_data: Vec<Vec<(f64, f64)>> = gpu_info
+        .engine_utilization
+        .iter()
+        .map(|(_, utilization)| break_at_gaps(&utilization.points(|util| util as f64)))
+        .collect();
+
+    let chart_engine_data: Vec<Dataset> = gpu_info
+        .engine_utilization
+        .iter()
+        .zip(engine_data.iter().zip(engine_names.iter()))
+        .map(|(&(engine, _), (data, name))| {
+            Dataset::default()
+                .name(name.as_str())
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(metric_style(
+                    theme,
+                    gpu_info,
+                    Metric::Engines,
+                    engine_color(engine),
+                ))
+                .data(data)
+        })
+        .collect();
+
+    let chart_engine_x_axis = time_axis(
+        gpu_info.engine_utilization[0].1.x_bounds(),
+        options.interval,
+    );
+    let chart_engine_y_axis = Axis::default()
+        .title("Engine %")
+        .bounds([0.0, 100.0])
+        .labels(vec!["0", "100"]);
+
+    let chart_engine = Chart::new(chart_engine_data)
+        .block(engine_block)
+        .x_axis(chart_engine_x_axis)
+        .y_axis(chart_engine_y_axis)
+        .legend_position(legend_position(
+            &engine_data.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+            gpu_info.engine_utilization[0].1.x_bounds(),
+            [0.0, 100.0],
+        ))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Engines,
+            theme.decoder_color,
+        ));
+    chart_engine.render(area, buf);
+}
+
+/// Renders one line per fan so an asymmetric fan failure stands out, bounded 0–100%.
+fn render_fan_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let fan_speeds: Vec<String> = gpu_info
+        .fan_speeds
+        .iter()
+        .map(|fan_speed| format!("{}%", fan_speed.latest()))
+        .collect();
+    let fan_title = metric_title(
+        "Fans",
+        gpu_info,
+        Metric::Fan,
+        fan_speeds.join(" / "),
+        area.width,
+    );
+    let fan_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Fan,
+            theme.fan_color(0),
+        ))
+        .title(fan_title.alignment(ratatui::layout::Alignment::Center));
+
+    let fan_names: Vec<String> = (0..gpu_info.fan_speeds.len())
+        .map(|fan| format!("Fan {}", fan))
+        .collect();
+    let fan_data: Vec<Vec<(f64, f64)>> = gpu_info
+        .fan_speeds
+        .iter()
+        .map(|fan_speed| break_at_gaps(&fan_speed.points(|speed| speed as f64)))
+        .collect();
+
+    let chart_fan_data: Vec<Dataset> = fan_data
+        .iter()
+        .zip(fan_names.iter())
+        .enumerate()
+        .map(|(fan, (data, name))| {
+            Dataset::default()
+                .name(name.as_str())
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(metric_style(
+                    theme,
+                    gpu_info,
+                    Metric::Fan,
+                    theme.fan_color(fan),
+                ))
+                .data(data)
+        })
+        .collect();
+
+    let chart_fan_x_axis = time_axis(gpu_info.fan_speeds[0].x_bounds(), options.interval);
+    let chart_fan_y_axis = Axis::default()
+        .title("Fan Speed %")
+        .bounds([0.0, 100.0])
+        .labels(vec!["0", "100"]);
+
+    let chart_fan = Chart::new(chart_fan_data)
+        .block(fan_block)
+        .x_axis(chart_fan_x_axis)
+        .y_axis(chart_fan_y_axis)
+        .legend_position(legend_position(
+            &fan_data.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+            gpu_info.fan_speeds[0].x_bounds(),
+            [0.0, 100.0],
+        ))
+        .style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Fan,
+            theme.fan_color(0),
+        ));
+    chart_fan.render(area, buf);
+}
+
+/// Renders the fan speeds of a single GPU together with its temperature, to see how
+/// promptly the fans ramp up as the GPU heats.
+///
+/// Fan speeds in % and temperatures in °C both mostly span 0–100, so they share one
+/// y-axis in their own units, stretched for GPUs running hotter than 100 degrees.
+fn render_cooling_chart(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let fan_speeds: Vec<String> = gpu_info
+        .fan_speeds
+        .iter()
+        .map(|fan_speed| format!("{}%", fan_speed.latest()))
+        .collect();
+    let prefix = format!("{} Fans vs Temp - ", gpu_info.label());
+    let suffix = format!(
+        " ({}, {})",
+        match gpu_info.unavailable(Metric::Fan) {
+            Some(unavailable) => String::from(unavailable),
+            None => fan_speeds.join(" / "),
+        },
+        match gpu_info.unavailable(Metric::Temperature) {
+            Some(unavailable) => String::from(unavailable),
+            None => gpu_info
+                .temperature_unit
+                .format(gpu_info.temperature.latest()),
+        },
+    );
+    let cooling_title = named_title(&prefix, gpu_info, &suffix, area.width);
+    let cooling_block = Block::bordered()
+        .border_style(metric_style(
+            theme,
+            gpu_info,
+            Metric::Fan,
+            theme.fan_color(0),
+        ))
+        .title(cooling_title.alignment(ratatui::layout::Alignment::Center));
+
+    let fan_names: Vec<String> = (0..gpu_info.fan_speeds.len())
+        .map(|fan| format!("Fan {}", fan))
+        .collect();
+    let fan_data: Vec<Vec<(f64, f64)>> = gpu_info
+        .fan_speeds
+        .iter()
+        .map(|fan_speed| break_at_gaps(&fan_speed.points(|speed| speed as f64)))
+        .collect();
+    let unit = gpu_info.temperature_unit;
+    let temperature_data = break_at_gaps(
+        &gpu_info
+            .temperature
+            .points(|temp| unit.convert(temp as f64)),
+    );
+
+    let mut datasets: Vec<Dataset> = fan_data
+        .iter()
+        .zip(fan_names.iter())
+        .enumerate()
+        .map(|(fan, (data, name))| {
+            Dataset::default()
+                .name(name.as_str())
+                .marker(options.marker)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(metric_style(
+                    theme,
+                    gpu_info,
+                    Metric::Fan,
+                    theme.fan_color(fan),
+                ))
+                .data(data)
+        })
+        .collect();
+    datasets.push(
+        Dataset::default()
+            .name("Temperature")
+            .marker(options.marker)
+            .graph_type(ratatui::widgets::GraphType::Line)
+            .style(metric_style(
+                theme,
+                gpu_info,
+                Metric::Temperature,
+                theme.temp_color,
+            ))
+            .data(&temperature_data),
+    );
+
+    let bound = unit.convert(gpu_info.temperature.max() as f64).max(100.0);
+    let bound_str = format!("{:.0}", bound);
+    let cooling_x_axis = time_axis(gpu_info.temperature.x_bounds(), options.interval);
+    let cooling_y_axis = Axis::default()
+        .title(format!("Fan % / {}", unit.symbol()))
+        .bounds([0.0, bound])
+        .labels(vec!["0", bound_str.as_str()]);
+
+    // The legend is what tells the lines apart, so keep it on narrower charts too
+    let mut named: Vec<&[(f64, f64)]> = fan_data.iter().map(Vec::as_slice).collect();
+    named.push(&temperature_data);
+    Chart::new(datasets)
+        .block(cooling_block)
+        .x_axis(cooling_x_axis)
+        .y_axis(cooling_y_axis)
+        .legend_position(legend_position(
+            &named,
+            gpu_info.temperature.x_bounds(),
+            [0.0, bound],
+        ))
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
+        .render(area, buf);
+}
+
+impl Widget for &NvidiaApp {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if let Some((i, _)) = self.focused_chart() {
+            let charted = self.charted(&self.gpus[i], area);
+            self.render_gpu(charted.as_ref().unwrap_or(&self.gpus[i]), area, buf);
+            self.render_overlays(area, buf);
+            return;
+        }
+
+        // Without any GPUs only the overlays, such as the paused badge, are drawn
+        let visible_gpus = self.visible_gpus();
+
+        let (area, banner_area) = self.split_banner(area);
+        if let (Some(banner_area), Some(banner)) = (banner_area, &self.banner) {
+            status::render_banner(banner, self.theme(), banner_area, buf);
+        }
+
+        let (area, alerts_area) = self.split_xid_alerts(area);
+        if let Some(alerts_area) = alerts_area {
+            xid::render_xid_alerts(&self.xid_events, self.theme(), alerts_area, buf);
+        }
+
+        let (area, status_area) = self.split_status_bar(area);
+        if let (Some(status_area), Some(versions)) = (status_area, &self.versions) {
+            let status_line = StatusLine {
+                gpu_order: self.gpu_order,
+                page: self.gpu_pages(),
+                age: self.update_age(),
+                collection_time: self.collection_time.filter(|_| self.show_timing),
+                interval: self.interval,
+            };
+            status::render_status_bar(versions, &status_line, self.theme(), status_area, buf);
+        }
+
+        let (area, messages_area) = self.split_messages_panel(area);
+        if let Some(messages_area) = messages_area {
+            messages::render_messages_panel(
+                &self.messages,
+                self.messages_scroll,
+                self.theme(),
+                messages_area,
+                buf,
+            );
+        }
+
+        if self.dashboard {
+            let (table_area, process_area) = self.split_dashboard(area);
+            let rows: Vec<(&GPUInfo, Option<Style>)> = visible_gpus
+                .iter()
+                .map(|&(_, gpu_info)| (gpu_info, self.temperature_alert(gpu_info)))
+                .collect();
+            summary::render_summary_table(&rows, self.theme(), self.units, table_area, buf);
+            let gpus: Vec<&GPUInfo> = visible_gpus.iter().map(|&(_, gpu)| gpu).collect();
+            process::render_all_processes(&gpus, self.theme(), process_area, buf);
+            self.render_overlays(area, buf);
+            return;
+        }
+
+        let (charts_area, process_area) = self.split_process_panel(area);
+        let (charts_area, totals_area) = self.split_totals_panel(charts_area);
+        if let Some(totals_area) = totals_area {
+            summary::render_totals_panel(&self.gpus, self.theme(), totals_area, buf);
+        }
+        match self.view(charts_area) {
+            View::Charts => {
+                if let Some(average) = self.average_gpu() {
+                    let average = self.charted(&average, charts_area).unwrap_or(average);
+                    self.render_gpu(&average, charts_area, buf);
+                }
+                for (i, chunk) in self.gpu_chunks(charts_area) {
+                    let charted = self.charted(&self.gpus[i], chunk);
+                    let gpu_info = charted.as_ref().unwrap_or(&self.gpus[i]);
+                    debug!("Drawing GPU {}: {}", i, gpu_info.device_name);
+                    self.render_gpu(gpu_info, chunk, buf);
+                    self.render_paused_badge(gpu_info, chunk, buf);
+                }
+            }
+            View::Bars => {
+                let options = &self.chart_options();
+                if let Some(average) = self.average_gpu() {
+                    bars::render_gpu_bars(
+                        &average,
+                        self.theme(),
+                        options,
+                        DEFAULT_GRADIENT_THRESHOLD,
+                        None,
+                        charts_area,
+                        buf,
+                    );
+                }
+                for (i, chunk) in self.gpu_chunks(charts_area) {
+                    let gpu_info = &self.gpus[i];
+                    bars::render_gpu_bars(
+                        gpu_info,
+                        self.theme(),
+                        options,
+                        self.temperature_thresholds(gpu_info).gradient(),
+                        self.temperature_alert(gpu_info),
+                        chunk,
+                        buf,
+                    );
+                    self.render_paused_badge(gpu_info, chunk, buf);
+                }
+            }
+            View::Compact => {
+                let rows: Vec<(&GPUInfo, Option<Style>)> = visible_gpus
+                    .iter()
+                    .map(|&(_, gpu_info)| (gpu_info, self.temperature_alert(gpu_info)))
+                    .collect();
+                summary::render_summary_table(&rows, self.theme(), self.units, charts_area, buf);
+            }
+            View::TooSmall => {
+                Paragraph::new("Terminal too small")
+                    .alignment(ratatui::layout::Alignment::Center)
+                    .wrap(Wrap { trim: true })
+                    .style(Style::new().fg(self.theme().text))
+                    .render(charts_area, buf);
+            }
+        }
+
+        if let (Some(process_area), Some(gpu_info)) = (process_area, self.process_gpu()) {
+            if gpu_info.process_memory.is_empty() {
+                process::render_process_table(gpu_info, self.theme(), process_area, buf);
+            } else {
+                let [table_area, memory_area] =
+                    Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                        .areas(process_area);
+                process::render_process_table(gpu_info, self.theme(), table_area, buf);
+                process::render_process_memory(gpu_info, self.theme(), memory_area, buf);
+            }
+        }
+
+        self.render_overlays(area, buf);
     }
 }
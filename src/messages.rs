@@ -0,0 +1,139 @@
+use crate::status::format_age;
+use crate::theme::Theme;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{block::Title, Block, Paragraph, Widget},
+};
+use std::collections::VecDeque;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// The most messages the messages pane keeps, dropping the oldest first.
+pub const MAX_MESSAGES: usize = 200;
+
+/// A message logged while the TUI runs, for the messages pane toggled with `n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub level: Level,
+    pub text: String,
+    pub at: Instant,
+}
+
+/// The messages logged since the TUI last took them.
+static PENDING: Mutex<VecDeque<Message>> = Mutex::new(VecDeque::new());
+
+/// Whether the alternate screen is active, so that messages go to the pane instead of
+/// stderr.
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+/// Logs like `env_logger` with `RUST_LOG`, but collects info messages and worse for the
+/// messages pane while the TUI runs. Writing them to the terminal then would scribble
+/// over the screen, so stderr is only written to when it's redirected elsewhere, as in
+/// `RUST_LOG=info nvidia 2> nvidia.log`.
+struct TuiLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for TuiLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let capturing = CAPTURING.load(Ordering::Relaxed);
+        if capturing && record.level() <= Level::Info {
+            push(Message {
+                level: record.level(),
+                text: record.args().to_string(),
+                at: Instant::now(),
+            });
+        }
+        if self.inner.matches(record) && !(capturing && std::io::stderr().is_terminal()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Installs the logger, with the `RUST_LOG` filter of `env_logger`.
+pub fn init() {
+    let inner = env_logger::Builder::from_default_env().build();
+    let max_level = inner.filter().max(LevelFilter::Info);
+    if log::set_boxed_logger(Box::new(TuiLogger { inner })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Starts or stops collecting messages for the pane, as the TUI enters and leaves the
+/// alternate screen.
+pub fn capture(capturing: bool) {
+    CAPTURING.store(capturing, Ordering::Relaxed);
+}
+
+fn push(message: Message) {
+    let mut pending = PENDING.lock().unwrap();
+    pending.push_back(message);
+    let excess = pending.len().saturating_sub(MAX_MESSAGES);
+    pending.drain(..excess);
+}
+
+/// Takes the messages logged since the last call, oldest first.
+pub fn take() -> Vec<Message> {
+    PENDING.lock().unwrap().drain(..).collect()
+}
+
+/// Renders `messages` in a bordered pane, newest at the bottom, scrolled up by `scroll`
+/// lines. Warnings and errors are colored like the temperature alerts.
+pub fn render_messages_panel(
+    messages: &VecDeque<Message>,
+    scroll: usize,
+    theme: &Theme,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let now = Instant::now();
+    let height = usize::from(area.height.saturating_sub(2));
+    let end = messages.len().saturating_sub(scroll);
+    let start = end.saturating_sub(height);
+    let lines: Vec<Line> = messages
+        .range(start..end)
+        .map(|message| {
+            let style = match message.level {
+                Level::Error => Style::new().fg(theme.temp_crit_color),
+                Level::Warn => Style::new().fg(theme.temp_warn_color),
+                _ => Style::new().fg(theme.text),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{:>6} {:<5} ",
+                        format_age(now.saturating_duration_since(message.at)),
+                        message.level
+                    ),
+                    style.add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(message.text.clone(), style),
+            ])
+        })
+        .collect();
+    let title = match scroll {
+        0 => format!("Messages ({})", messages.len()),
+        scroll => format!("Messages ({}, {} newer below)", messages.len(), scroll),
+    };
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme.border))
+        .title(Title::from(title).alignment(ratatui::layout::Alignment::Center));
+    Paragraph::new(lines)
+        .block(block)
+        .style(Style::new().fg(theme.text))
+        .render(area, buf);
+}
@@ -0,0 +1,511 @@
+use crate::ecc::EccErrors;
+use crate::source::GpuDevice;
+use bitflags::bitflags;
+use nvml_wrapper::{
+    enum_wrappers::device::{Clock, EncoderType, PcieUtilCounter, TemperatureSensor},
+    error::NvmlError,
+};
+use ratatui::symbols::Marker;
+use std::fmt;
+
+/// A metric tracked for every GPU, used to key per-metric state such as query errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Metric {
+    Clock,
+    Temperature,
+    Memory,
+    Utilization,
+    Power,
+    Pcie,
+    Codec,
+    Fan,
+    Processes,
+    Throttle,
+    Ecc,
+    /// The JPEG and optical flow engines.
+    Engines,
+    NvLink,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 13] = [
+        Metric::Clock,
+        Metric::Temperature,
+        Metric::Memory,
+        Metric::Utilization,
+        Metric::Power,
+        Metric::Pcie,
+        Metric::Codec,
+        Metric::Fan,
+        Metric::Processes,
+        Metric::Throttle,
+        Metric::Ecc,
+        Metric::Engines,
+        Metric::NvLink,
+    ];
+}
+
+/// A clock domain the clock chart can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockDomain {
+    Graphics,
+    Sm,
+    Memory,
+    Video,
+}
+
+impl ClockDomain {
+    pub const ALL: [ClockDomain; 4] = [
+        ClockDomain::Graphics,
+        ClockDomain::Sm,
+        ClockDomain::Memory,
+        ClockDomain::Video,
+    ];
+
+    pub fn clock(self) -> Clock {
+        match self {
+            ClockDomain::Graphics => Clock::Graphics,
+            ClockDomain::Sm => Clock::SM,
+            ClockDomain::Memory => Clock::Memory,
+            ClockDomain::Video => Clock::Video,
+        }
+    }
+
+    /// The domain shown after this one when cycling the clock chart.
+    pub fn next(self) -> Self {
+        let index = ClockDomain::ALL.iter().position(|&domain| domain == self);
+        ClockDomain::ALL[index.map_or(0, |index| (index + 1) % ClockDomain::ALL.len())]
+    }
+}
+
+impl fmt::Display for ClockDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ClockDomain::Graphics => "Graphics",
+            ClockDomain::Sm => "SM",
+            ClockDomain::Memory => "Memory",
+            ClockDomain::Video => "Video",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The order the GPUs are laid out in, cycled at runtime with `g`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GpuOrder {
+    /// By hardware index, as NVML enumerates them.
+    #[default]
+    Index,
+    /// Hottest first.
+    Temperature,
+    /// Busiest first, by SM utilization.
+    Utilization,
+    /// Most memory used first.
+    Memory,
+}
+
+impl GpuOrder {
+    pub const ALL: [GpuOrder; 4] = [
+        GpuOrder::Index,
+        GpuOrder::Temperature,
+        GpuOrder::Utilization,
+        GpuOrder::Memory,
+    ];
+
+    /// The order used after this one when cycling.
+    pub fn next(self) -> Self {
+        let index = GpuOrder::ALL.iter().position(|&order| order == self);
+        GpuOrder::ALL[index.map_or(0, |index| (index + 1) % GpuOrder::ALL.len())]
+    }
+}
+
+impl fmt::Display for GpuOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GpuOrder::Index => "index",
+            GpuOrder::Temperature => "temperature",
+            GpuOrder::Utilization => "utilization",
+            GpuOrder::Memory => "memory used",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How clocks and memory sizes are written, toggled at runtime with `u`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Units {
+    /// Clocks in MHz and memory in MiB, as `nvidia-smi` shows them.
+    #[default]
+    Mega,
+    /// Clocks in GHz and memory in GiB, for shorter titles on big numbers.
+    Giga,
+}
+
+impl Units {
+    pub const ALL: [Units; 2] = [Units::Mega, Units::Giga];
+
+    pub fn next(self) -> Self {
+        match self {
+            Units::Mega => Units::Giga,
+            Units::Giga => Units::Mega,
+        }
+    }
+
+    /// A clock of `mhz` as a number in these units, without the unit.
+    pub fn clock_number(self, mhz: f64) -> String {
+        match self {
+            Units::Mega => format!("{:.0}", mhz),
+            Units::Giga => format!("{:.2}", mhz / 1000.0),
+        }
+    }
+
+    /// A clock of `mhz` with its unit, e.g. `1875 MHz` or `1.88 GHz`.
+    pub fn clock(self, mhz: f64) -> String {
+        let unit = match self {
+            Units::Mega => "MHz",
+            Units::Giga => "GHz",
+        };
+        format!("{} {}", self.clock_number(mhz), unit)
+    }
+
+    /// A memory size of `bytes` as a number in these units, without the unit.
+    pub fn memory_number(self, bytes: u64) -> String {
+        match self {
+            Units::Mega => format!("{}", bytes / 1024 / 1024),
+            Units::Giga => format!("{:.1}", bytes as f64 / (1024.0 * 1024.0 * 1024.0)),
+        }
+    }
+
+    /// A memory size of `bytes` with its unit, e.g. `2048 MiB` or `2.0 GiB`.
+    pub fn memory(self, bytes: u64) -> String {
+        format!("{} {}", self.memory_number(bytes), self.memory_unit())
+    }
+
+    pub fn memory_unit(self) -> &'static str {
+        match self {
+            Units::Mega => "MiB",
+            Units::Giga => "GiB",
+        }
+    }
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Units::Mega => "mega",
+            Units::Giga => "giga",
+        })
+    }
+}
+
+/// The most decimal places the `power_decimals`, `efficiency_decimals` and
+/// `memory_percent_decimals` config keys accept.
+pub const MAX_DECIMALS: usize = 6;
+
+/// How many decimal places power, efficiency and the share of memory in use are shown
+/// with, so every panel and export rounds them the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Precision {
+    pub power: usize,
+    /// For the clock per watt of the efficiency chart.
+    pub efficiency: usize,
+    /// For the share of memory in use, in the GPU titles and the compact table.
+    pub memory_percent: usize,
+    /// Whether the exports keep the full precision of the readings, with `--raw`. The
+    /// panels are rounded either way.
+    pub raw: bool,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self {
+            power: 0,
+            efficiency: 2,
+            memory_percent: 0,
+            raw: false,
+        }
+    }
+}
+
+impl Precision {
+    /// A power draw of `milliwatts` in W as a number, without the unit.
+    pub fn watts_number(self, milliwatts: f64) -> String {
+        format!("{:.*}", self.power, round(milliwatts / 1000.0, self.power))
+    }
+
+    /// A power draw of `milliwatts` with its unit, e.g. `285 W`.
+    pub fn watts(self, milliwatts: f64) -> String {
+        format!("{} W", self.watts_number(milliwatts))
+    }
+
+    /// A clock per watt as a number, without the unit.
+    pub fn efficiency(self, mhz_per_watt: f64) -> String {
+        format!(
+            "{:.*}",
+            self.efficiency,
+            round(mhz_per_watt, self.efficiency)
+        )
+    }
+
+    /// `used` bytes of memory as a percentage of `total`, e.g. `84%`.
+    pub fn memory_percent(self, used: u64, total: u64) -> String {
+        let percent = match total {
+            0 => 0.0,
+            total => used as f64 * 100.0 / total as f64,
+        };
+        format!(
+            "{:.*}%",
+            self.memory_percent,
+            round(percent, self.memory_percent)
+        )
+    }
+
+    /// A power draw of `milliwatts` in W for the exports, rounded like the panels
+    /// unless `--raw` is given.
+    pub fn export_watts(self, milliwatts: u32) -> f64 {
+        let watts = f64::from(milliwatts) / 1000.0;
+        if self.raw {
+            watts
+        } else {
+            round(watts, self.power)
+        }
+    }
+}
+
+/// Rounds `value` to `decimals` places, halves away from zero, so that the exports and
+/// the text of the panels agree.
+fn round(value: f64, decimals: usize) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    (value * scale).round() / scale
+}
+
+/// How temperatures are shown, in °C unless `--fahrenheit` is passed. They're read,
+/// stored, compared against thresholds and exported in °C either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// A temperature of `celsius` in this unit.
+    pub fn convert(self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    /// A temperature difference of `celsius` degrees in this unit.
+    pub fn convert_difference(self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    /// A temperature of `celsius` in this unit as a whole number, without the unit.
+    pub fn number(self, celsius: u32) -> String {
+        format!("{:.0}", self.convert(f64::from(celsius)))
+    }
+
+    /// A temperature of `celsius` with its unit, e.g. `72 °C` or `162 °F`.
+    pub fn format(self, celsius: u32) -> String {
+        format!("{} {}", self.number(celsius), self.symbol())
+    }
+}
+
+/// How the samples of the line charts are drawn, set with `marker` in the config file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChartMarker {
+    /// One dot per cell, which every terminal can draw.
+    #[default]
+    Dot,
+    /// Eight dots per cell, for much smoother lines on terminals with Braille glyphs.
+    Braille,
+    /// Full blocks, or half blocks where the font has them.
+    Block,
+    /// Bars from the bottom of the cell up, in eighths.
+    Bar,
+}
+
+impl ChartMarker {
+    pub const ALL: [ChartMarker; 4] = [
+        ChartMarker::Dot,
+        ChartMarker::Braille,
+        ChartMarker::Block,
+        ChartMarker::Bar,
+    ];
+
+    pub fn marker(self) -> Marker {
+        match self {
+            ChartMarker::Dot => Marker::Dot,
+            ChartMarker::Braille => Marker::Braille,
+            ChartMarker::Block => Marker::HalfBlock,
+            ChartMarker::Bar => Marker::Bar,
+        }
+    }
+}
+
+impl fmt::Display for ChartMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChartMarker::Dot => "dot",
+            ChartMarker::Braille => "braille",
+            ChartMarker::Block => "block",
+            ChartMarker::Bar => "bar",
+        })
+    }
+}
+
+bitflags! {
+    /// A set of metrics, such as the ones a GPU supports, probed once at startup.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MetricFlags: u32 {
+        const CLOCK = 1 << 0;
+        const TEMPERATURE = 1 << 1;
+        const MEMORY = 1 << 2;
+        const UTILIZATION = 1 << 3;
+        const POWER = 1 << 4;
+        const FAN = 1 << 5;
+        const PROCESSES = 1 << 6;
+        const PCIE = 1 << 7;
+        const THROTTLE = 1 << 8;
+        const CODEC = 1 << 9;
+        const ECC = 1 << 10;
+        const ENGINES = 1 << 11;
+        const NVLINK = 1 << 12;
+    }
+}
+
+impl From<Metric> for MetricFlags {
+    fn from(metric: Metric) -> Self {
+        match metric {
+            Metric::Clock => MetricFlags::CLOCK,
+            Metric::Temperature => MetricFlags::TEMPERATURE,
+            Metric::Memory => MetricFlags::MEMORY,
+            Metric::Utilization => MetricFlags::UTILIZATION,
+            Metric::Power => MetricFlags::POWER,
+            Metric::Pcie => MetricFlags::PCIE,
+            Metric::Codec => MetricFlags::CODEC,
+            Metric::Fan => MetricFlags::FAN,
+            Metric::Processes => MetricFlags::PROCESSES,
+            Metric::Throttle => MetricFlags::THROTTLE,
+            Metric::Ecc => MetricFlags::ECC,
+            Metric::Engines => MetricFlags::ENGINES,
+            Metric::NvLink => MetricFlags::NVLINK,
+        }
+    }
+}
+
+/// Queries every metric of the device itself once, with the outcome of each query. The
+/// NVLink and media engine counters are queried through the [`crate::source::GpuSource`].
+pub fn probe_queries(gpu_device: &dyn GpuDevice) -> [(Metric, Result<(), NvmlError>); 11] {
+    [
+        (
+            Metric::Clock,
+            gpu_device.clock_info(Clock::Graphics).map(drop),
+        ),
+        (
+            Metric::Temperature,
+            gpu_device.temperature(TemperatureSensor::Gpu).map(drop),
+        ),
+        (Metric::Memory, gpu_device.memory_info().map(drop)),
+        (
+            Metric::Utilization,
+            gpu_device.utilization_rates().map(drop),
+        ),
+        (Metric::Power, gpu_device.power_usage().map(drop)),
+        (
+            Metric::Pcie,
+            gpu_device
+                .pcie_throughput(PcieUtilCounter::Receive)
+                .map(drop),
+        ),
+        // Cards without NVENC have no encoder capacity to report
+        (
+            Metric::Codec,
+            gpu_device
+                .encoder_capacity(EncoderType::H264)
+                .and_then(|_| gpu_device.encoder_utilization())
+                .map(drop),
+        ),
+        (
+            Metric::Fan,
+            gpu_device.num_fans().and_then(|num_fans| match num_fans {
+                0 => Err(NvmlError::NotSupported),
+                _ => Ok(()),
+            }),
+        ),
+        (Metric::Processes, gpu_device.running_processes().map(drop)),
+        (
+            Metric::Throttle,
+            gpu_device.current_throttle_reasons().map(drop),
+        ),
+        // Consumer cards have no ECC memory, and datacenter cards can have ECC disabled
+        (Metric::Ecc, EccErrors::read(gpu_device).map(drop)),
+    ]
+}
+
+/// Queries every metric once and reports the ones the device supports.
+///
+/// Only errors saying the query can never work on this device mark a metric as
+/// unsupported; other failures may be transient and are left to the per-tick error
+/// handling.
+pub fn probe_metrics(gpu_device: &dyn GpuDevice) -> MetricFlags {
+    probe_queries(gpu_device)
+        .into_iter()
+        .filter(|(_, result)| !matches!(result, Err(error) if is_unsupported(error)))
+        .fold(MetricFlags::empty(), |supported, (metric, _)| {
+            supported | MetricFlags::from(metric)
+        })
+}
+
+/// Whether `error` means the query can never succeed on this device or driver.
+pub fn is_unsupported(error: &NvmlError) -> bool {
+    matches!(
+        error,
+        NvmlError::NotSupported
+            | NvmlError::FunctionNotFound
+            | NvmlError::NotFound
+            | NvmlError::FailedToLoadSymbol(_)
+    )
+}
+
+/// What to tell the user when `metric` was refused with `NvmlError::NoPermission`, as
+/// the process list is for unprivileged users on some systems.
+pub fn permission_message(metric: Metric) -> String {
+    format!(
+        "insufficient permissions for {} (run as root or add to video group)",
+        metric
+    )
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Metric::Clock => "clock",
+            Metric::Temperature => "temperature",
+            Metric::Memory => "memory",
+            Metric::Utilization => "utilization",
+            Metric::Power => "power",
+            Metric::Pcie => "PCIe throughput",
+            Metric::Codec => "encoder/decoder utilization",
+            Metric::Fan => "fan speed",
+            Metric::Processes => "process list",
+            Metric::Throttle => "throttle reasons",
+            Metric::Ecc => "ECC errors",
+            Metric::Engines => "media engines",
+            Metric::NvLink => "NVLink",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,125 @@
+use crate::metric::Metric;
+use crate::theme::Theme;
+use crate::{metric_style, metric_title, GPUInfo};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Cell, Row, Table, Widget},
+};
+use std::time::Duration;
+
+/// What one NVLink reports: whether it's up, and the data it carried in each direction
+/// since the driver was loaded, in KiB.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NvLinkCounters {
+    pub active: bool,
+    pub rx: u64,
+    pub tx: u64,
+}
+
+/// One NVLink of a GPU, with its throughput derived from consecutive counter readings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NvLink {
+    pub link: u32,
+    /// The latest counters, or `None` before the first successful reading.
+    pub counters: Option<NvLinkCounters>,
+    /// KiB/s received and transmitted over the last tick, or `None` until two readings
+    /// in a row succeeded.
+    pub throughput: Option<(f64, f64)>,
+}
+
+impl NvLink {
+    pub fn new(link: u32) -> Self {
+        Self {
+            link,
+            counters: None,
+            throughput: None,
+        }
+    }
+
+    /// Takes the counters of the latest tick, `interval` after the previous one. A
+    /// failed reading clears the throughput until the link can be read twice again.
+    pub fn update(&mut self, counters: Option<NvLinkCounters>, interval: Duration) {
+        let seconds = interval.as_secs_f64();
+        self.throughput = match (self.counters, counters) {
+            (Some(previous), Some(counters)) if seconds > 0.0 => Some((
+                counters.rx.saturating_sub(previous.rx) as f64 / seconds,
+                counters.tx.saturating_sub(previous.tx) as f64 / seconds,
+            )),
+            _ => None,
+        };
+        self.counters = counters;
+    }
+}
+
+/// Formats a throughput in KiB/s the way NVLink bandwidth is usually quoted.
+fn format_throughput(kib_per_second: f64) -> String {
+    let bytes = kib_per_second * 1024.0;
+    if bytes >= 1e9 {
+        format!("{:.1} GB/s", bytes / 1e9)
+    } else {
+        format!("{:.0} MB/s", bytes / 1e6)
+    }
+}
+
+/// Renders the state and throughput of every NVLink of a single GPU, one row per link.
+///
+/// Training jobs that sync gradients over NVLink are often bound by it rather than by
+/// compute, so an inactive link is highlighted in the critical color.
+pub fn render_nvlink_panel(gpu_info: &GPUInfo, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let active = gpu_info
+        .nvlinks
+        .iter()
+        .filter(|link| link.counters.is_some_and(|counters| counters.active))
+        .count();
+    let title = metric_title(
+        "NVLink",
+        gpu_info,
+        Metric::NvLink,
+        format!("{} of {} links active", active, gpu_info.nvlinks.len()),
+        area.width,
+    );
+    let block = Block::bordered()
+        .border_style(metric_style(theme, gpu_info, Metric::NvLink, theme.border))
+        .title(title.alignment(ratatui::layout::Alignment::Center));
+
+    let rows = gpu_info.nvlinks.iter().map(|link| {
+        let state = match link.counters {
+            Some(counters) if counters.active => Cell::from("Active"),
+            Some(_) => Cell::from("Inactive").style(
+                Style::new()
+                    .fg(theme.temp_crit_color)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            None => Cell::from("N/A"),
+        };
+        let (rx, tx) = match link.throughput {
+            Some((rx, tx)) => (format_throughput(rx), format_throughput(tx)),
+            None => (String::from("N/A"), String::from("N/A")),
+        };
+        Row::new(vec![
+            Cell::from(link.link.to_string()),
+            state,
+            Cell::from(rx),
+            Cell::from(tx),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(vec!["Link", "State", "RX", "TX"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .block(block)
+    .style(metric_style(theme, gpu_info, Metric::NvLink, theme.text));
+    table.render(area, buf);
+}
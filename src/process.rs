@@ -0,0 +1,353 @@
+use crate::history::History;
+use crate::metric::{Metric, MetricFlags};
+use crate::theme::Theme;
+use crate::GPUInfo;
+use nvml_wrapper::{
+    enums::device::UsedGpuMemory, error::NvmlError, struct_wrappers::device::ProcessInfo, Device,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{block::Title, Block, Cell, Paragraph, Row, Table, Widget, Wrap},
+};
+use std::collections::BTreeMap;
+
+/// The longest process name NVML is asked for.
+const PROCESS_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessKind {
+    Compute,
+    Graphics,
+}
+
+/// A process running on a GPU, as listed in the process panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub name: String,
+    pub kind: ProcessKind,
+    /// GPU memory used by the process in bytes, if the driver reports it.
+    pub used_memory: Option<u64>,
+}
+
+/// The symbols the bands of the process memory view are filled with, so that
+/// neighbouring bands can be told apart even in the monochrome and plain themes.
+const BAND_SYMBOLS: [&str; 4] = ["█", "▓", "▒", "░"];
+
+/// The GPU memory each process used over the history window, for a stacked view of how
+/// the memory is shared out between them.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessMemory {
+    history_len: usize,
+    /// Keyed by PID, which keeps each process in the same band from tick to tick.
+    processes: BTreeMap<u32, ProcessHistory>,
+}
+
+#[derive(Debug, Clone)]
+struct ProcessHistory {
+    name: String,
+    used_memory: History<u64>,
+}
+
+impl ProcessMemory {
+    pub fn new(history_len: usize) -> Self {
+        Self {
+            history_len,
+            processes: BTreeMap::new(),
+        }
+    }
+
+    /// Records the memory of the processes running this tick. Processes that exited
+    /// record zero until their last sample scrolls out of the window, and are then
+    /// forgotten.
+    pub fn record(&mut self, processes: &[GpuProcess]) {
+        for process in processes {
+            if process.used_memory.is_some() {
+                self.processes
+                    .entry(process.pid)
+                    .or_insert_with(|| ProcessHistory {
+                        name: process.name.clone(),
+                        used_memory: History::new(self.history_len),
+                    });
+            }
+        }
+        for (&pid, history) in self.processes.iter_mut() {
+            let used_memory = processes
+                .iter()
+                .find(|process| process.pid == pid)
+                .and_then(|process| process.used_memory);
+            history.used_memory.push(used_memory.unwrap_or_default());
+        }
+        self.processes
+            .retain(|_, history| history.used_memory.max() > 0);
+    }
+
+    pub fn resize(&mut self, history_len: usize) {
+        self.history_len = history_len;
+        for history in self.processes.values_mut() {
+            history.used_memory.resize(history_len);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.processes.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty()
+    }
+
+    /// The name of the process whose memory grew the most since its first sample in the
+    /// window, and by how many bytes, which points at a leak. `None` if none grew.
+    pub fn largest_growth(&self) -> Option<(&str, u64)> {
+        self.processes
+            .values()
+            .filter_map(|history| {
+                let samples = history.used_memory.recent(history.used_memory.len());
+                let first = samples.iter().copied().find(|&used| used > 0)?;
+                let growth = history.used_memory.latest().saturating_sub(first);
+                (growth > 0).then_some((history.name.as_str(), growth))
+            })
+            .max_by_key(|&(_, growth)| growth)
+    }
+
+    /// The band of the process `pid` in the stacked view, counted from the bottom.
+    pub fn band(&self, pid: u32) -> Option<usize> {
+        self.processes
+            .keys()
+            .position(|&candidate| candidate == pid)
+    }
+}
+
+/// Lists the compute and graphics processes running on `gpu_device`, largest memory
+/// user first.
+pub fn running_processes(gpu_device: &Device) -> Result<Vec<GpuProcess>, NvmlError> {
+    let nvml = gpu_device.nvml();
+    let compute = gpu_device.running_compute_processes()?;
+    let graphics = gpu_device.running_graphics_processes()?;
+
+    let to_process = |kind: ProcessKind| {
+        move |info: ProcessInfo| GpuProcess {
+            pid: info.pid,
+            name: nvml
+                .sys_process_name(info.pid, PROCESS_NAME_LEN)
+                .unwrap_or_else(|_| String::from("?")),
+            kind,
+            used_memory: match info.used_gpu_memory {
+                UsedGpuMemory::Used(bytes) => Some(bytes),
+                UsedGpuMemory::Unavailable => None,
+            },
+        }
+    };
+
+    let mut processes: Vec<GpuProcess> = compute
+        .into_iter()
+        .map(to_process(ProcessKind::Compute))
+        .chain(graphics.into_iter().map(to_process(ProcessKind::Graphics)))
+        .collect();
+    processes.sort_by_key(|process| std::cmp::Reverse(process.used_memory));
+    Ok(processes)
+}
+
+/// Renders the processes of one GPU as a table of PID, type, name and used memory.
+pub fn render_process_table(gpu_info: &GPUInfo, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let title = match gpu_info.error(Metric::Processes) {
+        Some(_) => format!(
+            "{} Processes - {} (N/A)",
+            gpu_info.label(),
+            gpu_info.device_name
+        ),
+        None => format!(
+            "{} Processes - {} ({})",
+            gpu_info.label(),
+            gpu_info.device_name,
+            gpu_info.processes.len()
+        ),
+    };
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme.border))
+        .title(Title::from(title).alignment(ratatui::layout::Alignment::Center));
+
+    // Unprivileged users can often read everything but the processes, so say why
+    if let Some(message) = gpu_info
+        .error(Metric::Processes)
+        .filter(|_| gpu_info.denied.contains(MetricFlags::PROCESSES))
+    {
+        Paragraph::new(message)
+            .wrap(Wrap { trim: true })
+            .block(block)
+            .style(Style::new().fg(theme.text))
+            .render(area, buf);
+        return;
+    }
+
+    let rows: Vec<Row> = gpu_info
+        .processes
+        .iter()
+        .map(|process| {
+            let kind = match process.kind {
+                ProcessKind::Compute => "C",
+                ProcessKind::Graphics => "G",
+            };
+            let used_memory = match process.used_memory {
+                Some(bytes) => format!("{} MiB", bytes / (1024 * 1024)),
+                None => String::from("N/A"),
+            };
+            // The PID takes the color of the process's band in the memory view
+            let pid_style = match gpu_info.process_memory.band(process.pid) {
+                Some(band) => Style::new().fg(theme.process_color(band)),
+                None => Style::new(),
+            };
+            Row::new(vec![
+                Cell::from(process.pid.to_string()).style(pid_style),
+                Cell::from(kind),
+                Cell::from(process.name.clone()),
+                Cell::from(used_memory),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(4),
+            Constraint::Fill(1),
+            Constraint::Length(12),
+        ],
+    )
+    .header(
+        Row::new(vec!["PID", "Type", "Process", "GPU Memory"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .block(block)
+    .style(Style::new().fg(theme.text));
+    table.render(area, buf);
+}
+
+/// Renders the processes of all of `gpus` as one table for the dashboard, largest memory
+/// user first, with the GPU each one runs on. GPUs whose processes can't be listed are
+/// counted in the title.
+pub fn render_all_processes(gpus: &[&GPUInfo], theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let mut processes: Vec<(&GPUInfo, &GpuProcess)> = gpus
+        .iter()
+        .flat_map(|&gpu_info| {
+            gpu_info
+                .processes
+                .iter()
+                .map(move |process| (gpu_info, process))
+        })
+        .collect();
+    processes.sort_by_key(|&(_, process)| std::cmp::Reverse(process.used_memory));
+
+    let unlisted = gpus
+        .iter()
+        .filter(|gpu_info| gpu_info.error(Metric::Processes).is_some())
+        .count();
+    let title = match unlisted {
+        0 => format!("Processes - all GPUs ({})", processes.len()),
+        unlisted => format!(
+            "Processes - all GPUs ({}, N/A on {} of {})",
+            processes.len(),
+            unlisted,
+            gpus.len()
+        ),
+    };
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme.border))
+        .title(Title::from(title).alignment(ratatui::layout::Alignment::Center));
+
+    let rows: Vec<Row> = processes
+        .iter()
+        .map(|&(gpu_info, process)| {
+            let kind = match process.kind {
+                ProcessKind::Compute => "C",
+                ProcessKind::Graphics => "G",
+            };
+            let used_memory = match process.used_memory {
+                Some(bytes) => format!("{} MiB", bytes / (1024 * 1024)),
+                None => String::from("N/A"),
+            };
+            Row::new(vec![
+                Cell::from(gpu_info.id()),
+                Cell::from(process.pid.to_string()),
+                Cell::from(kind),
+                Cell::from(process.name.clone()),
+                Cell::from(used_memory),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(4),
+            Constraint::Fill(1),
+            Constraint::Length(12),
+        ],
+    )
+    .header(
+        Row::new(vec!["GPU", "PID", "Type", "Process", "GPU Memory"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .block(block)
+    .style(Style::new().fg(theme.text));
+    table.render(area, buf);
+}
+
+/// Renders the GPU memory of every process over the history window as stacked bands,
+/// oldest sample on the left, so a process whose share keeps growing stands out. Each
+/// cell is filled by the band its middle falls in.
+pub fn render_process_memory(gpu_info: &GPUInfo, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let bands: Vec<Vec<u64>> = gpu_info
+        .process_memory
+        .processes
+        .values()
+        .map(|history| history.used_memory.recent(history.used_memory.len()))
+        .collect();
+    let samples = bands.first().map_or(0, Vec::len);
+    let totals: Vec<u64> = (0..samples)
+        .map(|sample| bands.iter().map(|band| band[sample]).sum())
+        .collect();
+    let peak = totals.iter().copied().max().unwrap_or_default();
+
+    let title = match gpu_info.process_memory.largest_growth() {
+        Some((name, growth)) => format!(
+            "Process Memory - peak {} MiB, {} +{} MiB",
+            peak / (1024 * 1024),
+            name,
+            growth / (1024 * 1024)
+        ),
+        None => format!("Process Memory - peak {} MiB", peak / (1024 * 1024)),
+    };
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme.border))
+        .title(Title::from(title).alignment(ratatui::layout::Alignment::Center));
+    let inner = block.inner(area);
+    block.render(area, buf);
+    if samples == 0 || peak == 0 || inner.is_empty() {
+        return;
+    }
+
+    for column in 0..inner.width {
+        let sample = usize::from(column) * samples / usize::from(inner.width);
+        for row in 0..inner.height {
+            // Counted from the bottom, at the middle of the cell
+            let level = (f64::from(row) + 0.5) / f64::from(inner.height) * peak as f64;
+            let mut stacked = 0;
+            let band = bands.iter().position(|band| {
+                stacked += band[sample];
+                stacked as f64 >= level
+            });
+            if let Some(band) = band {
+                buf[(inner.x + column, inner.bottom() - 1 - row)]
+                    .set_symbol(BAND_SYMBOLS[band % BAND_SYMBOLS.len()])
+                    .set_fg(theme.process_color(band));
+            }
+        }
+    }
+}
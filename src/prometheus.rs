@@ -0,0 +1,261 @@
+use crate::json::Json;
+use crate::metric::Metric;
+use crate::snapshot;
+use crate::GPUInfo;
+use log::{debug, warn};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// The latest per-GPU state, shared between the TUI loop and the exporter thread.
+pub type SharedGpus = Arc<Mutex<Vec<GPUInfo>>>;
+
+/// Binds `port` on all interfaces and serves the latest metrics of `gpus` at `/metrics`
+/// from a background thread, in the Prometheus text exposition format, and at
+/// `/openmetrics` in the OpenMetrics format. The same server answers `/gpus` and
+/// `/gpus/<index>` with JSON, including the history of each metric.
+pub fn spawn(port: u16, gpus: SharedGpus) -> io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    debug!("Serving Prometheus metrics on port {}", port);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| handle_connection(stream, &gpus));
+            if let Err(error) = result {
+                warn!("Prometheus exporter connection failed: {}", error);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, gpus: &SharedGpus) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers; the exporter doesn't need any of them
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let (status, content_type, body) = {
+        let gpus = gpus.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        respond(path, &gpus)
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// The status, content type and body of the response to a GET of `path`.
+pub fn respond(path: &str, gpus: &[GPUInfo]) -> (&'static str, &'static str, String) {
+    const TEXT: &str = "text/plain; version=0.0.4";
+    const OPENMETRICS: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+    const JSON: &str = "application/json";
+    let not_found = || ("404 Not Found", TEXT, String::from("Not Found\n"));
+    match path.trim_end_matches('/') {
+        "/metrics" => ("200 OK", TEXT, render(gpus)),
+        "/openmetrics" => ("200 OK", OPENMETRICS, render_openmetrics(gpus)),
+        "/gpus" => {
+            let json = Json::Array(gpus.iter().map(snapshot::history_json).collect());
+            ("200 OK", JSON, json.to_string())
+        }
+        // Whole GPUs by hardware index, like the `gpu` label of the metrics
+        path => match path
+            .strip_prefix("/gpus/")
+            .and_then(|index| index.parse::<u32>().ok())
+            .and_then(|index| {
+                gpus.iter()
+                    .find(|gpu| gpu.index == index && gpu.mig_index.is_none())
+            }) {
+            Some(gpu_info) => ("200 OK", JSON, snapshot::history_json(gpu_info).to_string()),
+            None => not_found(),
+        },
+    }
+}
+
+/// The unit of a [`Gauge`], which both formats append to its name.
+#[derive(Clone, Copy)]
+enum Unit {
+    Megahertz,
+    Celsius,
+    Bytes,
+    Percent,
+    Watts,
+}
+
+impl Unit {
+    /// The suffix of the Prometheus name, in the unit the readings are kept in.
+    fn prometheus_suffix(self) -> &'static str {
+        match self {
+            Unit::Megahertz => "megahertz",
+            Unit::Celsius => "celsius",
+            Unit::Bytes => "bytes",
+            Unit::Percent => "percent",
+            Unit::Watts => "watts",
+        }
+    }
+
+    /// The base unit OpenMetrics names end with.
+    fn openmetrics_suffix(self) -> &'static str {
+        match self {
+            Unit::Megahertz => "hertz",
+            Unit::Percent => "ratio",
+            unit => unit.prometheus_suffix(),
+        }
+    }
+
+    /// Converts a reading to the base unit of [`Unit::openmetrics_suffix`].
+    fn to_base(self, value: f64) -> f64 {
+        match self {
+            Unit::Megahertz => value * 1e6,
+            Unit::Percent => value / 100.0,
+            Unit::Celsius | Unit::Bytes | Unit::Watts => value,
+        }
+    }
+}
+
+/// A gauge exported once per GPU, named `name` followed by its unit.
+struct Gauge {
+    name: &'static str,
+    unit: Unit,
+    help: &'static str,
+    metric: Metric,
+    value: fn(&GPUInfo) -> f64,
+}
+
+const GAUGES: [Gauge; 7] = [
+    Gauge {
+        name: "nvidia_gpu_clock",
+        unit: Unit::Megahertz,
+        help: "Current graphics clock speed.",
+        metric: Metric::Clock,
+        value: |gpu| gpu.core_clock.latest() as f64,
+    },
+    Gauge {
+        name: "nvidia_gpu_temperature",
+        unit: Unit::Celsius,
+        help: "Current GPU core temperature.",
+        metric: Metric::Temperature,
+        value: |gpu| gpu.temperature.latest() as f64,
+    },
+    Gauge {
+        name: "nvidia_gpu_memory_used",
+        unit: Unit::Bytes,
+        help: "Allocated framebuffer memory.",
+        metric: Metric::Memory,
+        value: |gpu| gpu.memory_used.latest() as f64,
+    },
+    Gauge {
+        name: "nvidia_gpu_memory_total",
+        unit: Unit::Bytes,
+        help: "Total installed framebuffer memory.",
+        metric: Metric::Memory,
+        value: |gpu| gpu.memory_total as f64,
+    },
+    Gauge {
+        name: "nvidia_gpu_sm_utilization",
+        unit: Unit::Percent,
+        help: "Percent of time a kernel was executing on the GPU.",
+        metric: Metric::Utilization,
+        value: |gpu| gpu.gpu_utilization.latest() as f64,
+    },
+    Gauge {
+        name: "nvidia_gpu_memory_utilization",
+        unit: Unit::Percent,
+        help: "Percent of time device memory was being read or written.",
+        metric: Metric::Utilization,
+        value: |gpu| gpu.memory_utilization.latest() as f64,
+    },
+    Gauge {
+        name: "nvidia_gpu_power",
+        unit: Unit::Watts,
+        help: "Current power draw.",
+        metric: Metric::Power,
+        value: |gpu| gpu.precision.export_watts(gpu.power_usage.latest()),
+    },
+];
+
+/// Formats the latest sample of every GPU in the Prometheus text format. Unsupported
+/// metrics and metrics whose query is currently failing are left out rather than
+/// reported as zero.
+pub fn render(gpus: &[GPUInfo]) -> String {
+    let mut output = String::new();
+    for gauge in GAUGES.iter() {
+        let name = format!("{}_{}", gauge.name, gauge.unit.prometheus_suffix());
+        let _ = writeln!(output, "# HELP {} {}", name, gauge.help);
+        let _ = writeln!(output, "# TYPE {} gauge", name);
+        render_samples(&mut output, &name, gauge, |value| value, gpus);
+    }
+    output
+}
+
+/// Formats the same samples as [`render`] in the OpenMetrics text format, with a `UNIT`
+/// line per metric and the values in base units, such as hertz and ratios of 1 instead
+/// of megahertz and percent.
+pub fn render_openmetrics(gpus: &[GPUInfo]) -> String {
+    let mut output = String::new();
+    for gauge in GAUGES.iter() {
+        let unit = gauge.unit.openmetrics_suffix();
+        let name = format!("{}_{}", gauge.name, unit);
+        let _ = writeln!(output, "# TYPE {} gauge", name);
+        let _ = writeln!(output, "# UNIT {} {}", name, unit);
+        let _ = writeln!(output, "# HELP {} {}", name, gauge.help);
+        render_samples(
+            &mut output,
+            &name,
+            gauge,
+            |value| gauge.unit.to_base(value),
+            gpus,
+        );
+    }
+    output.push_str("# EOF\n");
+    output
+}
+
+/// Writes one sample of `gauge` per GPU that has it available, its value passed through
+/// `convert`.
+fn render_samples(
+    output: &mut String,
+    name: &str,
+    gauge: &Gauge,
+    convert: impl Fn(f64) -> f64,
+    gpus: &[GPUInfo],
+) {
+    for gpu_info in gpus {
+        if !gpu_info.available(gauge.metric) {
+            continue;
+        }
+        let uuid = gpu_info
+            .uuid
+            .as_deref()
+            .map(|uuid| format!(",uuid=\"{}\"", escape_label(uuid)))
+            .unwrap_or_default();
+        let _ = writeln!(
+            output,
+            "{}{{gpu=\"{}\",name=\"{}\"{}}} {}",
+            name,
+            gpu_info.id(),
+            escape_label(&gpu_info.device_name),
+            uuid,
+            convert((gauge.value)(gpu_info))
+        );
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
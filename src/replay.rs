@@ -0,0 +1,460 @@
+use crate::export::RECORD_HEADER;
+use crate::nvlink::NvLinkCounters;
+use crate::process::GpuProcess;
+use crate::source::{FanPolicy, GpuDevice, GpuSource, MediaEngine};
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{
+        Clock, ComputeMode, EccCounter, EncoderType, MemoryError, PcieUtilCounter,
+        PerformanceState, Sampling, TemperatureSensor, TemperatureThreshold,
+    },
+    error::NvmlError,
+    struct_wrappers::device::{MemoryInfo, Utilization},
+    structs::device::UtilizationInfo,
+};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The GPUs of a `--record` file, played back with `--replay` as if they were being
+/// monitored live.
+///
+/// Each query answers with the row of the tick due at the moment, `--replay-speed` times
+/// as fast as it was recorded, and the last tick keeps being answered once the recording
+/// is over. A metric that was never recorded fails as unsupported, a metric that failed
+/// at the time fails again, and a GPU missing from a tick is lost for that tick.
+pub struct ReplaySource {
+    devices: Vec<ReplayDevice>,
+    clock: Arc<ReplayClock>,
+}
+
+impl ReplaySource {
+    /// Reads the recording at `path`, to be played back `speed` times as fast.
+    pub fn load(path: &Path, speed: f64) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?, speed)
+    }
+
+    /// Reads a recording written by [`crate::export::Recorder`].
+    pub fn parse(recording: &str, speed: f64) -> io::Result<Self> {
+        let mut lines = recording.lines().enumerate();
+        if lines.next().map(|(_, header)| header) != Some(RECORD_HEADER) {
+            return Err(invalid("not a recording of --record"));
+        }
+        let mut timestamps: Vec<f64> = Vec::new();
+        let mut ids: Vec<String> = Vec::new();
+        let mut ticks: Vec<Vec<(usize, Reading)>> = Vec::new();
+        for (number, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let fields = split_fields(line);
+            let (timestamp, reading) = parse_row(&fields)
+                .ok_or_else(|| invalid(&format!("malformed row on line {}", number + 1)))?;
+            if timestamps.last() != Some(&timestamp) {
+                timestamps.push(timestamp);
+                ticks.push(Vec::new());
+            }
+            let device = match ids.iter().position(|id| *id == fields[1]) {
+                Some(device) => device,
+                None => {
+                    ids.push(fields[1].clone());
+                    ids.len() - 1
+                }
+            };
+            ticks.last_mut().unwrap().push((device, reading));
+        }
+        if timestamps.is_empty() {
+            return Err(invalid("the recording has no samples"));
+        }
+
+        let clock = Arc::new(ReplayClock {
+            timestamps,
+            started: Instant::now(),
+            speed,
+        });
+        let mut devices: Vec<ReplayDevice> = (0..ids.len())
+            .map(|_| ReplayDevice {
+                clock: Arc::clone(&clock),
+                readings: vec![None; ticks.len()],
+                ever: Reading::default(),
+            })
+            .collect();
+        for (tick, readings) in ticks.into_iter().enumerate() {
+            for (device, reading) in readings {
+                devices[device].ever.fill(&reading);
+                devices[device].readings[tick] = Some(reading);
+            }
+        }
+        Ok(Self { devices, clock })
+    }
+
+    /// The median time between the recorded ticks at the replay speed, to sample the
+    /// replay as often as the GPUs were sampled, or `None` with a single tick.
+    pub fn interval(&self) -> Option<Duration> {
+        let mut gaps: Vec<f64> = self
+            .clock
+            .timestamps
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect();
+        gaps.sort_by(f64::total_cmp);
+        let median = gaps.get(gaps.len() / 2)?;
+        Some(Duration::from_secs_f64(median / self.clock.speed).max(Duration::from_millis(1)))
+    }
+
+    fn replay_device(&self, index: u32) -> Result<&ReplayDevice, NvmlError> {
+        self.devices
+            .get(index as usize)
+            .ok_or(NvmlError::InvalidArg)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The fields of a CSV row, with the quotes around the GPU name removed and doubled
+/// quotes inside it unescaped.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// The timestamp and readings of a row of [`RECORD_HEADER`] columns.
+fn parse_row(fields: &[String]) -> Option<(f64, Reading)> {
+    if fields.len() != RECORD_HEADER.split(',').count() {
+        return None;
+    }
+    // An empty field is a failed query, anything else has to parse
+    fn number<T: std::str::FromStr>(field: &str) -> Option<Option<T>> {
+        match field {
+            "" => Some(None),
+            field => field.parse().ok().map(Some),
+        }
+    }
+    let fan_speeds = match fields[14].as_str() {
+        "" => None,
+        fan_speeds => Some(
+            fan_speeds
+                .split(';')
+                .map(|fan_speed| fan_speed.parse().ok())
+                .collect::<Option<Vec<u32>>>()?,
+        ),
+    };
+    let reading = Reading {
+        name: Some(fields[2].clone()),
+        uuid: Some(fields[3].clone()).filter(|uuid| !uuid.is_empty()),
+        clock: number(&fields[4])?,
+        memory_clock: number(&fields[5])?,
+        max_clock: number(&fields[6])?,
+        temperature: number(&fields[7])?,
+        memory_used: number(&fields[8])?,
+        memory_total: number(&fields[9])?,
+        sm_utilization: number(&fields[10])?,
+        memory_utilization: number(&fields[11])?,
+        power: number(&fields[12])?,
+        power_limit: number(&fields[13])?,
+        fan_speeds,
+    };
+    Some((fields[0].parse().ok()?, reading))
+}
+
+/// The recorded ticks, shared by the devices so that they all play the same one.
+struct ReplayClock {
+    /// The Unix time of each tick, in recorded order.
+    timestamps: Vec<f64>,
+    started: Instant,
+    speed: f64,
+}
+
+impl ReplayClock {
+    /// The tick due now: the last one recorded at or before as much time after the first
+    /// as has passed since the replay started, times the speed.
+    fn tick(&self) -> usize {
+        let now = self.timestamps[0] + self.started.elapsed().as_secs_f64() * self.speed;
+        self.timestamps
+            .partition_point(|&timestamp| timestamp <= now)
+            .saturating_sub(1)
+    }
+}
+
+/// One recorded row of a GPU, with `None` for what couldn't be read.
+#[derive(Debug, Clone, Default)]
+struct Reading {
+    name: Option<String>,
+    uuid: Option<String>,
+    clock: Option<u32>,
+    memory_clock: Option<u32>,
+    max_clock: Option<u32>,
+    temperature: Option<u32>,
+    memory_used: Option<u64>,
+    memory_total: Option<u64>,
+    sm_utilization: Option<u32>,
+    memory_utilization: Option<u32>,
+    power: Option<u32>,
+    power_limit: Option<u32>,
+    fan_speeds: Option<Vec<u32>>,
+}
+
+impl Reading {
+    /// Keeps the first known value of each field, so that a field that's still `None`
+    /// after every row was never recorded.
+    fn fill(&mut self, reading: &Reading) {
+        fn keep<T: Clone>(known: &mut Option<T>, value: &Option<T>) {
+            if known.is_none() {
+                known.clone_from(value);
+            }
+        }
+        keep(&mut self.name, &reading.name);
+        keep(&mut self.uuid, &reading.uuid);
+        keep(&mut self.clock, &reading.clock);
+        keep(&mut self.memory_clock, &reading.memory_clock);
+        keep(&mut self.max_clock, &reading.max_clock);
+        keep(&mut self.temperature, &reading.temperature);
+        keep(&mut self.memory_used, &reading.memory_used);
+        keep(&mut self.memory_total, &reading.memory_total);
+        keep(&mut self.sm_utilization, &reading.sm_utilization);
+        keep(&mut self.memory_utilization, &reading.memory_utilization);
+        keep(&mut self.power, &reading.power);
+        keep(&mut self.power_limit, &reading.power_limit);
+        keep(&mut self.fan_speeds, &reading.fan_speeds);
+    }
+}
+
+impl GpuSource for ReplaySource {
+    fn device_count(&self) -> Result<u32, NvmlError> {
+        Ok(self.devices.len() as u32)
+    }
+
+    fn driver_version(&self) -> Result<String, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn nvml_version(&self) -> Result<String, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn cuda_driver_version(&self) -> Result<i32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn device(&self, index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        Ok(self.replay_device(index)?)
+    }
+
+    fn mig_device_count(&self, _index: u32) -> Result<u32, NvmlError> {
+        Ok(0)
+    }
+
+    fn mig_device(&self, _index: u32, _mig_index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        Err(NvmlError::NotFound)
+    }
+
+    fn clock_vf_offsets(&self, _index: u32) -> Result<(i32, i32), NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn clock_vf_offset_ranges(&self, _index: u32) -> Result<[(i32, i32); 2], NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn reserved_memory(&self, _index: u32) -> Result<u64, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn die_power(&self, _index: u32) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn nvlink_counters(&self, _index: u32, _link: u32) -> Result<NvLinkCounters, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn engine_utilization(&self, _index: u32, _engine: MediaEngine) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn fan_policy(&self, _index: u32, _fan: u32) -> Result<FanPolicy, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+}
+
+/// One GPU of a [`ReplaySource`].
+struct ReplayDevice {
+    clock: Arc<ReplayClock>,
+    /// The row of each tick, `None` where the GPU wasn't recorded.
+    readings: Vec<Option<Reading>>,
+    /// The first known value of each field over the whole recording.
+    ever: Reading,
+}
+
+impl ReplayDevice {
+    /// The field picked by `field` from the row of the tick due now.
+    fn read<T>(&self, field: impl Fn(&Reading) -> Option<T>) -> Result<T, NvmlError> {
+        let reading = self.readings[self.clock.tick()]
+            .as_ref()
+            .ok_or(NvmlError::GpuLost)?;
+        field(reading).ok_or_else(|| match field(&self.ever) {
+            Some(_) => NvmlError::Unknown,
+            None => NvmlError::NotSupported,
+        })
+    }
+}
+
+impl GpuDevice for ReplayDevice {
+    fn name(&self) -> Result<String, NvmlError> {
+        self.ever.name.clone().ok_or(NvmlError::NotSupported)
+    }
+
+    fn uuid(&self) -> Result<String, NvmlError> {
+        self.ever.uuid.clone().ok_or(NvmlError::NotSupported)
+    }
+
+    fn serial(&self) -> Result<String, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        match clock {
+            Clock::Graphics => self.read(|reading| reading.clock),
+            Clock::Memory => self.read(|reading| reading.memory_clock),
+            _ => Err(NvmlError::NotSupported),
+        }
+    }
+
+    fn max_clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        match clock {
+            Clock::Graphics => self.ever.max_clock.ok_or(NvmlError::NotSupported),
+            _ => Err(NvmlError::NotSupported),
+        }
+    }
+
+    fn memory_bus_width(&self) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn applications_clock(&self, _clock: Clock) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn default_applications_clock(&self, _clock: Clock) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn temperature(&self, _sensor: TemperatureSensor) -> Result<u32, NvmlError> {
+        self.read(|reading| reading.temperature)
+    }
+
+    fn memory_temperature(&self) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn temperature_threshold(&self, _threshold: TemperatureThreshold) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn memory_info(&self) -> Result<MemoryInfo, NvmlError> {
+        let used = self.read(|reading| reading.memory_used)?;
+        let total = self.read(|reading| reading.memory_total)?;
+        Ok(MemoryInfo {
+            free: total.saturating_sub(used),
+            total,
+            used,
+        })
+    }
+
+    fn utilization_rates(&self) -> Result<Utilization, NvmlError> {
+        Ok(Utilization {
+            gpu: self.read(|reading| reading.sm_utilization)?,
+            memory: self.read(|reading| reading.memory_utilization)?,
+        })
+    }
+
+    fn power_usage(&self) -> Result<u32, NvmlError> {
+        self.read(|reading| reading.power)
+    }
+
+    fn enforced_power_limit(&self) -> Result<u32, NvmlError> {
+        self.read(|reading| reading.power_limit)
+    }
+
+    fn power_limit_constraints(&self) -> Result<(u32, u32), NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn performance_state(&self) -> Result<PerformanceState, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn auto_boost_enabled(&self) -> Result<bool, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn persistence_mode_enabled(&self) -> Result<bool, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn compute_mode(&self) -> Result<ComputeMode, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn current_throttle_reasons(&self) -> Result<ThrottleReasons, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn pcie_throughput(&self, _counter: PcieUtilCounter) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn encoder_capacity(&self, _for_type: EncoderType) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn encoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn decoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn num_fans(&self) -> Result<u32, NvmlError> {
+        let fan_speeds = self.ever.fan_speeds.as_ref();
+        Ok(fan_speeds.map_or(0, |fan_speeds| fan_speeds.len() as u32))
+    }
+
+    fn fan_speed(&self, fan: u32) -> Result<u32, NvmlError> {
+        self.read(|reading| reading.fan_speeds.as_ref()?.get(fan as usize).copied())
+    }
+
+    fn total_ecc_errors(
+        &self,
+        _error_type: MemoryError,
+        _counter_type: EccCounter,
+    ) -> Result<u64, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn running_processes(&self) -> Result<Vec<GpuProcess>, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn samples(&self, _sampling: Sampling, _since: u64) -> Result<Vec<(u64, u32)>, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::source::GpuDevice;
+use nvml_wrapper::enum_wrappers::device::Sampling;
+
+/// The samples the driver takes of one metric between two ticks, read with `--high-res`.
+///
+/// NVML samples utilization and power many times a second into a buffer of its own,
+/// so reading that buffer catches the spikes an instantaneous reading once a tick misses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SampleBuffer {
+    /// The CPU timestamp in µs of the newest sample read, so that each tick only reads
+    /// the samples taken since the tick before.
+    last_seen: u64,
+    /// The lowest and highest sample of the latest tick, or `None` if it had none.
+    pub range: Option<(u32, u32)>,
+}
+
+impl SampleBuffer {
+    /// Reads the samples of `sampling` taken since the last tick and returns their mean,
+    /// or `None` if the GPU has none, in which case the caller keeps its instantaneous
+    /// reading.
+    pub fn read(&mut self, gpu_device: &dyn GpuDevice, sampling: Sampling) -> Option<u32> {
+        let samples = gpu_device
+            .samples(sampling, self.last_seen)
+            .unwrap_or_default();
+        let values = samples.iter().map(|&(_, value)| value);
+        self.range = values.clone().min().zip(values.clone().max());
+        self.last_seen = samples
+            .iter()
+            .map(|&(timestamp, _)| timestamp)
+            .fold(self.last_seen, u64::max);
+        let count = samples.len() as u64;
+        (count > 0).then(|| (values.map(u64::from).sum::<u64>() / count) as u32)
+    }
+
+    /// How far the samples of the latest tick spread, as in `12–97% within tick`, with
+    /// each sample written by `format` and followed by `unit`. `None` while the samples
+    /// were all the same.
+    pub fn spread(&self, format: impl Fn(u32) -> String, unit: &str) -> Option<String> {
+        match self.range {
+            Some((min, max)) if min < max => Some(format!(
+                "{}–{}{} within tick",
+                format(min),
+                format(max),
+                unit
+            )),
+            _ => None,
+        }
+    }
+}
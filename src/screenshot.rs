@@ -0,0 +1,102 @@
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The rendered frame as plain text, one line per row with trailing blanks trimmed.
+pub fn to_text(buf: &Buffer) -> String {
+    let mut text = String::new();
+    for row in buf.content().chunks(usize::from(buf.area.width)) {
+        let line: String = row.iter().map(Cell::symbol).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// The rendered frame with its colors and modifiers as ANSI escape sequences, for
+/// printing back with `cat` in a terminal. Every row ends with the style reset.
+pub fn to_ansi(buf: &Buffer) -> String {
+    let mut ansi = String::new();
+    for row in buf.content().chunks(usize::from(buf.area.width)) {
+        let mut style = None;
+        for cell in row {
+            let cell_style = (cell.fg, cell.bg, cell.modifier);
+            if style != Some(cell_style) {
+                ansi.push_str(&sgr(cell.fg, cell.bg, cell.modifier));
+                style = Some(cell_style);
+            }
+            ansi.push_str(cell.symbol());
+        }
+        ansi.push_str("\x1b[0m\n");
+    }
+    ansi
+}
+
+/// The escape sequence that switches to a style from scratch.
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut codes = String::from("0");
+    for (flag, code) in [
+        (Modifier::BOLD, 1),
+        (Modifier::DIM, 2),
+        (Modifier::ITALIC, 3),
+        (Modifier::UNDERLINED, 4),
+        (Modifier::SLOW_BLINK, 5),
+        (Modifier::RAPID_BLINK, 6),
+        (Modifier::REVERSED, 7),
+        (Modifier::HIDDEN, 8),
+        (Modifier::CROSSED_OUT, 9),
+    ] {
+        if modifier.contains(flag) {
+            let _ = write!(codes, ";{}", code);
+        }
+    }
+    push_color(&mut codes, fg, 30);
+    push_color(&mut codes, bg, 40);
+    format!("\x1b[{}m", codes)
+}
+
+/// Appends the SGR parameters of `color`, where `base` is 30 for the foreground and 40
+/// for the background. The terminal's default color needs none after the reset.
+fn push_color(codes: &mut String, color: Color, base: u8) {
+    // Writing to a String can't fail
+    let _ = match color {
+        Color::Reset => Ok(()),
+        Color::Black => write!(codes, ";{}", base),
+        Color::Red => write!(codes, ";{}", base + 1),
+        Color::Green => write!(codes, ";{}", base + 2),
+        Color::Yellow => write!(codes, ";{}", base + 3),
+        Color::Blue => write!(codes, ";{}", base + 4),
+        Color::Magenta => write!(codes, ";{}", base + 5),
+        Color::Cyan => write!(codes, ";{}", base + 6),
+        Color::Gray => write!(codes, ";{}", base + 7),
+        Color::DarkGray => write!(codes, ";{}", base + 60),
+        Color::LightRed => write!(codes, ";{}", base + 61),
+        Color::LightGreen => write!(codes, ";{}", base + 62),
+        Color::LightYellow => write!(codes, ";{}", base + 63),
+        Color::LightBlue => write!(codes, ";{}", base + 64),
+        Color::LightMagenta => write!(codes, ";{}", base + 65),
+        Color::LightCyan => write!(codes, ";{}", base + 66),
+        Color::White => write!(codes, ";{}", base + 67),
+        Color::Rgb(r, g, b) => write!(codes, ";{};2;{};{};{}", base + 8, r, g, b),
+        Color::Indexed(index) => write!(codes, ";{};5;{}", base + 8, index),
+    };
+}
+
+/// Writes the rendered frame to `dir` as `nvidia-screen-<unix time>.txt` in plain text
+/// and as `.ans` with its colors, returning the path of the plain-text file.
+pub fn save(buf: &Buffer, dir: &Path) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("nvidia-screen-{}.txt", timestamp));
+    fs::write(&path, to_text(buf))?;
+    fs::write(path.with_extension("ans"), to_ansi(buf))?;
+    Ok(path)
+}
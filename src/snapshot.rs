@@ -0,0 +1,507 @@
+use crate::ecc::EccErrors;
+use crate::history::History;
+use crate::json::Json;
+use crate::metric::{Metric, Precision, TemperatureUnit};
+use crate::process::{GpuProcess, ProcessKind};
+use crate::source::MediaEngine;
+use crate::status::DriverVersions;
+use crate::throttle;
+use crate::GPUInfo;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write as _};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The latest reading of every metric of one GPU, shared by the export features.
+///
+/// A metric that is unsupported or failed on the last tick is `None` rather than zero,
+/// and the reason for every failure is kept in `errors`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuSnapshot {
+    pub index: u32,
+    /// The MIG instance of the GPU at `index`, or `None` for a whole GPU.
+    pub mig_index: Option<u32>,
+    pub name: String,
+    pub uuid: Option<String>,
+    pub serial: Option<String>,
+    pub clock_mhz: Option<u32>,
+    pub temperature_c: Option<u32>,
+    /// Only GPUs with a memory junction sensor have a reading.
+    pub memory_temperature_c: Option<u32>,
+    pub memory_used_bytes: Option<u64>,
+    pub memory_total_bytes: u64,
+    pub sm_utilization_pct: Option<u32>,
+    pub memory_utilization_pct: Option<u32>,
+    pub power_mw: Option<u32>,
+    pub power_limit_mw: u32,
+    /// The performance state as a number, 0 for P0.
+    pub performance_state: Option<u32>,
+    /// The compute mode as [`throttle::compute_mode_name`] has it.
+    pub compute_mode: Option<&'static str>,
+    pub pcie_rx_kbps: Option<u32>,
+    pub pcie_tx_kbps: Option<u32>,
+    pub encoder_utilization_pct: Option<u32>,
+    pub decoder_utilization_pct: Option<u32>,
+    pub fan_speeds_pct: Option<Vec<u32>>,
+    /// Only GPUs that report the engine have a reading.
+    pub jpeg_utilization_pct: Option<u32>,
+    pub ofa_utilization_pct: Option<u32>,
+    pub throttle_reasons: Option<Vec<&'static str>>,
+    pub ecc_errors: Option<EccErrors>,
+    pub processes: Option<Vec<GpuProcess>>,
+    pub errors: BTreeMap<Metric, String>,
+}
+
+impl GpuSnapshot {
+    /// Captures the latest samples of `gpu_info`.
+    pub fn new(gpu_info: &GPUInfo) -> Self {
+        let latest = |metric: Metric, value: u32| gpu_info.available(metric).then_some(value);
+        Self {
+            index: gpu_info.index,
+            mig_index: gpu_info.mig_index,
+            name: gpu_info.device_name.clone(),
+            uuid: gpu_info.uuid.clone(),
+            serial: gpu_info.serial.clone(),
+            clock_mhz: latest(Metric::Clock, gpu_info.core_clock.latest()),
+            temperature_c: latest(Metric::Temperature, gpu_info.temperature.latest()),
+            memory_temperature_c: gpu_info
+                .memory_temperature
+                .as_ref()
+                .and_then(|temperature| temperature.ago(0)),
+            memory_used_bytes: gpu_info
+                .available(Metric::Memory)
+                .then(|| gpu_info.memory_used.latest()),
+            memory_total_bytes: gpu_info.memory_total,
+            sm_utilization_pct: latest(Metric::Utilization, gpu_info.gpu_utilization.latest()),
+            memory_utilization_pct: latest(
+                Metric::Utilization,
+                gpu_info.memory_utilization.latest(),
+            ),
+            power_mw: latest(Metric::Power, gpu_info.power_usage.latest()),
+            power_limit_mw: gpu_info.power_limit,
+            performance_state: gpu_info.performance_state,
+            compute_mode: gpu_info
+                .compute_mode
+                .clone()
+                .map(throttle::compute_mode_name),
+            pcie_rx_kbps: latest(Metric::Pcie, gpu_info.pcie_rx.latest()),
+            pcie_tx_kbps: latest(Metric::Pcie, gpu_info.pcie_tx.latest()),
+            encoder_utilization_pct: latest(Metric::Codec, gpu_info.encoder_utilization.latest()),
+            decoder_utilization_pct: latest(Metric::Codec, gpu_info.decoder_utilization.latest()),
+            fan_speeds_pct: gpu_info.available(Metric::Fan).then(|| {
+                gpu_info
+                    .fan_speeds
+                    .iter()
+                    .map(|fan_speed| fan_speed.latest())
+                    .collect()
+            }),
+            jpeg_utilization_pct: engine_utilization(gpu_info, MediaEngine::Jpeg),
+            ofa_utilization_pct: engine_utilization(gpu_info, MediaEngine::Ofa),
+            throttle_reasons: gpu_info
+                .available(Metric::Throttle)
+                .then(|| throttle::labels(gpu_info.throttle_reasons)),
+            ecc_errors: gpu_info
+                .available(Metric::Ecc)
+                .then_some(gpu_info.ecc_errors),
+            processes: gpu_info
+                .available(Metric::Processes)
+                .then(|| gpu_info.processes.clone()),
+            errors: gpu_info.errors.clone(),
+        }
+    }
+
+    /// How far `current` has moved from this snapshot for `metric`, such as `+12 °C`,
+    /// with temperatures in `temperature_unit` and power rounded to `precision`. `None`
+    /// for metrics without a single reading to compare, and for readings that either
+    /// snapshot is missing.
+    pub fn delta(
+        &self,
+        current: &GpuSnapshot,
+        metric: Metric,
+        temperature_unit: TemperatureUnit,
+        precision: Precision,
+    ) -> Option<String> {
+        let delta = |baseline: Option<u32>, current: Option<u32>| {
+            Some(i64::from(current?) - i64::from(baseline?))
+        };
+        match metric {
+            Metric::Temperature => Some(format!(
+                "{:+.0} {}",
+                temperature_unit
+                    .convert_difference(delta(self.temperature_c, current.temperature_c)? as f64),
+                temperature_unit.symbol()
+            )),
+            Metric::Clock => Some(format!(
+                "{:+} MHz",
+                delta(self.clock_mhz, current.clock_mhz)?
+            )),
+            Metric::Utilization => Some(format!(
+                "{:+}%",
+                delta(self.sm_utilization_pct, current.sm_utilization_pct)?
+            )),
+            Metric::Power => {
+                let delta = delta(self.power_mw, current.power_mw)? as f64;
+                let sign = if delta < 0.0 { "-" } else { "+" };
+                Some(format!("{}{}", sign, precision.watts(delta.abs())))
+            }
+            Metric::Memory => {
+                let used = current.memory_used_bytes? as i64 - self.memory_used_bytes? as i64;
+                Some(format!("{:+} MiB", used / 1024 / 1024))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let errors = self
+            .errors
+            .iter()
+            .fold(Json::object(), |errors, (metric, error)| {
+                errors.field(&metric.to_string(), error.as_str())
+            });
+        Json::object()
+            .field("index", self.index)
+            .field("mig_index", self.mig_index)
+            .field("name", self.name.as_str())
+            .field("uuid", self.uuid.as_deref())
+            .field("serial", self.serial.as_deref())
+            .field("clock_mhz", self.clock_mhz)
+            .field("temperature_c", self.temperature_c)
+            .field("memory_temperature_c", self.memory_temperature_c)
+            .field("memory_used_bytes", self.memory_used_bytes)
+            .field("memory_total_bytes", self.memory_total_bytes)
+            .field("sm_utilization_pct", self.sm_utilization_pct)
+            .field("memory_utilization_pct", self.memory_utilization_pct)
+            .field("power_mw", self.power_mw)
+            .field("power_limit_mw", self.power_limit_mw)
+            .field("performance_state", self.performance_state)
+            .field("compute_mode", self.compute_mode)
+            .field("pcie_rx_kbps", self.pcie_rx_kbps)
+            .field("pcie_tx_kbps", self.pcie_tx_kbps)
+            .field("encoder_utilization_pct", self.encoder_utilization_pct)
+            .field("decoder_utilization_pct", self.decoder_utilization_pct)
+            .field("fan_speeds_pct", self.fan_speeds_pct.clone())
+            .field("jpeg_utilization_pct", self.jpeg_utilization_pct)
+            .field("ofa_utilization_pct", self.ofa_utilization_pct)
+            .field("throttle_reasons", self.throttle_reasons.clone())
+            .field("ecc_errors", self.ecc_errors.as_ref().map(ecc_json))
+            .field(
+                "processes",
+                self.processes
+                    .as_ref()
+                    .map(|processes| processes.iter().map(process_json).collect::<Vec<_>>()),
+            )
+            .field("errors", errors)
+    }
+}
+
+/// The latest utilization of `engine`, if the GPU reports it and it was read on the
+/// last tick.
+fn engine_utilization(gpu_info: &GPUInfo, engine: MediaEngine) -> Option<u32> {
+    gpu_info
+        .engine_utilization
+        .iter()
+        .find(|(candidate, _)| *candidate == engine)
+        .filter(|_| gpu_info.available(Metric::Engines))
+        .map(|(_, utilization)| utilization.latest())
+}
+
+fn ecc_json(ecc: &EccErrors) -> Json {
+    Json::object()
+        .field("volatile_corrected", ecc.volatile_corrected)
+        .field("volatile_uncorrected", ecc.volatile_uncorrected)
+        .field("aggregate_corrected", ecc.aggregate_corrected)
+        .field("aggregate_uncorrected", ecc.aggregate_uncorrected)
+}
+
+fn process_json(process: &GpuProcess) -> Json {
+    let kind = match process.kind {
+        ProcessKind::Compute => "compute",
+        ProcessKind::Graphics => "graphics",
+    };
+    Json::object()
+        .field("pid", process.pid)
+        .field("name", process.name.as_str())
+        .field("type", kind)
+        .field("used_memory_bytes", process.used_memory)
+}
+
+/// The latest readings of one GPU with the charted history of each metric, oldest
+/// sample first, as served by the `/gpus` endpoints.
+pub fn history_json(gpu_info: &GPUInfo) -> Json {
+    let samples = |history: &History<u32>| history.recent(history.len());
+    let history = Json::object()
+        .field("clock_mhz", samples(&gpu_info.core_clock))
+        .field("temperature_c", samples(&gpu_info.temperature))
+        .field(
+            "memory_used_bytes",
+            gpu_info.memory_used.recent(gpu_info.memory_used.len()),
+        )
+        .field("sm_utilization_pct", samples(&gpu_info.gpu_utilization))
+        .field(
+            "memory_utilization_pct",
+            samples(&gpu_info.memory_utilization),
+        )
+        .field("power_mw", samples(&gpu_info.power_usage))
+        .field("pcie_rx_kbps", samples(&gpu_info.pcie_rx))
+        .field("pcie_tx_kbps", samples(&gpu_info.pcie_tx))
+        .field(
+            "encoder_utilization_pct",
+            samples(&gpu_info.encoder_utilization),
+        )
+        .field(
+            "decoder_utilization_pct",
+            samples(&gpu_info.decoder_utilization),
+        )
+        .field(
+            "fan_speeds_pct",
+            gpu_info.fan_speeds.iter().map(samples).collect::<Vec<_>>(),
+        );
+    GpuSnapshot::new(gpu_info)
+        .to_json()
+        .field("history", history)
+}
+
+/// Writes the whole history of every GPU, as [`history_json`] has it, to `dir` as
+/// `nvidia-history-<unix time>.json`, returning its path. The samples are `interval`
+/// apart and the last one was taken at about the time in the name.
+pub fn save_history(gpus: &[GPUInfo], interval: Duration, dir: &Path) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let json = Json::object()
+        .field("timestamp", timestamp)
+        .field("interval_ms", interval.as_millis() as u64)
+        .field("gpus", Json::Array(gpus.iter().map(history_json).collect()));
+    let path = dir.join(format!("nvidia-history-{}.json", timestamp));
+    fs::write(&path, json.pretty())?;
+    Ok(path)
+}
+
+/// The thermal and power envelope of every GPU over the session, for `--summary`: the
+/// highest temperature, clock and power and the lowest clock, or `null` for metrics
+/// the GPU never reported.
+pub fn summary_json(gpus: &[GPUInfo]) -> Json {
+    let summaries = gpus.iter().map(|gpu_info| {
+        // A history without a trough never got a sample, so its zero peak means nothing
+        let peak = |metric: Metric, history: &History<u32>| {
+            history.trough().filter(|_| gpu_info.supports(metric))?;
+            Some(history.peak())
+        };
+        let min_clock = gpu_info
+            .core_clock
+            .trough()
+            .filter(|_| gpu_info.supports(Metric::Clock));
+        Json::object()
+            .field("index", gpu_info.index)
+            .field("mig_index", gpu_info.mig_index)
+            .field("name", gpu_info.device_name.as_str())
+            .field("uuid", gpu_info.uuid.as_deref())
+            .field(
+                "max_temperature_c",
+                peak(Metric::Temperature, &gpu_info.temperature),
+            )
+            .field("max_clock_mhz", peak(Metric::Clock, &gpu_info.core_clock))
+            .field("min_clock_mhz", min_clock)
+            .field("max_power_mw", peak(Metric::Power, &gpu_info.power_usage))
+    });
+    Json::object().field("gpus", Json::Array(summaries.collect()))
+}
+
+/// Writes [`summary_json`] of every GPU to `path`.
+pub fn save_summary(gpus: &[GPUInfo], path: &Path) -> io::Result<()> {
+    fs::write(path, summary_json(gpus).pretty())
+}
+
+/// Snapshots every GPU as a JSON array, in device index order.
+pub fn snapshot_json(gpus: &[GPUInfo]) -> Json {
+    Json::Array(
+        gpus.iter()
+            .map(|gpu_info| GpuSnapshot::new(gpu_info).to_json())
+            .collect(),
+    )
+}
+
+/// Snapshots every GPU as a plain text table with aligned columns, one GPU per line.
+pub fn snapshot_table(gpus: &[GPUInfo]) -> String {
+    const HEADER: [&str; 6] = ["GPU", "Name", "Temp", "Clock", "Memory", "Power"];
+    let na = || String::from("N/A");
+    let rows: Vec<[String; 6]> = gpus
+        .iter()
+        .map(|gpu_info| {
+            let snapshot = GpuSnapshot::new(gpu_info);
+            [
+                gpu_info.id(),
+                snapshot.name,
+                snapshot
+                    .temperature_c
+                    .map_or_else(na, |temperature| format!("{} °C", temperature)),
+                snapshot
+                    .clock_mhz
+                    .map_or_else(na, |clock| format!("{} MHz", clock)),
+                snapshot.memory_used_bytes.map_or_else(na, |used| {
+                    format!(
+                        "{} / {} MiB",
+                        used / 1024 / 1024,
+                        snapshot.memory_total_bytes / 1024 / 1024
+                    )
+                }),
+                snapshot.power_mw.map_or_else(na, |power| {
+                    let precision = gpu_info.precision;
+                    format!(
+                        "{} / {}",
+                        precision.watts_number(f64::from(power)),
+                        precision.watts(f64::from(snapshot.power_limit_mw))
+                    )
+                }),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADER.map(|title| title.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header = HEADER.map(String::from);
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// Snapshots every GPU in the XML schema of `nvidia-smi -q -x`, for scripts written
+/// against it. Only the device name, serial, UUID, temperature, clocks, memory,
+/// utilization, power, fan and performance state are filled in, and the `id` of each
+/// GPU is its index rather than its PCI bus ID. Readings that aren't available are
+/// `N/A`, like in `nvidia-smi`.
+pub fn snapshot_xml(gpus: &[GPUInfo], versions: &DriverVersions) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" ?>\n");
+    xml.push_str("<!DOCTYPE nvidia_smi_log SYSTEM \"nvsmi_device_v12.dtd\">\n");
+    xml.push_str("<nvidia_smi_log>\n");
+    let cuda_version = versions
+        .cuda
+        .map(|(major, minor)| format!("{}.{}", major, minor));
+    xml_element(&mut xml, 1, "driver_version", versions.driver.clone());
+    xml_element(&mut xml, 1, "cuda_version", cuda_version);
+    xml_element(&mut xml, 1, "attached_gpus", Some(gpus.len()));
+    for gpu_info in gpus {
+        let snapshot = GpuSnapshot::new(gpu_info);
+        let mib = |bytes: u64| format!("{} MiB", bytes / 1024 / 1024);
+        let percent = |value: Option<u32>| value.map(|value| format!("{} %", value));
+        let watts = |milliwatts: u32| format!("{:.2} W", milliwatts as f64 / 1000.0);
+        let clock = |clock: &History<u32>| {
+            Some(clock.latest())
+                .filter(|&clock| clock > 0 && gpu_info.available(Metric::Clock))
+                .map(|clock| format!("{} MHz", clock))
+        };
+
+        let _ = writeln!(xml, "\t<gpu id=\"{}\">", escape_xml(&gpu_info.id()));
+        xml_element(
+            &mut xml,
+            2,
+            "product_name",
+            Some(escape_xml(&snapshot.name)),
+        );
+        let escaped = |value: &Option<String>| value.as_deref().map(escape_xml);
+        xml_element(&mut xml, 2, "serial", escaped(&snapshot.serial));
+        xml_element(&mut xml, 2, "uuid", escaped(&snapshot.uuid));
+        xml.push_str("\t\t<fb_memory_usage>\n");
+        let memory_total = Some(snapshot.memory_total_bytes).filter(|&total| total > 0);
+        xml_element(&mut xml, 3, "total", memory_total.map(mib));
+        xml_element(&mut xml, 3, "used", snapshot.memory_used_bytes.map(mib));
+        let memory_free = snapshot
+            .memory_used_bytes
+            .zip(memory_total)
+            .map(|(used, total)| mib(total.saturating_sub(used)));
+        xml_element(&mut xml, 3, "free", memory_free);
+        xml.push_str("\t\t</fb_memory_usage>\n");
+        xml.push_str("\t\t<utilization>\n");
+        xml_element(
+            &mut xml,
+            3,
+            "gpu_util",
+            percent(snapshot.sm_utilization_pct),
+        );
+        xml_element(
+            &mut xml,
+            3,
+            "memory_util",
+            percent(snapshot.memory_utilization_pct),
+        );
+        xml_element(
+            &mut xml,
+            3,
+            "encoder_util",
+            percent(snapshot.encoder_utilization_pct),
+        );
+        xml_element(
+            &mut xml,
+            3,
+            "decoder_util",
+            percent(snapshot.decoder_utilization_pct),
+        );
+        xml.push_str("\t\t</utilization>\n");
+        xml.push_str("\t\t<temperature>\n");
+        let temperature = snapshot.temperature_c.map(|temp| format!("{} C", temp));
+        xml_element(&mut xml, 3, "gpu_temp", temperature);
+        let memory_temperature = snapshot
+            .memory_temperature_c
+            .map(|temp| format!("{} C", temp));
+        xml_element(&mut xml, 3, "memory_temp", memory_temperature);
+        xml.push_str("\t\t</temperature>\n");
+        xml.push_str("\t\t<gpu_power_readings>\n");
+        xml_element(&mut xml, 3, "power_draw", snapshot.power_mw.map(watts));
+        let power_limit = Some(snapshot.power_limit_mw).filter(|&limit| limit > 0);
+        xml_element(&mut xml, 3, "current_power_limit", power_limit.map(watts));
+        xml.push_str("\t\t</gpu_power_readings>\n");
+        xml.push_str("\t\t<clocks>\n");
+        xml_element(&mut xml, 3, "graphics_clock", clock(&gpu_info.core_clock));
+        xml_element(&mut xml, 3, "sm_clock", clock(&gpu_info.sm_clock));
+        xml_element(&mut xml, 3, "mem_clock", clock(&gpu_info.memory_clock));
+        xml_element(&mut xml, 3, "video_clock", clock(&gpu_info.video_clock));
+        xml.push_str("\t\t</clocks>\n");
+        let fan_speed = snapshot
+            .fan_speeds_pct
+            .and_then(|fan_speeds| fan_speeds.first().copied());
+        xml_element(&mut xml, 2, "fan_speed", percent(fan_speed));
+        let performance_state = snapshot
+            .performance_state
+            .map(|state| format!("P{}", state));
+        xml_element(&mut xml, 2, "performance_state", performance_state);
+        let compute_mode = snapshot.compute_mode.map(|name| name.replace(' ', "_"));
+        xml_element(&mut xml, 2, "compute_mode", compute_mode);
+        xml.push_str("\t</gpu>\n");
+    }
+    xml.push_str("</nvidia_smi_log>\n");
+    xml
+}
+
+/// Writes `<name>value</name>` on its own line, indented by `depth` tabs as `nvidia-smi`
+/// does. `value` must already be escaped.
+fn xml_element(xml: &mut String, depth: usize, name: &str, value: Option<impl Display>) {
+    // Writing to a String can't fail
+    let _ = match value {
+        Some(value) => writeln!(xml, "{}<{name}>{}</{name}>", "\t".repeat(depth), value),
+        None => writeln!(xml, "{}<{name}>N/A</{name}>", "\t".repeat(depth)),
+    };
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
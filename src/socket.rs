@@ -0,0 +1,53 @@
+use crate::prometheus::SharedGpus;
+use crate::snapshot;
+use log::{debug, warn};
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// The socket file of `--socket`, removed again when this is dropped on exit.
+#[derive(Debug)]
+pub struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if let Err(error) = fs::remove_file(&self.0) {
+            debug!("Failed to remove {}: {}", self.0.display(), error);
+        }
+    }
+}
+
+/// Binds a Unix socket at `path` and writes the latest JSON snapshot of `gpus` to every
+/// client that connects, from a background thread. Each client gets a single line of
+/// JSON, the same array as `--dump-json`, after which the connection is closed.
+pub fn spawn(path: &Path, gpus: SharedGpus) -> io::Result<SocketFile> {
+    // A socket left behind by a run that crashed would make binding fail, but any other
+    // file at the path is the user's and stays
+    if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        debug!("Removing the stale socket {}", path.display());
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    debug!("Serving JSON snapshots on {}", path.display());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| handle_connection(stream, &gpus));
+            if let Err(error) = result {
+                warn!("Socket client connection failed: {}", error);
+            }
+        }
+    });
+    Ok(SocketFile(path.to_path_buf()))
+}
+
+fn handle_connection(mut stream: UnixStream, gpus: &SharedGpus) -> io::Result<()> {
+    let json = {
+        let gpus = gpus.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        snapshot::snapshot_json(&gpus).to_string()
+    };
+    writeln!(stream, "{}", json)?;
+    stream.flush()
+}
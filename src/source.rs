@@ -0,0 +1,946 @@
+use crate::nvlink::NvLinkCounters;
+use crate::process::{self, GpuProcess};
+use log::debug;
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{
+        Clock, ComputeMode, EccCounter, EncoderType, MemoryError, PcieUtilCounter,
+        PerformanceState, Sampling, TemperatureSensor, TemperatureThreshold,
+    },
+    enums::device::SampleValue,
+    error::{nvml_sym, nvml_try, NvmlError},
+    struct_wrappers::device::{MemoryInfo, Utilization},
+    structs::device::{FieldId, UtilizationInfo},
+    Device, Nvml,
+};
+use nvml_wrapper_sys::bindings::{
+    field_id::{
+        NVML_FI_DEV_MEMORY_TEMP, NVML_FI_DEV_NVLINK_THROUGHPUT_DATA_RX,
+        NVML_FI_DEV_NVLINK_THROUGHPUT_DATA_TX, NVML_FI_DEV_POWER_INSTANT,
+    },
+    nvmlFieldValue_t, nvmlMemory_v2_t, NvmlLib, NVML_DEVICE_MIG_ENABLE, NVML_FAN_POLICY_MANUAL,
+    NVML_POWER_SCOPE_GPU,
+};
+use std::cell::OnceCell;
+use std::fmt;
+
+/// The library `Nvml::init` loads, opened a second time for the queries nvml-wrapper
+/// doesn't wrap.
+#[cfg(target_os = "windows")]
+const NVML_LIB_PATH: &str = "nvml.dll";
+#[cfg(not(target_os = "windows"))]
+const NVML_LIB_PATH: &str = "libnvidia-ml.so";
+
+/// A media engine whose utilization newer drivers report, next to NVENC and NVDEC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaEngine {
+    Jpeg,
+    /// The optical flow accelerator.
+    Ofa,
+}
+
+impl MediaEngine {
+    pub const ALL: [MediaEngine; 2] = [MediaEngine::Jpeg, MediaEngine::Ofa];
+}
+
+impl fmt::Display for MediaEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MediaEngine::Jpeg => "JPEG",
+            MediaEngine::Ofa => "OFA",
+        })
+    }
+}
+
+/// Who sets the speed of a fan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FanPolicy {
+    /// The driver, following the temperature.
+    Auto,
+    /// A fixed speed set by the user, which the fan keeps however hot the GPU gets.
+    Manual,
+}
+
+/// Where GPU metrics come from: NVML on real hardware, or a scripted mock in tests.
+pub trait GpuSource {
+    fn device_count(&self) -> Result<u32, NvmlError>;
+    fn driver_version(&self) -> Result<String, NvmlError>;
+    fn nvml_version(&self) -> Result<String, NvmlError>;
+    /// The CUDA version supported by the driver, encoded as `1000 * major + 10 * minor`.
+    fn cuda_driver_version(&self) -> Result<i32, NvmlError>;
+
+    /// Looks up the GPU at hardware index `index`.
+    fn device(&self, index: u32) -> Result<&dyn GpuDevice, NvmlError>;
+
+    /// The number of MIG instances the GPU at `index` is split into, zero while MIG mode
+    /// is off or on cards without MIG support.
+    fn mig_device_count(&self, index: u32) -> Result<u32, NvmlError>;
+
+    /// Looks up MIG instance `mig_index` of the GPU at hardware index `index`.
+    fn mig_device(&self, index: u32, mig_index: u32) -> Result<&dyn GpuDevice, NvmlError>;
+
+    /// The overclock offsets in MHz applied to the graphics (GPC) and memory clocks of
+    /// the GPU at `index`.
+    fn clock_vf_offsets(&self, index: u32) -> Result<(i32, i32), NvmlError>;
+
+    /// The lowest and highest overclock offsets in MHz the graphics (GPC) and memory
+    /// clocks of the GPU at `index` accept, in that order. NVML only knows one offset for
+    /// the whole voltage-frequency curve, so there are no points of the curve to read.
+    fn clock_vf_offset_ranges(&self, index: u32) -> Result<[(i32, i32); 2], NvmlError>;
+
+    /// The bytes of memory the driver reserves for itself on the GPU at `index`, which
+    /// `memory_info` counts as used. Only newer drivers report it.
+    fn reserved_memory(&self, index: u32) -> Result<u64, NvmlError>;
+
+    /// The power in milliwatts the GPU at `index` draws on its own, without its memory
+    /// and the rest of the board that `power_usage` includes. Only newer drivers on some
+    /// datacenter cards report it.
+    fn die_power(&self, index: u32) -> Result<u32, NvmlError>;
+
+    /// Whether NVLink `link` of the GPU at `index` is up, and the data it carried. Links
+    /// the GPU doesn't have fail as unsupported or as an invalid argument.
+    fn nvlink_counters(&self, index: u32, link: u32) -> Result<NvLinkCounters, NvmlError>;
+
+    /// The utilization in percent of `engine` on the GPU at `index`.
+    fn engine_utilization(&self, index: u32, engine: MediaEngine) -> Result<u32, NvmlError>;
+
+    /// Who sets the speed of fan `fan` of the GPU at `index`. Only newer drivers report
+    /// it, on cards whose fans the driver controls.
+    fn fan_policy(&self, index: u32, fan: u32) -> Result<FanPolicy, NvmlError>;
+}
+
+/// The queries made against a single GPU, named after their NVML counterparts.
+pub trait GpuDevice {
+    fn name(&self) -> Result<String, NvmlError>;
+    fn uuid(&self) -> Result<String, NvmlError>;
+    /// The board serial number, which only some datacenter cards report.
+    fn serial(&self) -> Result<String, NvmlError>;
+    fn clock_info(&self, clock: Clock) -> Result<u32, NvmlError>;
+    fn max_clock_info(&self, clock: Clock) -> Result<u32, NvmlError>;
+    /// The width of the memory bus in bits.
+    fn memory_bus_width(&self) -> Result<u32, NvmlError>;
+    fn applications_clock(&self, clock: Clock) -> Result<u32, NvmlError>;
+    /// The application clock the GPU starts out with, which is its base clock.
+    fn default_applications_clock(&self, clock: Clock) -> Result<u32, NvmlError>;
+    fn temperature(&self, sensor: TemperatureSensor) -> Result<u32, NvmlError>;
+    /// The memory junction temperature in °C, which NVML only exposes as a field value
+    /// and only some cards report.
+    fn memory_temperature(&self) -> Result<u32, NvmlError>;
+    /// The temperature in °C at which the card starts to throttle or shuts down.
+    fn temperature_threshold(&self, threshold: TemperatureThreshold) -> Result<u32, NvmlError>;
+    fn memory_info(&self) -> Result<MemoryInfo, NvmlError>;
+    fn utilization_rates(&self) -> Result<Utilization, NvmlError>;
+    fn power_usage(&self) -> Result<u32, NvmlError>;
+    fn enforced_power_limit(&self) -> Result<u32, NvmlError>;
+    /// The lowest and highest power limit in milliwatts that can be set, which cards
+    /// without power management don't report.
+    fn power_limit_constraints(&self) -> Result<(u32, u32), NvmlError>;
+    fn performance_state(&self) -> Result<PerformanceState, NvmlError>;
+    /// Whether the card may boost its clocks above the base clock, which GeForce cards
+    /// don't report.
+    fn auto_boost_enabled(&self) -> Result<bool, NvmlError>;
+    /// Whether the driver stays loaded with no client, which only the Linux driver
+    /// reports. Without it, every NVML client waits for the driver to load.
+    fn persistence_mode_enabled(&self) -> Result<bool, NvmlError>;
+    /// Whether CUDA contexts may be created on the GPU, and how many at a time.
+    fn compute_mode(&self) -> Result<ComputeMode, NvmlError>;
+    fn current_throttle_reasons(&self) -> Result<ThrottleReasons, NvmlError>;
+    fn pcie_throughput(&self, counter: PcieUtilCounter) -> Result<u32, NvmlError>;
+    fn encoder_capacity(&self, for_type: EncoderType) -> Result<u32, NvmlError>;
+    fn encoder_utilization(&self) -> Result<UtilizationInfo, NvmlError>;
+    fn decoder_utilization(&self) -> Result<UtilizationInfo, NvmlError>;
+    fn num_fans(&self) -> Result<u32, NvmlError>;
+    fn fan_speed(&self, fan: u32) -> Result<u32, NvmlError>;
+    fn total_ecc_errors(
+        &self,
+        error_type: MemoryError,
+        counter_type: EccCounter,
+    ) -> Result<u64, NvmlError>;
+    /// The compute and graphics processes running on the GPU, largest memory user first.
+    fn running_processes(&self) -> Result<Vec<GpuProcess>, NvmlError>;
+    /// The samples of `sampling` the driver buffered after the CPU timestamp `since`, in
+    /// µs, as timestamps and values in the unit of the matching instantaneous query.
+    fn samples(&self, sampling: Sampling, since: u64) -> Result<Vec<(u64, u32)>, NvmlError>;
+}
+
+/// A sample of a metric that NVML reports as a whole number of some width.
+fn sample_u32(value: SampleValue) -> u32 {
+    match value {
+        SampleValue::U32(value) => value,
+        SampleValue::U64(value) => value as u32,
+        SampleValue::I64(value) => value as u32,
+        SampleValue::F64(value) => value as u32,
+    }
+}
+
+/// The GPUs of an initialized NVML instance.
+///
+/// Device handles are acquired on first use and reused on every later tick, since
+/// acquiring them is slow on some driver configurations. A lookup that fails is retried
+/// on the next tick. MIG instances are enumerated once per GPU, so reconfiguring MIG
+/// requires a restart.
+pub struct NvmlSource<'nvml> {
+    nvml: &'nvml Nvml,
+    /// For the MIG, clock offset and media engine queries, or `None` if the library
+    /// couldn't be opened again, in which case every GPU is monitored as a whole without
+    /// offsets or media engines.
+    lib: Option<NvmlLib>,
+    devices: Vec<OnceCell<Device<'nvml>>>,
+    mig_devices: Vec<OnceCell<Vec<Device<'nvml>>>>,
+}
+
+impl<'nvml> NvmlSource<'nvml> {
+    pub fn new(nvml: &'nvml Nvml) -> Result<Self, NvmlError> {
+        let device_count = nvml.device_count()?;
+        // Opening a library that is already loaded only adds a reference to it
+        let lib = unsafe { NvmlLib::new(NVML_LIB_PATH) }
+            .map_err(|error| debug!("Queries missing from nvml-wrapper unavailable: {}", error))
+            .ok();
+        Ok(Self {
+            nvml,
+            lib,
+            devices: (0..device_count).map(|_| OnceCell::new()).collect(),
+            mig_devices: (0..device_count).map(|_| OnceCell::new()).collect(),
+        })
+    }
+
+    fn nvml_device(&self, index: u32) -> Result<&Device<'nvml>, NvmlError> {
+        let cached = self
+            .devices
+            .get(index as usize)
+            .ok_or(NvmlError::InvalidArg)?;
+        if let Some(device) = cached.get() {
+            return Ok(device);
+        }
+        let device = self.nvml.device_by_index(index)?;
+        Ok(cached.get_or_init(|| device))
+    }
+
+    fn nvml_mig_devices(&self, index: u32) -> Result<&[Device<'nvml>], NvmlError> {
+        let cached = self
+            .mig_devices
+            .get(index as usize)
+            .ok_or(NvmlError::InvalidArg)?;
+        if let Some(mig_devices) = cached.get() {
+            return Ok(mig_devices);
+        }
+        let mig_devices = self.read_mig_devices(self.nvml_device(index)?)?;
+        Ok(cached.get_or_init(|| mig_devices))
+    }
+
+    /// Enumerates the MIG instances of `parent`, or none while MIG mode is off.
+    fn read_mig_devices(&self, parent: &Device<'nvml>) -> Result<Vec<Device<'nvml>>, NvmlError> {
+        let Some(lib) = &self.lib else {
+            return Ok(Vec::new());
+        };
+        // Safety: the handles come from the NVML instance `self.nvml` keeps initialized,
+        // and the MIG handles NVML returns live as long as their parent
+        unsafe {
+            let handle = parent.handle();
+            let get_mig_mode = nvml_sym(lib.nvmlDeviceGetMigMode.as_ref())?;
+            let (mut current_mode, mut pending_mode) = (0, 0);
+            match nvml_try(get_mig_mode(handle, &mut current_mode, &mut pending_mode)) {
+                Err(NvmlError::NotSupported) => return Ok(Vec::new()),
+                result => result?,
+            }
+            if current_mode != NVML_DEVICE_MIG_ENABLE {
+                return Ok(Vec::new());
+            }
+
+            let get_max_count = nvml_sym(lib.nvmlDeviceGetMaxMigDeviceCount.as_ref())?;
+            let mut max_count = 0;
+            nvml_try(get_max_count(handle, &mut max_count))?;
+
+            let get_mig_device = nvml_sym(lib.nvmlDeviceGetMigDeviceHandleByIndex.as_ref())?;
+            let mut mig_devices = Vec::new();
+            for mig_index in 0..max_count {
+                let mut mig_handle = std::ptr::null_mut();
+                // Slots that aren't carved into an instance report NotFound
+                match nvml_try(get_mig_device(handle, mig_index, &mut mig_handle)) {
+                    Ok(()) => mig_devices.push(Device::new(mig_handle, self.nvml)),
+                    Err(NvmlError::NotFound) => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            Ok(mig_devices)
+        }
+    }
+}
+
+impl GpuSource for NvmlSource<'_> {
+    fn device_count(&self) -> Result<u32, NvmlError> {
+        Ok(self.devices.len() as u32)
+    }
+
+    fn driver_version(&self) -> Result<String, NvmlError> {
+        self.nvml.sys_driver_version()
+    }
+
+    fn nvml_version(&self) -> Result<String, NvmlError> {
+        self.nvml.sys_nvml_version()
+    }
+
+    fn cuda_driver_version(&self) -> Result<i32, NvmlError> {
+        self.nvml.sys_cuda_driver_version()
+    }
+
+    fn device(&self, index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        Ok(self.nvml_device(index)?)
+    }
+
+    fn mig_device_count(&self, index: u32) -> Result<u32, NvmlError> {
+        Ok(self.nvml_mig_devices(index)?.len() as u32)
+    }
+
+    fn mig_device(&self, index: u32, mig_index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        self.nvml_mig_devices(index)?
+            .get(mig_index as usize)
+            .map(|mig_device| mig_device as &dyn GpuDevice)
+            .ok_or(NvmlError::InvalidArg)
+    }
+
+    fn clock_vf_offsets(&self, index: u32) -> Result<(i32, i32), NvmlError> {
+        let lib = self.lib.as_ref().ok_or(NvmlError::NotSupported)?;
+        let device = self.nvml_device(index)?;
+        // Safety: the handle comes from the NVML instance `self.nvml` keeps initialized
+        unsafe {
+            let handle = device.handle();
+            let get_gpc_offset = nvml_sym(lib.nvmlDeviceGetGpcClkVfOffset.as_ref())?;
+            let get_memory_offset = nvml_sym(lib.nvmlDeviceGetMemClkVfOffset.as_ref())?;
+            let (mut gpc_offset, mut memory_offset) = (0, 0);
+            nvml_try(get_gpc_offset(handle, &mut gpc_offset))?;
+            nvml_try(get_memory_offset(handle, &mut memory_offset))?;
+            Ok((gpc_offset, memory_offset))
+        }
+    }
+
+    fn clock_vf_offset_ranges(&self, index: u32) -> Result<[(i32, i32); 2], NvmlError> {
+        let lib = self.lib.as_ref().ok_or(NvmlError::NotSupported)?;
+        let device = self.nvml_device(index)?;
+        // Safety: the handle comes from the NVML instance `self.nvml` keeps initialized
+        unsafe {
+            let handle = device.handle();
+            let mut ranges = [(0, 0); 2];
+            for (range, get_range) in ranges.iter_mut().zip([
+                lib.nvmlDeviceGetGpcClkMinMaxVfOffset.as_ref(),
+                lib.nvmlDeviceGetMemClkMinMaxVfOffset.as_ref(),
+            ]) {
+                let get_range = nvml_sym(get_range)?;
+                nvml_try(get_range(handle, &mut range.0, &mut range.1))?;
+            }
+            Ok(ranges)
+        }
+    }
+
+    fn reserved_memory(&self, index: u32) -> Result<u64, NvmlError> {
+        let lib = self.lib.as_ref().ok_or(NvmlError::NotSupported)?;
+        let device = self.nvml_device(index)?;
+        // Safety: the handle comes from the NVML instance `self.nvml` keeps initialized
+        unsafe {
+            let get_memory_info = nvml_sym(lib.nvmlDeviceGetMemoryInfo_v2.as_ref())?;
+            let mut memory: nvmlMemory_v2_t = std::mem::zeroed();
+            // NVML_STRUCT_VERSION(Memory, 2)
+            memory.version = std::mem::size_of::<nvmlMemory_v2_t>() as u32 | 2 << 24;
+            nvml_try(get_memory_info(device.handle(), &mut memory))?;
+            Ok(memory.reserved)
+        }
+    }
+
+    fn die_power(&self, index: u32) -> Result<u32, NvmlError> {
+        let lib = self.lib.as_ref().ok_or(NvmlError::NotSupported)?;
+        let device = self.nvml_device(index)?;
+        // Safety: the handle comes from the NVML instance `self.nvml` keeps initialized.
+        // nvml-wrapper can't set the scope of a field value, which selects the die here.
+        unsafe {
+            let get_field_values = nvml_sym(lib.nvmlDeviceGetFieldValues.as_ref())?;
+            let mut value: nvmlFieldValue_t = std::mem::zeroed();
+            value.fieldId = NVML_FI_DEV_POWER_INSTANT;
+            value.scopeId = NVML_POWER_SCOPE_GPU;
+            nvml_try(get_field_values(device.handle(), 1, &mut value))?;
+            nvml_try(value.nvmlReturn)?;
+            Ok(value.value.uiVal)
+        }
+    }
+
+    fn nvlink_counters(&self, index: u32, link: u32) -> Result<NvLinkCounters, NvmlError> {
+        let device = self.nvml_device(index)?;
+        let active = device.link_wrapper_for(link).is_active()?;
+        let lib = self.lib.as_ref().ok_or(NvmlError::NotSupported)?;
+        // Safety: the handle comes from the NVML instance `self.nvml` keeps initialized.
+        // nvml-wrapper can't set the scope of a field value, which selects the link here.
+        unsafe {
+            let get_field_values = nvml_sym(lib.nvmlDeviceGetFieldValues.as_ref())?;
+            let mut values: [nvmlFieldValue_t; 2] = std::mem::zeroed();
+            for (value, field) in values.iter_mut().zip([
+                NVML_FI_DEV_NVLINK_THROUGHPUT_DATA_RX,
+                NVML_FI_DEV_NVLINK_THROUGHPUT_DATA_TX,
+            ]) {
+                value.fieldId = field;
+                value.scopeId = link;
+            }
+            nvml_try(get_field_values(
+                device.handle(),
+                values.len() as i32,
+                values.as_mut_ptr(),
+            ))?;
+            for value in &values {
+                nvml_try(value.nvmlReturn)?;
+            }
+            Ok(NvLinkCounters {
+                active,
+                rx: values[0].value.ullVal,
+                tx: values[1].value.ullVal,
+            })
+        }
+    }
+
+    fn engine_utilization(&self, index: u32, engine: MediaEngine) -> Result<u32, NvmlError> {
+        let lib = self.lib.as_ref().ok_or(NvmlError::NotSupported)?;
+        let device = self.nvml_device(index)?;
+        // Safety: the handle comes from the NVML instance `self.nvml` keeps initialized.
+        // Drivers older than the query fail to load its symbol.
+        unsafe {
+            let get_utilization = nvml_sym(match engine {
+                MediaEngine::Jpeg => lib.nvmlDeviceGetJpgUtilization.as_ref(),
+                MediaEngine::Ofa => lib.nvmlDeviceGetOfaUtilization.as_ref(),
+            })?;
+            let (mut utilization, mut sampling_period) = (0, 0);
+            nvml_try(get_utilization(
+                device.handle(),
+                &mut utilization,
+                &mut sampling_period,
+            ))?;
+            Ok(utilization)
+        }
+    }
+
+    fn fan_policy(&self, index: u32, fan: u32) -> Result<FanPolicy, NvmlError> {
+        let lib = self.lib.as_ref().ok_or(NvmlError::NotSupported)?;
+        let device = self.nvml_device(index)?;
+        // Safety: the handle comes from the NVML instance `self.nvml` keeps initialized.
+        // Drivers older than the query fail to load its symbol.
+        unsafe {
+            let get_policy = nvml_sym(lib.nvmlDeviceGetFanControlPolicy_v2.as_ref())?;
+            let mut policy = 0;
+            nvml_try(get_policy(device.handle(), fan, &mut policy))?;
+            Ok(match policy {
+                NVML_FAN_POLICY_MANUAL => FanPolicy::Manual,
+                _ => FanPolicy::Auto,
+            })
+        }
+    }
+}
+
+impl GpuDevice for Device<'_> {
+    fn name(&self) -> Result<String, NvmlError> {
+        Device::name(self)
+    }
+
+    fn uuid(&self) -> Result<String, NvmlError> {
+        Device::uuid(self)
+    }
+
+    fn serial(&self) -> Result<String, NvmlError> {
+        Device::serial(self)
+    }
+
+    fn clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        Device::clock_info(self, clock)
+    }
+
+    fn max_clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        Device::max_clock_info(self, clock)
+    }
+
+    fn memory_bus_width(&self) -> Result<u32, NvmlError> {
+        Device::memory_bus_width(self)
+    }
+
+    fn applications_clock(&self, clock: Clock) -> Result<u32, NvmlError> {
+        Device::applications_clock(self, clock)
+    }
+
+    fn default_applications_clock(&self, clock: Clock) -> Result<u32, NvmlError> {
+        Device::default_applications_clock(self, clock)
+    }
+
+    fn temperature(&self, sensor: TemperatureSensor) -> Result<u32, NvmlError> {
+        Device::temperature(self, sensor)
+    }
+
+    fn temperature_threshold(&self, threshold: TemperatureThreshold) -> Result<u32, NvmlError> {
+        Device::temperature_threshold(self, threshold)
+    }
+
+    fn memory_temperature(&self) -> Result<u32, NvmlError> {
+        let sample = self
+            .field_values_for(&[FieldId(NVML_FI_DEV_MEMORY_TEMP)])?
+            .pop()
+            .ok_or(NvmlError::NotSupported)??;
+        Ok(sample_u32(sample.value?))
+    }
+
+    fn memory_info(&self) -> Result<MemoryInfo, NvmlError> {
+        Device::memory_info(self)
+    }
+
+    fn utilization_rates(&self) -> Result<Utilization, NvmlError> {
+        Device::utilization_rates(self)
+    }
+
+    fn power_usage(&self) -> Result<u32, NvmlError> {
+        Device::power_usage(self)
+    }
+
+    fn enforced_power_limit(&self) -> Result<u32, NvmlError> {
+        Device::enforced_power_limit(self)
+    }
+
+    fn power_limit_constraints(&self) -> Result<(u32, u32), NvmlError> {
+        Device::power_management_limit_constraints(self)
+            .map(|constraints| (constraints.min_limit, constraints.max_limit))
+    }
+
+    fn performance_state(&self) -> Result<PerformanceState, NvmlError> {
+        Device::performance_state(self)
+    }
+
+    fn auto_boost_enabled(&self) -> Result<bool, NvmlError> {
+        Device::auto_boosted_clocks_enabled(self).map(|auto_boost| auto_boost.is_enabled)
+    }
+
+    fn persistence_mode_enabled(&self) -> Result<bool, NvmlError> {
+        Device::is_in_persistent_mode(self)
+    }
+
+    fn compute_mode(&self) -> Result<ComputeMode, NvmlError> {
+        Device::compute_mode(self)
+    }
+
+    fn current_throttle_reasons(&self) -> Result<ThrottleReasons, NvmlError> {
+        Device::current_throttle_reasons(self)
+    }
+
+    fn pcie_throughput(&self, counter: PcieUtilCounter) -> Result<u32, NvmlError> {
+        Device::pcie_throughput(self, counter)
+    }
+
+    fn encoder_capacity(&self, for_type: EncoderType) -> Result<u32, NvmlError> {
+        Device::encoder_capacity(self, for_type)
+    }
+
+    fn encoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Device::encoder_utilization(self)
+    }
+
+    fn decoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Device::decoder_utilization(self)
+    }
+
+    fn num_fans(&self) -> Result<u32, NvmlError> {
+        Device::num_fans(self)
+    }
+
+    fn fan_speed(&self, fan: u32) -> Result<u32, NvmlError> {
+        Device::fan_speed(self, fan)
+    }
+
+    fn total_ecc_errors(
+        &self,
+        error_type: MemoryError,
+        counter_type: EccCounter,
+    ) -> Result<u64, NvmlError> {
+        Device::total_ecc_errors(self, error_type, counter_type)
+    }
+
+    fn running_processes(&self) -> Result<Vec<GpuProcess>, NvmlError> {
+        process::running_processes(self)
+    }
+
+    fn samples(&self, sampling: Sampling, since: u64) -> Result<Vec<(u64, u32)>, NvmlError> {
+        let samples = Device::samples(self, sampling, since)?;
+        Ok(samples
+            .into_iter()
+            .map(|sample| (sample.timestamp, sample_u32(sample.value)))
+            .collect())
+    }
+}
+
+/// A scripted stand-in for NVML, so the collection and rendering paths can be
+/// exercised on machines without an NVIDIA GPU.
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use crate::ecc::EccErrors;
+
+    /// Serves the devices in order of their hardware index.
+    #[derive(Debug, Default)]
+    pub struct MockSource {
+        pub devices: Vec<MockDevice>,
+    }
+
+    /// The values one mock GPU reports. `None` fails the query as unsupported, and a
+    /// lost device fails every lookup; tests change the fields between ticks.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockDevice {
+        pub name: String,
+        pub uuid: Option<String>,
+        pub serial: Option<String>,
+        /// Reported for every clock domain.
+        pub clock: Option<u32>,
+        /// Reported as the maximum of every clock domain.
+        pub max_clock: Option<u32>,
+        pub memory_bus_width: Option<u32>,
+        /// Reported as the application clock of every clock domain.
+        pub applications_clock: Option<u32>,
+        /// Reported as the default application clock of every clock domain.
+        pub base_clock: Option<u32>,
+        pub temperature: Option<u32>,
+        /// `None` by default, like the many cards without a memory temperature sensor.
+        pub memory_temperature: Option<u32>,
+        /// The slowdown and shutdown temperatures, `None` by default like on cards that
+        /// don't report them.
+        pub temperature_thresholds: Option<(u32, u32)>,
+        pub memory_used: Option<u64>,
+        pub memory_total: u64,
+        /// The part of `memory_used` the driver reserves, `None` on older drivers.
+        pub memory_reserved: Option<u64>,
+        pub utilization: Option<(u32, u32)>,
+        pub power_usage: Option<u32>,
+        /// `None` by default, like cards that only report the board power.
+        pub die_power: Option<u32>,
+        pub power_limit: Option<u32>,
+        /// `None` by default, like cards without power management.
+        pub power_limit_constraints: Option<(u32, u32)>,
+        pub performance_state: Option<PerformanceState>,
+        pub auto_boost: Option<bool>,
+        pub persistence_mode: Option<bool>,
+        pub compute_mode: Option<ComputeMode>,
+        pub throttle_reasons: Option<ThrottleReasons>,
+        pub pcie_throughput: Option<(u32, u32)>,
+        /// Encoder and decoder utilization in percent.
+        pub codec_utilization: Option<(u32, u32)>,
+        pub fan_speeds: Vec<u32>,
+        /// Reported for every fan, `None` by default like on older drivers.
+        pub fan_policy: Option<FanPolicy>,
+        pub ecc_errors: Option<EccErrors>,
+        pub processes: Option<Vec<GpuProcess>>,
+        /// The MIG instances the GPU is split into.
+        pub mig_devices: Vec<MockDevice>,
+        /// The graphics and memory clock offsets in MHz.
+        pub clock_vf_offsets: Option<(i32, i32)>,
+        /// The lowest and highest graphics and memory clock offsets in MHz.
+        pub clock_vf_offset_ranges: Option<[(i32, i32); 2]>,
+        pub jpeg_utilization: Option<u32>,
+        /// One entry per NVLink, empty on cards without NVLink.
+        pub nvlinks: Vec<NvLinkCounters>,
+        pub ofa_utilization: Option<u32>,
+        /// The buffered SM utilization and power samples, `None` like on drivers that
+        /// don't buffer them.
+        pub utilization_samples: Option<Vec<(u64, u32)>>,
+        pub power_samples: Option<Vec<(u64, u32)>>,
+        pub lost: bool,
+    }
+
+    impl MockDevice {
+        /// A device that supports every metric, with all readings at zero.
+        pub fn new(name: &str) -> Self {
+            Self {
+                name: String::from(name),
+                clock: Some(0),
+                temperature: Some(0),
+                memory_used: Some(0),
+                utilization: Some((0, 0)),
+                power_usage: Some(0),
+                throttle_reasons: Some(ThrottleReasons::NONE),
+                pcie_throughput: Some((0, 0)),
+                codec_utilization: Some((0, 0)),
+                ecc_errors: Some(EccErrors::default()),
+                processes: Some(Vec::new()),
+                ..Self::default()
+            }
+        }
+    }
+
+    fn scripted<T: Clone>(value: &Option<T>) -> Result<T, NvmlError> {
+        value.clone().ok_or(NvmlError::NotSupported)
+    }
+
+    impl GpuSource for MockSource {
+        fn device_count(&self) -> Result<u32, NvmlError> {
+            Ok(self.devices.len() as u32)
+        }
+
+        fn driver_version(&self) -> Result<String, NvmlError> {
+            Ok(String::from("550.54.14"))
+        }
+
+        fn nvml_version(&self) -> Result<String, NvmlError> {
+            Ok(String::from("12.550.54.14"))
+        }
+
+        fn cuda_driver_version(&self) -> Result<i32, NvmlError> {
+            Ok(12040)
+        }
+
+        fn device(&self, index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+            match self.devices.get(index as usize) {
+                Some(device) if device.lost => Err(NvmlError::GpuLost),
+                Some(device) => Ok(device),
+                None => Err(NvmlError::InvalidArg),
+            }
+        }
+
+        fn mig_device_count(&self, index: u32) -> Result<u32, NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            Ok(device.mig_devices.len() as u32)
+        }
+
+        fn mig_device(&self, index: u32, mig_index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            match device.mig_devices.get(mig_index as usize) {
+                Some(mig_device) if device.lost || mig_device.lost => Err(NvmlError::GpuLost),
+                Some(mig_device) => Ok(mig_device),
+                None => Err(NvmlError::InvalidArg),
+            }
+        }
+
+        fn clock_vf_offsets(&self, index: u32) -> Result<(i32, i32), NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            scripted(&device.clock_vf_offsets)
+        }
+
+        fn clock_vf_offset_ranges(&self, index: u32) -> Result<[(i32, i32); 2], NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            scripted(&device.clock_vf_offset_ranges)
+        }
+
+        fn reserved_memory(&self, index: u32) -> Result<u64, NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            scripted(&device.memory_reserved)
+        }
+
+        fn die_power(&self, index: u32) -> Result<u32, NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            scripted(&device.die_power)
+        }
+
+        fn nvlink_counters(&self, index: u32, link: u32) -> Result<NvLinkCounters, NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            device
+                .nvlinks
+                .get(link as usize)
+                .copied()
+                .ok_or(NvmlError::NotSupported)
+        }
+
+        fn engine_utilization(&self, index: u32, engine: MediaEngine) -> Result<u32, NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            scripted(match engine {
+                MediaEngine::Jpeg => &device.jpeg_utilization,
+                MediaEngine::Ofa => &device.ofa_utilization,
+            })
+        }
+
+        fn fan_policy(&self, index: u32, fan: u32) -> Result<FanPolicy, NvmlError> {
+            let device = self
+                .devices
+                .get(index as usize)
+                .ok_or(NvmlError::InvalidArg)?;
+            if fan as usize >= device.fan_speeds.len() {
+                return Err(NvmlError::InvalidArg);
+            }
+            scripted(&device.fan_policy)
+        }
+    }
+
+    impl GpuDevice for MockDevice {
+        fn name(&self) -> Result<String, NvmlError> {
+            Ok(self.name.clone())
+        }
+
+        fn uuid(&self) -> Result<String, NvmlError> {
+            scripted(&self.uuid)
+        }
+
+        fn serial(&self) -> Result<String, NvmlError> {
+            scripted(&self.serial)
+        }
+
+        fn clock_info(&self, _clock: Clock) -> Result<u32, NvmlError> {
+            scripted(&self.clock)
+        }
+
+        fn max_clock_info(&self, _clock: Clock) -> Result<u32, NvmlError> {
+            scripted(&self.max_clock)
+        }
+
+        fn memory_bus_width(&self) -> Result<u32, NvmlError> {
+            scripted(&self.memory_bus_width)
+        }
+
+        fn applications_clock(&self, _clock: Clock) -> Result<u32, NvmlError> {
+            scripted(&self.applications_clock)
+        }
+
+        fn default_applications_clock(&self, _clock: Clock) -> Result<u32, NvmlError> {
+            scripted(&self.base_clock)
+        }
+
+        fn temperature(&self, _sensor: TemperatureSensor) -> Result<u32, NvmlError> {
+            scripted(&self.temperature)
+        }
+
+        fn memory_temperature(&self) -> Result<u32, NvmlError> {
+            scripted(&self.memory_temperature)
+        }
+
+        fn temperature_threshold(&self, threshold: TemperatureThreshold) -> Result<u32, NvmlError> {
+            let (slowdown, shutdown) = scripted(&self.temperature_thresholds)?;
+            match threshold {
+                TemperatureThreshold::Slowdown => Ok(slowdown),
+                TemperatureThreshold::Shutdown => Ok(shutdown),
+                _ => Err(NvmlError::NotSupported),
+            }
+        }
+
+        fn memory_info(&self) -> Result<MemoryInfo, NvmlError> {
+            let used = scripted(&self.memory_used)?;
+            Ok(MemoryInfo {
+                free: self.memory_total.saturating_sub(used),
+                total: self.memory_total,
+                used,
+            })
+        }
+
+        fn utilization_rates(&self) -> Result<Utilization, NvmlError> {
+            let (gpu, memory) = scripted(&self.utilization)?;
+            Ok(Utilization { gpu, memory })
+        }
+
+        fn power_usage(&self) -> Result<u32, NvmlError> {
+            scripted(&self.power_usage)
+        }
+
+        fn enforced_power_limit(&self) -> Result<u32, NvmlError> {
+            scripted(&self.power_limit)
+        }
+
+        fn power_limit_constraints(&self) -> Result<(u32, u32), NvmlError> {
+            scripted(&self.power_limit_constraints)
+        }
+
+        fn performance_state(&self) -> Result<PerformanceState, NvmlError> {
+            scripted(&self.performance_state)
+        }
+
+        fn auto_boost_enabled(&self) -> Result<bool, NvmlError> {
+            scripted(&self.auto_boost)
+        }
+
+        fn persistence_mode_enabled(&self) -> Result<bool, NvmlError> {
+            scripted(&self.persistence_mode)
+        }
+
+        fn compute_mode(&self) -> Result<ComputeMode, NvmlError> {
+            scripted(&self.compute_mode)
+        }
+
+        fn current_throttle_reasons(&self) -> Result<ThrottleReasons, NvmlError> {
+            scripted(&self.throttle_reasons)
+        }
+
+        fn pcie_throughput(&self, counter: PcieUtilCounter) -> Result<u32, NvmlError> {
+            let (rx, tx) = scripted(&self.pcie_throughput)?;
+            Ok(match counter {
+                PcieUtilCounter::Receive => rx,
+                PcieUtilCounter::Send => tx,
+            })
+        }
+
+        fn encoder_capacity(&self, _for_type: EncoderType) -> Result<u32, NvmlError> {
+            scripted(&self.codec_utilization).map(|(encoder, _)| 100 - encoder.min(100))
+        }
+
+        fn encoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+            let (utilization, _) = scripted(&self.codec_utilization)?;
+            Ok(UtilizationInfo {
+                utilization,
+                sampling_period: 100_000,
+            })
+        }
+
+        fn decoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+            let (_, utilization) = scripted(&self.codec_utilization)?;
+            Ok(UtilizationInfo {
+                utilization,
+                sampling_period: 100_000,
+            })
+        }
+
+        fn num_fans(&self) -> Result<u32, NvmlError> {
+            Ok(self.fan_speeds.len() as u32)
+        }
+
+        fn fan_speed(&self, fan: u32) -> Result<u32, NvmlError> {
+            self.fan_speeds
+                .get(fan as usize)
+                .copied()
+                .ok_or(NvmlError::InvalidArg)
+        }
+
+        fn total_ecc_errors(
+            &self,
+            error_type: MemoryError,
+            counter_type: EccCounter,
+        ) -> Result<u64, NvmlError> {
+            let ecc = scripted(&self.ecc_errors)?;
+            Ok(match (error_type, counter_type) {
+                (MemoryError::Corrected, EccCounter::Volatile) => ecc.volatile_corrected,
+                (MemoryError::Uncorrected, EccCounter::Volatile) => ecc.volatile_uncorrected,
+                (MemoryError::Corrected, EccCounter::Aggregate) => ecc.aggregate_corrected,
+                (_, _) => ecc.aggregate_uncorrected,
+            })
+        }
+
+        fn running_processes(&self) -> Result<Vec<GpuProcess>, NvmlError> {
+            scripted(&self.processes)
+        }
+
+        fn samples(&self, sampling: Sampling, since: u64) -> Result<Vec<(u64, u32)>, NvmlError> {
+            let samples = scripted(match sampling {
+                Sampling::GpuUtilization => &self.utilization_samples,
+                Sampling::Power => &self.power_samples,
+                _ => &None,
+            })?;
+            Ok(samples
+                .into_iter()
+                .filter(|&(timestamp, _)| timestamp > since)
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,528 @@
+use crate::history::{History, Stats};
+use crate::metric::{ClockDomain, Metric, TemperatureUnit};
+use crate::theme::Theme;
+use crate::{ChartOptions, GPUInfo};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+use std::time::Duration;
+
+/// Charts shorter than this keep all their rows rather than giving one up to the footer.
+const MIN_CHART_HEIGHT: u16 = 6;
+
+/// How many standard deviations from the mean a sample is flagged as an anomaly at,
+/// without `--anomaly-sigma`.
+pub const DEFAULT_ANOMALY_SIGMA: f64 = 3.0;
+
+/// How far back the rate of change of the temperature looks.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// The metrics compared against the mean of every GPU, each with how far from the mean
+/// a GPU is flagged as an outlier, in MHz, °C, percent and W.
+pub const DEVIATION_METRICS: [(Metric, f64); 4] = [
+    (Metric::Clock, 200.0),
+    (Metric::Temperature, 10.0),
+    (Metric::Utilization, 25.0),
+    (Metric::Power, 50.0),
+];
+
+/// How far the latest reading of one GPU is from the mean of every GPU.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    /// In MHz, °C, percent or W.
+    pub value: f64,
+    /// Whether it's far enough from the mean to flag the GPU.
+    pub outlier: bool,
+}
+
+/// How far the latest reading of `metric` on `gpu_info` is from the mean of the latest
+/// readings of `gpus`, or `None` unless at least two GPUs have one to compare. MIG
+/// instances are left out of the mean, since they'd count their GPU again.
+pub fn deviation(
+    gpus: &[GPUInfo],
+    gpu_info: &GPUInfo,
+    metric: Metric,
+    clock_domain: ClockDomain,
+) -> Option<Deviation> {
+    let &(_, limit) = DEVIATION_METRICS
+        .iter()
+        .find(|(compared, _)| *compared == metric)?;
+    let reading = |gpu: &GPUInfo| {
+        if !gpu.available(metric) {
+            return None;
+        }
+        Some(match metric {
+            Metric::Clock => gpu.clock(clock_domain).latest() as f64,
+            Metric::Temperature => gpu.temperature.latest() as f64,
+            Metric::Utilization => gpu.gpu_utilization.latest() as f64,
+            _ => gpu.power_usage.latest() as f64 / 1000.0,
+        })
+    };
+    let readings: Vec<f64> = gpus
+        .iter()
+        .filter(|gpu| gpu.mig_index.is_none())
+        .filter_map(reading)
+        .collect();
+    if readings.len() < 2 {
+        return None;
+    }
+    let mean = readings.iter().sum::<f64>() / readings.len() as f64;
+    let value = reading(gpu_info)? - mean;
+    Some(Deviation {
+        value,
+        outlier: value.abs() >= limit,
+    })
+}
+
+/// Writes a [`Deviation`] of `metric`, such as `+12 °C vs mean`.
+fn deviation_text(gpu_info: &GPUInfo, metric: Metric, deviation: Deviation) -> String {
+    let value = deviation.value;
+    match metric {
+        Metric::Clock => format!("{:+.0} MHz vs mean", value),
+        Metric::Temperature => {
+            let unit = gpu_info.temperature_unit;
+            format!(
+                "{:+.0} {} vs mean",
+                unit.convert_difference(value),
+                unit.symbol()
+            )
+        }
+        Metric::Utilization => format!("{:+.0}% vs mean", value),
+        _ => format!("{:+.0} W vs mean", value),
+    }
+}
+
+/// Splits the one-row statistics footer off the bottom of a chart area, if the chart is
+/// tall enough to spare it. Returns the area left for the chart and the footer area.
+pub fn split_footer(area: Rect) -> (Rect, Option<Rect>) {
+    if area.height < MIN_CHART_HEIGHT {
+        return (area, None);
+    }
+    let [chart_area, footer_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+    (chart_area, Some(footer_area))
+}
+
+/// The min, mean and max of every series drawn in the chart of `metric`, each with the
+/// name it is listed under (empty for single-series charts) and its unit.
+fn chart_stats(
+    gpu_info: &GPUInfo,
+    metric: Metric,
+    clock_domain: ClockDomain,
+) -> Vec<(String, Stats, &'static str)> {
+    let series = |history: &History<u32>| history.stats(|value| value as f64);
+    match metric {
+        Metric::Clock => vec![(
+            String::new(),
+            gpu_info.clock(clock_domain).stats(|clock| clock as f64),
+            " MHz",
+        )],
+        Metric::Temperature => {
+            let unit = gpu_info.temperature_unit;
+            let symbol = match unit {
+                TemperatureUnit::Celsius => " °C",
+                TemperatureUnit::Fahrenheit => " °F",
+            };
+            let temperature =
+                |history: &History<u32>| history.stats(|temp| unit.convert(temp as f64));
+            match &gpu_info.memory_temperature {
+                Some(memory_temperature) => vec![
+                    (
+                        String::from("GPU"),
+                        temperature(&gpu_info.temperature),
+                        symbol,
+                    ),
+                    (String::from("Mem"), temperature(memory_temperature), symbol),
+                ],
+                None => vec![(String::new(), temperature(&gpu_info.temperature), symbol)],
+            }
+        }
+        Metric::Memory => vec![(
+            String::new(),
+            gpu_info
+                .memory_used
+                .stats(|used| (used / 1024 / 1024) as f64),
+            " MiB",
+        )],
+        Metric::Utilization => vec![
+            (String::from("SM"), series(&gpu_info.gpu_utilization), "%"),
+            (
+                String::from("Mem"),
+                series(&gpu_info.memory_utilization),
+                "%",
+            ),
+        ],
+        Metric::Power => vec![(
+            String::new(),
+            // In mW, since the footer rounds power like the title
+            gpu_info.power_usage.stats(|power| power as f64),
+            " W",
+        )],
+        Metric::Pcie => vec![
+            (String::from("RX"), series(&gpu_info.pcie_rx), " KB/s"),
+            (String::from("TX"), series(&gpu_info.pcie_tx), " KB/s"),
+        ],
+        Metric::Codec => vec![
+            (
+                String::from("Enc"),
+                series(&gpu_info.encoder_utilization),
+                "%",
+            ),
+            (
+                String::from("Dec"),
+                series(&gpu_info.decoder_utilization),
+                "%",
+            ),
+        ],
+        Metric::Fan => gpu_info
+            .fan_speeds
+            .iter()
+            .enumerate()
+            .map(|(fan, speed)| (format!("Fan {}", fan), series(speed), "%"))
+            .collect(),
+        Metric::Engines => gpu_info
+            .engine_utilization
+            .iter()
+            .map(|(engine, utilization)| (engine.to_string(), series(utilization), "%"))
+            .collect(),
+        Metric::Processes | Metric::Throttle | Metric::Ecc | Metric::NvLink => Vec::new(),
+    }
+}
+
+/// The footer text of the chart of `metric`, such as `min 35 avg 41 max 60 °C`. Series
+/// of multi-series charts are prefixed with their name and separated by `|`.
+pub fn stats_line(gpu_info: &GPUInfo, metric: Metric, clock_domain: ClockDomain) -> String {
+    chart_stats(gpu_info, metric, clock_domain)
+        .into_iter()
+        .map(|(name, stats, unit)| {
+            let number = |value: f64| match metric {
+                Metric::Power => gpu_info.precision.watts_number(value),
+                _ => format!("{:.0}", value),
+            };
+            let summary = format!(
+                "min {} avg {} max {}{}",
+                number(stats.min),
+                number(stats.mean),
+                number(stats.max),
+                unit
+            );
+            if name.is_empty() {
+                summary
+            } else {
+                format!("{} {}", name, summary)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// The readings of every series drawn in the chart of `metric`, `back` samples before
+/// the newest, each with the name it is listed under (empty for single-series charts)
+/// and its unit. Gaps are `None`.
+fn chart_readings(
+    gpu_info: &GPUInfo,
+    metric: Metric,
+    clock_domain: ClockDomain,
+    back: usize,
+) -> Vec<(String, Option<f64>, &'static str)> {
+    let reading = |history: &History<u32>| history.ago(back).map(f64::from);
+    match metric {
+        Metric::Clock => vec![(String::new(), reading(gpu_info.clock(clock_domain)), " MHz")],
+        Metric::Temperature => {
+            let unit = gpu_info.temperature_unit;
+            let symbol = match unit {
+                TemperatureUnit::Celsius => " °C",
+                TemperatureUnit::Fahrenheit => " °F",
+            };
+            let temperature =
+                |history: &History<u32>| reading(history).map(|temp| unit.convert(temp));
+            match &gpu_info.memory_temperature {
+                Some(memory_temperature) => vec![
+                    (
+                        String::from("GPU"),
+                        temperature(&gpu_info.temperature),
+                        symbol,
+                    ),
+                    (String::from("Mem"), temperature(memory_temperature), symbol),
+                ],
+                None => vec![(String::new(), temperature(&gpu_info.temperature), symbol)],
+            }
+        }
+        Metric::Memory => vec![(
+            String::new(),
+            gpu_info
+                .memory_used
+                .ago(back)
+                .map(|used| (used / 1024 / 1024) as f64),
+            " MiB",
+        )],
+        Metric::Utilization => vec![
+            (String::from("SM"), reading(&gpu_info.gpu_utilization), "%"),
+            (
+                String::from("Mem"),
+                reading(&gpu_info.memory_utilization),
+                "%",
+            ),
+        ],
+        Metric::Power => vec![(String::new(), reading(&gpu_info.power_usage), " W")],
+        Metric::Pcie => vec![
+            (String::from("RX"), reading(&gpu_info.pcie_rx), " KB/s"),
+            (String::from("TX"), reading(&gpu_info.pcie_tx), " KB/s"),
+        ],
+        Metric::Codec => vec![
+            (
+                String::from("Enc"),
+                reading(&gpu_info.encoder_utilization),
+                "%",
+            ),
+            (
+                String::from("Dec"),
+                reading(&gpu_info.decoder_utilization),
+                "%",
+            ),
+        ],
+        Metric::Fan => gpu_info
+            .fan_speeds
+            .iter()
+            .enumerate()
+            .map(|(fan, speed)| (format!("Fan {}", fan), reading(speed), "%"))
+            .collect(),
+        Metric::Engines => gpu_info
+            .engine_utilization
+            .iter()
+            .map(|(engine, utilization)| (engine.to_string(), reading(utilization), "%"))
+            .collect(),
+        Metric::Processes | Metric::Throttle | Metric::Ecc | Metric::NvLink => Vec::new(),
+    }
+}
+
+/// The footer text of the chart of `metric` under the cursor, `back` samples of
+/// `interval` before the newest, such as `-12s: 65 °C`.
+pub fn cursor_line(
+    gpu_info: &GPUInfo,
+    metric: Metric,
+    clock_domain: ClockDomain,
+    back: usize,
+    interval: Duration,
+) -> String {
+    let readings: Vec<String> = chart_readings(gpu_info, metric, clock_domain, back)
+        .into_iter()
+        .map(|(name, reading, unit)| {
+            let reading = match reading {
+                Some(value) if metric == Metric::Power => {
+                    format!("{}{}", gpu_info.precision.watts_number(value), unit)
+                }
+                Some(value) => format!("{:.0}{}", value, unit),
+                None => String::from("N/A"),
+            };
+            if name.is_empty() {
+                reading
+            } else {
+                format!("{} {}", name, reading)
+            }
+        })
+        .collect();
+    format!(
+        "{}: {}",
+        crate::format_time_ago(interval * back as u32),
+        readings.join(" | ")
+    )
+}
+
+/// Renders the readings under the cursor of the chart of `metric` as one centered line,
+/// in place of the statistics.
+pub fn render_cursor_footer(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    metric: Metric,
+    back: usize,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    Line::from(cursor_line(
+        gpu_info,
+        metric,
+        options.domain,
+        back,
+        options.interval,
+    ))
+    .style(Style::new().fg(theme.text))
+    .centered()
+    .render(area, buf);
+}
+
+/// How fast the temperature of `gpu_info` changed over the last minute of samples taken
+/// every `interval`, in °C per minute, as the slope of the least-squares line through
+/// them. `None` until two samples have been taken.
+pub fn temperature_rate(gpu_info: &GPUInfo, interval: Duration) -> Option<f64> {
+    if !gpu_info.available(Metric::Temperature) {
+        return None;
+    }
+    let window = (RATE_WINDOW.as_secs_f64() / interval.as_secs_f64()).ceil() as usize;
+    let samples = gpu_info.temperature.recent(window.max(2));
+    // Unfilled slots are zero, which no running GPU reads
+    let first = samples
+        .iter()
+        .rposition(|&temperature| temperature == 0)
+        .map_or(0, |last_unfilled| last_unfilled + 1);
+    let samples = &samples[first..];
+    if samples.len() < 2 {
+        return None;
+    }
+    let count = samples.len() as f64;
+    let mean_x = (count - 1.0) / 2.0;
+    let mean_y = samples
+        .iter()
+        .map(|&temperature| f64::from(temperature))
+        .sum::<f64>()
+        / count;
+    let (covariance, variance) =
+        samples
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(covariance, variance), (x, &temperature)| {
+                let dx = x as f64 - mean_x;
+                (
+                    covariance + dx * (f64::from(temperature) - mean_y),
+                    variance + dx * dx,
+                )
+            });
+    let per_sample = covariance / variance;
+    Some(per_sample * 60.0 / interval.as_secs_f64())
+}
+
+/// The Pearson correlation between the `domain` clock and the temperature of `gpu_info`
+/// over the history window, from -1 to 1. A strongly negative one means the clock drops
+/// as the card heats up, the mark of thermal throttling. `None` until three ticks have
+/// both readings, and while either stays flat, since a flat series correlates with
+/// nothing.
+pub fn clock_temperature_correlation(gpu_info: &GPUInfo, domain: ClockDomain) -> Option<f64> {
+    if !gpu_info.available(Metric::Clock) || !gpu_info.available(Metric::Temperature) {
+        return None;
+    }
+    let clock = gpu_info.clock(domain);
+    // Zero is an unfilled slot or a failed reading, not a sample
+    let pairs: Vec<(f64, f64)> = clock
+        .recent(clock.len())
+        .into_iter()
+        .zip(gpu_info.temperature.recent(clock.len()))
+        .filter(|&(clock, temperature)| clock > 0 && temperature > 0)
+        .map(|(clock, temperature)| (f64::from(clock), f64::from(temperature)))
+        .collect();
+    if pairs.len() < 3 {
+        return None;
+    }
+    let count = pairs.len() as f64;
+    let mean_clock = pairs.iter().map(|&(clock, _)| clock).sum::<f64>() / count;
+    let mean_temperature = pairs
+        .iter()
+        .map(|&(_, temperature)| temperature)
+        .sum::<f64>()
+        / count;
+    let (covariance, clock_variance, temperature_variance) = pairs.iter().fold(
+        (0.0, 0.0, 0.0),
+        |(covariance, clock_variance, temperature_variance), &(clock, temperature)| {
+            let dc = clock - mean_clock;
+            let dt = temperature - mean_temperature;
+            (
+                covariance + dc * dt,
+                clock_variance + dc * dc,
+                temperature_variance + dt * dt,
+            )
+        },
+    );
+    if clock_variance == 0.0 || temperature_variance == 0.0 {
+        return None;
+    }
+    Some(covariance / (clock_variance * temperature_variance).sqrt())
+}
+
+/// The chart points further than `sigma` standard deviations from the mean of all
+/// `points`, to mark the spikes of an otherwise steady workload without an absolute
+/// threshold. None until there are three points, and none while they're all the same.
+pub fn anomalies(points: &[(f64, f64)], sigma: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+    let count = points.len() as f64;
+    let mean = points.iter().map(|&(_, value)| value).sum::<f64>() / count;
+    let variance = points
+        .iter()
+        .map(|&(_, value)| (value - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let deviation = variance.sqrt();
+    if deviation == 0.0 {
+        return Vec::new();
+    }
+    points
+        .iter()
+        .copied()
+        .filter(|&(_, value)| (value - mean).abs() > sigma * deviation)
+        .collect()
+}
+
+/// Renders the statistics of the displayed history of `metric` as one centered line.
+/// The temperature footer ends with how fast the temperature changes, such as
+/// `+2.5 °C/min`, and the clock footer with how the clock correlates with the
+/// temperature, such as `r -0.85 vs temp`. The [`deviation`] from the mean of the GPUs
+/// `options` compares with comes last, highlighted if it makes the GPU an outlier, or
+/// alone if the line doesn't fit.
+pub fn render_stats_footer(
+    gpu_info: &GPUInfo,
+    theme: &Theme,
+    options: &ChartOptions,
+    metric: Metric,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let clock_domain = options.domain;
+    let deviation = options
+        .compared_gpus
+        .and_then(|gpus| deviation(gpus, gpu_info, metric, clock_domain));
+    let mut line = stats_line(gpu_info, metric, clock_domain);
+    let rate = temperature_rate(gpu_info, options.interval);
+    if let (Metric::Temperature, Some(rate)) = (metric, rate) {
+        let unit = gpu_info.temperature_unit;
+        line.push_str(&format!(
+            " | {:+.1} {}/min",
+            unit.convert_difference(rate),
+            unit.symbol()
+        ));
+    }
+    if let (Metric::Clock, Some(correlation)) = (
+        metric,
+        clock_temperature_correlation(gpu_info, clock_domain),
+    ) {
+        line.push_str(&format!(" | r {:+.2} vs temp", correlation));
+    }
+    let mut spans = Vec::new();
+    if let Some(deviation) = deviation {
+        let style = if deviation.outlier {
+            Style::new()
+                .fg(theme.temp_warn_color)
+                .add_modifier(Modifier::BOLD)
+                .remove_modifier(Modifier::DIM)
+        } else {
+            Style::new()
+        };
+        let text = deviation_text(gpu_info, metric, deviation);
+        // On narrow charts the comparison is what matters, so it's what's left
+        if line.chars().count() + text.chars().count() + 3 <= usize::from(area.width) {
+            spans.push(Span::raw(line));
+            spans.push(Span::raw(" | "));
+        }
+        spans.push(Span::styled(text, style));
+    } else {
+        spans.push(Span::raw(line));
+    }
+    Line::from(spans)
+        .style(Style::new().fg(theme.text).add_modifier(Modifier::DIM))
+        .centered()
+        .render(area, buf);
+}
@@ -0,0 +1,195 @@
+use crate::metric::GpuOrder;
+use crate::source::GpuSource;
+use crate::theme::Theme;
+use nvml_wrapper::{cuda_driver_version_major, cuda_driver_version_minor};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+use std::fmt;
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+
+/// The driver stack the GPUs are monitored through, shown in the status bar. Each
+/// version is `None` if the driver couldn't report it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DriverVersions {
+    pub driver: Option<String>,
+    pub nvml: Option<String>,
+    /// The major and minor CUDA version supported by the driver.
+    pub cuda: Option<(i32, i32)>,
+}
+
+impl DriverVersions {
+    /// Reads the versions once, since they can't change while the driver is loaded.
+    pub fn read(source: &dyn GpuSource) -> Self {
+        Self {
+            driver: source.driver_version().ok(),
+            nvml: source.nvml_version().ok(),
+            cuda: source.cuda_driver_version().ok().map(|version| {
+                (
+                    cuda_driver_version_major(version),
+                    cuda_driver_version_minor(version),
+                )
+            }),
+        }
+    }
+}
+
+/// Written as in the status bar, e.g. `Driver 550.54.14 | NVML 12.550.54.14 | CUDA 12.4`,
+/// with `N/A` for the versions the driver didn't report.
+impl fmt::Display for DriverVersions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown = || String::from("N/A");
+        write!(
+            f,
+            "Driver {} | NVML {} | CUDA {}",
+            self.driver.clone().unwrap_or_else(unknown),
+            self.nvml.clone().unwrap_or_else(unknown),
+            self.cuda
+                .map_or_else(unknown, |(major, minor)| format!("{}.{}", major, minor)),
+        )
+    }
+}
+
+/// The line `--version` prints: the version of this program followed by `versions`, for
+/// pasting into bug reports.
+pub fn version_line(versions: &DriverVersions) -> String {
+    format!("nvidia {} | {}", env!("CARGO_PKG_VERSION"), versions)
+}
+
+/// The name of this machine, or `None` if it can't be found. Read from the kernel on
+/// Linux, from the environment on Windows, and from the `hostname` command elsewhere.
+pub fn hostname() -> Option<String> {
+    let hostname = fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+        })?;
+    let hostname = hostname.trim();
+    (!hostname.is_empty()).then(|| String::from(hostname))
+}
+
+/// The text of the banner: the `--label` and the hostname, whichever are known.
+pub fn banner(label: Option<&str>, hostname: Option<&str>) -> Option<String> {
+    match (label, hostname) {
+        (Some(label), Some(hostname)) => Some(format!("{} @ {}", label, hostname)),
+        (Some(name), None) | (None, Some(name)) => Some(String::from(name)),
+        (None, None) => None,
+    }
+}
+
+/// Draws the banner centered in bold on the single row `area`.
+pub fn render_banner(banner: &str, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    Line::from(banner)
+        .style(Style::new().fg(theme.text).add_modifier(Modifier::BOLD))
+        .centered()
+        .render(area, buf);
+}
+
+/// What the status bar shows right of the versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusLine {
+    /// Shown unless the GPUs are in hardware order.
+    pub gpu_order: GpuOrder,
+    /// The page of `--per-page` and how many pages there are, or `None` without it.
+    pub page: Option<(usize, usize)>,
+    /// How long ago the last readings arrived, or `None` before the first readings.
+    pub age: Option<Duration>,
+    /// How long collecting the last readings took, with `--timing`.
+    pub collection_time: Option<Duration>,
+    pub interval: Duration,
+}
+
+/// Draws the driver, NVML and CUDA versions on the single row `area`, and on the right
+/// the GPU order and page of `status_line`, followed by how long ago the last readings
+/// arrived.
+///
+/// Once the readings are more than two intervals old, as while paused or reconnecting,
+/// the age is highlighted as stale. The collection time is shown before the age and
+/// highlighted the same way once [`collection_slow`].
+pub fn render_status_bar(
+    versions: &DriverVersions,
+    status_line: &StatusLine,
+    theme: &Theme,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let StatusLine {
+        gpu_order,
+        page,
+        age,
+        collection_time,
+        interval,
+    } = *status_line;
+    let status = format!(" {}", versions);
+    let style = Style::new().fg(theme.text).add_modifier(Modifier::DIM);
+    Line::from(status).style(style).render(area, buf);
+    let mut spans = Vec::new();
+    if gpu_order != GpuOrder::Index {
+        spans.push(Span::styled(format!("Sorted by {} ", gpu_order), style));
+    }
+    if let Some((page, page_count)) = page {
+        if !spans.is_empty() {
+            spans.push(Span::styled("| ", style));
+        }
+        spans.push(Span::styled(
+            format!("Page {}/{} ", page + 1, page_count),
+            style,
+        ));
+    }
+    let stale_style = Style::new()
+        .fg(theme.temp_warn_color)
+        .add_modifier(Modifier::BOLD);
+    if let Some(collection_time) = collection_time {
+        if !spans.is_empty() {
+            spans.push(Span::styled("| ", style));
+        }
+        spans.push(Span::styled(
+            format!("Collected in {} ms ", collection_time.as_millis()),
+            if collection_slow(collection_time, interval) {
+                stale_style
+            } else {
+                style
+            },
+        ));
+    }
+    if let Some(age) = age {
+        if !spans.is_empty() {
+            spans.push(Span::styled("| ", style));
+        }
+        let age_style = if age > interval * 2 {
+            stale_style
+        } else {
+            style
+        };
+        spans.push(Span::styled(
+            format!("Updated {} ago ", format_age(age)),
+            age_style,
+        ));
+    }
+    Line::from(spans).right_aligned().render(area, buf);
+}
+
+/// Whether collecting the readings took `collection_time`, three quarters or more of the
+/// `interval`, leaving the collection thread little time to sleep between ticks.
+pub fn collection_slow(collection_time: Duration, interval: Duration) -> bool {
+    collection_time >= interval * 3 / 4
+}
+
+/// Writes `age` in whole seconds, e.g. `4s` or `2m05s`.
+pub fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        seconds @ 0..=59 => format!("{}s", seconds),
+        seconds => format!("{}m{:02}s", seconds / 60, seconds % 60),
+    }
+}
@@ -0,0 +1,203 @@
+use crate::metric::{Metric, Units};
+use crate::theme::Theme;
+use crate::GPUInfo;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{block::Title, Block, Paragraph, Row, Sparkline, Table, Widget},
+};
+
+/// The number of temperature samples in the sparkline at the end of each compact row.
+const TREND_WIDTH: u16 = 20;
+
+/// Renders one row per GPU in the style of `nvidia-smi`, for the `--compact` view, each
+/// ending with a sparkline of its recent temperatures.
+///
+/// Each GPU comes with the style of its temperature alert, if any, which is applied to
+/// its whole row.
+pub fn render_summary_table(
+    gpus: &[(&GPUInfo, Option<Style>)],
+    theme: &Theme,
+    units: Units,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme.border))
+        .title(Title::from("NVIDIA GPUs").alignment(ratatui::layout::Alignment::Center));
+    let inner = block.inner(area);
+    block.render(area, buf);
+    // The sparklines are widgets of their own, so they get a column next to the table
+    let [table_area, trend_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(TREND_WIDTH + 1)]).areas(inner);
+
+    // What the other columns and the gaps between all seven leave for the names
+    let name_width = usize::from(table_area.width).saturating_sub(41 + 6);
+    let rows: Vec<Row> = gpus
+        .iter()
+        .map(|&(gpu_info, alert_style)| {
+            let value = |metric: Metric, value: String| {
+                if gpu_info.available(metric) {
+                    value
+                } else {
+                    String::from("N/A")
+                }
+            };
+            let memory_percent = gpu_info
+                .precision
+                .memory_percent(gpu_info.memory_used.latest(), gpu_info.memory_total);
+            let row = Row::new(vec![
+                gpu_info.id(),
+                gpu_info.title_name(name_width),
+                value(
+                    Metric::Temperature,
+                    gpu_info
+                        .temperature_unit
+                        .format(gpu_info.temperature.latest()),
+                ),
+                value(
+                    Metric::Clock,
+                    units.clock(gpu_info.core_clock.latest() as f64),
+                ),
+                value(
+                    Metric::Utilization,
+                    format!("{}%", gpu_info.gpu_utilization.latest()),
+                ),
+                value(Metric::Memory, memory_percent),
+                value(
+                    Metric::Power,
+                    gpu_info
+                        .precision
+                        .watts(gpu_info.power_usage.latest() as f64),
+                ),
+            ]);
+            match alert_style {
+                Some(style) => row.style(style),
+                None => row,
+            }
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(7),
+        ],
+    )
+    .header(
+        Row::new(vec!["GPU", "Name", "Temp", "Clock", "Util", "Mem", "Power"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .style(Style::new().fg(theme.text));
+    table.render(table_area, buf);
+
+    let trend_area = Rect {
+        x: trend_area.x + 1,
+        width: trend_area.width.saturating_sub(1),
+        ..trend_area
+    };
+    if trend_area.is_empty() {
+        return;
+    }
+    Line::from("Temp trend")
+        .style(Style::new().fg(theme.text).add_modifier(Modifier::BOLD))
+        .render(
+            Rect {
+                height: 1,
+                ..trend_area
+            },
+            buf,
+        );
+    for (&(gpu_info, alert_style), y) in gpus.iter().zip(trend_area.y + 1..trend_area.bottom()) {
+        if !gpu_info.available(Metric::Temperature) {
+            continue;
+        }
+        let temperatures: Vec<u64> = gpu_info
+            .temperature
+            .recent(trend_area.width as usize)
+            .into_iter()
+            .map(u64::from)
+            .collect();
+        // Scaled to 100 °C like the temperature chart, so the bars of two GPUs compare
+        let max = temperatures.iter().copied().fold(100, u64::max);
+        let style = alert_style.unwrap_or_else(|| Style::new().fg(theme.temp_color));
+        Sparkline::default()
+            .data(&temperatures)
+            .max(max)
+            .style(style)
+            .render(Rect::new(trend_area.x, y, trend_area.width, 1), buf);
+    }
+}
+
+/// The power draw and memory use of the whole machine, summed over every GPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub gpus: usize,
+    /// Summed over the GPUs whose power could be read, `None` if there are none.
+    pub power_mw: Option<u64>,
+    pub power_limit_mw: u64,
+    pub memory_used: u64,
+    pub memory_total: u64,
+}
+
+impl Totals {
+    /// Sums the latest readings of `gpus`. MIG instances are left out, since their
+    /// memory is part of their GPU's and they don't report power of their own.
+    pub fn new(gpus: &[GPUInfo]) -> Self {
+        let mut totals = Totals::default();
+        for gpu_info in gpus.iter().filter(|gpu_info| gpu_info.mig_index.is_none()) {
+            totals.gpus += 1;
+            if gpu_info.available(Metric::Power) {
+                *totals.power_mw.get_or_insert(0) += gpu_info.power_usage.latest() as u64;
+                totals.power_limit_mw += gpu_info.power_limit as u64;
+            }
+            if gpu_info.available(Metric::Memory) {
+                totals.memory_used += gpu_info.memory_used.latest();
+                totals.memory_total += gpu_info.memory_total;
+            }
+        }
+        totals
+    }
+}
+
+/// Renders the total power draw and memory use of every GPU on one line, for keeping an
+/// eye on the power budget of a whole box.
+pub fn render_totals_panel(gpus: &[GPUInfo], theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let totals = Totals::new(gpus);
+    // The precision is the same for every GPU
+    let precision = gpus
+        .first()
+        .map(|gpu_info| gpu_info.precision)
+        .unwrap_or_default();
+    let power = match totals.power_mw {
+        Some(power) => format!(
+            "{} of {}",
+            precision.watts(power as f64),
+            precision.watts(totals.power_limit_mw as f64)
+        ),
+        None => String::from("N/A"),
+    };
+    let line = format!(
+        "Power {}  ·  Memory {} / {} MiB  ·  {} GPUs",
+        power,
+        totals.memory_used / 1024 / 1024,
+        totals.memory_total / 1024 / 1024,
+        totals.gpus
+    );
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme.border))
+        .title(Title::from("Totals").alignment(ratatui::layout::Alignment::Center));
+    Paragraph::new(Line::from(line))
+        .alignment(ratatui::layout::Alignment::Center)
+        .block(block)
+        .style(Style::new().fg(theme.text))
+        .render(area, buf);
+}
@@ -0,0 +1,344 @@
+use crate::nvlink::NvLinkCounters;
+use crate::process::GpuProcess;
+use crate::source::{FanPolicy, GpuDevice, GpuSource, MediaEngine};
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{
+        Clock, ComputeMode, EccCounter, EncoderType, MemoryError, PcieUtilCounter,
+        PerformanceState, Sampling, TemperatureSensor, TemperatureThreshold,
+    },
+    error::NvmlError,
+    struct_wrappers::device::{MemoryInfo, Utilization},
+    structs::device::UtilizationInfo,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The PCI vendor ID of NVIDIA, as sysfs writes it.
+const NVIDIA_VENDOR: &str = "0x10de";
+
+/// The NVIDIA GPUs the kernel lists under `/sys`, for when NVML can't be loaded.
+///
+/// Only the temperature of the hwmon sensor and the clocks in the `pstate` file of
+/// debugfs are read, and everything else fails as unsupported. The open-source nouveau
+/// driver provides both, debugfs only to root; the proprietary driver provides neither,
+/// but the GPUs are still listed.
+pub struct SysfsSource {
+    devices: Vec<SysfsDevice>,
+    /// The loaded driver's version, if it says.
+    driver_version: Option<String>,
+}
+
+impl SysfsSource {
+    /// Finds the NVIDIA GPUs under `root`, normally `/sys`, in PCI bus order like NVML.
+    pub fn detect(root: &Path) -> Self {
+        let mut cards: Vec<(String, PathBuf, u32)> = fs::read_dir(root.join("class/drm"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                // Connectors such as card0-DP-1 and render nodes share the PCI device
+                let card = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("card")?
+                    .parse()
+                    .ok()?;
+                let device = entry.path().join("device");
+                let vendor = fs::read_to_string(device.join("vendor")).ok()?;
+                if vendor.trim() != NVIDIA_VENDOR {
+                    return None;
+                }
+                let address = fs::canonicalize(&device)
+                    .ok()
+                    .and_then(|path| Some(path.file_name()?.to_str()?.to_owned()))
+                    .unwrap_or_default();
+                Some((address, device, card))
+            })
+            .collect();
+        cards.sort();
+        cards.dedup_by(|a, b| a.0 == b.0 && !a.0.is_empty());
+        let devices = cards
+            .into_iter()
+            .map(|(_, device, card)| SysfsDevice {
+                hwmon: find_hwmon(&device),
+                pstate: root.join(format!("kernel/debug/dri/{}/pstate", card)),
+                device,
+            })
+            .collect();
+        let driver_version = ["nvidia", "nouveau"].iter().find_map(|module| {
+            let version = fs::read_to_string(root.join("module").join(module).join("version"));
+            version.ok().map(|version| version.trim().to_owned())
+        });
+        Self {
+            devices,
+            driver_version,
+        }
+    }
+
+    fn sysfs_device(&self, index: u32) -> Result<&SysfsDevice, NvmlError> {
+        self.devices
+            .get(index as usize)
+            .ok_or(NvmlError::InvalidArg)
+    }
+}
+
+/// The hwmon directory of the PCI device at `device` with a temperature sensor.
+fn find_hwmon(device: &Path) -> Option<PathBuf> {
+    fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|hwmon| hwmon.join("temp1_input").exists())
+}
+
+/// Reads the sysfs file at `path`, failing as unsupported if it doesn't exist.
+fn read_file(path: &Path) -> Result<String, NvmlError> {
+    fs::read_to_string(path).map_err(|error| match error.kind() {
+        io::ErrorKind::NotFound => NvmlError::NotSupported,
+        io::ErrorKind::PermissionDenied => NvmlError::NoPermission,
+        _ => NvmlError::Unknown,
+    })
+}
+
+/// The lowest and highest MHz of `domain`, `core` or `memory`, on a line of the nouveau
+/// `pstate` file such as `0f: core 405-1950 MHz memory 7000 MHz AC DC *`.
+pub fn pstate_clock(line: &str, domain: &str) -> Option<(u32, u32)> {
+    let mut words = line.split_whitespace();
+    words.find(|&word| word == domain)?;
+    let clock = words.next()?;
+    match clock.split_once('-') {
+        Some((low, high)) => Some((low.parse().ok()?, high.parse().ok()?)),
+        None => clock.parse().ok().map(|clock| (clock, clock)),
+    }
+}
+
+/// The `pstate` word of an NVML clock. The video clock isn't listed.
+fn pstate_domain(clock: Clock) -> Result<&'static str, NvmlError> {
+    match clock {
+        Clock::Graphics | Clock::SM => Ok("core"),
+        Clock::Memory => Ok("memory"),
+        Clock::Video => Err(NvmlError::NotSupported),
+    }
+}
+
+impl GpuSource for SysfsSource {
+    fn device_count(&self) -> Result<u32, NvmlError> {
+        Ok(self.devices.len() as u32)
+    }
+
+    fn driver_version(&self) -> Result<String, NvmlError> {
+        self.driver_version.clone().ok_or(NvmlError::NotSupported)
+    }
+
+    fn nvml_version(&self) -> Result<String, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn cuda_driver_version(&self) -> Result<i32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn device(&self, index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        Ok(self.sysfs_device(index)?)
+    }
+
+    fn mig_device_count(&self, _index: u32) -> Result<u32, NvmlError> {
+        Ok(0)
+    }
+
+    fn mig_device(&self, _index: u32, _mig_index: u32) -> Result<&dyn GpuDevice, NvmlError> {
+        Err(NvmlError::NotFound)
+    }
+
+    fn clock_vf_offsets(&self, _index: u32) -> Result<(i32, i32), NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn clock_vf_offset_ranges(&self, _index: u32) -> Result<[(i32, i32); 2], NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn reserved_memory(&self, _index: u32) -> Result<u64, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn die_power(&self, _index: u32) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn nvlink_counters(&self, _index: u32, _link: u32) -> Result<NvLinkCounters, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn engine_utilization(&self, _index: u32, _engine: MediaEngine) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn fan_policy(&self, _index: u32, _fan: u32) -> Result<FanPolicy, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+}
+
+/// One GPU of a [`SysfsSource`].
+struct SysfsDevice {
+    /// The PCI device directory, such as `/sys/class/drm/card0/device`.
+    device: PathBuf,
+    hwmon: Option<PathBuf>,
+    /// The nouveau clocks file in debugfs, which may not exist.
+    pstate: PathBuf,
+}
+
+impl SysfsDevice {
+    /// The lines of the `pstate` file: one per performance level, then the current
+    /// clocks on lines starting with `AC:` or `DC:`.
+    fn pstate_lines(&self) -> Result<Vec<String>, NvmlError> {
+        Ok(read_file(&self.pstate)?.lines().map(String::from).collect())
+    }
+}
+
+impl GpuDevice for SysfsDevice {
+    fn name(&self) -> Result<String, NvmlError> {
+        let id = read_file(&self.device.join("device"))?;
+        let id = id.trim().trim_start_matches("0x");
+        Ok(format!("NVIDIA GPU 10de:{}", id))
+    }
+
+    fn uuid(&self) -> Result<String, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn serial(&self) -> Result<String, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        let domain = pstate_domain(clock)?;
+        self.pstate_lines()?
+            .iter()
+            .filter(|line| line.starts_with("AC:") || line.starts_with("DC:"))
+            .find_map(|line| pstate_clock(line, domain))
+            .map(|(clock, _)| clock)
+            .ok_or(NvmlError::NotSupported)
+    }
+
+    fn max_clock_info(&self, clock: Clock) -> Result<u32, NvmlError> {
+        let domain = pstate_domain(clock)?;
+        self.pstate_lines()?
+            .iter()
+            .filter_map(|line| pstate_clock(line, domain))
+            .map(|(_, high)| high)
+            .max()
+            .ok_or(NvmlError::NotSupported)
+    }
+
+    fn memory_bus_width(&self) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn applications_clock(&self, _clock: Clock) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn default_applications_clock(&self, _clock: Clock) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn temperature(&self, _sensor: TemperatureSensor) -> Result<u32, NvmlError> {
+        let hwmon = self.hwmon.as_ref().ok_or(NvmlError::NotSupported)?;
+        let millidegrees: u32 = read_file(&hwmon.join("temp1_input"))?
+            .trim()
+            .parse()
+            .map_err(|_| NvmlError::Unknown)?;
+        Ok(millidegrees / 1000)
+    }
+
+    fn memory_temperature(&self) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn temperature_threshold(&self, _threshold: TemperatureThreshold) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn memory_info(&self) -> Result<MemoryInfo, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn utilization_rates(&self) -> Result<Utilization, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn power_usage(&self) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn enforced_power_limit(&self) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn power_limit_constraints(&self) -> Result<(u32, u32), NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn performance_state(&self) -> Result<PerformanceState, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn auto_boost_enabled(&self) -> Result<bool, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn persistence_mode_enabled(&self) -> Result<bool, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn compute_mode(&self) -> Result<ComputeMode, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn current_throttle_reasons(&self) -> Result<ThrottleReasons, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn pcie_throughput(&self, _counter: PcieUtilCounter) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn encoder_capacity(&self, _for_type: EncoderType) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn encoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn decoder_utilization(&self) -> Result<UtilizationInfo, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn num_fans(&self) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn fan_speed(&self, _fan: u32) -> Result<u32, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn total_ecc_errors(
+        &self,
+        _error_type: MemoryError,
+        _counter_type: EccCounter,
+    ) -> Result<u64, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn running_processes(&self) -> Result<Vec<GpuProcess>, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+
+    fn samples(&self, _sampling: Sampling, _since: u64) -> Result<Vec<(u64, u32)>, NvmlError> {
+        Err(NvmlError::NotSupported)
+    }
+}
@@ -1,6 +1,9 @@
-#[cfg(test)]
 use super::*;
-use ratatui::{buffer::Buffer, layout::Rect, style::Style};
+use export::{CsvLogger, InfluxLogger};
+use process::ProcessKind;
+use source::mock::{MockDevice, MockSource};
+use std::sync::Mutex;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
 
 #[test]
 fn render() {
@@ -18,4 +21,3656 @@ fn handle_key_event() -> std::io::Result<()> {
   app.handle_key_event(KeyCode::Char('q').into());
   assert!(app.exit);
   Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn cycle_history_len() {
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  app.gpus[0].core_clock.push(1500);
+
+  app.handle_key_event(KeyCode::Char('+').into());
+  assert_eq!(app.history_len, 60);
+  assert_eq!(app.gpus[0].core_clock.len(), 60);
+  assert_eq!(app.gpus[0].core_clock.latest(), 1500);
+
+  app.handle_key_event(KeyCode::Char('-').into());
+  app.handle_key_event(KeyCode::Char('-').into());
+  assert_eq!(app.history_len, 30);
+  assert_eq!(app.gpus[0].core_clock.len(), 30);
+  assert_eq!(app.gpus[0].core_clock.latest(), 1500);
+}
+
+
+#[test]
+fn parse_interval() {
+  let options = Options::parse(vec![String::from("--interval"), String::from("200")]).unwrap();
+  assert_eq!(options.interval, Duration::from_millis(200));
+
+  assert_eq!(Options::parse(Vec::new()).unwrap().interval, DEFAULT_INTERVAL);
+  assert!(Options::parse(vec![String::from("--interval")]).is_err());
+  assert!(Options::parse(vec![String::from("--interval"), String::from("0")]).is_err());
+  assert!(Options::parse(vec![String::from("--interval"), String::from("fast")]).is_err());
+}
+
+
+#[test]
+fn record_clears_error_on_recovery() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, DEFAULT_HISTORY_LEN);
+
+  assert_eq!(gpu_info.record::<u32>(Metric::Clock, Err(NvmlError::Unknown)), None);
+  assert!(gpu_info.error(Metric::Clock).is_some());
+  assert!(gpu_info.error(Metric::Temperature).is_none());
+
+  assert_eq!(gpu_info.record(Metric::Clock, Ok(1500)), Some(1500));
+  assert!(gpu_info.error(Metric::Clock).is_none());
+}
+
+
+#[test]
+fn select_gpu() {
+  let mut app = NvidiaApp::default();
+  for name in ["GPU 0", "GPU 1", "GPU 2"] {
+    app.gpus.push(GPUInfo::new(String::from(name), 0, 0, app.history_len));
+  }
+  assert_eq!(app.visible_gpus().len(), 3);
+
+  app.handle_key_event(KeyCode::Char('j').into());
+  assert_eq!(app.selected, Some(0));
+  app.handle_key_event(KeyCode::Up.into());
+  assert_eq!(app.selected, Some(2));
+  assert_eq!(app.visible_gpus().len(), 1);
+  assert_eq!(app.visible_gpus()[0].1.device_name, "GPU 2");
+
+  app.handle_key_event(KeyCode::Esc.into());
+  assert_eq!(app.selected, None);
+  assert_eq!(app.visible_gpus().len(), 3);
+}
+
+
+#[test]
+fn csv_logger_rows() {
+  let mut gpu_info = GPUInfo::new(String::from("Test \"GPU\""), 8192, 2, DEFAULT_HISTORY_LEN);
+  gpu_info.core_clock.push(1500);
+  gpu_info.temperature.push(65);
+  gpu_info.fan_speeds[0].push(40);
+  gpu_info.fan_speeds[1].push(42);
+  gpu_info.record::<u32>(Metric::Power, Err(NvmlError::NotSupported));
+
+  let mut output = Vec::new();
+  let mut csv_logger = CsvLogger::new(&mut output).unwrap();
+  csv_logger.log(&[gpu_info]).unwrap();
+
+  let output = String::from_utf8(output).unwrap();
+  let lines: Vec<&str> = output.lines().collect();
+  assert_eq!(lines.len(), 2);
+  assert!(lines[0].starts_with("timestamp,gpu,name,clock_mhz") && lines[0].ends_with(",fan_speeds_pct,uuid,serial"));
+  let row = lines[1].split_once(',').unwrap().1;
+  assert_eq!(row, "0,\"Test \"\"GPU\"\"\",1500,65,0,8192,0,0,,40;42,,");
+}
+
+
+#[test]
+fn prometheus_render() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 0, DEFAULT_HISTORY_LEN);
+  gpu_info.temperature.push(65);
+  gpu_info.power_usage.push(250500);
+  gpu_info.record::<u32>(Metric::Clock, Err(NvmlError::NotSupported));
+
+  let output = prometheus::render(&[gpu_info]);
+  assert!(output.contains("# TYPE nvidia_gpu_temperature_celsius gauge\n"));
+  assert!(output.contains("nvidia_gpu_temperature_celsius{gpu=\"0\",name=\"Test GPU\"} 65\n"));
+  assert!(output.contains("nvidia_gpu_power_watts{gpu=\"0\",name=\"Test GPU\"} 251\n"));
+  assert!(!output.contains("nvidia_gpu_clock_megahertz{"));
+}
+
+
+#[test]
+fn openmetrics_render() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 0, DEFAULT_HISTORY_LEN);
+  gpu_info.core_clock.push(1800);
+  gpu_info.gpu_utilization.push(45);
+  gpu_info.power_usage.push(250500);
+  let gpus = [gpu_info];
+
+  let output = prometheus::render_openmetrics(&gpus);
+  assert!(output.contains("# TYPE nvidia_gpu_clock_hertz gauge\n# UNIT nvidia_gpu_clock_hertz hertz\n# HELP nvidia_gpu_clock_hertz "));
+  assert!(output.contains("nvidia_gpu_clock_hertz{gpu=\"0\",name=\"Test GPU\"} 1800000000\n"));
+  assert!(output.contains("nvidia_gpu_sm_utilization_ratio{gpu=\"0\",name=\"Test GPU\"} 0.45\n"));
+  assert!(output.contains("nvidia_gpu_memory_total_bytes{gpu=\"0\",name=\"Test GPU\"} 8192\n"));
+  assert!(output.contains("nvidia_gpu_power_watts{gpu=\"0\",name=\"Test GPU\"} 251\n"));
+  assert!(output.ends_with("\n# EOF\n"));
+  // Every sample belongs to the family declared before it
+  let mut family = "";
+  for line in output.lines() {
+    match line.strip_prefix("# TYPE ") {
+      Some(declared) => family = declared.split(' ').next().unwrap(),
+      None if !line.starts_with('#') => assert!(line.starts_with(family) && line[family.len()..].starts_with('{'), "{}", line),
+      None => {}
+    }
+  }
+
+  let (status, content_type, body) = prometheus::respond("/openmetrics", &gpus);
+  assert_eq!((status, content_type), ("200 OK", "application/openmetrics-text; version=1.0.0; charset=utf-8"));
+  assert_eq!(body, output);
+  // The Prometheus names stay the same
+  assert!(prometheus::render(&gpus).contains("# TYPE nvidia_gpu_sm_utilization_percent gauge\n"));
+}
+
+
+#[test]
+fn render_paused() {
+  let mut app = NvidiaApp::default();
+  app.handle_key_event(KeyCode::Char('p').into());
+  assert!(app.paused);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 50, 4));
+  app.render(buf.area, &mut buf);
+  let top_row: String = (0..50).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(top_row.contains("PAUSED"));
+
+  app.handle_key_event(KeyCode::Char('p').into());
+  assert!(!app.paused);
+}
+
+
+#[test]
+fn watchdog_restarts_after_repeated_failures() {
+  use collector::{Watchdog, RECONNECT_AFTER};
+  let mut source = MockSource { devices: vec![MockDevice::new("GPU A"), MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  let mut watchdog = Watchdog::default();
+
+  // One GPU still answering keeps NVML alive
+  source.devices[0].lost = true;
+  for _ in 0..RECONNECT_AFTER {
+    app.update_state(&source).unwrap();
+    assert!(!watchdog.check(&app.gpus));
+  }
+
+  source.devices[1].lost = true;
+  for _ in 1..RECONNECT_AFTER {
+    app.update_state(&source).unwrap();
+    assert!(!watchdog.check(&app.gpus));
+  }
+  app.update_state(&source).unwrap();
+  assert!(watchdog.check(&app.gpus));
+  app.update_state(&source).unwrap();
+  assert!(!watchdog.check(&app.gpus));
+
+  app.reconnecting = true;
+  let mut buf = Buffer::empty(Rect::new(0, 0, 50, 4));
+  app.render(buf.area, &mut buf);
+  let top_row: String = (0..50).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(top_row.contains("RECONNECTING..."));
+}
+
+
+#[test]
+fn render_process_panel() {
+  let mut app = NvidiaApp::default();
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len);
+  gpu_info.processes.push(GpuProcess {
+    pid: 4242,
+    name: String::from("python"),
+    kind: ProcessKind::Compute,
+    used_memory: Some(512 * 1024 * 1024),
+  });
+  app.gpus.push(gpu_info);
+  app.handle_key_event(KeyCode::Char('t').into());
+  assert!(app.show_processes);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 80, 30));
+  app.render(buf.area, &mut buf);
+  let screen: String = (0..30).flat_map(|y| (0..80).map(move |x| (x, y))).map(|pos| buf[pos].symbol().to_string()).collect();
+  assert!(screen.contains("4242"));
+  assert!(screen.contains("python"));
+  assert!(screen.contains("512 MiB"));
+}
+
+
+#[test]
+fn y_axis_bound_uses_window_max() {
+  let mut history = History::new(3);
+  for clock in [1800, 1200, 900] {
+    history.push(clock);
+  }
+  assert_eq!(history.max(), 1800);
+  assert!((y_axis_bound(history.max() as f64) - 1980.0).abs() < 1e-9);
+  assert_eq!(y_axis_bound(0.0), 1.0);
+}
+
+
+#[test]
+fn unsupported_metrics_are_hidden() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 1, DEFAULT_HISTORY_LEN);
+  let area = Rect::new(0, 0, 90, 20);
+  assert_eq!(gpu_info.chart_areas(area, false, None).len(), 9);
+
+  gpu_info.supported.remove(MetricFlags::POWER | MetricFlags::FAN);
+  let chart_areas = gpu_info.chart_areas(area, false, None);
+  assert_eq!(chart_areas.len(), 7);
+  assert!(!chart_areas.contains_key(&Metric::Power));
+  assert!(!chart_areas.contains_key(&Metric::Fan));
+
+  assert_eq!(gpu_info.query(Metric::Power, || Err::<u32, _>(NvmlError::Unknown)), None);
+  assert!(gpu_info.error(Metric::Power).is_none());
+  assert!(!gpu_info.available(Metric::Power));
+}
+
+
+#[test]
+fn snapshot_json() {
+  let options = Options::parse(vec![String::from("--dump-json")]).unwrap();
+  assert!(options.dump_json);
+
+  let mut gpu_info = GPUInfo::new(String::from("Test \"GPU\""), 8192, 1, DEFAULT_HISTORY_LEN);
+  gpu_info.core_clock.push(1500);
+  gpu_info.fan_speeds[0].push(40);
+  gpu_info.record::<u32>(Metric::Power, Err(NvmlError::Unknown));
+  gpu_info.supported.remove(MetricFlags::PROCESSES);
+
+  let snapshot = snapshot::GpuSnapshot::new(&gpu_info);
+  assert_eq!(snapshot.clock_mhz, Some(1500));
+  assert_eq!(snapshot.power_mw, None);
+  assert_eq!(snapshot.processes, None);
+
+  let json = snapshot::snapshot_json(&[gpu_info]).to_string();
+  assert!(json.starts_with("[{\"index\":0,\"mig_index\":null,\"name\":\"Test \\\"GPU\\\"\",\"uuid\":null,\"serial\":null,\"clock_mhz\":1500,"));
+  assert!(json.contains("\"power_mw\":null,"));
+  assert!(json.contains("\"fan_speeds_pct\":[40],"));
+  assert!(json.contains("\"errors\":{\"power\":\"an internal driver error occured\"}}]"));
+
+  let pretty = json::Json::object().field("gpus", vec![1u32, 2]).field("empty", Vec::<u32>::new()).pretty();
+  assert_eq!(pretty, "{\n  \"gpus\": [\n    1,\n    2\n  ],\n  \"empty\": []\n}");
+}
+
+
+#[test]
+fn cycle_theme() {
+  let mut app = NvidiaApp::default();
+  assert_eq!(app.theme().name, "neon");
+
+  for _ in 0..THEMES.len() - 1 {
+    app.handle_key_event(KeyCode::Char('c').into());
+  }
+  assert_eq!(app.theme().name, THEMES[THEMES.len() - 1].name);
+
+  app.handle_key_event(KeyCode::Char('c').into());
+  assert_eq!(app.theme().name, "neon");
+}
+
+
+#[test]
+fn cycle_clock_domain() {
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  app.gpus[0].core_clock.push(1500);
+  app.gpus[0].memory_clock.push(10501);
+  assert_eq!(app.clock_domain, ClockDomain::Graphics);
+
+  app.handle_key_event(KeyCode::Char('d').into());
+  app.handle_key_event(KeyCode::Char('d').into());
+  assert_eq!(app.clock_domain, ClockDomain::Memory);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 150, 30));
+  app.render(buf.area, &mut buf);
+  let top = app.gpus[0].chart_areas(buf.area, false, None)[&Metric::Clock].top();
+  let top_row: String = (0..150).map(|x| buf[(x, top)].symbol()).collect();
+  assert!(top_row.contains("Memory Clock - Test GPU (10501 MHz"));
+
+  app.handle_key_event(KeyCode::Char('d').into());
+  app.handle_key_event(KeyCode::Char('d').into());
+  assert_eq!(app.clock_domain, ClockDomain::Graphics);
+}
+
+
+#[test]
+fn ctrl_c_exits() {
+  let mut app = NvidiaApp::default();
+  app.handle_key_event(KeyCode::Char('c').into());
+  assert!(!app.exit);
+
+  app.handle_key_event(event::KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+  assert!(app.exit);
+
+  let mut app = NvidiaApp::default();
+  app.interrupted.store(true, Ordering::Relaxed);
+  assert!(!app.handle_events(Duration::from_secs(1)).unwrap());
+  assert!(app.exit);
+}
+
+
+#[test]
+fn temperature_alert() {
+  let options = Options::parse(vec![
+    String::from("--temp-warn"), String::from("80"),
+    String::from("--temp-crit"), String::from("90"),
+  ]).unwrap();
+  assert_eq!((options.temp_warn, options.temp_crit), (Some(80), Some(90)));
+  assert!(Options::parse(vec![
+    String::from("--temp-warn"), String::from("95"),
+    String::from("--temp-crit"), String::from("90"),
+  ]).is_err());
+
+  let mut app = NvidiaApp { temp_warn: Some(80), temp_crit: Some(90), ..NvidiaApp::default() };
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  let theme = app.theme().clone();
+
+  app.gpus[0].temperature.push(79);
+  assert_eq!(app.temperature_alert(&app.gpus[0]), None);
+  app.gpus[0].temperature.push(80);
+  assert_eq!(app.temperature_alert(&app.gpus[0]).and_then(|style| style.fg), Some(theme.temp_warn_color));
+  app.gpus[0].temperature.push(93);
+  assert_eq!(app.temperature_alert(&app.gpus[0]).and_then(|style| style.fg), Some(theme.temp_crit_color));
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 90, 20));
+  app.render(buf.area, &mut buf);
+  let top = app.gpus[0].chart_areas(buf.area, false, None)[&Metric::Clock].top();
+  assert_eq!(buf[(0, top)].fg, theme.temp_crit_color);
+  assert!(buf[(0, top)].modifier.contains(Modifier::SLOW_BLINK));
+}
+
+
+#[test]
+fn history_peak_outlives_window() {
+  let mut history: History<u32> = History::new(3);
+  history.push(85);
+  for _ in 0..3 {
+    history.push(60);
+  }
+  assert_eq!(history.max(), 60);
+  assert_eq!(history.peak(), 85);
+
+  history.resize(1);
+  assert_eq!(history.peak(), 85);
+}
+
+
+#[test]
+fn filter_gpus() {
+  let options = Options::parse(vec![String::from("--gpus"), String::from("5,0,3,3")]).unwrap();
+  assert_eq!(options.gpus, Some(vec![0, 3, 5]));
+  assert!(Options::parse(vec![String::from("--gpus"), String::from("0,x")]).is_err());
+  assert!(Options::parse(vec![String::from("--gpus")]).is_err());
+
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 0, DEFAULT_HISTORY_LEN);
+  gpu_info.index = 3;
+  gpu_info.temperature.push(65);
+  let output = prometheus::render(&[gpu_info]);
+  assert!(output.contains("nvidia_gpu_temperature_celsius{gpu=\"3\",name=\"Test GPU\"} 65\n"));
+}
+
+
+#[test]
+fn throttle_badges() {
+  use nvml_wrapper::bitmasks::device::ThrottleReasons;
+
+  let reasons = ThrottleReasons::SW_POWER_CAP | ThrottleReasons::HW_THERMAL_SLOWDOWN;
+  assert_eq!(throttle::labels(reasons), vec!["HW Thermal", "Power Cap"]);
+  assert!(throttle::labels(ThrottleReasons::NONE).is_empty());
+
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  app.gpus[0].throttle_reasons = reasons;
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 150, 30));
+  app.render(buf.area, &mut buf);
+  let clock_area = app.gpus[0].chart_areas(buf.area, false, None)[&Metric::Clock];
+  let bottom_row: String = (clock_area.left()..clock_area.right()).map(|x| buf[(x, clock_area.bottom() - 1)].symbol()).collect();
+  assert!(bottom_row.contains(" HW Thermal   Power Cap "));
+}
+
+
+#[test]
+fn mock_source_rotates_buffers() {
+  let mut rtx = MockDevice::new("Mock RTX");
+  rtx.memory_total = 8192;
+  rtx.fan_speeds = vec![30, 35];
+  rtx.clock = None;
+  let mut source = MockSource { devices: vec![rtx, MockDevice::new("Mock Tesla")] };
+
+  let mut app = NvidiaApp { history_len: 3, show_processes: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  assert_eq!(app.gpus.len(), 2);
+  assert!(!app.gpus[0].supports(Metric::Clock));
+  assert_eq!(app.gpus[0].fan_speeds.len(), 2);
+  assert!(!app.gpus[1].supports(Metric::Fan));
+
+  for temperature in [60, 65, 70, 75] {
+    source.devices[0].temperature = Some(temperature);
+    app.update_state(&source).unwrap();
+  }
+  assert_eq!(app.gpus[0].temperature.points(|temp| temp as f64), vec![(-2.0, 65.0), (-1.0, 70.0), (0.0, 75.0)]);
+  assert_eq!(app.gpus[0].fan_speeds[1].latest(), 35);
+  assert!(app.gpus[0].error(Metric::Clock).is_none());
+
+  source.devices[1].lost = true;
+  app.update_state(&source).unwrap();
+  assert!(app.gpus[1].error(Metric::Temperature).is_some());
+  source.devices[1].lost = false;
+  source.devices[1].temperature = Some(50);
+  app.update_state(&source).unwrap();
+  assert!(app.gpus[1].error(Metric::Temperature).is_none());
+  // The tick the GPU was lost is a gap in the line
+  assert_eq!(app.gpus[1].temperature.points(|temp| temp as f64), vec![(-2.0, 0.0), (0.0, 50.0)]);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 150, 40));
+  app.render(buf.area, &mut buf);
+  let top = app.gpus[0].chart_areas(buf.area, false, None)[&Metric::Temperature].top();
+  let top_row: String = (0..150).map(|x| buf[(x, top)].symbol()).collect();
+  assert!(top_row.contains("GPU 0 Temperature - Mock RTX (75 °C"));
+}
+
+
+#[test]
+fn codec_chart_hidden_without_encoder() {
+  let mut no_nvenc = MockDevice::new("Mock Compute");
+  no_nvenc.codec_utilization = None;
+  let mut source = MockSource { devices: vec![MockDevice::new("Mock RTX"), no_nvenc] };
+  source.devices[0].codec_utilization = Some((42, 7));
+
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].encoder_utilization.latest(), 42);
+  assert_eq!(app.gpus[0].decoder_utilization.latest(), 7);
+
+  let area = Rect::new(0, 0, 150, 30);
+  assert!(app.gpus[0].chart_areas(area, false, None).contains_key(&Metric::Codec));
+  assert!(!app.gpus[1].chart_areas(area, false, None).contains_key(&Metric::Codec));
+}
+
+
+#[test]
+fn render_compact() {
+  let options = Options::parse(vec![String::from("--compact")]).unwrap();
+  assert!(options.compact);
+
+  let mut app = NvidiaApp { compact: options.compact, ..NvidiaApp::default() };
+  let source = MockSource { devices: vec![MockDevice { temperature: Some(64), ..MockDevice::new("GPU A") }, MockDevice { temperature: Some(71), ..MockDevice::new("GPU B") }] };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 80, 6));
+  app.render(buf.area, &mut buf);
+  let rows: Vec<String> = (0..6).map(|y| (0..80).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(rows[2].contains("GPU A") && rows[2].contains("64 °C"));
+  assert!(rows[3].contains("GPU B") && rows[3].contains("71 °C"));
+}
+
+
+#[test]
+fn compact_temperature_sparklines() {
+  let mut source = MockSource { devices: vec![MockDevice { temperature: Some(30), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp { compact: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  source.devices[0].temperature = Some(90);
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].temperature.recent(2), vec![30, 90]);
+  assert_eq!(app.gpus[0].temperature.recent(3), vec![0, 30, 90]);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 100, 5));
+  app.render(buf.area, &mut buf);
+  let rows: Vec<String> = (0..5).map(|y| (0..100).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(rows[1].contains("Temp trend"));
+  assert!(rows[2].contains("GPU A") && rows[2].trim_end_matches(['│', ' ']).ends_with('▇'));
+}
+
+
+#[test]
+fn click_selects_gpu() {
+  let source = MockSource { devices: vec![MockDevice::new("GPU A"), MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  let area = Rect::new(0, 0, 120, 40);
+
+  assert!(app.click(area, 60, 30));
+  assert_eq!(app.selected, Some(1));
+  app.selected = None;
+  app.show_processes = true;
+  assert!(!app.click(area, 60, 35));
+  assert_eq!(app.selected, None);
+  assert!(app.click(area, 5, 2));
+  assert_eq!(app.selected, Some(0));
+
+  // Rows of the compact table, below its border and header
+  let mut app = NvidiaApp { compact: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  assert!(!app.click(area, 10, 1));
+  assert!(app.click(area, 10, 3));
+  assert_eq!(app.selected, Some(1));
+  assert!(!app.handle_event(Event::Mouse(MouseEvent { kind: MouseEventKind::Moved, column: 10, row: 2, modifiers: KeyModifiers::NONE })));
+}
+
+
+#[test]
+fn totals_panel() {
+  let source = MockSource { devices: vec![
+    MockDevice { power_usage: Some(350_000), power_limit: Some(450_000), memory_used: Some(2048 << 20), memory_total: 24576 << 20, ..MockDevice::new("GPU A") },
+    MockDevice { power_usage: Some(70_500), power_limit: Some(70_000), memory_used: Some(1024 << 20), memory_total: 16384 << 20, ..MockDevice::new("GPU B") },
+    MockDevice { power_usage: None, memory_used: None, ..MockDevice::new("GPU C") },
+  ] };
+  let mut app = NvidiaApp { compact: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(summary::Totals::new(&app.gpus), summary::Totals { gpus: 3, power_mw: Some(420_500), power_limit_mw: 520_000, memory_used: 3072 << 20, memory_total: 40960 << 20 });
+
+  let screen = |app: &NvidiaApp| {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 100, 30));
+    app.render(buf.area, &mut buf);
+    (0..30).map(|y| (0..100).map(|x| buf[(x, y)].symbol()).collect::<String>()).collect::<Vec<_>>()
+  };
+  let totals = "Power 421 W of 520 W  ·  Memory 3072 / 40960 MiB  ·  3 GPUs";
+  assert!(screen(&app).iter().any(|line| line.contains(totals)));
+
+  app.compact = false;
+  assert!(!screen(&app).iter().any(|line| line.contains(totals)));
+  app.handle_key_event(KeyCode::Char('a').into());
+  assert!(screen(&app)[28].contains(totals));
+}
+
+
+#[test]
+fn fixed_axis_ranges() {
+  let options = Options::parse(["--temp-range", "20:90", "--power-range", "0:500"].map(String::from)).unwrap();
+  assert_eq!(options.axis_ranges[&Metric::Temperature], cli::AxisRange { min: 20.0, max: 90.0 });
+  assert!(Options::parse(["--temp-range", "90:20"].map(String::from)).unwrap_err().contains("minimum must be below"));
+  assert!(Options::parse(["--fan-range", "0:100"].map(String::from)).is_err());
+
+  let config = Config::parse("clock_range = \"0:3000\"\n").unwrap();
+  assert_eq!(config.axis_ranges[&Metric::Clock], cli::AxisRange { min: 0.0, max: 3000.0 });
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("temp_range = \"hot\"").is_err());
+
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, 10);
+  gpu_info.supported = MetricFlags::TEMPERATURE;
+  gpu_info.temperature.push(45);
+  let labels = |range: Option<cli::AxisRange>| {
+    let area = Rect::new(0, 0, 60, 12);
+    let mut buf = Buffer::empty(area);
+    let ranges = range.into_iter().map(|range| (Metric::Temperature, range)).collect();
+    render_temperature_chart(&gpu_info, &THEMES[0], &ChartOptions { axis_ranges: &ranges, ..ChartOptions::default() }, TemperatureThresholds::default(), area, &mut buf);
+    (0..12).map(|y| (1..3).map(|x| buf[(x, y)].symbol()).collect::<String>().trim().to_string()).filter(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_digit())).collect::<Vec<_>>()
+  };
+  assert_eq!(labels(None), vec!["50", "0"]);
+  assert_eq!(labels(Some(options.axis_ranges[&Metric::Temperature])), vec!["90", "20"]);
+}
+
+
+#[test]
+fn config_round_trip() {
+  let config = Config::parse("# saved settings\ninterval_ms = 500\ntheme = \"monochrome\"\nclock_domain = \"sm\"\ntemp_warn = 80\n").unwrap();
+  assert_eq!(config.interval, Some(Duration::from_millis(500)));
+  assert_eq!(config.clock_domain, Some(ClockDomain::Sm));
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("theme = \"sepia\"").is_err());
+  assert!(Config::parse("volume = 11").is_err());
+  assert_eq!(Config::parse("temp_warn = -1").unwrap_err(), "line 1: temp_warn must be a whole number");
+  assert_eq!(Config::parse("temp_crit = 90.5").unwrap_err(), "line 1: temp_crit must be a whole number");
+  assert_eq!(Config::parse("\nmax_fps = 4294967296").unwrap_err(), "line 2: max_fps is too large");
+
+  // Flags override the file, which overrides the defaults
+  let options = Options::parse_over(config.options(), vec![String::from("--interval"), String::from("200")]).unwrap();
+  assert_eq!(options.interval, Duration::from_millis(200));
+  assert_eq!(options.temp_warn, Some(80));
+
+  let mut app = NvidiaApp::default();
+  config.apply(&mut app);
+  assert_eq!(app.theme().name, "monochrome");
+  app.handle_key_event(KeyCode::Char('+').into());
+  let mut saved = config.clone();
+  saved.remember(&app);
+  assert_eq!(saved.history_len, Some(60));
+  assert_eq!(saved.interval, config.interval);
+}
+
+
+#[test]
+fn clock_percent_of_max() {
+  let source = MockSource { devices: vec![MockDevice { clock: Some(1260), max_clock: Some(2520), ..MockDevice::new("GPU") }, MockDevice { clock: Some(1260), ..MockDevice::new("GPU") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].max_clock(ClockDomain::Graphics), Some(2520));
+  assert_eq!(app.gpus[1].max_clock(ClockDomain::Graphics), None);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 200, 40));
+  app.render(buf.area, &mut buf);
+  let title = |gpu: usize| { let area = app.gpus[gpu].chart_areas(Rect::new(0, gpu as u16 * 20, 200, 20), false, None)[&Metric::Clock]; (area.left()..area.right()).map(|x| buf[(x, area.top())].symbol()).collect::<String>() };
+  assert!(title(0).contains("1260 MHz (50%)"));
+  assert!(title(1).contains("(1260 MHz, max 1260)"));
+}
+
+
+#[test]
+fn resize_redraws() {
+  let mut app = NvidiaApp::default();
+  assert!(app.handle_event(Event::Resize(120, 40)));
+  assert!(app.handle_event(Event::Key(KeyCode::Char('p').into())));
+  assert!(app.paused);
+  assert!(!app.handle_event(Event::FocusGained));
+}
+
+
+#[test]
+fn snapshot_table() {
+  assert!(Options::parse(vec![String::from("--once")]).unwrap().once);
+  let source = MockSource { devices: vec![
+    MockDevice { temperature: Some(64), clock: Some(1980), memory_used: Some(2048 << 20), memory_total: 24576 << 20, power_usage: Some(350_000), power_limit: Some(450_000), ..MockDevice::new("NVIDIA GeForce RTX 4090") },
+    MockDevice { temperature: None, ..MockDevice::new("Tesla T4") },
+  ] };
+  let mut app = NvidiaApp { history_len: 1, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+
+  assert_eq!(snapshot::snapshot_table(&app.gpus),
+"GPU  Name                     Temp   Clock     Memory            Power
+0    NVIDIA GeForce RTX 4090  64 °C  1980 MHz  2048 / 24576 MiB  350 / 450 W
+1    Tesla T4                 N/A    0 MHz     0 / 0 MiB         0 / 0 W
+");
+}
+
+
+#[test]
+fn snapshot_xml() {
+  assert!(Options::parse(vec![String::from("--xml")]).unwrap().xml);
+  let source = MockSource { devices: vec![
+    MockDevice { temperature: Some(64), clock: Some(1980), memory_used: Some(2048 << 20), memory_total: 24576 << 20, power_usage: Some(350_500), power_limit: Some(450_000), utilization: Some((87, 40)), fan_speeds: vec![55, 60], ..MockDevice::new("RTX <OC>") },
+    MockDevice { temperature: None, clock: None, ..MockDevice::new("Tesla T4") },
+  ] };
+  let mut app = NvidiaApp { history_len: 1, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  let versions = DriverVersions { driver: Some(String::from("550.54.14")), nvml: None, cuda: Some((12, 4)) };
+  let xml = snapshot::snapshot_xml(&app.gpus, &versions);
+
+  assert!(xml.starts_with("<?xml version=\"1.0\" ?>\n<!DOCTYPE nvidia_smi_log SYSTEM \"nvsmi_device_v12.dtd\">\n<nvidia_smi_log>\n\t<driver_version>550.54.14</driver_version>\n\t<cuda_version>12.4</cuda_version>\n\t<attached_gpus>2</attached_gpus>\n"));
+  assert!(xml.contains("\t<gpu id=\"0\">\n\t\t<product_name>RTX &lt;OC&gt;</product_name>\n\t\t<serial>N/A</serial>\n\t\t<uuid>N/A</uuid>\n\t\t<fb_memory_usage>\n\t\t\t<total>24576 MiB</total>\n\t\t\t<used>2048 MiB</used>\n\t\t\t<free>22528 MiB</free>\n"));
+  assert!(xml.contains("\t\t\t<gpu_util>87 %</gpu_util>\n\t\t\t<memory_util>40 %</memory_util>\n"));
+  assert!(xml.contains("\t\t\t<gpu_temp>64 C</gpu_temp>\n"));
+  assert!(xml.contains("\t\t\t<power_draw>350.50 W</power_draw>\n\t\t\t<current_power_limit>450.00 W</current_power_limit>\n"));
+  assert!(xml.contains("\t\t\t<graphics_clock>1980 MHz</graphics_clock>\n\t\t\t<sm_clock>1980 MHz</sm_clock>\n"));
+  assert!(xml.contains("\t\t<fan_speed>55 %</fan_speed>\n"));
+  let tesla = &xml[xml.find("<gpu id=\"1\">").unwrap()..];
+  assert!(tesla.contains("<gpu_temp>N/A</gpu_temp>") && tesla.contains("<graphics_clock>N/A</graphics_clock>") && tesla.contains("<total>N/A</total>"));
+  assert!(xml.ends_with("\t</gpu>\n</nvidia_smi_log>\n"));
+}
+
+
+#[test]
+fn memory_gauge() {
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 8192 << 20, 0, app.history_len));
+  app.gpus[0].memory_used.push(6144 << 20);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 120, 30));
+  app.render(buf.area, &mut buf);
+  let gauge_row: String = (0..120).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(gauge_row.contains("GPU 0 Memory 6144 / 8192 MiB (75% VRAM)"));
+  assert_eq!(buf[(0, 0)].fg, app.theme().temp_warn_color);
+  assert_eq!(app.gpus[0].chart_areas(buf.area, false, None)[&Metric::Clock].top(), 1);
+}
+
+
+#[test]
+fn overlay_clock_and_temperature() {
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  app.gpus[0].core_clock.push(1800);
+  app.gpus[0].temperature.push(72);
+  let area = Rect::new(0, 0, 180, 40);
+  assert!(app.gpus[0].chart_areas(area, false, None).contains_key(&Metric::Temperature));
+
+  app.handle_key_event(KeyCode::Char('o').into());
+  assert!(app.overlay);
+  let chart_areas = app.gpus[0].chart_areas(area, true, None);
+  assert!(!chart_areas.contains_key(&Metric::Temperature));
+
+  let mut buf = Buffer::empty(area);
+  app.render(buf.area, &mut buf);
+  let clock_area = chart_areas[&Metric::Clock];
+  let screen: String = (clock_area.top()..clock_area.bottom()).flat_map(|y| (clock_area.left()..clock_area.right()).map(move |x| (x, y))).map(|pos| buf[pos].symbol().to_string()).collect();
+  assert!(screen.contains("Clock vs Temp - Test GPU (1800 MHz, 72 °C)"));
+  assert!(screen.contains("Temperature"));
+
+  // Without a temperature sensor there is nothing to overlay
+  app.gpus[0].supported.remove(MetricFlags::TEMPERATURE);
+  assert!(!app.gpus[0].overlays_temperature());
+}
+
+
+#[test]
+fn overlay_fans_and_temperature() {
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 2, app.history_len));
+  app.gpus[0].supported = MetricFlags::TEMPERATURE | MetricFlags::FAN;
+  app.gpus[0].temperature.push(72);
+  app.gpus[0].fan_speeds[0].push(45);
+  app.gpus[0].fan_speeds[1].push(50);
+  let area = Rect::new(0, 0, 180, 40);
+  let fan_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Fan];
+  let screen = |app: &NvidiaApp| {
+    let mut buf = Buffer::empty(area);
+    app.render(area, &mut buf);
+    (fan_area.top()..fan_area.bottom()).flat_map(|y| (fan_area.left()..fan_area.right()).map(move |x| (x, y))).map(|pos| buf[pos].symbol().to_string()).collect::<String>()
+  };
+  assert!(!screen(&app).contains("Fans vs Temp"));
+
+  app.handle_key_event(KeyCode::Char('f').into());
+  assert!(app.cooling_overlay);
+  let fans = screen(&app);
+  assert!(fans.contains("GPU 0 Fans vs Temp - Test GPU (45% / 50%, 72 °C)"));
+  assert!(fans.contains("Fan 1") && fans.contains("Temperature") && fans.contains("Fan % / °C"));
+}
+
+
+#[test]
+fn smoothing_keeps_raw_samples() {
+  let mut history = History::new(4);
+  for sample in [0u32, 60, 0, 60] { history.push(sample); }
+  assert_eq!(history.smoothed_points(1, |sample| sample as f64), history.points(|sample| sample as f64));
+  assert_eq!(history.smoothed_points(2, |sample| sample as f64), vec![(-3.0, 0.0), (-2.0, 30.0), (-1.0, 30.0), (0.0, 30.0)]);
+
+  let mut app = NvidiaApp::default();
+  Config::parse("smoothing_window = 2").unwrap().apply(&mut app);
+  assert_eq!(app.active_smoothing_window(), 1);
+  app.handle_key_event(KeyCode::Char('s').into());
+  assert_eq!(app.active_smoothing_window(), 2);
+  assert!(Config::parse("smoothing_window = 0").is_err());
+}
+
+
+#[test]
+fn status_bar() {
+  let source = MockSource { devices: vec![MockDevice::new("GPU")] };
+  let mut app = NvidiaApp { versions: Some(DriverVersions::read(&source)), ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 120, 30));
+  app.render(buf.area, &mut buf);
+  let bottom_row: String = (0..120).map(|x| buf[(x, 29)].symbol()).collect();
+  assert!(bottom_row.contains("Driver 550.54.14 | NVML 12.550.54.14 | CUDA 12.4"));
+}
+
+
+#[test]
+fn time_axis_labels() {
+  assert_eq!(format_time_ago(Duration::ZERO), "0s");
+  assert_eq!(format_time_ago(Duration::from_millis(6000)), "-6s");
+  assert_eq!(format_time_ago(Duration::from_secs(120)), "-2m");
+  assert_eq!(format_time_ago(Duration::from_secs(90)), "-1m30s");
+
+  let mut app = NvidiaApp { interval: Duration::from_secs(2), ..NvidiaApp::default() };
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  for _ in 0..app.history_len {
+    app.gpus[0].core_clock.push(1500);
+  }
+  let mut buf = Buffer::empty(Rect::new(0, 0, 150, 30));
+  app.render(buf.area, &mut buf);
+  let clock_area = app.gpus[0].chart_areas(buf.area, false, None)[&Metric::Clock];
+  let label_row: String = (clock_area.left()..clock_area.right()).map(|x| buf[(x, clock_area.bottom() - 2)].symbol()).collect();
+  assert!(label_row.contains("-1m") && label_row.contains("-40s") && label_row.contains("-20s") && label_row.contains("0s"));
+}
+
+
+#[test]
+fn reset_history() {
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 2, app.history_len));
+  app.gpus[0].temperature.push(88);
+  app.gpus[0].fan_speeds[1].push(70);
+
+  app.handle_key_event(KeyCode::Char('r').into());
+  assert_eq!(app.gpus[0].temperature.latest(), 0);
+  assert_eq!(app.gpus[0].temperature.peak(), 0);
+  assert_eq!(app.gpus[0].temperature.len(), app.history_len);
+  assert_eq!(app.gpus[0].fan_speeds[1].max(), 0);
+}
+
+
+#[test]
+fn mig_instances_are_monitored() {
+  let mig = |name: &str, memory_used| MockDevice { memory_used: Some(memory_used), memory_total: 5 << 30, utilization: None, ..MockDevice::new(name) };
+  let a100 = MockDevice { mig_devices: vec![mig("MIG 1g.5gb", 1 << 30), mig("MIG 1g.5gb", 2 << 30)], ..MockDevice::new("A100") };
+  let source = MockSource { devices: vec![a100] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+
+  assert_eq!(app.gpus.len(), 3);
+  assert_eq!(app.gpus[2].label(), "GPU 0 MIG 1");
+  assert_eq!(app.gpus[2].id(), "0:1");
+  assert_eq!(app.gpus[2].memory_used.latest(), 2 << 30);
+  assert!(!app.gpus[2].supports(Metric::Utilization));
+  assert!(prometheus::render(&app.gpus).contains("gpu=\"0:1\""));
+}
+
+
+#[test]
+fn ecc_error_panel() {
+  let ecc_errors = ecc::EccErrors { volatile_corrected: 3, volatile_uncorrected: 0, aggregate_corrected: 12, aggregate_uncorrected: 2 };
+  let source = MockSource { devices: vec![MockDevice { ecc_errors: Some(ecc_errors), ..MockDevice::new("Tesla") }, MockDevice { ecc_errors: None, ..MockDevice::new("GeForce") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].ecc_errors, ecc_errors);
+  assert!(!app.gpus[1].supports(Metric::Ecc));
+  assert!(snapshot::snapshot_json(&app.gpus).to_string().contains("\"ecc_errors\":{\"volatile_corrected\":3,\"volatile_uncorrected\":0,\"aggregate_corrected\":12,\"aggregate_uncorrected\":2}"));
+
+  let gpu_info = &app.gpus[0];
+  let area = Rect::new(0, 0, 150, 50);
+  let ecc_area = gpu_info.chart_areas(area, false, None)[&Metric::Ecc];
+  let mut buf = Buffer::empty(area);
+  ecc::render_ecc_panel(gpu_info, app.theme(), ecc_area, &mut buf);
+  let rows: Vec<String> = (ecc_area.top()..ecc_area.bottom()).map(|y| (ecc_area.left()..ecc_area.right()).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(rows[0].contains("GPU 0 ECC Errors - Tesla (2 uncorrected)"));
+  assert!(rows[1].contains("Corrected") && rows[1].contains("Uncorrected"));
+  assert!(rows[3].contains("Aggregate"));
+
+  let red = app.theme().temp_crit_color;
+  let last_cell = |row: usize, text: &str| (ecc_area.left() + rows[row][..rows[row].rfind(text).unwrap()].chars().count() as u16, ecc_area.top() + row as u16);
+  assert_eq!(buf[last_cell(3, "2")].fg, red);
+  assert_ne!(buf[last_cell(2, "0")].fg, red);
+}
+
+
+#[test]
+fn stats_footer() {
+  let mut app = NvidiaApp { history_len: 4, ..NvidiaApp::default() };
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 1, app.history_len));
+  for temperature in [40, 50, 60, 70] {
+    app.gpus[0].temperature.push(temperature);
+  }
+  app.gpus[0].gpu_utilization.push(80);
+  assert_eq!(app.gpus[0].temperature.stats(|temp| temp as f64), history::Stats { min: 40.0, mean: 55.0, max: 70.0 });
+  assert_eq!(stats::stats_line(&app.gpus[0], Metric::Temperature, app.clock_domain), "min 40 avg 55 max 70 °C");
+  assert_eq!(stats::stats_line(&app.gpus[0], Metric::Utilization, app.clock_domain), "SM min 80 avg 80 max 80% | Mem min 0 avg 0 max 0%");
+
+  let area = Rect::new(0, 0, 150, 40);
+  let temp_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Temperature];
+  let footer_row = |app: &NvidiaApp| {
+    let mut buf = Buffer::empty(area);
+    app.render(area, &mut buf);
+    (temp_area.left()..temp_area.right()).map(|x| buf[(x, temp_area.bottom() - 1)].symbol()).collect::<String>()
+  };
+  assert!(!footer_row(&app).contains("avg"));
+  app.handle_key_event(KeyCode::Char('m').into());
+  assert!(footer_row(&app).contains("min 40 avg 55 max 70 °C"));
+}
+
+
+#[test]
+fn readings_from_collector() {
+  let mut source = MockSource { devices: vec![MockDevice { fan_speeds: vec![30], ..MockDevice::new("Mock RTX") }] };
+  let mut collector = NvidiaApp { history_len: 1, ..NvidiaApp::default() };
+  collector.add_gpus(&source, None).unwrap();
+  let mut app = NvidiaApp { history_len: 4, gpus: collector.gpus.clone(), ..NvidiaApp::default() };
+  app.gpus[0].resize_history(app.history_len);
+  let mut direct = NvidiaApp { history_len: 4, ..NvidiaApp::default() };
+  direct.add_gpus(&source, None).unwrap();
+
+  for temperature in [55, 60, 65] {
+    source.devices[0].temperature = Some(temperature);
+    source.devices[0].power_usage = None;
+    collector.update_state(&source).unwrap();
+    direct.update_state(&source).unwrap();
+    app.push_readings(&collector.gpus);
+  }
+  assert_eq!(app.gpus[0].temperature.points(|temp| temp as f64), direct.gpus[0].temperature.points(|temp| temp as f64));
+  assert_eq!(app.gpus[0].fan_speeds[0].points(|speed| speed as f64), direct.gpus[0].fan_speeds[0].points(|speed| speed as f64));
+  assert_eq!(app.gpus[0].errors, direct.gpus[0].errors);
+  assert_eq!(app.gpus[0].temperature.peak(), 65);
+
+  app.paused = true;
+  source.devices[0].temperature = Some(90);
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+  assert_eq!(app.gpus[0].temperature.latest(), 65);
+}
+
+
+#[test]
+fn clock_offset_in_title() {
+  let source = MockSource { devices: vec![MockDevice { clock: Some(1950), clock_vf_offsets: Some((150, -200)), ..MockDevice::new("RTX") }, MockDevice { clock_vf_offsets: Some((0, 0)), ..MockDevice::new("Stock") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].clock_vf_offset(ClockDomain::Memory), Some(-200));
+  assert_eq!(app.gpus[0].clock_vf_offset(ClockDomain::Video), None);
+
+  let title = |app: &NvidiaApp, gpu: usize| {
+    let area = Rect::new(0, 0, 240, 40);
+    let clock_area = app.gpus[gpu].chart_areas(area, false, None)[&Metric::Clock];
+    let mut buf = Buffer::empty(area);
+    render_clock_chart(&app.gpus[gpu], app.theme(), &app.chart_options(), clock_area, &mut buf);
+    (clock_area.left()..clock_area.right()).map(|x| buf[(x, clock_area.top())].symbol()).collect::<String>()
+  };
+  assert!(title(&app, 0).contains("(1950 MHz, max 1950, +150 MHz offset)"));
+  assert!(!title(&app, 1).contains("offset"));
+  app.clock_domain = ClockDomain::Memory;
+  assert!(title(&app, 0).contains("-200 MHz offset"));
+
+  let source = MockSource { devices: vec![MockDevice { clock_vf_offsets: Some((150, -200)), clock_vf_offset_ranges: Some([(-200, 1000), (-1000, 3000)]), ..MockDevice::new("RTX") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert!(title(&app, 0).contains("+150 MHz offset (-200 to +1000)"));
+  app.clock_domain = ClockDomain::Memory;
+  assert!(title(&app, 0).contains("-200 MHz offset (-1000 to +3000)"));
+}
+
+
+#[test]
+fn giga_units() {
+  assert_eq!(Units::Giga.clock(1875.0), "1.88 GHz");
+  assert_eq!(Units::Mega.clock(1875.0), "1875 MHz");
+  assert_eq!(Units::Giga.memory(2048 << 20), "2.0 GiB");
+  assert_eq!(Units::Mega.memory(2048 << 20), "2048 MiB");
+  let config = Config::parse("units = \"giga\"").unwrap();
+  assert_eq!(config.units, Some(Units::Giga));
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("units = \"kilo\"").is_err());
+
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 24576 << 20, 0, app.history_len));
+  app.gpus[0].supported = MetricFlags::CLOCK | MetricFlags::MEMORY;
+  app.gpus[0].core_clock.push(1875);
+  app.gpus[0].memory_used.push(2048 << 20);
+  app.handle_key_event(KeyCode::Char('u').into());
+  assert_eq!(app.units, Units::Giga);
+
+  let area = Rect::new(0, 0, 120, 30);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let screen: Vec<String> = (0..30).map(|y| (0..120).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(screen[0].contains("GPU 0 Memory 2.0 / 24.0 GiB (8% VRAM)"));
+  assert!(screen.iter().any(|line| line.contains("(1.88 GHz, max 1.88)")));
+  assert!(screen.iter().any(|line| line.contains("(2.0 GiB, max 2.0)")));
+}
+
+
+#[test]
+fn applications_clock_reference_line() {
+  let source = MockSource { devices: vec![MockDevice { clock: Some(1200), max_clock: Some(2000), applications_clock: Some(1500), ..MockDevice::new("Tesla") }, MockDevice { clock: Some(1200), ..MockDevice::new("Unset") }] };
+  let mut app = NvidiaApp { history_len: 30, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  for _ in 0..30 {
+    app.update_state(&source).unwrap();
+  }
+  assert_eq!(app.gpus[0].applications_clock(ClockDomain::Graphics), Some(1500));
+  assert_eq!(app.gpus[1].applications_clock(ClockDomain::Graphics), None);
+
+  let screen = |gpu: usize| {
+    let area = Rect::new(0, 0, 100, 20);
+    let mut buf = Buffer::empty(area);
+    render_clock_chart(&app.gpus[gpu], app.theme(), &app.chart_options(), area, &mut buf);
+    (0..20).map(|y| (0..100).map(|x| buf[(x, y)].symbol()).collect::<String>()).collect::<Vec<_>>()
+  };
+  let tesla = screen(0);
+  assert!(tesla.iter().any(|line| line.contains(" Application 1500 MHz ")));
+  // The setpoint is drawn as a dashed row of dots above the clock line
+  let dots = |line: &String| line.matches('•').count();
+  let setpoint_row = tesla.iter().position(|line| dots(line) > 5).unwrap();
+  let clock_row = tesla.iter().rposition(|line| dots(line) > 5).unwrap();
+  assert!(setpoint_row < clock_row);
+  assert!(!screen(1).iter().any(|line| line.contains(" Application 1500 MHz ")));
+}
+
+
+#[test]
+fn influx_logger_lines() {
+  let options = Options::parse(vec![String::from("--influx-file"), String::from("gpu.lp")]).unwrap();
+  assert_eq!(options.influx_file, Some(std::path::PathBuf::from("gpu.lp")));
+
+  let mut gpu_info = GPUInfo::new(String::from("RTX 4090, OC"), 8192, 1, DEFAULT_HISTORY_LEN);
+  gpu_info.core_clock.push(1800);
+  gpu_info.temperature.push(65);
+  gpu_info.power_usage.push(215500);
+  gpu_info.fan_speeds[0].push(40);
+  gpu_info.record::<u32>(Metric::Utilization, Err(NvmlError::Unknown));
+  let mut unreadable = GPUInfo::new(String::from("Lost GPU"), 0, 0, DEFAULT_HISTORY_LEN);
+  unreadable.skip_tick(&NvmlError::GpuLost);
+
+  let mut output = Vec::new();
+  export::InfluxLogger::new(&mut output).log(&[gpu_info, unreadable]).unwrap();
+  let output = String::from_utf8(output).unwrap();
+  let lines: Vec<&str> = output.lines().collect();
+  assert_eq!(lines.len(), 1);
+  let (point, timestamp) = lines[0].rsplit_once(' ').unwrap();
+  assert_eq!(point, "gpu,index=0,name=RTX\\ 4090\\,\\ OC temp=65,clock=1800,memory_used=0,memory_total=8192,power=216,fan0=40");
+  assert!(timestamp.parse::<u128>().unwrap() > 1_600_000_000_000_000_000);
+}
+
+
+#[test]
+fn help_popup() {
+  assert_eq!(keys::action(event::KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), Some(Action::Quit));
+  assert_eq!(keys::action(KeyCode::Char('c').into()), Some(Action::CycleTheme));
+  assert_eq!(keys::action(KeyCode::Char('X').into()), None);
+
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  app.handle_key_event(KeyCode::Char('?').into());
+  assert!(app.show_help);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 120, 40));
+  app.render(buf.area, &mut buf);
+  let screen: Vec<String> = (0..40).map(|y| (0..120).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  for binding in keys::KEY_BINDINGS.iter() {
+    assert!(screen.iter().any(|line| line.contains(binding.description)), "{} isn't listed", binding.description);
+  }
+  assert!(screen.iter().any(|line| line.contains("↓/j")));
+  assert!(screen.iter().any(|line| line.contains("Ctrl-c")));
+
+  app.selected = Some(0);
+  app.handle_key_event(KeyCode::Esc.into());
+  assert!(!app.show_help);
+  assert_eq!(app.selected, Some(0));
+}
+
+
+#[test]
+fn tab_pages_through_charts() {
+  let mut no_ecc = MockDevice::new("GPU B");
+  no_ecc.ecc_errors = None;
+  let source = MockSource { devices: vec![MockDevice::new("GPU A"), no_ecc] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  let area = Rect::new(0, 0, 120, 40);
+  assert_eq!(app.gpus[0].chart_page_count(false), 4);
+  assert_eq!(app.gpus[1].chart_page_count(false), 4);
+  assert_eq!(app.gpus[0].chart_areas(area, false, None).len(), 8);
+
+  app.handle_key_event(KeyCode::Tab.into());
+  assert_eq!(app.chart_page, Some(0));
+  let pair: Vec<Metric> = app.gpus[0].chart_areas(area, false, app.chart_page).into_keys().collect();
+  assert_eq!(pair, vec![Metric::Clock, Metric::Temperature]);
+
+  app.handle_key_event(KeyCode::BackTab.into());
+  assert_eq!(app.chart_page, None);
+  app.handle_key_event(KeyCode::BackTab.into());
+  assert_eq!(app.chart_page, Some(3));
+  let last: Vec<Metric> = app.gpus[1].chart_areas(area, false, app.chart_page).into_keys().collect();
+  assert_eq!(last, vec![Metric::Codec]);
+  app.handle_key_event(KeyCode::Tab.into());
+  assert_eq!(app.chart_page, None);
+
+  let mut buf = Buffer::empty(area);
+  app.chart_page = Some(1);
+  app.render(area, &mut buf);
+  let screen: String = (0..40).flat_map(|y| (0..120).map(move |x| (x, y))).map(|(x, y)| buf[(x, y)].symbol().to_string()).collect();
+  assert!(screen.contains("Memory") && !screen.contains("Temperature"));
+}
+
+
+#[test]
+fn performance_state_in_clock_title() {
+  use nvml_wrapper::enum_wrappers::device::PerformanceState;
+
+  let source = MockSource { devices: vec![MockDevice { clock: Some(210), performance_state: Some(PerformanceState::Eight), ..MockDevice::new("Idle GPU") }, MockDevice { performance_state: Some(PerformanceState::Unknown), ..MockDevice::new("Odd GPU") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].performance_state, Some(8));
+  assert_eq!(app.gpus[1].performance_state, None);
+  assert!(snapshot::snapshot_json(&app.gpus).to_string().contains("\"performance_state\":8,"));
+
+  let area = Rect::new(0, 0, 240, 40);
+  let clock_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Clock];
+  let mut buf = Buffer::empty(area);
+  render_clock_chart(&app.gpus[0], app.theme(), &app.chart_options(), clock_area, &mut buf);
+  let title: String = (clock_area.left()..clock_area.right()).map(|x| buf[(x, clock_area.top())].symbol()).collect();
+  assert!(title.contains("(210 MHz, max 210, P8)"));
+}
+
+
+#[test]
+fn sort_gpus_by_metric() {
+  let gpu = |name: &str, temperature, utilization| MockDevice { temperature: Some(temperature), utilization: Some((utilization, 0)), ..MockDevice::new(name) };
+  let source = MockSource { devices: vec![gpu("Cool", 40, 90), gpu("Hot", 80, 10), gpu("Warm", 60, 90)] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  let names = |app: &NvidiaApp| app.visible_gpus().iter().map(|(_, gpu_info)| gpu_info.device_name.clone()).collect::<Vec<_>>();
+  assert_eq!(names(&app), ["Cool", "Hot", "Warm"]);
+
+  app.handle_key_event(KeyCode::Char('g').into());
+  assert_eq!(app.gpu_order, metric::GpuOrder::Temperature);
+  assert_eq!(app.display_order(), [1, 2, 0]);
+  assert_eq!(app.process_gpu().unwrap().device_name, "Hot");
+  // Selection follows the displayed order but keeps pointing into `gpus`
+  app.handle_key_event(KeyCode::Down.into());
+  assert_eq!(app.selected, Some(1));
+  app.handle_key_event(KeyCode::Down.into());
+  assert_eq!(app.selected, Some(2));
+
+  app.selected = None;
+  app.handle_key_event(KeyCode::Char('g').into());
+  assert_eq!(names(&app), ["Cool", "Warm", "Hot"]);
+
+  app.versions = Some(status::DriverVersions::default());
+  let mut buf = Buffer::empty(Rect::new(0, 0, 100, 30));
+  app.render(buf.area, &mut buf);
+  let status_row: String = (0..100).map(|x| buf[(x, 29)].symbol()).collect();
+  assert!(status_row.ends_with("Sorted by utilization "));
+}
+
+
+#[test]
+fn rolling_buffers_evict_oldest_sample() {
+  let mut source = MockSource { devices: vec![MockDevice::new("Mock RTX")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  let ticks = DEFAULT_HISTORY_LEN as u32 + 5;
+  for tick in 1..=ticks {
+    source.devices[0].clock = Some(3000 - tick * 10);
+    source.devices[0].temperature = Some(tick);
+    app.update_state(&source).unwrap();
+  }
+
+  let gpu_info = &app.gpus[0];
+  let clocks: Vec<u32> = gpu_info.core_clock.points(|clock| clock as f64).iter().map(|&(_, clock)| clock as u32).collect();
+  let temperatures: Vec<u32> = gpu_info.temperature.points(|temp| temp as f64).iter().map(|&(_, temp)| temp as u32).collect();
+  assert_eq!(temperatures, (6..=ticks).collect::<Vec<_>>());
+  assert_eq!(clocks, (6..=ticks).map(|tick| 3000 - tick * 10).collect::<Vec<_>>());
+  assert_eq!(gpu_info.temperature.latest(), ticks);
+  // The first five ticks scrolled out, but the highest clock is remembered
+  assert_eq!(gpu_info.core_clock.max(), 2940);
+  assert_eq!(gpu_info.core_clock.peak(), 2990);
+}
+
+
+#[test]
+fn no_color_mode() {
+  use nvml_wrapper::bitmasks::device::ThrottleReasons;
+
+  let options = Options::parse(vec![String::from("--no-color")]).unwrap();
+  assert!(options.no_color);
+
+  let mut app = NvidiaApp { use_color: false, paused: true, temp_warn: Some(70), ..NvidiaApp::default() };
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 8192, 1, app.history_len));
+  app.gpus[0].temperature.push(75);
+  app.gpus[0].memory_used.push(4096);
+  app.gpus[0].throttle_reasons = ThrottleReasons::SW_POWER_CAP;
+  app.handle_key_event(KeyCode::Char('c').into());
+  assert_eq!(app.theme().name, "plain");
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 150, 40));
+  app.render(buf.area, &mut buf);
+  assert!(buf.content.iter().all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+  let screen: String = buf.content.iter().map(|cell| cell.symbol()).collect();
+  assert!(screen.contains(" PAUSED ") && screen.contains(" Power Cap "));
+
+  // The theme picked with `c` is still the one remembered
+  let mut config = Config::default();
+  config.remember(&app);
+  assert_eq!(config.theme.as_deref(), Some(THEMES[1].name));
+}
+
+
+#[test]
+fn media_engine_chart() {
+  let mut source = MockSource { devices: vec![MockDevice { jpeg_utilization: Some(35), ..MockDevice::new("L4") }, MockDevice::new("T4")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  assert_eq!(app.gpus[0].engine_utilization.iter().map(|(engine, _)| *engine).collect::<Vec<_>>(), [source::MediaEngine::Jpeg]);
+  assert!(app.gpus[0].supports(Metric::Engines));
+  assert!(!app.gpus[1].supports(Metric::Engines));
+  let area = Rect::new(0, 0, 150, 40);
+  assert!(!app.gpus[1].chart_areas(area, false, None).contains_key(&Metric::Engines));
+
+  app.update_state(&source).unwrap();
+  source.devices[0].jpeg_utilization = Some(60);
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].engine_utilization[0].1.latest(), 60);
+  let json = snapshot::snapshot_json(&app.gpus).to_string();
+  assert!(json.contains("\"jpeg_utilization_pct\":60,\"ofa_utilization_pct\":null,"));
+
+  app.selected = Some(0);
+  let engine_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Engines];
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let title: String = (engine_area.left()..engine_area.right()).map(|x| buf[(x, engine_area.top())].symbol()).collect();
+  assert!(title.contains("GPU 0 Media Engines - L4 (JPEG 60%)"));
+}
+
+
+#[test]
+fn memory_junction_temperature() {
+  let mut source = MockSource { devices: vec![MockDevice { temperature: Some(60), memory_temperature: Some(85), ..MockDevice::new("HBM GPU") }, MockDevice::new("GDDR GPU")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  assert!(app.gpus[0].memory_temperature.is_some());
+  assert!(app.gpus[1].memory_temperature.is_none());
+  app.update_state(&source).unwrap();
+  source.devices[0].memory_temperature = None;
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].memory_temperature.as_ref().unwrap().recent(2), vec![85, 0]);
+  assert!(app.gpus[0].errors.is_empty());
+
+  source.devices[0].memory_temperature = Some(95);
+  app.update_state(&source).unwrap();
+  let area = Rect::new(0, 0, 80, 12);
+  let mut buf = Buffer::empty(area);
+  render_temperature_chart(&app.gpus[0], &THEMES[0], &ChartOptions::default(), TemperatureThresholds::default(), area, &mut buf);
+  let title: String = (0..80).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(title.contains("60 °C, memory 95 °C, max 60"));
+  let labels: Vec<String> = (0..12).map(|y| (1..4).map(|x| buf[(x, y)].symbol()).collect::<String>().trim().to_string()).filter(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_digit())).collect();
+  assert!(labels[0].parse::<u32>().unwrap() > 95);
+}
+
+
+#[test]
+fn reserved_memory_breakdown() {
+  let mut source = MockSource { devices: vec![MockDevice { memory_used: Some(6 << 30), memory_total: 10 << 30, memory_reserved: Some(1 << 30), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].memory_reserved, Some(1 << 30));
+
+  let gauge = |gpu_info: &GPUInfo| {
+    let area = Rect::new(0, 0, 100, 1);
+    let mut buf = Buffer::empty(area);
+    render_memory_gauge(gpu_info, &THEMES[0], Units::Mega, area, &mut buf);
+    buf
+  };
+  let buf = gauge(&app.gpus[0]);
+  let label: String = (0..100).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(label.contains("GPU 0 Memory 6144 / 10240 MiB (60% VRAM), 1024 MiB reserved"));
+  // 50 cells used by processes, 10 reserved by the driver and 40 free
+  assert_eq!(buf[(49, 0)].bg, THEMES[0].gauge_color);
+  assert_eq!(buf[(50, 0)].bg, THEMES[0].memory_reserved_color);
+  assert_eq!(buf[(59, 0)].bg, THEMES[0].memory_reserved_color);
+  assert_eq!(buf[(60, 0)].bg, THEMES[0].background);
+
+  // Older drivers fall back to the plain used / free gauge
+  source.devices[0].memory_reserved = None;
+  app.update_state(&source).unwrap();
+  let buf = gauge(&app.gpus[0]);
+  let label: String = (0..100).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(label.contains("(60% VRAM)") && !label.contains("reserved"));
+}
+
+
+#[test]
+#[cfg(unix)]
+fn crit_hooks_fire_once_per_crossing() {
+  let options = Options::parse(["--temp-crit", "90", "--on-crit-webhook", "http://localhost:8080/alert"].map(String::from)).unwrap();
+  assert_eq!(options.crit_hooks.webhook, Some(alert::Webhook { host: String::from("localhost"), port: 8080, path: String::from("/alert") }));
+  assert_eq!("http://example.com".parse::<alert::Webhook>().unwrap().to_string(), "http://example.com:80/");
+  assert!(Options::parse(["--temp-crit", "90", "--on-crit-webhook", "https://example.com"].map(String::from)).is_err());
+  assert!(Options::parse(["--on-crit", "true"].map(String::from)).unwrap_err().contains("require --temp-crit"));
+
+  let log = std::env::temp_dir().join(format!("nvidia-on-crit-{}.log", std::process::id()));
+  let _ = std::fs::remove_file(&log);
+  let command = format!("echo \"$NVIDIA_GPU_INDEX $NVIDIA_GPU_TEMP\" >> '{}'", log.display());
+  let options = Options::parse([String::from("--temp-crit"), String::from("90"), String::from("--on-crit"), command]).unwrap();
+  let mut source = MockSource { devices: vec![MockDevice::new("Mock RTX")] };
+  let mut collector = NvidiaApp { history_len: 1, ..NvidiaApp::default() };
+  collector.add_gpus(&source, None).unwrap();
+  let mut app = NvidiaApp { temp_crit: options.temp_crit, crit_hooks: options.crit_hooks, gpus: collector.gpus.clone(), ..NvidiaApp::default() };
+  let lines = |count: usize| {
+    for _ in 0..100 {
+      match std::fs::read_to_string(&log) {
+        Ok(contents) if contents.lines().count() >= count => return contents,
+        _ => std::thread::sleep(Duration::from_millis(20)),
+      }
+    }
+    std::fs::read_to_string(&log).unwrap_or_default()
+  };
+
+  for temperature in [85, 91, 95, 80, 92] {
+    source.devices[0].temperature = Some(temperature);
+    collector.update_state(&source).unwrap();
+    app.push_readings(&collector.gpus);
+    if temperature == 95 {
+      assert_eq!(lines(1), "0 91\n");
+    }
+  }
+  std::thread::sleep(Duration::from_millis(100));
+  assert_eq!(lines(2), "0 91\n0 92\n");
+  let _ = std::fs::remove_file(&log);
+}
+
+
+#[test]
+fn small_terminal_falls_back_to_compact() {
+  let source = MockSource { devices: (0..8).map(|i| MockDevice::new(&format!("GPU {}", i))).collect() };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  let screen = |app: &NvidiaApp, area: Rect| {
+    let mut buf = Buffer::empty(area);
+    app.render(area, &mut buf);
+    (0..area.height).map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect::<String>()).collect::<Vec<_>>()
+  };
+
+  assert_eq!(app.view(Rect::new(0, 0, 120, 200)), View::Charts);
+  let area = Rect::new(0, 0, 120, 20);
+  assert_eq!(app.view(area), View::Compact);
+  let rows = screen(&app, area);
+  assert!(rows[0].contains("NVIDIA GPUs") && rows[9].contains("GPU 7"));
+  app.click(area, 5, 4);
+  assert_eq!(app.selected, Some(2));
+  // A single focused GPU has room for its charts again
+  assert_eq!(app.view(area), View::Charts);
+
+  let area = Rect::new(0, 0, 20, 3);
+  assert_eq!(app.view(area), View::TooSmall);
+  assert!(screen(&app, area).concat().contains("Terminal too small"));
+  assert!(app.gpu_areas(area).is_empty());
+}
+
+
+#[test]
+fn efficiency_chart() {
+  let mut source = MockSource { devices: vec![MockDevice { clock: Some(1800), power_usage: Some(250_000), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert!((app.gpus[0].efficiency.latest() - 7.2).abs() < 1e-9);
+  source.devices[0].power_usage = None;
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].efficiency.latest(), 0.0);
+  source.devices[0].power_usage = Some(300_000);
+  app.update_state(&source).unwrap();
+
+  app.handle_key_event(KeyCode::Char('e').into());
+  assert!(app.show_efficiency);
+  let area = Rect::new(0, 0, 150, 30);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let power_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Power];
+  let title: String = (power_area.left()..power_area.right()).map(|x| buf[(x, power_area.top())].symbol()).collect();
+  assert!(title.contains("Efficiency") && title.contains("(6.00 MHz/W, best 7.20)"));
+}
+
+
+#[test]
+fn nvlink_panel() {
+  use nvlink::NvLinkCounters;
+  let links = vec![NvLinkCounters { active: true, rx: 0, tx: 0 }, NvLinkCounters { active: false, rx: 0, tx: 0 }];
+  let mut source = MockSource { devices: vec![MockDevice { nvlinks: links, ..MockDevice::new("GPU A") }, MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  assert_eq!(app.gpus[0].nvlinks.len(), 2);
+  assert!(app.gpus[1].nvlinks.is_empty());
+  let area = Rect::new(0, 0, 150, 40);
+  assert!(!app.gpus[1].chart_areas(area, false, None).contains_key(&Metric::NvLink));
+
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].nvlinks[0].throughput, None);
+  // 25 GB/s received and 500 MB/s sent over the default one-second interval
+  source.devices[0].nvlinks[0].rx = 25_000_000_000 / 1024;
+  source.devices[0].nvlinks[0].tx = 500_000_000 / 1024;
+  app.update_state(&source).unwrap();
+
+  let mut buf = Buffer::empty(area);
+  let panel = app.gpus[0].chart_areas(area, false, None)[&Metric::NvLink];
+  nvlink::render_nvlink_panel(&app.gpus[0], &THEMES[0], panel, &mut buf);
+  let rows: Vec<String> = (panel.top()..panel.bottom()).map(|y| (panel.left()..panel.right()).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(rows[0].contains("1 of 2 links active"));
+  assert!(rows[2].contains("Active") && rows[2].contains("25.0 GB/s") && rows[2].contains("500 MB/s"));
+  assert!(rows[3].contains("Inactive") && rows[3].contains("0 MB/s"));
+}
+
+
+#[test]
+fn rescan_picks_up_hotplugged_gpus() {
+  assert_eq!(Options::parse(["--rescan", "10"].map(String::from)).unwrap().rescan, Some(10));
+  assert!(Options::parse(["--rescan", "0"].map(String::from)).is_err());
+
+  let mut source = MockSource { devices: vec![MockDevice::new("GPU A")] };
+  let mut collector = NvidiaApp { history_len: 1, ..NvidiaApp::default() };
+  collector.add_gpus(&source, None).unwrap();
+  let mut app = NvidiaApp { history_len: 4, gpus: collector.gpus.clone(), ..NvidiaApp::default() };
+  app.gpus[0].resize_history(app.history_len);
+  for temperature in [50, 60] {
+    source.devices[0].temperature = Some(temperature);
+    collector.update_state(&source).unwrap();
+    app.push_readings(&collector.gpus);
+  }
+
+  source.devices.push(MockDevice { temperature: Some(70), ..MockDevice::new("GPU B") });
+  collector.rescan_gpus(&source, None).unwrap();
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+  assert_eq!(app.gpus.len(), 2);
+  assert_eq!(app.gpus[0].temperature.recent(3), [50, 60, 60]);
+  assert_eq!(app.gpus[1].temperature.recent(4), [0, 0, 0, 70]);
+
+  // GPU A leaves, so GPU B takes over index 0 and counts as a new GPU
+  app.selected = Some(1);
+  source.devices.remove(0);
+  collector.rescan_gpus(&source, Some(&[1])).unwrap();
+  assert!(collector.gpus.len() == 2 && collector.gpus.iter().all(|gpu_info| gpu_info.disconnected));
+  collector.rescan_gpus(&source, Some(&[1])).unwrap();
+  assert!(collector.gpus.is_empty());
+  collector.rescan_gpus(&source, None).unwrap();
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+  assert_eq!(app.gpus.len(), 1);
+  assert_eq!(app.gpus[0].temperature.recent(2), [0, 70]);
+  assert_eq!(app.selected, None);
+}
+
+
+#[test]
+fn baseline_deltas() {
+  let mut source = MockSource { devices: vec![MockDevice { temperature: Some(50), power_usage: Some(100_000), memory_used: Some(1 << 30), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  let title = |app: &NvidiaApp, metric: Metric| metric_title("Chart", &app.gpus[0], metric, String::from("now"), u16::MAX).content.to_string();
+  assert!(title(&app, Metric::Temperature).ends_with("(now)"));
+
+  app.handle_key_event(KeyCode::Char('b').into());
+  source.devices[0].temperature = Some(62);
+  source.devices[0].power_usage = Some(80_000);
+  source.devices[0].memory_used = Some(3 << 30);
+  app.update_state(&source).unwrap();
+  assert!(title(&app, Metric::Temperature).ends_with("(now, +12 °C since baseline)"));
+  assert!(title(&app, Metric::Power).ends_with("(now, -20 W since baseline)"));
+  assert!(title(&app, Metric::Memory).ends_with("(now, +2048 MiB since baseline)"));
+  assert!(title(&app, Metric::Pcie).ends_with("(now)"));
+
+  app.handle_key_event(KeyCode::Char('b').into());
+  assert!(app.gpus[0].baseline.is_none());
+}
+
+
+#[cfg(unix)]
+#[test]
+fn socket_serves_json_snapshots() {
+  use std::io::Read;
+  use std::os::unix::net::UnixStream;
+
+  let path = std::env::temp_dir().join(format!("nvidia-socket-{}.sock", std::process::id()));
+  assert_eq!(Options::parse(["--socket".to_string(), path.display().to_string()]).unwrap().socket, Some(path.clone()));
+  // A stale socket from an earlier run is replaced
+  let stale = std::os::unix::net::UnixListener::bind(&path).unwrap();
+  drop(stale);
+
+  let shared_gpus = Arc::new(Mutex::new(vec![GPUInfo::new(String::from("GPU A"), 0, 0, 1)]));
+  let socket_file = socket::spawn(&path, Arc::clone(&shared_gpus)).unwrap();
+  let read = || {
+    let mut json = String::new();
+    UnixStream::connect(&path).unwrap().read_to_string(&mut json).unwrap();
+    json
+  };
+  assert!(read().starts_with("[{\"index\":0,\"mig_index\":null,\"name\":\"GPU A\""));
+  shared_gpus.lock().unwrap()[0].temperature.push(64);
+  assert!(read().contains("\"temperature_c\":64"));
+  assert!(read().ends_with("}]\n"));
+
+  drop(socket_file);
+  assert!(!path.exists());
+}
+
+
+#[test]
+fn braille_chart_marker() {
+  let config = Config::parse("marker = \"braille\"").unwrap();
+  assert_eq!(config.marker, Some(ChartMarker::Braille));
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("marker = \"line\"").is_err());
+  let mut app = NvidiaApp::default();
+  config.apply(&mut app);
+  assert_eq!(app.marker.marker(), Marker::Braille);
+
+  let source = MockSource { devices: vec![MockDevice { temperature: Some(60), ..MockDevice::new("GPU A") }] };
+  app.add_gpus(&source, None).unwrap();
+  for _ in 0..5 {
+    app.update_state(&source).unwrap();
+  }
+  let area = Rect::new(0, 0, 150, 40);
+  let mut buf = Buffer::empty(area);
+  app.render_gpu(&app.gpus[0], area, &mut buf);
+  let braille = |buf: &Buffer| buf.content().iter().any(|cell| cell.symbol().chars().all(|c| ('\u{2801}'..='\u{28ff}').contains(&c)));
+  assert!(braille(&buf));
+  app.marker = ChartMarker::Dot;
+  let mut buf = Buffer::empty(area);
+  app.render_gpu(&app.gpus[0], area, &mut buf);
+  assert!(!braille(&buf));
+}
+
+
+#[test]
+fn throttle_event_counter() {
+  let mut source = MockSource { devices: vec![MockDevice::new("GPU A")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  let reasons = [
+    ThrottleReasons::SW_THERMAL_SLOWDOWN,
+    ThrottleReasons::HW_THERMAL_SLOWDOWN,
+    ThrottleReasons::NONE,
+    ThrottleReasons::SW_THERMAL_SLOWDOWN | ThrottleReasons::SW_POWER_CAP,
+    ThrottleReasons::SW_POWER_CAP,
+    ThrottleReasons::GPU_IDLE,
+  ];
+  for reason in reasons {
+    source.devices[0].throttle_reasons = Some(reason);
+    app.update_state(&source).unwrap();
+  }
+  assert_eq!(app.gpus[0].throttle_events, ThrottleEvents { thermal: 2, power: 1 });
+  // A failed query counts as nothing
+  source.devices[0].throttle_reasons = None;
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].throttle_events.summary().as_deref(), Some("2 thermal / 1 power throttles"));
+  assert_eq!(ThrottleEvents::default().summary(), None);
+
+  let area = Rect::new(0, 0, 240, 40);
+  let clock_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Clock];
+  let mut buf = Buffer::empty(area);
+  render_clock_chart(&app.gpus[0], app.theme(), &app.chart_options(), clock_area, &mut buf);
+  let title: String = (clock_area.left()..clock_area.right()).map(|x| buf[(x, clock_area.top())].symbol()).collect();
+  assert!(title.contains("2 thermal / 1 power throttles"));
+}
+
+
+#[test]
+fn status_bar_update_age() {
+  assert_eq!(status::format_age(Duration::from_millis(4900)), "4s");
+  assert_eq!(status::format_age(Duration::from_secs(125)), "2m05s");
+
+  let source = MockSource { devices: vec![MockDevice::new("GPU A")] };
+  let mut collector = NvidiaApp { history_len: 1, ..NvidiaApp::default() };
+  collector.add_gpus(&source, None).unwrap();
+  collector.update_state(&source).unwrap();
+  let mut app = NvidiaApp { gpus: collector.gpus.clone(), versions: Some(status::DriverVersions::default()), ..NvidiaApp::default() };
+  let status_row = |app: &NvidiaApp| {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 100, 30));
+    app.render(buf.area, &mut buf);
+    let row: String = (0..100).map(|x| buf[(x, 29)].symbol()).collect();
+    (row, buf[(98, 29)].fg)
+  };
+  assert!(!status_row(&app).0.contains("Updated"));
+
+  app.push_readings(&collector.gpus);
+  let (row, color) = status_row(&app);
+  assert!(row.ends_with("Updated 0s ago "));
+  assert_ne!(color, app.theme().temp_warn_color);
+
+  // Paused, no readings are taken in and the age keeps growing
+  app.paused = true;
+  app.last_update = Some(Instant::now() - Duration::from_secs(125));
+  app.push_readings(&collector.gpus);
+  let (row, color) = status_row(&app);
+  assert!(row.ends_with("Updated 2m05s ago "));
+  assert_eq!(color, app.theme().temp_warn_color);
+}
+
+
+#[test]
+fn gpu_uuid_and_serial() {
+  let uuid = "GPU-5f1c2e4a-77b0-4c1d-9a3e-0123456789ab";
+  let card = MockDevice { uuid: Some(String::from(uuid)), serial: Some(String::from("1320221012345")), temperature: Some(60), ..MockDevice::new("A100") };
+  let mut source = MockSource { devices: vec![card] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].serial.as_deref(), Some("1320221012345"));
+
+  let title = |app: &NvidiaApp| metric_title("Clock", &app.gpus[0], Metric::Clock, String::new(), u16::MAX).content.to_string();
+  assert!(title(&app).starts_with("GPU 0 Clock - A100 ("));
+  app.handle_key_event(KeyCode::Char('i').into());
+  assert!(title(&app).starts_with(&format!("GPU 0 Clock - {} (", uuid)));
+
+  assert!(snapshot::snapshot_json(&app.gpus).to_string().contains(&format!("\"name\":\"A100\",\"uuid\":\"{}\",\"serial\":\"1320221012345\",", uuid)));
+  assert!(snapshot::snapshot_xml(&app.gpus, &status::DriverVersions::default()).contains(&format!("\t\t<serial>1320221012345</serial>\n\t\t<uuid>{}</uuid>\n", uuid)));
+  assert!(prometheus::render(&app.gpus).contains(&format!("nvidia_gpu_temperature_celsius{{gpu=\"0\",name=\"A100\",uuid=\"{}\"}} 60\n", uuid)));
+  let mut output = Vec::new();
+  InfluxLogger::new(&mut output).log(&app.gpus).unwrap();
+  assert!(String::from_utf8(output).unwrap().starts_with(&format!("gpu,index=0,name=A100,uuid={} temp=60,", uuid)));
+  let mut output = Vec::new();
+  CsvLogger::new(&mut output).unwrap().log(&app.gpus).unwrap();
+  assert!(String::from_utf8(output).unwrap().contains(&format!(",{},1320221012345\n", uuid)));
+
+  // The same card enumerated at another index after a reboot or hotplug keeps its history
+  source.devices.insert(0, MockDevice::new("Other"));
+  app.rescan_gpus(&source, None).unwrap();
+  assert_eq!(app.gpus.len(), 2);
+  assert_eq!((app.gpus[1].index, app.gpus[1].temperature.latest()), (1, 60));
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[1].temperature.latest(), 60);
+  assert!(app.gpus[1].show_uuid && app.gpus[0].show_uuid);
+}
+
+
+#[test]
+fn demo_source() {
+  assert!(Options::parse(vec![String::from("--demo")]).unwrap().demo);
+
+  let source = demo::DemoSource::new();
+  let mut app = NvidiaApp { show_processes: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus.len(), 4);
+  assert!(app.gpus.iter().all(|gpu_info| !gpu_info.tick_failed() && gpu_info.uuid.is_some()));
+  assert!(app.gpus.iter().all(|gpu_info| (35..=85).contains(&gpu_info.temperature.latest())));
+  assert!(app.gpus[0].supports(Metric::Fan) && !app.gpus[0].supports(Metric::NvLink));
+  assert!(!app.gpus[2].supports(Metric::Fan) && app.gpus[2].supports(Metric::Ecc));
+  assert_eq!(app.gpus[3].nvlinks.len(), 4);
+}
+
+
+#[test]
+fn clock_chart_y_labels_and_gridlines() {
+  let source = MockSource { devices: vec![MockDevice { clock: Some(300), max_clock: Some(2000), ..MockDevice::new("A100") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(y_ticks([0.0, 2000.0]), vec![0.0, 500.0, 1000.0, 1500.0, 2000.0]);
+
+  let area = Rect::new(0, 0, 60, 20);
+  let render = |gridlines: bool| {
+    let mut buf = Buffer::empty(area);
+    render_clock_chart(&app.gpus[0], app.theme(), &ChartOptions { gridlines, ..app.chart_options() }, area, &mut buf);
+    buf
+  };
+  let labels: Vec<String> = (0..20).map(|y| (1..5).map(|x| render(false)[(x, y)].symbol().to_string()).collect::<String>().trim().to_string()).filter(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_digit())).collect();
+  assert_eq!(labels, vec!["2000", "1500", "1000", "500", "0"]);
+  let dots = |buf: &Buffer| buf.content().iter().filter(|cell| cell.symbol() == "•").count();
+  assert!(dots(&render(true)) > dots(&render(false)) + 3 * 40);
+
+  let config = Config::parse("gridlines = true\n").unwrap();
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  config.apply(&mut app);
+  assert!(app.gridlines);
+}
+
+
+#[test]
+fn save_screen() {
+  let mut app = NvidiaApp::default();
+  app.handle_key_event(KeyCode::Char('w').into());
+  assert!(app.save_screen);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 8, 2));
+  buf.set_string(0, 0, "GPU 0", Style::new().fg(Color::Rgb(255, 0, 128)).add_modifier(Modifier::BOLD));
+  buf.set_string(0, 1, "72 °C", Style::new().fg(Color::Yellow));
+  assert_eq!(screenshot::to_text(&buf), "GPU 0\n72 °C\n");
+  assert_eq!(screenshot::to_ansi(&buf), "\x1b[0;1;38;2;255;0;128mGPU 0\x1b[0m   \x1b[0m\n\x1b[0;33m72 °C\x1b[0m   \x1b[0m\n");
+
+  let dir = std::env::temp_dir().join(format!("nvidia-screen-test-{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = screenshot::save(&buf, &dir).unwrap();
+  assert_eq!(std::fs::read_to_string(&path).unwrap(), screenshot::to_text(&buf));
+  assert!(path.with_extension("ans").exists());
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+
+#[test]
+fn long_run_keeps_histories_bounded() {
+  // Multi-day runs sample hundreds of thousands of times, so nothing may grow per tick
+  let history_lens = |gpu_info: &GPUInfo| {
+    let mut lens = vec![gpu_info.core_clock.len(), gpu_info.sm_clock.len(), gpu_info.memory_clock.len(), gpu_info.video_clock.len(), gpu_info.temperature.len(), gpu_info.memory_used.len(), gpu_info.gpu_utilization.len(), gpu_info.memory_utilization.len(), gpu_info.power_usage.len(), gpu_info.efficiency.len(), gpu_info.pcie_rx.len(), gpu_info.pcie_tx.len(), gpu_info.encoder_utilization.len(), gpu_info.decoder_utilization.len()];
+    lens.extend(gpu_info.memory_temperature.iter().map(History::len));
+    lens.extend(gpu_info.fan_speeds.iter().map(History::len));
+    lens.extend(gpu_info.engine_utilization.iter().map(|(_, utilization)| utilization.len()));
+    lens
+  };
+  let source = demo::DemoSource::new();
+  let mut collector = NvidiaApp { history_len: 1, show_processes: true, ..NvidiaApp::default() };
+  collector.add_gpus(&source, None).unwrap();
+  let mut app = NvidiaApp { show_processes: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  let sizes = |app: &NvidiaApp| app.gpus.iter().map(history_lens).collect::<Vec<_>>();
+  let (collector_sizes, app_sizes) = (sizes(&collector), sizes(&app));
+  assert!(collector_sizes.iter().flatten().all(|&len| len == 1));
+  assert!(app_sizes.iter().flatten().all(|&len| len == app.history_len));
+
+  for _ in 0..2000 {
+    collector.update_state(&source).unwrap();
+    app.push_readings(&collector.gpus);
+  }
+  assert_eq!(sizes(&collector), collector_sizes);
+  assert_eq!(sizes(&app), app_sizes);
+  assert!(app.gpus.iter().all(|gpu_info| gpu_info.processes.len() == 1 && gpu_info.nvlinks.len() <= 4));
+}
+
+
+#[test]
+fn fahrenheit_display() {
+  assert!(Options::parse(vec![String::from("--fahrenheit")]).unwrap().fahrenheit);
+  assert_eq!(TemperatureUnit::Fahrenheit.format(100), "212 °F");
+  assert_eq!(TemperatureUnit::Celsius.format(100), "100 °C");
+
+  let mut source = MockSource { devices: vec![MockDevice { temperature: Some(50), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  app.gpus[0].temperature_unit = TemperatureUnit::Fahrenheit;
+  app.handle_key_event(KeyCode::Char('b').into());
+  source.devices[0].temperature = Some(60);
+  app.update_state(&source).unwrap();
+
+  let area = Rect::new(0, 0, 80, 12);
+  let mut buf = Buffer::empty(area);
+  render_temperature_chart(&app.gpus[0], &THEMES[0], &ChartOptions::default(), TemperatureThresholds::default(), area, &mut buf);
+  let title: String = (0..80).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(title.contains("(140 °F, max 140, +18 °F since baseline)"));
+  let labels: Vec<String> = (0..12).map(|y| (1..4).map(|x| buf[(x, y)].symbol()).collect::<String>().trim().to_string()).filter(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_digit())).collect();
+  assert_eq!(labels, vec!["151", "32"]);
+  assert!(stats::stats_line(&app.gpus[0], Metric::Temperature, app.clock_domain).ends_with("max 140 °F"));
+
+  // Exports stay in °C
+  assert!(snapshot::snapshot_json(&app.gpus).to_string().contains("\"temperature_c\":60"));
+}
+
+
+#[test]
+fn clock_steps_are_marked() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, 8);
+  gpu_info.supported = MetricFlags::CLOCK;
+  for clock in [1800, 1810, 1200, 1250, 1900, 1900] {
+    gpu_info.core_clock.push(clock);
+  }
+  // The step up from the unfilled zeros isn't a boost
+  assert_eq!(clock_steps(&gpu_info.core_clock), vec![(-3.0, 1200.0), (-1.0, 1900.0)]);
+
+  let area = Rect::new(0, 0, 80, 20);
+  let mut buf = Buffer::empty(area);
+  render_clock_chart(&gpu_info, &THEMES[0], &ChartOptions::default(), area, &mut buf);
+  let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+  assert!(screen.contains("Clock Step"));
+  assert!(buf.content().iter().any(|cell| cell.symbol() == "█" && cell.fg == THEMES[0].temp_warn_color));
+}
+
+
+#[test]
+fn charts_enabled_in_config() {
+  let config = Config::parse("charts = \"temperature, power\"\ngpu1_charts = \"clock\"\n").unwrap();
+  assert_eq!(config.charts, Some(MetricFlags::TEMPERATURE | MetricFlags::POWER));
+  assert_eq!(config.gpu_charts[&1], MetricFlags::CLOCK);
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("charts = \"sparkles\"").unwrap_err().contains("unknown chart \"sparkles\""));
+  assert!(Config::parse("charts = \"\"").is_err());
+  assert!(Config::parse("gpuX_charts = \"clock\"").is_err());
+
+  let source = MockSource { devices: vec![MockDevice::new("GPU A"), MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  config.apply(&mut app);
+  app.add_gpus(&source, None).unwrap();
+  let mut gpus = std::mem::take(&mut app.gpus);
+  for gpu_info in gpus.iter_mut() {
+    app.apply_display_settings(gpu_info);
+  }
+  app.gpus = gpus;
+
+  // The remaining charts share the space the others left
+  let area = Rect::new(0, 0, 120, 30);
+  let charts: Vec<Metric> = app.gpus[0].chart_areas(area, false, None).into_keys().collect();
+  assert_eq!(charts, vec![Metric::Temperature, Metric::Power]);
+  assert_eq!(app.gpus[0].chart_areas(area, false, None)[&Metric::Temperature].width, 60);
+  assert_eq!(app.gpus[1].chart_areas(area, false, None)[&Metric::Clock], area);
+}
+
+
+#[test]
+fn high_res_sample_averaging() {
+  assert!(Options::parse(vec![String::from("--high-res")]).unwrap().high_res);
+  let source = MockSource { devices: vec![MockDevice { utilization: Some((40, 10)), utilization_samples: Some(vec![(10, 20), (20, 90), (30, 40)]), power_usage: Some(100_000), power_samples: Some(vec![(5, 100_000), (6, 300_000)]), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!((app.gpus[0].gpu_utilization.latest(), app.gpus[0].power_usage.latest()), (40, 100_000));
+
+  app.high_res = true;
+  app.update_state(&source).unwrap();
+  assert_eq!((app.gpus[0].gpu_utilization.latest(), app.gpus[0].power_usage.latest()), (50, 200_000));
+  let title = |app: &NvidiaApp, metric: Metric| {
+    let area = Rect::new(0, 0, 100, 12);
+    let mut buf = Buffer::empty(area);
+    match metric {
+      Metric::Power => render_power_chart(&app.gpus[0], app.theme(), &app.chart_options(), area, &mut buf),
+      _ => render_utilization_chart(&app.gpus[0], app.theme(), &app.chart_options(), area, &mut buf),
+    }
+    (0..100).map(|x| buf[(x, 0)].symbol()).collect::<String>()
+  };
+  assert!(title(&app, Metric::Utilization).contains("(SM 50% / Mem 10%, SM 20–90% within tick)"));
+  assert!(title(&app, Metric::Power).contains("(200 W, 100–300 W within tick)"));
+
+  // Only samples newer than the last tick's count, and without any the reading stands
+  app.update_state(&source).unwrap();
+  assert_eq!((app.gpus[0].gpu_utilization.latest(), app.gpus[0].power_usage.latest()), (40, 100_000));
+  assert!(title(&app, Metric::Utilization).contains("(SM 40% / Mem 10%)"));
+}
+
+
+#[test]
+fn log_scale_pcie_chart() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, 8);
+  gpu_info.supported = MetricFlags::PCIE;
+  for throughput in [0, 10, 100_000] {
+    gpu_info.pcie_rx.push(throughput);
+  }
+  // Zero is clamped to the epsilon, which sits at the bottom of the axis
+  assert_eq!(log_points(&[(0.0, 0.0), (1.0, 100.0)]), vec![(0.0, 0.0), (1.0, 2.0)]);
+  assert_eq!(log_axis(100_000.0, "KB/s"), ([0.0, 5.0], ["1", "10", "100", "1k", "10k", "100k KB/s"].map(String::from).to_vec()));
+  assert_eq!(log_axis(0.0, "W").1, vec!["1", "10 W"]);
+
+  let area = Rect::new(0, 0, 80, 20);
+  let mut buf = Buffer::empty(area);
+  render_pcie_chart(&gpu_info, &THEMES[0], &ChartOptions { log_charts: MetricFlags::PCIE, ..ChartOptions::default() }, area, &mut buf);
+  let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+  assert!(screen.contains("100k KB/s"));
+  assert!(screen.contains("PCIe KB/s (log)"));
+
+  let mut app = NvidiaApp::default();
+  assert!(!app.chart_options().log_scaled(Metric::Pcie));
+  app.handle_key_event(KeyCode::Char('l').into());
+  assert!(app.chart_options().log_scaled(Metric::Pcie));
+  assert!(!app.chart_options().log_scaled(Metric::Power));
+
+  let config = Config::parse("log_charts = \"power,pcie\"").unwrap();
+  assert_eq!(config.log_charts, Some(config::LOG_SCALE_CHARTS));
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("log_charts = \"clock\"").is_err());
+  config.apply(&mut app);
+  assert!(app.chart_options().log_scaled(Metric::Power));
+}
+
+
+#[test]
+fn process_memory_over_time() {
+  const MIB: u64 = 1024 * 1024;
+  let process = |pid, name: &str, mib| GpuProcess { pid, name: String::from(name), kind: ProcessKind::Compute, used_memory: Some(mib * MIB) };
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, 4);
+  gpu_info.process_memory.record(&[process(10, "leaky", 100), process(20, "steady", 300)]);
+  gpu_info.process_memory.record(&[process(10, "leaky", 500), process(20, "steady", 300)]);
+  assert_eq!(gpu_info.process_memory.largest_growth(), Some(("leaky", 400 * MIB)));
+  assert_eq!((gpu_info.process_memory.band(10), gpu_info.process_memory.band(20)), (Some(0), Some(1)));
+
+  // Stacked from the bottom, with the newest samples on the right
+  let area = Rect::new(0, 0, 62, 10);
+  let mut buf = Buffer::empty(area);
+  process::render_process_memory(&gpu_info, &THEMES[0], area, &mut buf);
+  let title: String = (0..62).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(title.contains("peak 800 MiB, leaky +400 MiB"));
+  assert_eq!(buf[(60, 8)].symbol(), "█");
+  assert_eq!(buf[(60, 1)].symbol(), "▓");
+  assert_eq!(buf[(1, 8)].symbol(), " ");
+
+  // An exited process ages out once its last sample scrolls out of the window
+  for _ in 0..3 {
+    gpu_info.process_memory.record(&[process(20, "steady", 300)]);
+  }
+  assert_eq!(gpu_info.process_memory.band(10), Some(0));
+  gpu_info.process_memory.record(&[process(20, "steady", 300)]);
+  assert_eq!(gpu_info.process_memory.band(10), None);
+  assert_eq!(gpu_info.process_memory.band(20), Some(0));
+}
+
+
+#[test]
+fn gpus_json_endpoints() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 1, 3);
+  gpu_info.index = 2;
+  for temperature in [50, 55, 60] {
+    gpu_info.temperature.push(temperature);
+  }
+  let gpus = [gpu_info];
+
+  let (status, content_type, body) = prometheus::respond("/gpus/2", &gpus);
+  assert_eq!((status, content_type), ("200 OK", "application/json"));
+  assert!(body.starts_with("{\"index\":2,"));
+  assert!(body.contains("\"temperature_c\":[50,55,60]"));
+  assert!(body.contains("\"fan_speeds_pct\":[[0,0,0]]"));
+
+  let (_, _, body) = prometheus::respond("/gpus", &gpus);
+  assert!(body.starts_with("[{\"index\":2,") && body.ends_with("}]"));
+  assert_eq!(prometheus::respond("/gpus/0", &gpus).0, "404 Not Found");
+  assert_eq!(prometheus::respond("/gpus/x", &gpus).0, "404 Not Found");
+  assert_eq!(prometheus::respond("/metrics", &gpus).1, "text/plain; version=0.0.4");
+}
+
+
+#[test]
+fn insufficient_permissions_are_explained() {
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, 8);
+  gpu_info.record::<Vec<GpuProcess>>(Metric::Processes, Err(NvmlError::NoPermission));
+  gpu_info.record::<u32>(Metric::Power, Err(NvmlError::NoPermission));
+  gpu_info.record::<u32>(Metric::Clock, Err(NvmlError::Unknown));
+  gpu_info.temperature.push(60);
+  assert_eq!(gpu_info.error(Metric::Processes), Some("insufficient permissions for process list (run as root or add to video group)"));
+  assert_eq!(gpu_info.unavailable(Metric::Clock), Some("N/A"));
+  assert_eq!(gpu_info.unavailable(Metric::Temperature), None);
+
+  let area = Rect::new(0, 0, 60, 8);
+  let mut buf = Buffer::empty(area);
+  process::render_process_table(&gpu_info, &THEMES[0], area, &mut buf);
+  let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+  assert!(screen.contains("insufficient permissions for process"));
+  assert!(screen.contains("add to video group"));
+
+  let title = metric_title("Power", &gpu_info, Metric::Power, String::from("250 W"), u16::MAX);
+  assert!(title.content.to_string().contains("insufficient permissions for power"));
+  // As do the titles of the charts of two metrics
+  gpu_info.record::<u32>(Metric::Fan, Err(NvmlError::NoPermission));
+  let area = Rect::new(0, 0, 200, 8);
+  let mut buf = Buffer::empty(area);
+  render_cooling_chart(&gpu_info, &THEMES[0], &ChartOptions::default(), area, &mut buf);
+  let top: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(top.contains("(insufficient permissions for fan"));
+
+  // Once the query is allowed again, the panel goes back to normal
+  gpu_info.record(Metric::Processes, Ok(()));
+  assert!(!gpu_info.denied.contains(MetricFlags::PROCESSES));
+  let mut buf = Buffer::empty(area);
+  process::render_process_table(&gpu_info, &THEMES[0], area, &mut buf);
+  let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+  assert!(screen.contains("GPU Memory"));
+}
+
+
+#[test]
+fn hostname_banner() {
+  let options = Options::parse(vec![String::from("--label"), String::from("training-node-3")]).unwrap();
+  assert_eq!(options.label.as_deref(), Some("training-node-3"));
+  assert_eq!(status::banner(Some("training-node-3"), Some("gpubox")).as_deref(), Some("training-node-3 @ gpubox"));
+  assert_eq!(status::banner(None, Some("gpubox")).as_deref(), Some("gpubox"));
+  assert_eq!(status::banner(None, None), None);
+
+  let mut app = NvidiaApp { banner: Some(String::from("training-node-3 @ gpubox")), ..NvidiaApp::default() };
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, 8));
+  let area = Rect::new(0, 0, 80, 30);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let banner: String = (0..80).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(banner.trim() == "training-node-3 @ gpubox");
+  assert_eq!(app.gpu_areas(area)[0].1.y, 1);
+}
+
+
+#[test]
+fn temperature_rate_of_change() {
+  let mut app = NvidiaApp { interval: Duration::from_secs(10), temp_rate_warn: Some(5.0), ..NvidiaApp::default() };
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, 30));
+  assert_eq!(stats::temperature_rate(&app.gpus[0], app.interval), None);
+  // 1 °C every 10 s, with the unfilled zeros before the first sample left out
+  for temperature in [50, 51, 52, 53] {
+    app.gpus[0].temperature.push(temperature);
+  }
+  assert!((stats::temperature_rate(&app.gpus[0], app.interval).unwrap() - 6.0).abs() < 1e-9);
+  assert!(app.temperature_alert(&app.gpus[0]).is_some());
+  app.temp_rate_warn = Some(10.0);
+  assert!(app.temperature_alert(&app.gpus[0]).is_none());
+
+  let area = Rect::new(0, 0, 80, 1);
+  let mut buf = Buffer::empty(area);
+  stats::render_stats_footer(&app.gpus[0], &THEMES[0], &app.chart_options(), Metric::Temperature, area, &mut buf);
+  let footer: String = (0..80).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(footer.contains("max 53 °C | +6.0 °C/min"));
+
+  assert_eq!(Options::parse(vec![String::from("--temp-rate-warn"), String::from("2.5")]).unwrap().temp_rate_warn, Some(2.5));
+  assert!(Options::parse(vec![String::from("--temp-rate-warn"), String::from("0")]).is_err());
+}
+
+
+#[test]
+fn grid_layout() {
+  use cli::GridLayout;
+
+  let layout: GridLayout = "2x4".parse().unwrap();
+  assert_eq!(layout, GridLayout { columns: 2, rows: 4 });
+  assert!("2x0".parse::<GridLayout>().is_err() && "8".parse::<GridLayout>().is_err());
+  assert_eq!((layout.fit(8), layout.fit(3), layout.fit(1), layout.fit(10)), ((2, 4), (2, 2), (1, 1), (3, 4)));
+  assert_eq!(Options::parse(vec![String::from("--layout"), String::from("2x4")]).unwrap().layout, Some(layout));
+
+  let mut app = NvidiaApp { layout: Some(layout), ..NvidiaApp::default() };
+  for _ in 0..8 {
+    app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, 8));
+  }
+  let chunks = app.gpu_chunks(Rect::new(0, 0, 200, 80));
+  assert_eq!(chunks[0], (0, Rect::new(0, 0, 100, 20)));
+  assert_eq!(chunks[1], (1, Rect::new(100, 0, 100, 20)));
+  assert_eq!(chunks[7], (7, Rect::new(100, 60, 100, 20)));
+
+  // A single GPU full-screen takes the whole area
+  app.selected = Some(5);
+  assert_eq!(app.gpu_chunks(Rect::new(0, 0, 200, 80)), vec![(5, Rect::new(0, 0, 200, 80))]);
+}
+
+
+#[test]
+fn xid_error_alerts() {
+  use xid::XidEvent;
+
+  assert!(Options::parse(vec![String::from("--xid")]).unwrap().xid);
+  let mut app = NvidiaApp::default();
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, 8));
+  for xid in [13, 31, 43, 48, 63] {
+    app.record_xid(XidEvent { index: 0, xid: Some(xid), at: Instant::now() });
+  }
+  app.record_xid(XidEvent { index: 1, xid: Some(79), at: Instant::now() });
+  assert_eq!(app.xid_events.iter().map(|event| event.xid.unwrap()).collect::<Vec<_>>(), vec![43, 48, 63, 79]);
+
+  // Newest first, on a row of its own above the charts
+  let area = Rect::new(0, 0, 120, 30);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let alerts: String = (0..120).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(alerts.starts_with(" XID 79 on GPU 1, 0s ago | XID 63 on GPU 0, 0s ago"));
+  assert_eq!(buf[(119, 0)].bg, THEMES[0].temp_crit_color);
+  assert_eq!(app.gpu_areas(area)[0].1.y, 1);
+}
+
+
+#[test]
+fn display_precision() {
+  use metric::Precision;
+  let precision = Precision::default();
+  assert_eq!(precision.watts(285_431.0), "285 W");
+  assert_eq!(precision.efficiency(6.0), "6.00");
+  assert_eq!(precision.export_watts(285_500), 286.0);
+  assert_eq!(Precision { raw: true, ..precision }.export_watts(285_431), 285.431);
+
+  let config = Config::parse("power_decimals = 1\nefficiency_decimals = 0\n").unwrap();
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  let precision = config.options().precision;
+  assert_eq!(precision.watts(285_431.0), "285.4 W");
+  assert_eq!(precision.efficiency(7.25), "7");
+  assert!(Config::parse("power_decimals = 7").unwrap_err().contains("at most 6 decimal places"));
+
+  let options = Options::parse_over(config.options(), vec![String::from("--raw")]).unwrap();
+  assert_eq!(options.precision, Precision { power: 1, efficiency: 0, memory_percent: 0, raw: true });
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 0, DEFAULT_HISTORY_LEN);
+  gpu_info.power_usage.push(250_456);
+  gpu_info.precision = options.precision;
+  assert!(prometheus::render(&[gpu_info]).contains("nvidia_gpu_power_watts{gpu=\"0\",name=\"Test GPU\"} 250.456\n"));
+}
+
+
+#[test]
+fn clock_histogram() {
+  let mut clock = History::new(6);
+  for sample in [210, 1900, 1950, 2000, 2600] {
+    clock.push(sample);
+  }
+  assert_eq!(histogram::clock_histogram(&clock, 2000, 4), vec![1, 0, 0, 4]);
+  assert_eq!(histogram::clock_histogram(&History::new(3), 2000, 4), vec![0; 4]);
+
+  let mut source = MockSource { devices: vec![MockDevice { clock: Some(1800), max_clock: Some(2000), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  for _ in 0..3 {
+    app.update_state(&source).unwrap();
+  }
+  source.devices[0].clock = Some(300);
+  app.update_state(&source).unwrap();
+
+  app.handle_key_event(KeyCode::Char('v').into());
+  assert!(app.show_clock_histogram);
+  let area = Rect::new(0, 0, 240, 40);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let clock_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Clock];
+  let rows: Vec<String> = (clock_area.top()..clock_area.bottom()).map(|y| (clock_area.left()..clock_area.right()).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(rows[0].contains("Clock Distribution") && rows[0].contains("mostly 1800–2000 MHz"));
+  assert!(rows.iter().any(|row| row.contains("75%")) && rows.iter().any(|row| row.contains("25%")));
+}
+
+
+#[test]
+fn power_limit_constraints() {
+  let source = MockSource { devices: vec![
+    MockDevice { power_usage: Some(300_000), power_limit: Some(450_000), power_limit_constraints: Some((150_000, 600_000)), ..MockDevice::new("GPU A") },
+    MockDevice { power_usage: Some(300_000), power_limit: Some(450_000), ..MockDevice::new("GPU B") },
+  ] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  for _ in 0..app.history_len {
+    app.update_state(&source).unwrap();
+  }
+  assert_eq!(app.gpus[0].power_limit_constraints, Some((150_000, 600_000)));
+  assert_eq!(app.gpus[1].power_limit_constraints, None);
+
+  let render = |gpu_info: &GPUInfo| {
+    let area = Rect::new(0, 0, 100, 14);
+    let mut buf = Buffer::empty(area);
+    render_power_chart(gpu_info, app.theme(), &app.chart_options(), area, &mut buf);
+    (0..14).map(|y| (0..100).map(|x| buf[(x, y)].symbol()).collect::<String>()).collect::<Vec<_>>()
+  };
+  let rows = render(&app.gpus[0]);
+  assert!(rows[0].contains("(300 W, limit 450 W (150–600 W))"));
+  // The axis reaches the highest limit, which is dashed along its top
+  assert!(rows.iter().any(|row| row.contains("600 W")));
+  assert!(rows[1].contains("•     •"));
+  let rows = render(&app.gpus[1]);
+  assert!(rows[0].contains("(300 W)") && !rows.concat().contains("600"));
+}
+
+
+#[test]
+fn daemon_mode() {
+  assert!(Options::parse(vec![String::from("--daemon")]).unwrap_err().contains("--daemon requires"));
+  let path = std::env::temp_dir().join(format!("nvidia-daemon-{}.csv", std::process::id()));
+  let args = ["--daemon", "--demo", "--interval", "20", "--log-csv", path.to_str().unwrap()];
+  let options = Options::parse(args.map(String::from)).unwrap();
+  assert!(options.daemon && options.exports());
+
+  let mut app = NvidiaApp { interval: options.interval, ..NvidiaApp::default() };
+  let interrupted = Arc::clone(&app.interrupted);
+  let stopper = std::thread::spawn(move || {
+    std::thread::sleep(Duration::from_millis(300));
+    interrupted.store(true, Ordering::Relaxed);
+  });
+  app.run_export_loop(&options).unwrap();
+  stopper.join().unwrap();
+  let csv = std::fs::read_to_string(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+  // A row for each of the four demo GPUs on every reading, after the header
+  let rows = csv.lines().count() - 1;
+  assert!(rows >= 4 && rows.is_multiple_of(4));
+  assert!(app.gpus[0].power_usage.latest() > 0);
+}
+
+
+#[test]
+fn clock_temperature_correlation() {
+  let mut gpu_info = GPUInfo::new(String::from("GPU A"), 0, 0, 6);
+  assert_eq!(stats::clock_temperature_correlation(&gpu_info, ClockDomain::Graphics), None);
+  for (clock, temperature) in [(1900, 60), (1850, 70), (1800, 80), (1750, 90)] {
+    gpu_info.core_clock.push(clock);
+    gpu_info.temperature.push(temperature);
+  }
+  // The unfilled slots are left out, so the clock falls exactly as the card heats up
+  let correlation = stats::clock_temperature_correlation(&gpu_info, ClockDomain::Graphics).unwrap();
+  assert!((correlation + 1.0).abs() < 1e-9);
+  assert!(stats::stats_line(&gpu_info, Metric::Clock, ClockDomain::Graphics).starts_with("min 1750"));
+
+  let area = Rect::new(0, 0, 80, 1);
+  let mut buf = Buffer::empty(area);
+  stats::render_stats_footer(&gpu_info, &THEMES[0], &ChartOptions::default(), Metric::Clock, area, &mut buf);
+  let footer: String = (0..80).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(footer.contains("| r -1.00 vs temp"));
+
+  // A clock pinned at its limit correlates with nothing
+  for _ in 0..6 {
+    gpu_info.core_clock.push(1800);
+  }
+  assert_eq!(stats::clock_temperature_correlation(&gpu_info, ClockDomain::Graphics), None);
+}
+
+
+#[test]
+fn messages_pane() {
+  use messages::Message;
+  let mut app = NvidiaApp::default();
+  for (level, text) in [(log::Level::Info, "Saved the screen"), (log::Level::Warn, "GPU 0 is hot"), (log::Level::Error, "Power query failed")] {
+    app.messages.push_back(Message { level, text: String::from(text), at: Instant::now() });
+  }
+  let screen = |app: &NvidiaApp| {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 80, 30));
+    app.render(buf.area, &mut buf);
+    (0..30).map(|y| (0..80).map(|x| buf[(x, y)].symbol()).collect::<String>()).collect::<Vec<_>>()
+  };
+  assert!(!screen(&app).concat().contains("Messages"));
+
+  app.handle_key_event(KeyCode::Char('n').into());
+  let rows = screen(&app);
+  assert!(rows[22].contains("Messages (3)"));
+  assert!(rows[23].contains("0s INFO  Saved the screen") && rows[25].contains("ERROR Power query failed"));
+
+  app.handle_key_event(KeyCode::PageUp.into());
+  app.handle_key_event(KeyCode::PageUp.into());
+  app.handle_key_event(KeyCode::PageUp.into());
+  assert_eq!(app.messages_scroll, 2);
+  let rows = screen(&app);
+  assert!(rows[22].contains("Messages (3, 2 newer below)") && !rows.concat().contains("GPU 0 is hot"));
+  app.handle_key_event(KeyCode::PageDown.into());
+  assert_eq!(app.messages_scroll, 1);
+}
+
+
+#[test]
+fn temperature_gradient() {
+  let points = [(-3.0, 40.0), (-2.0, 40.0), (-1.0, 70.0), (0.0, 85.0)];
+  assert_eq!(gradient_segments(&points, 55.0, 85.0), vec![
+    (0.0, points[0..2].to_vec()),
+    (0.5, points[1..3].to_vec()),
+    (1.0, points[2..4].to_vec()),
+  ]);
+  assert_eq!(THEMES[0].temp_gradient_color(0.0), Color::Rgb(117, 255, 0));
+  assert_eq!(THEMES[0].temp_gradient_color(0.5), Color::Rgb(255, 215, 0));
+  assert_eq!(THEMES[0].temp_gradient_color(2.0), Color::Rgb(255, 0, 0));
+  assert_eq!(THEMES[0].temp_gradient_color(0.75), Color::Rgb(255, 108, 0));
+  // Palette colors can't be blended
+  assert_eq!(THEMES[1].temp_gradient_color(0.2), Color::Gray);
+
+  let mut gpu_info = GPUInfo::new(String::from("GPU A"), 0, 0, 10);
+  for temperature in [40, 40, 40, 40, 40, 90, 90, 90, 90, 90] {
+    gpu_info.temperature.push(temperature);
+  }
+  let area = Rect::new(0, 0, 60, 20);
+  let mut buf = Buffer::empty(area);
+  render_temperature_chart(&gpu_info, &THEMES[0], &ChartOptions { marker: Marker::Braille, ..ChartOptions::default() }, TemperatureThresholds { warn: None, crit: Some(80) }, area, &mut buf);
+  let colors: Vec<Color> = buf.content().iter().filter(|cell| cell.symbol() != " ").map(|cell| cell.fg).collect();
+  assert!(colors.contains(&Color::Rgb(117, 255, 0)) && colors.contains(&Color::Rgb(255, 0, 0)));
+}
+
+
+#[test]
+fn process_dashboard() {
+  const MIB: u64 = 1024 * 1024;
+  let process = |pid, name: &str, mib| GpuProcess { pid, name: String::from(name), kind: ProcessKind::Compute, used_memory: Some(mib * MIB) };
+  let mut app = NvidiaApp::default();
+  for (index, name) in ["GPU A", "GPU B"].iter().enumerate() {
+    let mut gpu_info = GPUInfo::new(String::from(*name), 0, 0, app.history_len);
+    gpu_info.index = index as u32;
+    app.gpus.push(gpu_info);
+  }
+  app.gpus[0].processes = vec![process(10, "small", 100)];
+  app.gpus[1].processes = vec![process(20, "large", 900), process(30, "medium", 400)];
+
+  app.handle_key_event(KeyCode::Char('y').into());
+  assert!(app.dashboard);
+  assert!(app.lists_processes());
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 120, 30));
+  app.render(buf.area, &mut buf);
+  let screen: Vec<String> = (0..30).map(|y| (0..120).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(screen[1].contains("GPU"));
+  assert!(screen.iter().any(|line| line.contains("Processes - all GPUs (3)")));
+  let order: Vec<usize> = ["large", "medium", "small"].iter().map(|name| screen.iter().position(|line| line.contains(name)).unwrap()).collect();
+  assert!(order[0] < order[1] && order[1] < order[2]);
+  let large = &screen[order[0]];
+  assert!(large.trim_start_matches('│').trim_start().starts_with('1'));
+  assert!(large.contains("900 MiB"));
+  assert_eq!(app.gpu_areas(buf.area).iter().map(|&(i, area)| (i, area.y)).collect::<Vec<_>>(), vec![(0, 2), (1, 3)]);
+
+  let mut device = MockDevice::new("GPU A");
+  device.processes = Some(vec![process(40, "trainer", 2000)]);
+  let source = MockSource { devices: vec![device] };
+  app.gpus.truncate(1);
+  app.show_processes = false;
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].processes[0].name, "trainer");
+}
+
+
+#[test]
+fn history_warmup() {
+  let mut history = History::new(5);
+  assert!(history.points(|sample: u32| sample as f64).is_empty());
+  assert_eq!(history.x_bounds(), [-1.0, 0.0]);
+  history.push(60);
+  history.push(70);
+  assert_eq!(history.points(|sample| sample as f64), vec![(-1.0, 60.0), (0.0, 70.0)]);
+  assert_eq!(history.x_bounds(), [-2.0, 0.0]);
+  assert_eq!(history.stats(|sample| sample as f64), history::Stats { min: 60.0, mean: 65.0, max: 70.0 });
+
+  history.resize(10);
+  assert_eq!(history.points(|sample| sample as f64).len(), 2);
+  for sample in 0..12 {
+    history.push(sample);
+  }
+  assert_eq!(history.points(|sample| sample as f64).len(), 10);
+  assert_eq!(history.x_bounds(), [-10.0, 0.0]);
+  history.clear();
+  assert!(history.points(|sample| sample as f64).is_empty());
+}
+
+
+#[test]
+fn card_temperature_thresholds() {
+  let mut source = MockSource { devices: vec![
+    MockDevice { temperature_thresholds: Some((87, 95)), ..MockDevice::new("GPU A") },
+    MockDevice::new("GPU B"),
+  ] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  assert_eq!((app.gpus[0].slowdown_temperature, app.gpus[0].shutdown_temperature), (Some(87), Some(95)));
+  assert_eq!(app.temperature_thresholds(&app.gpus[1]), TemperatureThresholds::default());
+
+  source.devices[0].temperature = Some(88);
+  app.update_state(&source).unwrap();
+  assert_eq!(app.temperature_level(&app.gpus[0]), Some(TemperatureLevel::Warning));
+  // The command line and the config file take precedence
+  app.temp_crit = Some(85);
+  assert_eq!(app.temperature_thresholds(&app.gpus[0]), TemperatureThresholds { warn: Some(87), crit: Some(85) });
+  assert_eq!(app.temperature_level(&app.gpus[0]), Some(TemperatureLevel::Critical));
+
+  app.temp_crit = None;
+  let area = Rect::new(0, 0, 80, 20);
+  let mut buf = Buffer::empty(area);
+  render_temperature_chart(&app.gpus[0], &THEMES[0], &ChartOptions::default(), app.temperature_thresholds(&app.gpus[0]), area, &mut buf);
+  // The axis reaches the shutdown temperature, so both lines are drawn
+  for color in [THEMES[0].temp_warn_color, THEMES[0].temp_crit_color] {
+    assert!(buf.content().iter().any(|cell| cell.symbol() == "•" && cell.fg == color));
+  }
+}
+
+
+#[test]
+fn auto_boost_badge() {
+  use nvml_wrapper::bitmasks::device::ThrottleReasons;
+  let mut source = MockSource { devices: vec![MockDevice { auto_boost: Some(false), ..MockDevice::new("GPU A") }, MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!((app.gpus[0].auto_boost, app.gpus[1].auto_boost), (Some(false), None));
+
+  let bottom_row = |gpu_info: &GPUInfo| {
+    let area = Rect::new(0, 0, 100, 12);
+    let mut buf = Buffer::empty(area);
+    throttle::render_throttle_badges(gpu_info, &THEMES[0], area, &mut buf);
+    (0..100).map(|x| buf[(x, 11)].symbol()).collect::<String>()
+  };
+  app.gpus[0].throttle_reasons = ThrottleReasons::SW_POWER_CAP;
+  assert!(bottom_row(&app.gpus[0]).contains(" Boost Off   Power Cap "));
+  assert!(!bottom_row(&app.gpus[1]).contains("Boost"));
+
+  source.devices[0].auto_boost = Some(true);
+  app.update_state(&source).unwrap();
+  assert!(bottom_row(&app.gpus[0]).contains(" Boost On "));
+}
+
+
+#[test]
+fn render_fps_cap() {
+  let mut app = NvidiaApp::default();
+  assert_eq!(app.frame_time(), Duration::from_millis(50));
+  let config = Config::parse("max_fps = 5").unwrap();
+  config.apply(&mut app);
+  assert_eq!(app.frame_time(), Duration::from_millis(200));
+  assert!(config.to_toml().contains("max_fps = 5"));
+  assert!(Config::parse("max_fps = 0").is_err());
+}
+
+
+#[test]
+fn memory_bandwidth_estimate() {
+  let source = MockSource { devices: vec![
+    MockDevice { memory_bus_width: Some(384), max_clock: Some(10_501), utilization: Some((90, 40)), ..MockDevice::new("GPU A") },
+    MockDevice { max_clock: Some(10_501), ..MockDevice::new("GPU B") },
+  ] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].peak_memory_bandwidth().map(f64::round), Some(1008.0));
+  assert_eq!(app.gpus[1].peak_memory_bandwidth(), None);
+
+  app.handle_key_event(KeyCode::Char('z').into());
+  assert!(app.show_bandwidth);
+  let area = Rect::new(0, 0, 150, 40);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let screen: Vec<String> = (0..40).map(|y| (0..150).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(screen.iter().any(|line| line.contains("Bandwidth (est.) - GPU A (~403/1008 GB/s)")));
+  // Without a bus width there's nothing to estimate from, so the utilization stays
+  assert!(screen.iter().any(|line| line.contains("Utilization - GPU B")));
+}
+
+
+#[test]
+fn pause_single_gpu() {
+  let mut app = NvidiaApp::default();
+  for name in ["GPU A", "GPU B"] {
+    app.gpus.push(GPUInfo::new(String::from(name), 0, 0, app.history_len));
+  }
+  app.gpus[1].index = 1;
+  let mut readings = app.gpus.clone();
+  readings[0].temperature.push(50);
+  readings[1].temperature.push(60);
+  app.push_readings(&readings);
+
+  // Without a selected GPU there's nothing to pause
+  app.handle_key_event(KeyCode::Char('P').into());
+  assert!(!app.gpus.iter().any(|gpu_info| gpu_info.paused));
+  app.selected = Some(1);
+  app.handle_key_event(event::KeyEvent::new(KeyCode::Char('P'), KeyModifiers::SHIFT));
+  assert!(app.gpus[1].paused && !app.paused);
+
+  readings[0].temperature.push(55);
+  readings[1].temperature.push(65);
+  app.push_readings(&readings);
+  assert_eq!((app.gpus[0].temperature.latest(), app.gpus[1].temperature.latest()), (55, 60));
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 150, 40));
+  app.render(buf.area, &mut buf);
+  let top_row: String = (0..150).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(top_row.contains(" PAUSED "));
+
+  app.handle_key_event(KeyCode::Char('P').into());
+  app.push_readings(&readings);
+  assert_eq!(app.gpus[1].temperature.latest(), 65);
+}
+
+
+#[test]
+fn version_line() {
+  assert!(Options::parse(vec![String::from("--version")]).unwrap().version);
+  assert!(Options::parse(vec![String::from("-V")]).unwrap().version);
+  let versions = status::DriverVersions::read(&DemoSource::new());
+  assert_eq!(status::version_line(&versions), format!("nvidia {} | Driver demo | NVML demo | CUDA 12.6", env!("CARGO_PKG_VERSION")));
+  assert!(status::version_line(&status::DriverVersions::default()).ends_with("| Driver N/A | NVML N/A | CUDA N/A"));
+}
+
+
+#[test]
+fn clock_interpolation() {
+  assert_eq!(interpolate_points(&[(-2.0, 1000.0), (-1.0, 1400.0), (0.0, 1400.0)], 4), vec![(-2.0, 1000.0), (-1.75, 1100.0), (-1.5, 1200.0), (-1.25, 1300.0), (-1.0, 1400.0), (-0.75, 1400.0), (-0.5, 1400.0), (-0.25, 1400.0), (0.0, 1400.0)]);
+  assert_eq!(interpolate_points(&[(0.0, 1000.0)], 4), vec![(0.0, 1000.0)]);
+  assert!(interpolate_points(&[], 4).is_empty());
+
+  let mut app = NvidiaApp::default();
+  assert!(!app.interpolate);
+  app.handle_key_event(KeyCode::Char('x').into());
+  assert!(app.interpolate);
+}
+
+
+#[test]
+fn die_power_line() {
+  let mut source = MockSource { devices: vec![MockDevice { power_usage: Some(300_000), die_power: Some(240_000), ..MockDevice::new("SXM GPU") }, MockDevice { power_usage: Some(200_000), ..MockDevice::new("PCIe GPU") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  assert!(app.gpus[0].die_power.is_some());
+  assert!(app.gpus[1].die_power.is_none());
+  app.update_state(&source).unwrap();
+  source.devices[0].die_power = None;
+  app.update_state(&source).unwrap();
+  assert_eq!(app.gpus[0].die_power.as_ref().unwrap().recent(2), vec![240_000, 0]);
+
+  source.devices[0].die_power = Some(240_000);
+  app.update_state(&source).unwrap();
+  let area = Rect::new(0, 0, 80, 12);
+  let mut buf = Buffer::empty(area);
+  render_power_chart(&app.gpus[0], app.theme(), &app.chart_options(), area, &mut buf);
+  let title: String = (0..80).map(|x| buf[(x, 0)].symbol()).collect();
+  assert!(title.contains("300 W, die 240 W"), "{}", title);
+  assert!(buf.content().iter().any(|cell| cell.fg == app.theme().memory_color && cell.symbol() == "•"));
+}
+
+
+#[test]
+fn bell_on_crossing() {
+  assert!(Options::parse(vec![String::from("--bell")]).unwrap().bell);
+
+  let mut source = MockSource { devices: vec![MockDevice { temperature: Some(60), power_usage: Some(200_000), power_limit: Some(300_000), ..MockDevice::new("GPU") }] };
+  let mut app = NvidiaApp { temp_crit: Some(90), bell: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  let mut collector = NvidiaApp::default();
+  collector.add_gpus(&source, None).unwrap();
+  let mut tick = |app: &mut NvidiaApp, source: &MockSource| {
+    collector.update_state(source).unwrap();
+    app.push_readings(&collector.gpus);
+    mem::take(&mut app.ring_bell)
+  };
+  assert!(!tick(&mut app, &source));
+  source.devices[0].temperature = Some(95);
+  assert!(tick(&mut app, &source));
+  assert!(!tick(&mut app, &source));
+
+  // Another crossing within the interval stays quiet
+  source.devices[0].power_usage = Some(300_000);
+  assert!(!tick(&mut app, &source));
+  app.bell_rung_at = Some(Instant::now() - BELL_INTERVAL);
+  source.devices[0].power_usage = Some(200_000);
+  assert!(!tick(&mut app, &source));
+  source.devices[0].power_usage = Some(300_000);
+  assert!(tick(&mut app, &source));
+
+  app.bell = false;
+  app.bell_rung_at = None;
+  source.devices[0].temperature = Some(60);
+  assert!(!tick(&mut app, &source));
+  source.devices[0].temperature = Some(95);
+  assert!(!tick(&mut app, &source));
+}
+
+
+#[test]
+fn gauge_bars_view() {
+  assert!(Options::parse(vec![String::from("--bars")]).unwrap().bars);
+
+  let source = MockSource { devices: vec![MockDevice { temperature: Some(65), clock: Some(1500), max_clock: Some(2000), utilization: Some((80, 40)), memory_used: Some(4 << 30), memory_total: 8 << 30, power_usage: Some(150_000), power_limit: Some(300_000), ..MockDevice::new("RTX") }, MockDevice { power_usage: None, ..MockDevice::new("GTX") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  app.handle_key_event(KeyCode::Char('B').into());
+  assert!(app.bars);
+
+  // Far too small for the charts of two GPUs, but not for their bars
+  let area = Rect::new(0, 0, 60, 14);
+  assert_eq!(app.view(area), View::Bars);
+  assert_eq!(app.gpu_areas(area), vec![(0, Rect::new(0, 0, 60, 7)), (1, Rect::new(0, 7, 60, 7))]);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let rows: Vec<String> = (0..14).map(|y| (0..60).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(rows[0].contains("GPU 0 RTX"));
+  assert!(rows[1].starts_with("│Temp") && rows[1].contains("65 °C"));
+  assert!(rows[2].contains("1500 MHz"));
+  assert!(rows[3].contains("80%"));
+  assert!(rows[4].contains("4096 / 8192 MiB"));
+  assert!(rows[5].contains("150 / 300 W"));
+  assert!(rows[12].starts_with("│Power") && rows[12].contains("N/A"));
+  assert_eq!(buf[(7, 2)].fg, app.theme().clock_color);
+
+  assert_eq!(app.view(Rect::new(0, 0, 60, 12)), View::Compact);
+}
+
+
+#[test]
+fn sysfs_source() {
+  use sysfs::SysfsSource;
+  let root = std::env::temp_dir().join(format!("nvidia-sysfs-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&root);
+  let gpu = root.join("class/drm/card1/device");
+  std::fs::create_dir_all(gpu.join("hwmon/hwmon3")).unwrap();
+  std::fs::write(gpu.join("vendor"), "0x10de\n").unwrap();
+  std::fs::write(gpu.join("device"), "0x2684\n").unwrap();
+  std::fs::write(gpu.join("hwmon/hwmon3/temp1_input"), "47000\n").unwrap();
+  let other = root.join("class/drm/card0/device");
+  std::fs::create_dir_all(&other).unwrap();
+  std::fs::write(other.join("vendor"), "0x8086\n").unwrap();
+  let pstate = root.join("kernel/debug/dri/1");
+  std::fs::create_dir_all(&pstate).unwrap();
+  std::fs::write(pstate.join("pstate"), "07: core 405 MHz memory 810 MHz\n0f: core 1000-1950 MHz memory 7000 MHz AC DC *\nAC: core 1620 MHz memory 7000 MHz\n").unwrap();
+
+  let source = SysfsSource::detect(&root);
+  assert_eq!(source.device_count().unwrap(), 1);
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  let gpu_info = &app.gpus[0];
+  assert_eq!(gpu_info.device_name, "NVIDIA GPU 10de:2684");
+  assert_eq!(gpu_info.temperature.latest(), 47);
+  assert_eq!(gpu_info.core_clock.latest(), 1620);
+  assert_eq!(gpu_info.memory_clock.latest(), 7000);
+  assert_eq!(gpu_info.max_clock(ClockDomain::Graphics), Some(1950));
+  assert!(!gpu_info.supports(Metric::Power));
+
+  assert_eq!(sysfs::pstate_clock("0f: core 1000-1950 MHz memory 7000 MHz AC DC *", "core"), Some((1000, 1950)));
+  assert_eq!(sysfs::pstate_clock("07: core 405 MHz memory 810 MHz", "memory"), Some((810, 810)));
+  assert_eq!(sysfs::pstate_clock("AC: memory 810 MHz", "core"), None);
+  std::fs::remove_dir_all(&root).unwrap();
+}
+
+
+#[test]
+fn history_scrolling() {
+  let mut history = History::new(10);
+  for value in 1..=8 {
+    history.push(value);
+  }
+  let window = history.window(3, 2);
+  assert_eq!(window.points(|value| value as f64), vec![(-4.0, 4.0), (-3.0, 5.0), (-2.0, 6.0)]);
+  assert_eq!(window.x_bounds(), [-5.0, -2.0]);
+  assert_eq!(window.latest(), 6);
+  assert_eq!(history.window(3, 100).points(|value| value as f64), vec![(-7.0, 1.0), (-6.0, 2.0), (-5.0, 3.0)]);
+  assert_eq!(history.window(20, 4).points(|value| value as f64).len(), 8);
+
+  let mut app = NvidiaApp { history_len: 300, ..NvidiaApp::default() };
+  let mut gpu_info = GPUInfo::new(String::from("GPU"), 0, 0, 300);
+  for temperature in 0..300 {
+    gpu_info.temperature.push(temperature);
+  }
+  app.gpus.push(gpu_info);
+  app.handle_key_event(KeyCode::Left.into());
+  assert_eq!(app.scroll, 0);
+  app.handle_key_event(KeyCode::Char('p').into());
+  app.handle_key_event(KeyCode::Left.into());
+  app.handle_key_event(KeyCode::Left.into());
+  app.handle_key_event(KeyCode::Right.into());
+  assert_eq!(app.scroll, 10);
+  app.handle_key_event(KeyCode::Left.into());
+
+  let area = Rect::new(0, 0, 120, 40);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+  assert!(screen.contains(" PAUSED, -20s "));
+  assert!(screen.contains("(279 °C"));
+  assert!(screen.contains("-2m20s"));
+
+  app.handle_key_event(KeyCode::Char('p').into());
+  assert_eq!(app.scroll, 0);
+}
+
+
+#[test]
+fn persistence_mode_badge() {
+  let mut source = MockSource { devices: vec![MockDevice { auto_boost: Some(true), persistence_mode: Some(false), ..MockDevice::new("GPU A") }, MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!((app.gpus[0].persistence_mode, app.gpus[1].persistence_mode), (Some(false), None));
+
+  let bottom_row = |gpu_info: &GPUInfo| {
+    let area = Rect::new(0, 0, 100, 12);
+    let mut buf = Buffer::empty(area);
+    throttle::render_throttle_badges(gpu_info, &THEMES[0], area, &mut buf);
+    ((0..100).map(|x| buf[(x, 11)].symbol()).collect::<String>(), buf[(13, 11)].bg)
+  };
+  let (row, badge_color) = bottom_row(&app.gpus[0]);
+  assert!(row.contains(" Boost On   Persistence Off "));
+  assert_eq!(badge_color, THEMES[0].throttle_color);
+  assert!(!bottom_row(&app.gpus[1]).0.contains("Persistence"));
+
+  source.devices[0].persistence_mode = Some(true);
+  app.update_state(&source).unwrap();
+  assert!(bottom_row(&app.gpus[0]).0.contains(" Persistence On "));
+}
+
+
+#[test]
+fn detailed_view() {
+  let source = MockSource { devices: vec![MockDevice::new("GPU A"), MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  app.handle_key_event(KeyCode::Char('D').into());
+  app.selected = Some(1);
+
+  let area = Rect::new(0, 0, 100, 60);
+  let chunks = app.gpu_chunks(area);
+  assert_eq!(chunks, vec![(1, area)]);
+  let charts = app.gpu_chart_areas(&app.gpus[1], area);
+  assert!(app.gpus[1].detail_charts().iter().all(|metric| charts.contains_key(metric)) && charts.len() == 4);
+  assert!(charts.values().all(|chart| chart.width == charts[&Metric::Clock].width));
+  assert!(charts[&Metric::Clock].y < charts[&Metric::Temperature].y && charts[&Metric::Power].y < charts[&Metric::Utilization].y);
+
+  let short = Rect::new(0, 0, 100, 20);
+  let first = app.gpu_chart_areas(&app.gpus[1], short);
+  assert!(first.len() < charts.len() && first.contains_key(&Metric::Clock));
+  app.handle_key_event(KeyCode::Tab.into());
+  assert_eq!(app.detail_scroll, 1);
+  assert!(!app.gpu_chart_areas(&app.gpus[1], short).contains_key(&Metric::Clock));
+
+  app.selected = None;
+  assert_eq!(app.gpu_chunks(area), vec![(0, area)]);
+  app.handle_key_event(KeyCode::Char('D').into());
+  assert_eq!((app.detail_scroll, app.gpu_chunks(area).len()), (0, 2));
+}
+
+
+#[test]
+fn export_rotation() {
+  assert!(Options::parse(vec![String::from("--rotate-mb"), String::from("1")]).is_err());
+  assert!(Options::parse(["--log-csv", "a.csv", "--rotate-mb", "18446744073710"].map(String::from)).unwrap_err().contains("--rotate-mb is too large"));
+  let path = std::env::temp_dir().join(format!("nvidia-rotate-{}.csv", std::process::id()));
+  let args = ["--log-csv", path.to_str().unwrap(), "--rotate-mb", "5"];
+  assert_eq!(Options::parse(args.map(String::from)).unwrap().rotate_size, Some(5_000_000));
+
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 0, DEFAULT_HISTORY_LEN);
+  gpu_info.temperature.push(65);
+  let mut csv_logger = CsvLogger::create(&path, Some(250)).unwrap();
+  let row_count = |path: &std::path::Path| std::fs::read_to_string(path).unwrap().lines().count();
+  csv_logger.log(&[gpu_info.clone()]).unwrap();
+  assert_eq!(row_count(&path), 2);
+  for _ in 0..3 {
+    csv_logger.log(&[gpu_info.clone()]).unwrap();
+  }
+  let rotated = path.with_extension("csv.1");
+  assert!(row_count(&rotated) > 2);
+  let fresh = std::fs::read_to_string(&path).unwrap();
+  assert!(fresh.starts_with("timestamp,gpu,name") && fresh.lines().count() == 1);
+  std::fs::remove_file(&path).unwrap();
+  std::fs::remove_file(&rotated).unwrap();
+}
+
+
+#[test]
+fn disconnected_gpu() {
+  let mut source = MockSource { devices: vec![MockDevice::new("GPU A"), MockDevice { temperature: Some(60), ..MockDevice::new("GPU B") }] };
+  let mut collector = NvidiaApp { history_len: 1, ..NvidiaApp::default() };
+  collector.add_gpus(&source, None).unwrap();
+  let mut app = NvidiaApp { gpus: collector.gpus.clone(), ..NvidiaApp::default() };
+  app.gpus.iter_mut().for_each(|gpu_info| gpu_info.resize_history(DEFAULT_HISTORY_LEN));
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+
+  // A blip that's over by the next rescan keeps the panel and its history
+  let unplugged = source.devices.remove(1);
+  collector.rescan_gpus(&source, None).unwrap();
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+  assert_eq!(app.gpus.len(), 2);
+  assert!(app.gpus[1].disconnected && !app.gpus[0].disconnected);
+  assert_eq!(app.gpus[1].title_name(usize::MAX), "GPU B (disconnected)");
+  let mut buf = Buffer::empty(Rect::new(0, 0, 120, 40));
+  app.render(buf.area, &mut buf);
+  assert!(buf.content().iter().map(|cell| cell.symbol()).collect::<String>().contains("GPU B (disconnected)"));
+  source.devices.push(unplugged);
+  collector.rescan_gpus(&source, None).unwrap();
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+  assert!(!app.gpus[1].disconnected);
+  assert_eq!(app.gpus[1].temperature.recent(3), [60, 0, 60]);
+
+  // A lost GPU is dropped by the next rescan
+  source.devices[1].lost = true;
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+  assert!(app.gpus[1].disconnected);
+  collector.rescan_gpus(&source, None).unwrap();
+  collector.update_state(&source).unwrap();
+  app.push_readings(&collector.gpus);
+  assert_eq!(app.gpus.len(), 1);
+}
+
+
+#[test]
+fn memory_percent_in_titles() {
+  let config = Config::parse("memory_percent_decimals = 1\n").unwrap();
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  let precision = config.options().precision;
+  assert_eq!(precision.memory_percent(2048, 3000), "68.3%");
+  assert_eq!(metric::Precision::default().memory_percent(2048, 3000), "68%");
+
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8 << 30, 0, DEFAULT_HISTORY_LEN);
+  gpu_info.memory_used.push(6 << 30);
+  gpu_info.precision = precision;
+  // The memory gauge carries it while the memory chart is shown
+  assert!(!metric_title("Clock", &gpu_info, Metric::Clock, String::from("now"), u16::MAX).content.to_string().contains("VRAM"));
+  gpu_info.enabled_charts = MetricFlags::CLOCK;
+  assert_eq!(metric_title("Clock", &gpu_info, Metric::Clock, String::from("now"), u16::MAX).content.to_string(), "GPU 0 Clock - Test GPU, 75.0% VRAM (now)");
+
+  let area = Rect::new(0, 0, 60, bars::BARS_HEIGHT);
+  let mut buf = Buffer::empty(area);
+  bars::render_gpu_bars(&gpu_info, &THEMES[0], &ChartOptions::default(), 100, None, area, &mut buf);
+  assert!((0..60).map(|x| buf[(x, 0)].symbol()).collect::<String>().contains("GPU 0 Test GPU, 75.0% VRAM"));
+
+  gpu_info.record::<u64>(Metric::Memory, Err(NvmlError::Unknown));
+  assert_eq!(gpu_info.memory_title(), None);
+}
+
+
+#[test]
+fn history_json_file() {
+  assert_eq!(keys::action(KeyCode::Char('E').into()), Some(Action::SaveHistory));
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 0, 4);
+  for temperature in [50, 55, 60] {
+    gpu_info.temperature.push(temperature);
+  }
+  let dir = std::env::temp_dir().join(format!("nvidia-history-test-{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = snapshot::save_history(&[gpu_info], Duration::from_millis(500), &dir).unwrap();
+  assert!(path.file_name().unwrap().to_str().unwrap().starts_with("nvidia-history-"));
+  let json = std::fs::read_to_string(&path).unwrap();
+  assert!(json.contains("\"interval_ms\": 500"));
+  assert!(json.contains("\"name\": \"Test GPU\""));
+  assert!(json.replace([' ', '\n'], "").contains("\"temperature_c\":[0,50,55,60]"));
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+
+#[test]
+fn required_metrics() {
+  let config = Config::parse("required_metrics = \"temperature,power\"\n").unwrap();
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("required_metrics = \"voltage\"").is_err());
+
+  let mut source = MockSource { devices: vec![MockDevice::new("GPU A")] };
+  let mut app = NvidiaApp::default();
+  config.apply(&mut app);
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert!(app.check_required_metrics().is_ok());
+
+  // Optional metrics only degrade
+  source.devices[0].pcie_throughput = None;
+  app.update_state(&source).unwrap();
+  assert!(app.check_required_metrics().is_ok());
+  source.devices[0].temperature = None;
+  app.update_state(&source).unwrap();
+  let error = app.check_required_metrics().unwrap_err().to_string();
+  assert!(error.starts_with("GPU 0 temperature can't be read: "), "{}", error);
+
+  app.required_metrics = MetricFlags::ECC;
+  app.gpus[0].supported.remove(MetricFlags::ECC);
+  assert!(app.check_required_metrics().unwrap_err().to_string().contains("ECC errors can't be read: it isn't supported"));
+}
+
+
+#[test]
+fn anomaly_highlight() {
+  assert_eq!(Options::parse(["--anomaly-sigma", "2.5"].map(String::from)).unwrap().anomaly_sigma, Some(2.5));
+  assert!(Options::parse(["--anomaly-sigma", "0"].map(String::from)).is_err());
+  let mut points: Vec<(f64, f64)> = (0..20).map(|x| (f64::from(x), 60.0 + f64::from(x % 2))).collect();
+  assert!(stats::anomalies(&points, 3.0).is_empty());
+  points[12].1 = 90.0;
+  assert_eq!(stats::anomalies(&points, 3.0), vec![(12.0, 90.0)]);
+  assert!(stats::anomalies(&[(0.0, 5.0); 5], 3.0).is_empty());
+
+  let mut app = NvidiaApp::default();
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 0, 0, 40);
+  for (x, temperature) in points.iter() {
+    gpu_info.temperature.push(if *x == 12.0 { 90 } else { *temperature as u32 });
+  }
+  app.gpus.push(gpu_info);
+  assert_eq!(app.anomaly_sigma(), None);
+  app.handle_key_event(KeyCode::Char('A').into());
+  assert_eq!(app.anomaly_sigma(), Some(stats::DEFAULT_ANOMALY_SIGMA));
+  let area = Rect::new(0, 0, 80, 20);
+  let mut buf = Buffer::empty(area);
+  render_temperature_chart(&app.gpus[0], &THEMES[0], &ChartOptions { anomaly_sigma: app.anomaly_sigma(), ..ChartOptions::default() }, TemperatureThresholds::default(), area, &mut buf);
+  assert!(buf.content().iter().any(|cell| cell.symbol() == "█" && cell.fg == THEMES[0].temp_crit_color));
+}
+
+
+#[test]
+fn compute_mode_badge() {
+  use nvml_wrapper::enum_wrappers::device::ComputeMode;
+  let mut source = MockSource { devices: vec![MockDevice { compute_mode: Some(ComputeMode::Default), ..MockDevice::new("GPU A") }, MockDevice::new("GPU B")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!((app.gpus[0].compute_mode.clone(), app.gpus[1].compute_mode.clone()), (Some(ComputeMode::Default), None));
+
+  let bottom_row = |gpu_info: &GPUInfo| {
+    let area = Rect::new(0, 0, 100, 12);
+    let mut buf = Buffer::empty(area);
+    throttle::render_throttle_badges(gpu_info, &THEMES[0], area, &mut buf);
+    let row: String = (0..100).map(|x| buf[(x, 11)].symbol()).collect();
+    let start = row.find(" Compute").map(|start| buf[(start as u16 + 1, 11)].bg);
+    (row, start)
+  };
+  let (row, color) = bottom_row(&app.gpus[0]);
+  assert!(row.contains(" Compute Default "));
+  assert_eq!(color, Some(THEMES[0].unavailable));
+  assert_eq!(bottom_row(&app.gpus[1]).1, None);
+
+  source.devices[0].compute_mode = Some(ComputeMode::Prohibited);
+  app.update_state(&source).unwrap();
+  let (row, color) = bottom_row(&app.gpus[0]);
+  assert!(row.contains(" Compute Prohibited "));
+  assert_eq!(color, Some(THEMES[0].throttle_color));
+  assert!(snapshot::snapshot_json(&app.gpus).to_string().contains("\"compute_mode\":\"Prohibited\""));
+  assert!(snapshot::snapshot_xml(&app.gpus, &status::DriverVersions::default()).contains("<compute_mode>Prohibited</compute_mode>"));
+}
+
+
+#[test]
+fn abbreviated_names() {
+  let name = "NVIDIA GeForce RTX 4090 Laptop GPU";
+  assert_eq!(abbreviate_name(name, 40), name);
+  assert_eq!(abbreviate_name(name, 30), "GeForce RTX 4090 Laptop GPU");
+  assert_eq!(abbreviate_name(name, 20), "RTX 4090 Laptop GPU");
+  assert_eq!(abbreviate_name(name, 16), "RTX 4090 Laptop");
+  assert_eq!(abbreviate_name(name, 9), "RTX 4090…");
+  assert_eq!(abbreviate_name(name, 0), "…");
+
+  let mut gpu_info = GPUInfo::new(String::from(name), 0, 0, DEFAULT_HISTORY_LEN);
+  gpu_info.disconnected = true;
+  assert_eq!(gpu_info.title_name(30), "RTX 4090 Laptop (disconnected)");
+  gpu_info.disconnected = false;
+  let title = |width: u16| metric_title("Clock", &gpu_info, Metric::Clock, String::from("now"), width).content.to_string();
+  assert_eq!(title(u16::MAX), "GPU 0 Clock - NVIDIA GeForce RTX 4090 Laptop GPU (now)");
+  assert_eq!(title(41), "GPU 0 Clock - RTX 4090 Laptop GPU (now)");
+  assert_eq!(title(40), "GPU 0 Clock - RTX 4090 Laptop (now)");
+  assert_eq!(title(20), "GPU 0 Clock - RTX 4090 La… (now)");
+
+  // The overlay and cooling charts fit the name into their titles the same way
+  gpu_info.disconnected = true;
+  let area = Rect::new(0, 0, 80, 10);
+  for render in [render_overlay_chart, render_cooling_chart] {
+    let mut buf = Buffer::empty(area);
+    render(&gpu_info, &THEMES[0], &ChartOptions::default(), area, &mut buf);
+    let top: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+    assert!(top.contains(" Temp - ") && !top.contains("NVIDIA") && top.contains(" RTX 4090 Laptop") && top.contains(" (disconnected) ("));
+  }
+}
+
+
+#[test]
+fn collection_timing() {
+  assert!(Options::parse(vec![String::from("--timing")]).unwrap().timing);
+  assert!(!status::collection_slow(Duration::from_millis(749), Duration::from_secs(1)));
+  assert!(status::collection_slow(Duration::from_millis(750), Duration::from_secs(1)));
+
+  let mut app = NvidiaApp { show_timing: true, versions: Some(status::DriverVersions::default()), ..NvidiaApp::default() };
+  let status_row = |app: &NvidiaApp| {
+    let mut buf = Buffer::empty(Rect::new(0, 0, 100, 30));
+    app.render(buf.area, &mut buf);
+    let row: String = (0..100).map(|x| buf[(x, 29)].symbol()).collect();
+    let start = row.find("Collected").map(|start| buf[(start as u16, 29)].fg);
+    (row, start)
+  };
+  assert_eq!(status_row(&app).1, None);
+
+  app.record_collection_time(Duration::from_millis(12));
+  let (row, color) = status_row(&app);
+  assert!(row.ends_with("Collected in 12 ms "));
+  assert_ne!(color, Some(app.theme().temp_warn_color));
+  assert!(!app.slow_collection_warned);
+
+  app.record_collection_time(Duration::from_millis(900));
+  assert_eq!(status_row(&app).1, Some(app.theme().temp_warn_color));
+  assert!(app.slow_collection_warned);
+
+  app.show_timing = false;
+  assert_eq!(status_row(&app).1, None);
+}
+
+
+#[test]
+fn text_stream_lines() {
+  let mut gpu_info = GPUInfo::new(String::from("RTX \"4090\""), 8 << 30, 1, DEFAULT_HISTORY_LEN);
+  gpu_info.core_clock.push(1800);
+  gpu_info.temperature.push(65);
+  gpu_info.memory_used.push(2 << 30);
+  gpu_info.power_usage.push(215500);
+  gpu_info.record::<u32>(Metric::Utilization, Err(NvmlError::Unknown));
+  let mut unreadable = GPUInfo::new(String::from("Lost GPU"), 0, 0, DEFAULT_HISTORY_LEN);
+  unreadable.skip_tick(&NvmlError::GpuLost);
+
+  let mut output = Vec::new();
+  export::TextLogger::new(&mut output).log(&[gpu_info, unreadable]).unwrap();
+  let output = String::from_utf8(output).unwrap();
+  let lines: Vec<(&str, &str)> = output.lines().map(|line| line.split_once(' ').unwrap()).collect();
+  assert_eq!(lines.len(), 2);
+  assert!(lines[0].0.parse::<f64>().unwrap() > 1_600_000_000.0);
+  assert_eq!(lines[0].1, "gpu=0 name=\"RTX '4090'\" temp_c=65 clock_mhz=1800 mem_used_mib=2048 mem_total_mib=8192 power_w=216");
+  assert_eq!(lines[1].1, "gpu=0 name=\"Lost GPU\"");
+}
+
+
+#[test]
+fn base_and_boost_clock_lines() {
+  let source = MockSource { devices: vec![MockDevice { clock: Some(1200), max_clock: Some(2000), base_clock: Some(1500), applications_clock: Some(1500), ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp { history_len: 30, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  for _ in 0..30 {
+    app.update_state(&source).unwrap();
+  }
+  assert_eq!(app.gpus[0].base_clock(ClockDomain::Graphics), Some(1500));
+
+  let area = Rect::new(0, 0, 100, 20);
+  let mut buf = Buffer::empty(area);
+  render_clock_chart(&app.gpus[0], app.theme(), &app.chart_options(), area, &mut buf);
+  let screen: Vec<String> = (0..20).map(|y| (0..100).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  let row = |text: &str| screen.iter().position(|line| line.contains(text));
+  // The application clock still at its default is only drawn as the base clock
+  assert_eq!(row("Application"), None);
+  let (boost, base) = (row(" Boost 2000 MHz ").unwrap(), row(" Base 1500 MHz ").unwrap());
+  let clock = screen.iter().rposition(|line| line.matches('•').count() > 5).unwrap();
+  assert!(boost < base && base < clock);
+  // Labeled on the row of their dashed line
+  assert!(screen[base].matches('•').count() > 5);
+}
+
+
+#[test]
+fn average_of_all_gpus() {
+  let mut short = History::new(4);
+  short.push(30);
+  let mut long = History::new(4);
+  [10, 20, 40].into_iter().for_each(|sample| long.push(sample));
+  // The newest slot is averaged over both, the older ones over the history that has them
+  let mean = History::mean(&[&short, &long]);
+  assert_eq!(mean.points(f64::from), vec![(-2.0, 10.0), (-1.0, 20.0), (0.0, 35.0)]);
+
+  let source = MockSource { devices: vec![MockDevice { temperature: Some(40), clock: Some(1000), ..MockDevice::new("GPU A") }, MockDevice { temperature: Some(61), clock: Some(2000), ..MockDevice::new("GPU B") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert!(app.average_gpu().is_none());
+
+  app.handle_key_event(KeyCode::Char('G').into());
+  let average = app.average_gpu().unwrap();
+  assert_eq!((average.temperature.latest(), average.core_clock.latest()), (50, 1500));
+  assert_eq!(average.charts(false), vec![Metric::Clock, Metric::Temperature, Metric::Utilization]);
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 120, 40));
+  app.render(buf.area, &mut buf);
+  let screen: Vec<String> = (0..40).map(|y| (0..120).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(screen.iter().any(|line| line.contains("Average Temperature - All 2 GPUs (50")));
+  assert!(!screen.iter().any(|line| line.contains("GPU A") || line.contains("GPU B")));
+  assert!(app.gpu_areas(buf.area).is_empty());
+
+  // A focused GPU is shown as itself
+  app.selected = Some(1);
+  assert!(app.average_gpu().is_none());
+}
+
+
+#[test]
+fn record_and_replay() {
+  use export::{Recorder, RECORD_HEADER};
+  use replay::ReplaySource;
+
+  let source = MockSource { devices: vec![MockDevice { clock: Some(1800), max_clock: Some(2500), temperature: Some(65), memory_used: Some(1024), memory_total: 8192, utilization: Some((90, 40)), power_usage: Some(250000), power_limit: Some(450000), fan_speeds: vec![40, 42], ..MockDevice::new("Test \"GPU\"") }, MockDevice { temperature: Some(50), power_usage: None, ..MockDevice::new("Other GPU") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  let mut output = Vec::new();
+  Recorder::new(&mut output).unwrap().record(&app.gpus).unwrap();
+  let recording = String::from_utf8(output).unwrap();
+  assert!(recording.starts_with(RECORD_HEADER));
+
+  // Played back, the recording reads like the GPUs it was recorded from
+  let replay = ReplaySource::parse(&recording, 1.0).unwrap();
+  let mut replayed = NvidiaApp::default();
+  replayed.add_gpus(&replay, None).unwrap();
+  replayed.update_state(&replay).unwrap();
+  assert_eq!(replayed.gpus.len(), 2);
+  let gpu_info = &replayed.gpus[0];
+  assert_eq!(gpu_info.device_name, "Test \"GPU\"");
+  assert_eq!((gpu_info.core_clock.latest(), gpu_info.max_clock(ClockDomain::Graphics)), (1800, Some(2500)));
+  assert_eq!((gpu_info.temperature.latest(), gpu_info.memory_used.latest(), gpu_info.memory_total), (65, 1024, 8192));
+  assert_eq!((gpu_info.gpu_utilization.latest(), gpu_info.memory_utilization.latest()), (90, 40));
+  assert_eq!((gpu_info.power_usage.latest(), gpu_info.power_limit), (250000, 450000));
+  assert_eq!(gpu_info.fan_speeds.iter().map(History::latest).collect::<Vec<_>>(), vec![40, 42]);
+  // Metrics that were never recorded are unsupported
+  assert!(!replayed.gpus[1].supports(Metric::Power));
+  assert_eq!(replayed.gpus[1].temperature.latest(), 50);
+  assert!(replay.interval().is_none());
+
+  let header = RECORD_HEADER;
+  let recording = format!("{header}\n100.000,0,\"A\",,1000,,,60,,,,,,,\n100.000,1,\"B\",,,,,30,,,,,,,\n102.000,0,\"A\",,,,,61,,,,,,,\n");
+  let slow = ReplaySource::parse(&recording, 1.0).unwrap();
+  assert_eq!(slow.interval(), Some(Duration::from_secs(2)));
+  assert_eq!(slow.device(0).unwrap().temperature(TemperatureSensor::Gpu).ok(), Some(60));
+  // Far faster, the replay is already at its last tick, where B is missing and A's clock failed
+  let fast = ReplaySource::parse(&recording, 1e9).unwrap();
+  assert_eq!(fast.interval(), Some(Duration::from_millis(1)));
+  assert_eq!(fast.device(0).unwrap().temperature(TemperatureSensor::Gpu).ok(), Some(61));
+  assert!(matches!(fast.device(0).unwrap().clock_info(ClockDomain::Graphics.clock()), Err(NvmlError::Unknown)));
+  assert!(matches!(fast.device(0).unwrap().power_usage(), Err(NvmlError::NotSupported)));
+  assert!(matches!(fast.device(1).unwrap().temperature(TemperatureSensor::Gpu), Err(NvmlError::GpuLost)));
+  assert!(ReplaySource::parse("timestamp,gpu\n", 1.0).is_err());
+}
+
+
+#[test]
+fn failed_reads_are_gaps() {
+  let mut source = MockSource { devices: vec![MockDevice::new("Mock RTX")] };
+  let mut app = NvidiaApp { history_len: 4, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  // An idle 0 MHz is charted as such, and only the failed read is left out
+  for clock in [Some(1500), Some(0), None, Some(1400)] {
+    source.devices[0].clock = clock;
+    app.update_state(&source).unwrap();
+  }
+  let clock = &app.gpus[0].core_clock;
+  let points = clock.points(|clock| clock as f64);
+  assert_eq!(points, vec![(-3.0, 1500.0), (-2.0, 0.0), (0.0, 1400.0)]);
+  assert_eq!(break_at_gaps(&points), vec![(-3.0, 1500.0), (-2.0, 0.0), (-1.0, f64::NEG_INFINITY), (0.0, 1400.0)]);
+  assert_eq!(interpolate_points(&points, 2), vec![(-3.0, 1500.0), (-2.5, 750.0), (-2.0, 0.0), (0.0, 1400.0)]);
+  // The drop to idle is a clock step, the rise across the gap isn't
+  assert_eq!(clock_steps(clock), vec![(-2.0, 0.0)]);
+
+  // The collection thread's gaps carry over to the histories of the TUI
+  let mut shown = History::new(4);
+  shown.push_latest(clock);
+  assert_eq!(shown.points(f64::from), vec![(0.0, 1400.0)]);
+  let mut failed = History::<u32>::new(4);
+  failed.push_gap();
+  shown.push_latest(&failed);
+  assert_eq!(shown.points(f64::from), vec![(-1.0, 1400.0)]);
+  assert_eq!(History::mean(&[&failed]).points(f64::from), vec![]);
+
+  // Nor is a failed PCIe or codec read charted as an idle bus or codec
+  let mut source = MockSource { devices: vec![MockDevice { pcie_throughput: Some((500, 200)), codec_utilization: Some((30, 10)), ..MockDevice::new("Mock RTX") }] };
+  let mut app = NvidiaApp { history_len: 4, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  (source.devices[0].pcie_throughput, source.devices[0].codec_utilization) = (None, None);
+  app.update_state(&source).unwrap();
+  let gpu_info = &app.gpus[0];
+  assert_eq!(gpu_info.pcie_rx.points(f64::from), vec![(-1.0, 500.0)]);
+  assert_eq!(gpu_info.decoder_utilization.points(f64::from), vec![(-1.0, 10.0)]);
+  assert_eq!(gpu_info.efficiency.points(|efficiency| efficiency), vec![]);
+}
+
+
+#[test]
+fn newest_sample_on_the_left() {
+  let mut history = History::new(10);
+  [1, 2, 3].into_iter().for_each(|sample| history.push(sample));
+  let mirrored = history.clone().mirror(true);
+  assert_eq!(mirrored.points(|value| value as f64), vec![(2.0, 1.0), (1.0, 2.0), (0.0, 3.0)]);
+  assert_eq!(mirrored.x_bounds(), [0.0, 3.0]);
+  assert_eq!(history.window(2, 1).mirror(true).x_bounds(), [1.0, 3.0]);
+
+  let config = Config::parse("newest_left = true\n").unwrap();
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  let mut app = NvidiaApp::default();
+  config.apply(&mut app);
+  assert!(app.newest_left);
+  app.gpus.push(GPUInfo::new(String::from("Test GPU"), 0, 0, app.history_len));
+  for clock in 0..DEFAULT_HISTORY_LEN as u32 {
+    app.gpus[0].core_clock.push(1000 + clock);
+  }
+  let area = Rect::new(0, 0, 80, 20);
+  let charted = app.charted(&app.gpus[0], area).unwrap();
+  assert_eq!(charted.core_clock.points(|clock| clock as f64)[0], (29.0, 1000.0));
+  let mut buf = Buffer::empty(area);
+  render_clock_chart(&charted, app.theme(), &app.chart_options(), area, &mut buf);
+  let screen: Vec<String> = (0..20).map(|y| (0..80).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  let time_labels = screen.iter().find(|line| line.contains("0s") && line.contains("-30s")).unwrap();
+  assert!(time_labels.find("0s").unwrap() < time_labels.find("-30s").unwrap());
+}
+
+
+#[test]
+fn check_report_lists_supported_metrics() {
+  let source = MockSource { devices: vec![MockDevice { fan_speeds: vec![40], ..MockDevice::new("Mock RTX") }, MockDevice { power_usage: None, ..MockDevice::new("Mock Tesla") }, MockDevice { lost: true, ..MockDevice::new("Mock Lost") }] };
+  let report = check::check_report(&source).unwrap();
+  let lines: Vec<&str> = report.lines().collect();
+  assert_eq!(lines[1], "GPU 0: Mock RTX");
+  assert!(lines.contains(&"Metric                       GPU 0   GPU 1   GPU 2"));
+  assert!(lines.contains(&"power                        yes     no      error"));
+  assert!(lines.contains(&"fan speed                    yes     no      error"));
+  assert!(lines.iter().any(|line| line.starts_with("GPU 2: clock failed: ")));
+  assert!(!lines.iter().any(|line| line.starts_with("GPU 0: clock failed")));
+}
+
+
+#[test]
+fn utilization_scatter_plot() {
+  let source = MockSource { devices: vec![
+    MockDevice { utilization: Some((95, 10)), ..MockDevice::new("GPU A") },
+  ] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+
+  app.handle_key_event(KeyCode::Char('U').into());
+  assert!(app.show_utilization_scatter);
+  let area = Rect::new(0, 0, 150, 40);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let screen: Vec<String> = (0..40).map(|y| (0..150).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(screen.iter().any(|line| line.contains("SM vs Memory - GPU A (SM 95% / Mem 10%)")));
+  assert!(screen.iter().any(|line| line.contains("Memory %")));
+  assert!(!screen.iter().any(|line| line.contains("Utilization - GPU A")));
+}
+
+
+#[test]
+fn unsupported_clock_drops_only_its_chart() {
+  // Like the graphics clock on some vGPUs
+  let source = MockSource { devices: vec![MockDevice { clock: None, temperature: Some(55), ..MockDevice::new("GRID A100") }] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  let gpu_info = &app.gpus[0];
+  assert!(!gpu_info.supports(Metric::Clock) && gpu_info.error(Metric::Clock).is_none());
+  assert_eq!(gpu_info.temperature.latest(), 55);
+  assert_eq!(gpu_info.core_clock.points(|clock| clock as f64), vec![]);
+
+  let area = Rect::new(0, 0, 150, 40);
+  let chart_areas = gpu_info.chart_areas(area, false, None);
+  assert!(!chart_areas.contains_key(&Metric::Clock) && chart_areas.contains_key(&Metric::Temperature));
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let screen: Vec<String> = (0..40).map(|y| (0..150).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(screen.iter().any(|line| line.contains("Temperature - GRID A100")));
+  assert!(!screen.iter().any(|line| line.contains("Clock - GRID A100")));
+}
+
+
+#[test]
+fn accent_color() {
+  assert_eq!(theme::parse_hex_color("#75FF00"), Ok(Color::Rgb(0x75, 0xff, 0x00)));
+  assert_eq!(theme::parse_hex_color("1e90ff"), Ok(Color::Rgb(0x1e, 0x90, 0xff)));
+  for malformed in ["#75FF0", "#75FF000", "#GGFF00", "", "#75FF0é"] {
+    assert!(theme::parse_hex_color(malformed).is_err(), "{}", malformed);
+  }
+  let options = Options::parse(vec![String::from("--accent"), String::from("#75FF00")]).unwrap();
+  assert_eq!(options.accent.as_deref(), Some("#75FF00"));
+
+  let mut app = NvidiaApp::default();
+  app.set_accent(Color::Rgb(1, 2, 3));
+  for _ in 0..THEMES.len() {
+    assert_eq!((app.theme().border, app.theme().clock_color), (Color::Rgb(1, 2, 3), Color::Rgb(1, 2, 3)));
+    assert_eq!(app.theme().temp_color, THEMES[app.theme].temp_color);
+    app.cycle_theme();
+  }
+}
+
+
+#[test]
+fn legend_avoids_the_lines() {
+  let rising: Vec<(f64, f64)> = (0..10).map(|i| (i as f64 - 9.0, i as f64 * 10.0)).collect();
+  let high: Vec<(f64, f64)> = (0..10).map(|i| (i as f64 - 9.0, 90.0)).collect();
+  let bounds = ([-9.0, 0.0], [0.0, 100.0]);
+  // One series needs no legend, however many empty ones it's charted with
+  assert_eq!(legend_position(&[&rising, &[]], bounds.0, bounds.1), None);
+  assert_eq!(legend_position(&[&rising, &high], bounds.0, bounds.1), Some(LegendPosition::BottomRight));
+  let low: Vec<(f64, f64)> = rising.iter().map(|&(x, _)| (x, 5.0)).collect();
+  assert_eq!(legend_position(&[&low, &low], bounds.0, bounds.1), Some(LegendPosition::TopRight));
+  // Nor do the points of gaps below the chart count
+  let gapped = break_at_gaps(&[(-9.0, 90.0), (-4.0, 90.0), (0.0, 90.0)]);
+  assert_eq!(legend_position(&[&gapped, &high], bounds.0, bounds.1), Some(LegendPosition::BottomRight));
+}
+
+
+#[test]
+fn default_charts_lead_the_layout() {
+  let config = Config::parse("default_charts = \"utilization, memory, utilization\"\n").unwrap();
+  assert_eq!(config.default_charts, Some(vec![Metric::Utilization, Metric::Memory]));
+  assert!(config.to_toml().contains("default_charts = \"utilization,memory\"\n"));
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert!(Config::parse("default_charts = \"gpu\"").is_err());
+  assert!(Config::parse("default_charts = \"\"").is_err());
+
+  let source = MockSource { devices: vec![MockDevice::new("GPU A")] };
+  let mut app = NvidiaApp::default();
+  config.apply(&mut app);
+  app.add_gpus(&source, None).unwrap();
+  let mut gpus = std::mem::take(&mut app.gpus);
+  app.apply_display_settings(&mut gpus[0]);
+  app.gpus = gpus;
+  let charts = app.gpus[0].charts(false);
+  assert_eq!(charts[..3], [Metric::Utilization, Metric::Memory, Metric::Clock]);
+  assert_eq!(charts.iter().filter(|&&metric| metric == Metric::Memory).count(), 1);
+  let area = Rect::new(0, 0, 150, 40);
+  let first_page: Vec<Metric> = app.gpus[0].chart_areas(area, false, Some(0)).into_keys().collect();
+  assert_eq!(first_page, vec![Metric::Memory, Metric::Utilization]);
+}
+
+
+#[test]
+fn help_and_commands() {
+  let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+  assert!(parse(&["--help"]).unwrap().help && parse(&["-h"]).unwrap().help);
+  // Even next to arguments that are missing something
+  assert!(parse(&["--daemon", "--help"]).unwrap().help);
+  assert!(parse(&["dump"]).unwrap().dump_json);
+  assert!(parse(&["check", "--demo"]).unwrap().check);
+  assert_eq!(parse(&["tui", "--compact"]).unwrap(), parse(&["--compact"]).unwrap());
+  assert!(parse(&["daemon", "--log-csv", "gpus.csv"]).unwrap().daemon);
+  assert!(parse(&["daemon"]).unwrap_err().contains("--daemon requires"));
+  assert!(parse(&["serve"]).unwrap_err().contains("Unknown command: serve"));
+  assert!(parse(&["--compact", "check"]).unwrap_err().contains("Unknown argument: check"));
+
+  // Every flag the help lists is one the parser knows
+  for flag in cli::HELP.split(|c: char| c.is_whitespace() || c == ',').filter(|word| word.starts_with('-') && word.len() > 1) {
+    let error = parse(&[flag, "1"]).err().unwrap_or_default();
+    assert!(!error.contains(&format!("Unknown argument: {} ", flag)), "{}", error);
+  }
+}
+
+
+#[test]
+fn cursor_reads_chart_values() {
+  let mut source = MockSource { devices: vec![MockDevice { power_usage: None, ..MockDevice::new("GPU A") }] };
+  let mut app = NvidiaApp { history_len: 10, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  for temperature in [Some(50), None, Some(70)] {
+    source.devices[0].temperature = temperature;
+    app.update_state(&source).unwrap();
+  }
+  assert_eq!(app.gpus[0].temperature.ago(0), Some(70));
+  assert_eq!(app.gpus[0].temperature.ago(1), None);
+  assert_eq!(app.gpus[0].temperature.ago(2), Some(50));
+  assert_eq!(app.gpus[0].temperature.ago(3), None);
+
+  app.handle_key_event(KeyCode::Char('C').into());
+  assert!(app.paused && app.cursor == Some(0));
+  for _ in 0..3 {
+    app.handle_key_event(KeyCode::Left.into());
+  }
+  app.handle_key_event(KeyCode::Right.into());
+  assert_eq!((app.cursor, app.scroll), (Some(2), 0));
+  assert_eq!(stats::cursor_line(&app.gpus[0], Metric::Temperature, app.clock_domain, 2, app.interval), "-2s: 50 °C");
+  assert_eq!(stats::cursor_line(&app.gpus[0], Metric::Temperature, app.clock_domain, 1, app.interval), "-1s: N/A");
+  assert_eq!(stats::cursor_line(&app.gpus[0], Metric::Utilization, app.clock_domain, 0, app.interval), "0s: SM 0% | Mem 0%");
+
+  let area = Rect::new(0, 0, 150, 40);
+  let mut buf = Buffer::empty(area);
+  app.render(area, &mut buf);
+  let screen: Vec<String> = (0..40).map(|y| (0..150).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  assert!(screen.iter().any(|line| line.contains("-2s: 50 °C")));
+  // The crosshair runs down the temperature chart, clear of its axis
+  let temperature_area = app.gpus[0].chart_areas(area, false, None)[&Metric::Temperature];
+  let crossed = (temperature_area.left() + 8..temperature_area.right() - 1).filter(|&x| {
+    (temperature_area.top() + 1..temperature_area.top() + 4).all(|y| buf[(x, y)].symbol() == "│")
+  }).count();
+  assert_eq!(crossed, 1);
+
+  app.handle_key_event(KeyCode::Char('p').into());
+  assert!(!app.paused && app.cursor.is_none());
+}
+
+
+#[test]
+fn memory_temperature_footers_and_exports() {
+  let source = MockSource { devices: vec![
+    MockDevice { temperature: Some(60), memory_temperature: Some(90), ..MockDevice::new("GDDR6X GPU") },
+    MockDevice { temperature: Some(50), ..MockDevice::new("Core only") },
+  ] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!(stats::stats_line(&app.gpus[0], Metric::Temperature, app.clock_domain), "GPU min 60 avg 60 max 60 °C | Mem min 90 avg 90 max 90 °C");
+  assert_eq!(stats::stats_line(&app.gpus[1], Metric::Temperature, app.clock_domain), "min 50 avg 50 max 50 °C");
+  assert_eq!(stats::cursor_line(&app.gpus[0], Metric::Temperature, app.clock_domain, 0, app.interval), "0s: GPU 60 °C | Mem 90 °C");
+
+  let json = snapshot::snapshot_json(&app.gpus).to_string();
+  assert!(json.contains("\"temperature_c\":60,\"memory_temperature_c\":90,"));
+  assert!(json.contains("\"temperature_c\":50,\"memory_temperature_c\":null,"));
+  let versions = DriverVersions::default();
+  let xml = snapshot::snapshot_xml(&app.gpus, &versions);
+  assert!(xml.contains("<gpu_temp>60 C</gpu_temp>\n\t\t\t<memory_temp>90 C</memory_temp>\n"));
+  assert!(xml.contains("<gpu_temp>50 C</gpu_temp>\n\t\t\t<memory_temp>N/A</memory_temp>\n"));
+}
+
+
+#[test]
+fn gpus_per_page() {
+  assert_eq!(Options::parse(["--per-page", "2"].map(String::from)).unwrap().per_page, Some(2));
+  assert!(Options::parse(["--per-page", "0"].map(String::from)).is_err());
+
+  let source = MockSource { devices: ["A", "B", "C", "D", "E"].map(MockDevice::new).to_vec() };
+  let mut app = NvidiaApp { per_page: Some(2), ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  let names = |app: &NvidiaApp| app.visible_gpus().iter().map(|(_, gpu_info)| gpu_info.device_name.clone()).collect::<Vec<_>>();
+  assert_eq!(names(&app), ["A", "B"]);
+  assert_eq!(app.gpu_pages(), Some((0, 3)));
+
+  app.handle_key_event(KeyCode::PageDown.into());
+  assert_eq!(names(&app), ["C", "D"]);
+  app.handle_key_event(KeyCode::PageDown.into());
+  assert_eq!(names(&app), ["E"]);
+  app.handle_key_event(KeyCode::PageDown.into());
+  assert_eq!(names(&app), ["A", "B"]);
+  app.handle_key_event(KeyCode::PageUp.into());
+  assert_eq!(names(&app), ["E"]);
+
+  app.versions = Some(status::DriverVersions::default());
+  let mut buf = Buffer::empty(Rect::new(0, 0, 100, 30));
+  app.render(buf.area, &mut buf);
+  let status_row: String = (0..100).map(|x| buf[(x, 29)].symbol()).collect();
+  assert!(status_row.ends_with("Page 3/3 "));
+
+  // The messages keep the keys while shown
+  app.show_messages = true;
+  app.handle_key_event(KeyCode::PageUp.into());
+  assert_eq!(names(&app), ["E"]);
+}
+
+
+#[test]
+fn random_query_failures_keep_the_state_consistent() {
+  // A xorshift generator with a fixed seed, so a failure reproduces
+  let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+  let mut random = move || {
+    seed ^= seed << 13;
+    seed ^= seed >> 7;
+    seed ^= seed << 17;
+    seed
+  };
+  let mut source = MockSource { devices: vec![MockDevice::new("A"), MockDevice::new("B")] };
+  // Listed so that the process query recovers too
+  let mut app = NvidiaApp { show_processes: true, ..NvidiaApp::default() };
+  app.add_gpus(&source, None).unwrap();
+  let history_len = app.history_len;
+  let charts = app.gpus[0].charts(false);
+
+  let mut last = Vec::new();
+  for tick in 0..300_u32 {
+    let mut expected = Vec::new();
+    for device in &mut source.devices {
+      // Each query fails one tick in four, and the whole device one tick in twenty
+      let mut reading = |value: u32| (random() % 4 != 0).then_some(value);
+      let readings = [reading(tick % 100), reading(1000 + tick), reading(tick % 101), reading(50_000 + tick), reading(tick)];
+      device.temperature = readings[0];
+      device.clock = readings[1];
+      device.utilization = readings[2].map(|sm| (sm, 0));
+      device.power_usage = readings[3];
+      device.memory_used = readings[4].map(u64::from);
+      device.lost = random() % 20 == 0;
+      expected.push(if device.lost { [None; 5] } else { readings });
+    }
+    app.update_state(&source).unwrap();
+
+    for (gpu_info, readings) in app.gpus.iter().zip(&expected) {
+      let metrics = [Metric::Temperature, Metric::Clock, Metric::Utilization, Metric::Power, Metric::Memory];
+      for (metric, reading) in metrics.into_iter().zip(readings) {
+        assert_eq!(gpu_info.errors.contains_key(&metric), reading.is_none(), "{} on tick {}", metric, tick);
+      }
+      assert_eq!(gpu_info.temperature.ago(0), readings[0]);
+      assert_eq!(gpu_info.core_clock.ago(0), readings[1]);
+      assert_eq!(gpu_info.gpu_utilization.ago(0), readings[2]);
+      assert_eq!(gpu_info.power_usage.ago(0), readings[3]);
+      assert_eq!(gpu_info.memory_used.ago(0), readings[4].map(u64::from));
+      let lost = readings.iter().all(Option::is_none) && gpu_info.disconnected;
+      assert_eq!(gpu_info.tick_failed(), lost, "tick {}", tick);
+      // Every history advances by one slot per tick, failed or not
+      for len in [gpu_info.temperature.len(), gpu_info.core_clock.len(), gpu_info.gpu_utilization.len(), gpu_info.power_usage.len(), gpu_info.memory_used.len()] {
+        assert_eq!(len, history_len);
+      }
+      // A failure is a gap, never a dropped chart
+      assert_eq!(gpu_info.charts(false), charts);
+    }
+    last = expected;
+  }
+
+  source.devices = vec![MockDevice::new("A"), MockDevice::new("B")];
+  app.update_state(&source).unwrap();
+  for (gpu_info, readings) in app.gpus.iter().zip(&last) {
+    assert!(gpu_info.errors.is_empty() && !gpu_info.disconnected);
+    assert_eq!(gpu_info.temperature.ago(0), Some(0));
+    assert_eq!(gpu_info.temperature.ago(1), readings[0]);
+  }
+}
+
+
+#[test]
+fn focus_one_chart_full_screen() {
+  let source = MockSource { devices: vec![MockDevice::new("First GPU"), MockDevice::new("Second GPU")] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  app.selected = Some(1);
+  let charts = app.gpus[1].charts(false);
+  assert!(charts.len() > 2);
+
+  app.handle_key_event(KeyCode::Char('F').into());
+  assert_eq!(app.chart_focus, Some((1, charts[0])));
+  app.handle_key_event(KeyCode::Char('F').into());
+  assert_eq!(app.chart_focus, Some((1, charts[1])));
+
+  let mut buf = Buffer::empty(Rect::new(0, 0, 100, 30));
+  app.render(buf.area, &mut buf);
+  let screen: Vec<String> = (0..30).map(|y| (0..100).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  // One chart from corner to corner, without the memory gauge above it
+  assert!(screen[0].starts_with('┌') && screen[0].ends_with('┐') && screen[0].contains("Second GPU"));
+  assert!(screen[29].starts_with('└') && screen[29].ends_with('┘'));
+  assert!(screen[1..29].iter().all(|line| line.starts_with('│') && line.ends_with('│')));
+  assert_eq!(app.gpu_areas(buf.area), [(1, buf.area)]);
+
+  // Esc leaves the chart, then the GPU
+  app.handle_key_event(KeyCode::Esc.into());
+  assert_eq!((app.chart_focus, app.selected), (None, Some(1)));
+  app.handle_key_event(KeyCode::Esc.into());
+  assert_eq!(app.selected, None);
+
+  // Stepping past the last chart shows every chart again
+  for &chart in &app.gpus[0].charts(false) {
+    app.handle_key_event(KeyCode::Char('F').into());
+    assert_eq!(app.chart_focus, Some((0, chart)));
+  }
+  app.handle_key_event(KeyCode::Char('F').into());
+  assert_eq!(app.chart_focus, None);
+}
+
+
+#[test]
+fn fan_policy_badge() {
+  use source::FanPolicy;
+
+  let fans = |fan_policy| MockDevice { fan_speeds: vec![30, 30], fan_policy, ..MockDevice::new("GPU") };
+  let mut source = MockSource { devices: vec![fans(Some(FanPolicy::Manual)), fans(None)] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+  assert_eq!((app.gpus[0].fan_policy, app.gpus[1].fan_policy), (Some(FanPolicy::Manual), None));
+
+  let bottom_row = |gpu_info: &GPUInfo| {
+    let area = Rect::new(0, 0, 60, 12);
+    let mut buf = Buffer::empty(area);
+    throttle::render_fan_policy_badge(gpu_info, &THEMES[0], area, &mut buf);
+    (0..60).map(|x| buf[(x, 11)].symbol()).collect::<String>()
+  };
+  assert!(bottom_row(&app.gpus[0]).starts_with("  Fan Manual "));
+  assert!(!bottom_row(&app.gpus[1]).contains("Fan"));
+
+  source.devices[0].fan_policy = Some(FanPolicy::Auto);
+  app.update_state(&source).unwrap();
+  assert!(bottom_row(&app.gpus[0]).starts_with("  Fan Auto "));
+}
+
+
+#[test]
+fn alert_hysteresis() {
+  let config = Config::parse("temp_hysteresis = 5").unwrap();
+  assert_eq!(Config::parse(&config.to_toml()).unwrap(), config);
+  assert_eq!(Config::parse("temp_hysteresis = 4294967296").unwrap_err(), "line 1: temp_hysteresis is too large");
+  // The alert would never clear at 0 °C
+  assert_eq!(Config::parse("temp_warn = 80\ntemp_hysteresis = 80").unwrap_err(), "line 2: temp_hysteresis must be below temp_warn and temp_crit");
+  assert!(Config::parse("temp_hysteresis = 10\ntemp_crit = 11").is_ok());
+  let mut app = NvidiaApp { temp_warn: Some(80), temp_crit: Some(90), bell: true, ..NvidiaApp::default() };
+  config.apply(&mut app);
+  assert_eq!(app.temp_hysteresis, 5);
+  app.gpus.push(GPUInfo::new(String::from("GPU A"), 0, 0, app.history_len));
+
+  let mut readings = app.gpus.clone();
+  let mut step = |app: &mut NvidiaApp, temperature| {
+    readings[0].temperature.push(temperature);
+    app.bell_rung_at = None;
+    app.push_readings(&readings);
+    (app.temperature_level(&app.gpus[0]), std::mem::take(&mut app.ring_bell))
+  };
+  assert_eq!(step(&mut app, 79), (None, false));
+  assert_eq!(step(&mut app, 90), (Some(TemperatureLevel::Critical), true));
+  // Hovering around the threshold neither clears the alert nor rings again
+  assert_eq!(step(&mut app, 88), (Some(TemperatureLevel::Critical), false));
+  assert_eq!(step(&mut app, 91), (Some(TemperatureLevel::Critical), false));
+  assert_eq!(step(&mut app, 85), (Some(TemperatureLevel::Critical), false));
+  assert_eq!(step(&mut app, 84), (Some(TemperatureLevel::Warning), false));
+  assert_eq!(step(&mut app, 90), (Some(TemperatureLevel::Critical), true));
+  assert_eq!(step(&mut app, 76), (Some(TemperatureLevel::Warning), false));
+  assert_eq!(step(&mut app, 74), (None, false));
+  assert_eq!(step(&mut app, 79), (None, false));
+}
+
+
+#[test]
+fn deviation_from_the_mean() {
+  let gpu = |name: &str, temperature| MockDevice { temperature: Some(temperature), clock: Some(1800), ..MockDevice::new(name) };
+  let source = MockSource { devices: vec![gpu("GPU A", 60), gpu("GPU B", 62), gpu("GPU C", 61), gpu("GPU D", 77)] };
+  let mut app = NvidiaApp::default();
+  app.add_gpus(&source, None).unwrap();
+  app.update_state(&source).unwrap();
+
+  let deviation = |app: &NvidiaApp, i: usize, metric| stats::deviation(&app.gpus, &app.gpus[i], metric, ClockDomain::Graphics).unwrap();
+  assert_eq!(deviation(&app, 3, Metric::Temperature), stats::Deviation { value: 12.0, outlier: true });
+  assert_eq!(deviation(&app, 0, Metric::Temperature), stats::Deviation { value: -5.0, outlier: false });
+  assert_eq!(deviation(&app, 0, Metric::Clock), stats::Deviation { value: 0.0, outlier: false });
+  assert_eq!(stats::deviation(&app.gpus[..1], &app.gpus[0], Metric::Temperature, ClockDomain::Graphics), None);
+  assert_eq!(stats::deviation(&app.gpus, &app.gpus[0], Metric::Pcie, ClockDomain::Graphics), None);
+
+  // The footers show up for the compared charts alone
+  app.handle_key_event(KeyCode::Char('O').into());
+  app.selected = Some(3);
+  let mut buf = Buffer::empty(Rect::new(0, 0, 120, 50));
+  app.render(buf.area, &mut buf);
+  let screen: Vec<String> = (0..50).map(|y| (0..120).map(|x| buf[(x, y)].symbol()).collect()).collect();
+  let row = screen.iter().position(|line| line.contains("+12 °C vs mean")).unwrap();
+  let x = screen[row].split("+12 °C vs mean").next().unwrap().chars().count() as u16;
+  assert_eq!(buf[(x, row as u16)].fg, THEMES[0].temp_warn_color);
+  assert!(screen.iter().any(|line| line.contains("+0 MHz vs mean")));
+  assert!(!screen.iter().any(|line| line.contains("KB/s vs mean")));
+}
+
+
+#[test]
+fn summary_on_exit() {
+  let path = std::env::temp_dir().join(format!("nvidia-summary-{}.json", std::process::id()));
+  let options = Options::parse(["--daemon", "--summary", path.to_str().unwrap()].map(String::from)).unwrap();
+  assert_eq!(options.summary.as_deref(), Some(path.as_path()));
+  assert!(Options::parse(["--summary"].map(String::from)).unwrap_err().contains("--summary requires a value"));
+
+  let mut history = History::new(2);
+  assert_eq!(history.trough(), None);
+  for clock in [1500, 210, 1800] {
+    history.push(clock);
+  }
+  // Both extremes outlive the window
+  assert_eq!((history.peak(), history.trough()), (1800, Some(210)));
+  history.clear();
+  assert_eq!(history.trough(), None);
+
+  let mut gpu_info = GPUInfo::new(String::from("Test GPU"), 8192, 1, DEFAULT_HISTORY_LEN);
+  for (clock, temperature) in [(1500, 60), (1950, 83), (300, 45)] {
+    gpu_info.core_clock.push(clock);
+    gpu_info.temperature.push(temperature);
+  }
+  gpu_info.record::<u32>(Metric::Power, Err(NvmlError::NotSupported));
+  let idle = GPUInfo::new(String::from("Idle GPU"), 8192, 1, DEFAULT_HISTORY_LEN);
+
+  snapshot::save_summary(&[gpu_info, idle], &path).unwrap();
+  let json = std::fs::read_to_string(&path).unwrap();
+  let _ = std::fs::remove_file(&path);
+  let compact: String = json.split_whitespace().collect();
+  assert!(compact.starts_with("{\"gpus\":[{\"index\":0,\"mig_index\":null,\"name\":\"TestGPU\",\"uuid\":null,\"max_temperature_c\":83,\"max_clock_mhz\":1950,\"min_clock_mhz\":300,\"max_power_mw\":null},"));
+  // A GPU that never got a reading has no envelope rather than zeros
+  assert!(compact.contains("\"name\":\"IdleGPU\",\"uuid\":null,\"max_temperature_c\":null,\"max_clock_mhz\":null,\"min_clock_mhz\":null,\"max_power_mw\":null}]}"));
+}
+
+
+#[test]
+fn alert_hysteresis_outlives_the_history() {
+  let mut app = NvidiaApp { temp_crit: Some(90), temp_hysteresis: 5, history_len: 4, bell: true, ..NvidiaApp::default() };
+  app.gpus.push(GPUInfo::new(String::from("GPU A"), 0, 0, app.history_len));
+  let mut readings = app.gpus.clone();
+  let mut step = |app: &mut NvidiaApp, temperature| {
+    readings[0].temperature.push(temperature);
+    app.bell_rung_at = None;
+    app.push_readings(&readings);
+    (app.temperature_level(&app.gpus[0]), std::mem::take(&mut app.ring_bell))
+  };
+  assert_eq!(step(&mut app, 90), (Some(TemperatureLevel::Critical), true));
+  // Long after the 90 °C sample scrolled out of the history, the band still holds it
+  for _ in 0..3 * app.history_len {
+    assert_eq!(step(&mut app, 87), (Some(TemperatureLevel::Critical), false));
+  }
+  app.gpus[0].clear_history();
+  assert_eq!(step(&mut app, 90), (Some(TemperatureLevel::Critical), false));
+  assert_eq!(step(&mut app, 84), (None, false));
+}
+
+
+#[test]
+#[cfg(unix)]
+fn daemon_fires_crit_hooks() {
+  let dir = std::env::temp_dir().join(format!("nvidia-daemon-crit-{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let (recording, log, summary) = (dir.join("replay.csv"), dir.join("crit.log"), dir.join("summary.json"));
+  std::fs::write(&recording, format!("{}\n100.000,0,\"A\",,1000,,,95,,,,,,,\n", export::RECORD_HEADER)).unwrap();
+  let command = format!("echo \"$NVIDIA_GPU_INDEX $NVIDIA_GPU_TEMP\" >> '{}'", log.display());
+  let args = ["--daemon", "--replay", recording.to_str().unwrap(), "--summary", summary.to_str().unwrap(), "--temp-crit", "90", "--on-crit", &command];
+  let options = Options::parse(args.map(String::from)).unwrap();
+
+  let interrupted = Arc::new(AtomicBool::new(false));
+  let mut app = daemon_app(&options, Arc::clone(&interrupted));
+  // Stops the daemon once the hook ran, or gives up on it
+  let watcher = {
+    let log = log.clone();
+    std::thread::spawn(move || {
+      for _ in 0..250 {
+        if std::fs::read_to_string(&log).is_ok_and(|contents| contents.ends_with('\n')) {
+          break;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+      }
+      interrupted.store(true, Ordering::Relaxed);
+    })
+  };
+  app.run_export_loop(&options).unwrap();
+  watcher.join().unwrap();
+  assert_eq!(std::fs::read_to_string(&log).unwrap_or_default(), "0 95\n");
+  let _ = std::fs::remove_dir_all(&dir);
+}
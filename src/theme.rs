@@ -0,0 +1,223 @@
+use ratatui::style::Color;
+
+/// The colors used to draw the charts and panels, cycled at runtime with `c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Borders of the clock chart and the process panel.
+    pub border: Color,
+    /// Text of the process panel.
+    pub text: Color,
+    pub clock_color: Color,
+    pub temp_color: Color,
+    pub memory_color: Color,
+    pub sm_utilization_color: Color,
+    pub memory_utilization_color: Color,
+    pub power_color: Color,
+    pub pcie_rx_color: Color,
+    pub pcie_tx_color: Color,
+    pub encoder_color: Color,
+    pub decoder_color: Color,
+    /// Line colors for the fans of one GPU, reused in order when a card has more fans.
+    pub fan_colors: [Color; 4],
+    /// Bands of the process memory view, reused in order when more processes run.
+    pub process_colors: [Color; 4],
+    /// Background of the badges listing why the clocks are throttled.
+    pub throttle_color: Color,
+    /// Fill of the memory gauge below 70% used. Fuller gauges use the warning and
+    /// critical colors below.
+    pub gauge_color: Color,
+    /// The part of the memory gauge the driver reserves for itself.
+    pub memory_reserved_color: Color,
+    /// Borders of a GPU whose temperature reached `--temp-warn` or `--temp-crit`.
+    pub temp_warn_color: Color,
+    pub temp_crit_color: Color,
+    /// The colors of the temperature line from the safe range, through warm, to the
+    /// alert threshold.
+    pub temp_gradient: [Color; 3],
+    /// Charts of metrics whose query keeps failing.
+    pub unavailable: Color,
+    /// Text of the badges, which are drawn on a colored background, and the unfilled
+    /// part of the memory gauge.
+    pub background: Color,
+}
+
+impl Theme {
+    pub fn fan_color(&self, fan: usize) -> Color {
+        self.fan_colors[fan % self.fan_colors.len()]
+    }
+
+    pub fn process_color(&self, band: usize) -> Color {
+        self.process_colors[band % self.process_colors.len()]
+    }
+
+    /// The color of the temperature line at `heat`, from 0 in the safe range to 1 at the
+    /// alert threshold. RGB stops of [`Theme::temp_gradient`] are blended, and other
+    /// colors snap to the nearest stop, since terminal palettes can't be mixed.
+    pub fn temp_gradient_color(&self, heat: f64) -> Color {
+        let position = heat.clamp(0.0, 1.0) * (self.temp_gradient.len() - 1) as f64;
+        let stop = (position as usize).min(self.temp_gradient.len() - 2);
+        let blend = position - stop as f64;
+        match (self.temp_gradient[stop], self.temp_gradient[stop + 1]) {
+            (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+                let mix = |from: u8, to: u8| {
+                    (f64::from(from) + (f64::from(to) - f64::from(from)) * blend).round() as u8
+                };
+                Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+            }
+            (from, _) if blend < 0.5 => from,
+            (_, to) => to,
+        }
+    }
+
+    /// The theme with `accent` as the border and clock line color, for `--accent`.
+    pub fn with_accent(mut self, accent: Color) -> Self {
+        self.border = accent;
+        self.clock_color = accent;
+        self
+    }
+}
+
+/// Parses a color written `#RRGGBB`, as `--accent` takes it. The `#` may be left out.
+pub fn parse_hex_color(hex: &str) -> Result<Color, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let channel = |at: usize| u8::from_str_radix(digits.get(at..at + 2)?, 16).ok();
+    match (channel(0), channel(2), channel(4)) {
+        (Some(r), Some(g), Some(b)) if digits.len() == 6 => Ok(Color::Rgb(r, g, b)),
+        _ => Err(format!("invalid color `{}`, expected `#RRGGBB`", hex)),
+    }
+}
+
+pub const THEMES: [Theme; 3] = [
+    Theme {
+        name: "neon",
+        border: Color::Rgb(117, 255, 0),
+        text: Color::Rgb(48, 226, 173),
+        clock_color: Color::Rgb(48, 226, 173),
+        temp_color: Color::Rgb(255, 0, 255),
+        memory_color: Color::Rgb(0, 191, 255),
+        sm_utilization_color: Color::Rgb(255, 165, 0),
+        memory_utilization_color: Color::Rgb(0, 191, 255),
+        power_color: Color::Rgb(255, 215, 0),
+        pcie_rx_color: Color::Rgb(135, 206, 250),
+        pcie_tx_color: Color::Rgb(255, 127, 80),
+        encoder_color: Color::Rgb(186, 85, 211),
+        decoder_color: Color::Rgb(64, 224, 208),
+        fan_colors: [
+            Color::Rgb(0, 255, 255),
+            Color::Rgb(255, 105, 180),
+            Color::Rgb(173, 255, 47),
+            Color::Rgb(238, 130, 238),
+        ],
+        process_colors: [
+            Color::Rgb(0, 191, 255),
+            Color::Rgb(255, 0, 255),
+            Color::Rgb(255, 215, 0),
+            Color::Rgb(173, 255, 47),
+        ],
+        throttle_color: Color::Rgb(255, 165, 0),
+        gauge_color: Color::Green,
+        memory_reserved_color: Color::Gray,
+        temp_warn_color: Color::Yellow,
+        temp_crit_color: Color::Red,
+        temp_gradient: [
+            Color::Rgb(117, 255, 0),
+            Color::Rgb(255, 215, 0),
+            Color::Rgb(255, 0, 0),
+        ],
+        unavailable: Color::DarkGray,
+        background: Color::Black,
+    },
+    Theme {
+        name: "monochrome",
+        border: Color::White,
+        text: Color::White,
+        clock_color: Color::White,
+        temp_color: Color::White,
+        memory_color: Color::White,
+        sm_utilization_color: Color::White,
+        memory_utilization_color: Color::Gray,
+        power_color: Color::White,
+        pcie_rx_color: Color::White,
+        pcie_tx_color: Color::Gray,
+        encoder_color: Color::White,
+        decoder_color: Color::Gray,
+        fan_colors: [Color::White, Color::Gray, Color::White, Color::Gray],
+        process_colors: [Color::White, Color::Gray, Color::White, Color::Gray],
+        throttle_color: Color::White,
+        gauge_color: Color::White,
+        memory_reserved_color: Color::Gray,
+        temp_warn_color: Color::White,
+        temp_crit_color: Color::White,
+        temp_gradient: [Color::Gray, Color::White, Color::White],
+        unavailable: Color::DarkGray,
+        background: Color::Black,
+    },
+    // The Okabe-Ito palette, distinguishable with the common forms of color blindness
+    Theme {
+        name: "colorblind-safe",
+        border: Color::Rgb(86, 180, 233),
+        text: Color::Rgb(240, 240, 240),
+        clock_color: Color::Rgb(0, 158, 115),
+        temp_color: Color::Rgb(213, 94, 0),
+        memory_color: Color::Rgb(0, 114, 178),
+        sm_utilization_color: Color::Rgb(230, 159, 0),
+        memory_utilization_color: Color::Rgb(86, 180, 233),
+        power_color: Color::Rgb(240, 228, 66),
+        pcie_rx_color: Color::Rgb(86, 180, 233),
+        pcie_tx_color: Color::Rgb(230, 159, 0),
+        encoder_color: Color::Rgb(204, 121, 167),
+        decoder_color: Color::Rgb(0, 158, 115),
+        fan_colors: [
+            Color::Rgb(86, 180, 233),
+            Color::Rgb(230, 159, 0),
+            Color::Rgb(204, 121, 167),
+            Color::Rgb(0, 158, 115),
+        ],
+        process_colors: [
+            Color::Rgb(0, 114, 178),
+            Color::Rgb(213, 94, 0),
+            Color::Rgb(0, 158, 115),
+            Color::Rgb(204, 121, 167),
+        ],
+        throttle_color: Color::Rgb(230, 159, 0),
+        gauge_color: Color::Rgb(0, 158, 115),
+        memory_reserved_color: Color::Rgb(86, 180, 233),
+        temp_warn_color: Color::Rgb(240, 228, 66),
+        temp_crit_color: Color::Rgb(213, 94, 0),
+        temp_gradient: [
+            Color::Rgb(0, 158, 115),
+            Color::Rgb(240, 228, 66),
+            Color::Rgb(213, 94, 0),
+        ],
+        unavailable: Color::DarkGray,
+        background: Color::Black,
+    },
+];
+
+/// The theme of `--no-color`, which leaves every color to the terminal's defaults.
+pub const PLAIN: Theme = Theme {
+    name: "plain",
+    border: Color::Reset,
+    text: Color::Reset,
+    clock_color: Color::Reset,
+    temp_color: Color::Reset,
+    memory_color: Color::Reset,
+    sm_utilization_color: Color::Reset,
+    memory_utilization_color: Color::Reset,
+    power_color: Color::Reset,
+    pcie_rx_color: Color::Reset,
+    pcie_tx_color: Color::Reset,
+    encoder_color: Color::Reset,
+    decoder_color: Color::Reset,
+    fan_colors: [Color::Reset; 4],
+    process_colors: [Color::Reset; 4],
+    throttle_color: Color::Reset,
+    gauge_color: Color::Reset,
+    memory_reserved_color: Color::Reset,
+    temp_warn_color: Color::Reset,
+    temp_crit_color: Color::Reset,
+    temp_gradient: [Color::Reset; 3],
+    unavailable: Color::Reset,
+    background: Color::Reset,
+};
@@ -0,0 +1,170 @@
+use crate::metric::Metric;
+use crate::source::FanPolicy;
+use crate::theme::Theme;
+use crate::GPUInfo;
+use nvml_wrapper::{bitmasks::device::ThrottleReasons, enum_wrappers::device::ComputeMode};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+/// Short labels for the throttle reasons NVML reports, in the order they're shown.
+const THROTTLE_LABELS: [(ThrottleReasons, &str); 9] = [
+    (ThrottleReasons::HW_THERMAL_SLOWDOWN, "HW Thermal"),
+    (ThrottleReasons::SW_THERMAL_SLOWDOWN, "SW Thermal"),
+    (ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN, "Power Brake"),
+    (ThrottleReasons::HW_SLOWDOWN, "HW Slowdown"),
+    (ThrottleReasons::SW_POWER_CAP, "Power Cap"),
+    (ThrottleReasons::SYNC_BOOST, "Sync Boost"),
+    (ThrottleReasons::APPLICATIONS_CLOCKS_SETTING, "App Clocks"),
+    (ThrottleReasons::DISPLAY_CLOCK_SETTING, "Display Clocks"),
+    (ThrottleReasons::GPU_IDLE, "Idle"),
+];
+
+/// The reasons that mean the GPU runs too hot, counted by [`ThrottleEvents`].
+const THERMAL: ThrottleReasons =
+    ThrottleReasons::HW_THERMAL_SLOWDOWN.union(ThrottleReasons::SW_THERMAL_SLOWDOWN);
+/// The reasons that mean the GPU draws too much power.
+const POWER: ThrottleReasons =
+    ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN.union(ThrottleReasons::SW_POWER_CAP);
+
+/// How many times a GPU started throttling for its temperature or its power draw since
+/// monitoring started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThrottleEvents {
+    pub thermal: u32,
+    pub power: u32,
+}
+
+impl ThrottleEvents {
+    /// Counts the throttling that starts going from the `old` reasons to the `new` ones.
+    /// Switching between reasons of the same kind, such as from software to hardware
+    /// thermal slowdown, continues the same episode.
+    pub fn record(&mut self, old: ThrottleReasons, new: ThrottleReasons) {
+        let started = |kind: ThrottleReasons| !old.intersects(kind) && new.intersects(kind);
+        if started(THERMAL) {
+            self.thermal += 1;
+        }
+        if started(POWER) {
+            self.power += 1;
+        }
+    }
+
+    /// A summary such as `2 thermal / 1 power throttles` for the clock chart's title, or
+    /// `None` while the GPU hasn't throttled.
+    pub fn summary(&self) -> Option<String> {
+        match (self.thermal, self.power) {
+            (0, 0) => None,
+            (thermal, 0) => Some(format!("{} thermal throttles", thermal)),
+            (0, power) => Some(format!("{} power throttles", power)),
+            (thermal, power) => Some(format!("{} thermal / {} power throttles", thermal, power)),
+        }
+    }
+}
+
+/// Decodes the active throttle reasons into human-readable labels, most severe first.
+pub fn labels(reasons: ThrottleReasons) -> Vec<&'static str> {
+    THROTTLE_LABELS
+        .iter()
+        .filter(|(reason, _)| reasons.contains(*reason))
+        .map(|&(_, label)| label)
+        .collect()
+}
+
+/// How `nvidia-smi` names a compute mode, with spaces for underscores.
+pub fn compute_mode_name(compute_mode: ComputeMode) -> &'static str {
+    match compute_mode {
+        ComputeMode::Default => "Default",
+        ComputeMode::ExclusiveThread => "Exclusive Thread",
+        ComputeMode::Prohibited => "Prohibited",
+        ComputeMode::ExclusiveProcess => "Exclusive Process",
+    }
+}
+
+/// Draws the active throttle reasons as badges on the bottom border of the clock chart
+/// in `area`, after whether auto boost and persistence mode are on and the compute mode
+/// on GPUs that report them. A disabled auto boost is flagged like a throttle reason,
+/// since it holds the clocks at base under load too, and so is a disabled persistence
+/// mode, which makes queries slow whenever the driver has to load again, and any compute
+/// mode but the default, which explains why a job can't launch.
+pub fn render_throttle_badges(gpu_info: &GPUInfo, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    if area.height < 2 || area.width < 3 {
+        return;
+    }
+    let badge_style = Style::new()
+        .fg(theme.background)
+        .bg(theme.throttle_color)
+        .add_modifier(Modifier::BOLD);
+    let idle_style = Style::new().fg(theme.background).bg(theme.unavailable);
+
+    let mut spans = Vec::new();
+    for (enabled, name) in [
+        (gpu_info.auto_boost, "Boost"),
+        (gpu_info.persistence_mode, "Persistence"),
+    ] {
+        match enabled {
+            Some(true) => spans.push(Span::styled(format!(" {} On ", name), idle_style)),
+            Some(false) => spans.push(Span::styled(format!(" {} Off ", name), badge_style)),
+            None => continue,
+        }
+        spans.push(Span::raw(" "));
+    }
+    // Anything but the default keeps some jobs from launching
+    if let Some(compute_mode) = gpu_info.compute_mode.clone() {
+        let style = match compute_mode {
+            ComputeMode::Default => idle_style,
+            _ => badge_style,
+        };
+        let name = compute_mode_name(compute_mode);
+        spans.push(Span::styled(format!(" Compute {} ", name), style));
+        spans.push(Span::raw(" "));
+    }
+    let reasons = if gpu_info.available(Metric::Throttle) {
+        gpu_info.throttle_reasons
+    } else {
+        ThrottleReasons::empty()
+    };
+    for &(reason, label) in THROTTLE_LABELS.iter() {
+        if !reasons.contains(reason) {
+            continue;
+        }
+        // An idle GPU clocking down is expected, so it isn't flagged like the others
+        let style = if reason == ThrottleReasons::GPU_IDLE {
+            idle_style
+        } else {
+            badge_style
+        };
+        spans.push(Span::styled(format!(" {} ", label), style));
+        spans.push(Span::raw(" "));
+    }
+    let badge_area = Rect::new(area.x + 1, area.bottom() - 1, area.width - 2, 1);
+    Line::from(spans).render(badge_area, buf);
+}
+
+/// Draws who sets the fan speeds as a badge on the bottom border of the fan chart in
+/// `area`, on GPUs that report it. A manual policy is flagged like a throttle reason,
+/// since a fan held at a low speed explains a GPU running hot.
+pub fn render_fan_policy_badge(gpu_info: &GPUInfo, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    if area.height < 2 || area.width < 3 {
+        return;
+    }
+    let (label, style) = match gpu_info.fan_policy {
+        Some(FanPolicy::Auto) => (
+            " Fan Auto ",
+            Style::new().fg(theme.background).bg(theme.unavailable),
+        ),
+        Some(FanPolicy::Manual) => (
+            " Fan Manual ",
+            Style::new()
+                .fg(theme.background)
+                .bg(theme.throttle_color)
+                .add_modifier(Modifier::BOLD),
+        ),
+        None => return,
+    };
+    let badge_area = Rect::new(area.x + 1, area.bottom() - 1, area.width - 2, 1);
+    Line::from(Span::styled(label, style)).render(badge_area, buf);
+}
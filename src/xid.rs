@@ -0,0 +1,141 @@
+use crate::collector::Update;
+use crate::status::format_age;
+use crate::theme::Theme;
+use log::{debug, warn};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::Widget,
+};
+use std::sync::mpsc::Sender;
+use std::time::Instant;
+
+/// The most recent XID errors listed in the alert row. Older ones are only logged.
+pub const MAX_XID_ALERTS: usize = 4;
+
+/// How long the event thread waits for an event before trying again, in ms.
+#[cfg(target_os = "linux")]
+const WAIT_TIMEOUT_MS: u32 = 1000;
+
+/// A critical XID error reported by the driver, which means a GPU fault such as a
+/// fallen-off-the-bus card or a double-bit ECC error and calls for attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XidEvent {
+    /// The hardware index of the GPU.
+    pub index: u32,
+    /// The XID, or `None` if the driver didn't say which.
+    pub xid: Option<u64>,
+    pub at: Instant,
+}
+
+/// Registers for the critical XID errors of every GPU with `--xid` and sends each one
+/// to the TUI from a background thread. The thread has an NVML of its own, since the
+/// event set borrows it and waiting on it blocks.
+#[cfg(target_os = "linux")]
+pub fn spawn(updates_tx: Sender<Update>) {
+    use nvml_wrapper::{enums::event::XidError, error::NvmlError, Nvml};
+    use std::thread;
+
+    thread::spawn(move || {
+        let nvml = match Nvml::init() {
+            Ok(nvml) => nvml,
+            Err(error) => {
+                warn!("Failed to initialize NVML for XID errors: {}", error);
+                return;
+            }
+        };
+        let set = match register(&nvml) {
+            Ok(set) => set,
+            Err(error) => {
+                warn!("Failed to register for XID errors: {}", error);
+                return;
+            }
+        };
+        loop {
+            match set.wait(WAIT_TIMEOUT_MS) {
+                Ok(event) => {
+                    let Ok(index) = event.device.index() else {
+                        continue;
+                    };
+                    let xid = match event.event_data {
+                        Some(XidError::Value(xid)) => Some(xid),
+                        _ => None,
+                    };
+                    let event = XidEvent {
+                        index,
+                        xid,
+                        at: Instant::now(),
+                    };
+                    if updates_tx.send(Update::Xid(event)).is_err() {
+                        return;
+                    }
+                }
+                Err(NvmlError::Timeout) => {}
+                Err(error) => {
+                    warn!("Stopped waiting for XID errors: {}", error);
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Without Linux there are no NVML events to wait for.
+#[cfg(not(target_os = "linux"))]
+pub fn spawn(_updates_tx: Sender<Update>) {
+    warn!("XID errors are only reported by the Linux driver");
+}
+
+/// An event set for the critical XID errors of every GPU that reports them.
+#[cfg(target_os = "linux")]
+fn register(
+    nvml: &nvml_wrapper::Nvml,
+) -> Result<nvml_wrapper::EventSet<'_>, nvml_wrapper::error::NvmlErrorWithSource> {
+    use nvml_wrapper::bitmasks::event::EventTypes;
+
+    let mut set = nvml.create_event_set()?;
+    for index in 0..nvml.device_count()? {
+        let device = nvml.device_by_index(index)?;
+        // A failed registration frees the whole set, so skip the GPUs that can't
+        if !device
+            .supported_event_types()
+            .is_ok_and(|events| events.contains(EventTypes::CRITICAL_XID_ERROR))
+        {
+            debug!("GPU {} doesn't report XID errors", index);
+            continue;
+        }
+        set = device.register_events(EventTypes::CRITICAL_XID_ERROR, set)?;
+    }
+    Ok(set)
+}
+
+/// Draws the latest XID errors on the single row `area` in the critical color, newest
+/// first, such as `XID 79 on GPU 0, 4s ago`.
+pub fn render_xid_alerts(events: &[XidEvent], theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let now = Instant::now();
+    let alerts: Vec<String> = events
+        .iter()
+        .rev()
+        .map(|event| {
+            format!(
+                "{} on GPU {}, {} ago",
+                match event.xid {
+                    Some(xid) => format!("XID {}", xid),
+                    None => String::from("Unknown XID"),
+                },
+                event.index,
+                format_age(now.saturating_duration_since(event.at))
+            )
+        })
+        .collect();
+    let style = Style::new()
+        .fg(theme.background)
+        .bg(theme.temp_crit_color)
+        .add_modifier(Modifier::BOLD);
+    buf.set_style(area, style);
+    Line::from(format!(" {} ", alerts.join(" | ")))
+        .style(style)
+        .render(area, buf);
+}